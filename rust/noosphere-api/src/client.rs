@@ -1,26 +1,45 @@
 use std::str::FromStr;
 
 use crate::{
-    data::{FetchParameters, FetchResponse, IdentifyResponse, PushBody, PushResponse},
+    data::{
+        FetchParameters, FetchResponse, FetchResponseHeader, IdentifyResponse, PublishBody,
+        PublishResponse, PushBody, PushBodyHeader, PushResponse,
+    },
     route::{Route, RouteUrl},
 };
 
 use anyhow::{anyhow, Result};
 use cid::Cid;
+use futures::{io::AsyncRead, stream, Stream, TryStreamExt};
 use libipld_cbor::DagCborCodec;
 
-use noosphere_core::authority::{Author, SphereAction, SphereReference};
-use noosphere_storage::{block_deserialize, block_serialize};
+use noosphere_core::{
+    authority::{Author, SphereAction, SphereReference, SPHERE_SEMANTICS, SUPPORTED_KEYS},
+    data::{Bundle, CarReader, Jwt, CAR_CONTENT_TYPE},
+    view::SPHERE_LIFETIME,
+};
+use noosphere_storage::{block_deserialize, block_serialize, BlockStore, BlockStoreSend};
 use reqwest::{header::HeaderMap, Body, StatusCode};
 use ucan::{
     builder::UcanBuilder,
     capability::{Capability, Resource, With},
+    chain::ProofChain,
     crypto::{did::DidParser, KeyMaterial},
     store::{UcanJwtStore, UcanStore},
     ucan::Ucan,
 };
 use url::Url;
 
+/// The lifetime (in seconds) of the bearer tokens that authorize individual
+/// requests to the gateway
+const BEARER_TOKEN_LIFETIME: u64 = 120;
+
+/// The lifetime (in seconds) of the UCAN that delegates the capability to
+/// publish a sphere to the gateway, so that it may sign name records on the
+/// sphere's behalf; it must not expire before the records that it is the proof
+/// of, and those are issued with the [SPHERE_LIFETIME]
+const NAME_RECORD_PROOF_LIFETIME: u64 = SPHERE_LIFETIME;
+
 /// A [Client] is a simple, portable HTTP client for the Noosphere gateway REST
 /// API. It embodies the intended usage of the REST API, which includes an
 /// opening handshake (with associated key verification) and various
@@ -74,14 +93,16 @@ where
         let (jwt, ucan_headers) = Self::make_bearer_token(
             &gateway_identity,
             author,
-            &Capability {
+            &[Capability {
                 with: With::Resource {
                     kind: Resource::Scoped(SphereReference {
                         did: sphere_identity.to_string(),
+                        path: None,
                     }),
                 },
                 can: SphereAction::Fetch,
-            },
+            }],
+            BEARER_TOKEN_LIFETIME,
             &store,
         )
         .await?;
@@ -115,16 +136,21 @@ where
     async fn make_bearer_token(
         gateway_identity: &str,
         author: &Author<K>,
-        capability: &Capability<SphereReference, SphereAction>,
+        capabilities: &[Capability<SphereReference, SphereAction>],
+        lifetime: u64,
         store: &S,
     ) -> Result<(String, HeaderMap)> {
-        let mut signable = UcanBuilder::default()
+        let mut builder = UcanBuilder::default()
             .issued_by(&author.key)
             .for_audience(gateway_identity)
-            .with_lifetime(120)
-            .claiming_capability(capability)
-            .with_nonce()
-            .build()?;
+            .with_lifetime(lifetime)
+            .with_nonce();
+
+        for capability in capabilities {
+            builder = builder.claiming_capability(capability);
+        }
+
+        let mut signable = builder.build()?;
 
         let mut ucan_headers = HeaderMap::new();
 
@@ -173,13 +199,96 @@ where
         Ok((jwt, ucan_headers))
     }
 
+    /// The capabilities to claim when pushing to the gateway. If the author
+    /// has only been authorized to push to some parts of the sphere, a bearer
+    /// token that claims the whole sphere would not be backed by its proofs,
+    /// so the narrower capabilities are claimed instead.
+    async fn push_capabilities(&self) -> Result<Vec<Capability<SphereReference, SphereAction>>> {
+        let whole_sphere = Capability {
+            with: With::Resource {
+                kind: Resource::Scoped(SphereReference {
+                    did: self.sphere_identity.clone(),
+                    path: None,
+                }),
+            },
+            can: SphereAction::Push,
+        };
+
+        let ucan = match self
+            .author
+            .require_authorization()?
+            .resolve_ucan(&self.store)
+            .await
+        {
+            Ok(ucan) => ucan,
+            Err(_) => return Ok(vec![whole_sphere]),
+        };
+
+        let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+        let proof_chain = ProofChain::from_ucan(ucan, &mut did_parser, &self.store).await?;
+        let mut scoped_capabilities = Vec::new();
+
+        for info in proof_chain.reduce_capabilities(&SPHERE_SEMANTICS) {
+            if !info.originators.contains(&self.sphere_identity) {
+                continue;
+            }
+
+            if info.capability.enables(&whole_sphere) {
+                return Ok(vec![whole_sphere]);
+            }
+
+            if let With::Resource {
+                kind: Resource::Scoped(scope),
+            } = &info.capability.with
+            {
+                if info.capability.can >= SphereAction::Push && scope.did == self.sphere_identity {
+                    scoped_capabilities.push(Capability {
+                        with: info.capability.with.clone(),
+                        can: SphereAction::Push,
+                    });
+                }
+            }
+        }
+
+        Ok(match scoped_capabilities.is_empty() {
+            true => vec![whole_sphere],
+            false => scoped_capabilities,
+        })
+    }
+
     pub async fn fetch(&self, params: &FetchParameters) -> Result<FetchResponse> {
+        FetchResponse::from_car(self.request_fetch(params).await?).await
+    }
+
+    /// Same as [Client::fetch], except that the blocks of the response are
+    /// loaded into the given [BlockStore] as they are read (rather than being
+    /// collected into a [Bundle] first); only the
+    /// header of the response is returned
+    pub async fn fetch_into<B: BlockStore>(
+        &self,
+        params: &FetchParameters,
+        store: &mut B,
+    ) -> Result<FetchResponseHeader> {
+        let mut car = CarReader::new(self.request_fetch(params).await?).await?;
+        let header = FetchResponse::read_car_header(&mut car).await?;
+
+        if let FetchResponseHeader::NewChanges { .. } = header {
+            let count = Bundle::load_car_into(&mut car, store).await?;
+            debug!("Client loaded {} fetched blocks", count);
+        }
+
+        Ok(header)
+    }
+
+    /// Make a fetch request, returning a reader over the CAR in its response
+    async fn request_fetch(&self, params: &FetchParameters) -> Result<impl AsyncRead + Unpin> {
         let url = Url::try_from(RouteUrl(&self.api_base, Route::Fetch, Some(params)))?;
         debug!("Client fetching blocks from {}", url);
         let capability = Capability {
             with: With::Resource {
                 kind: Resource::Scoped(SphereReference {
                     did: self.sphere_identity.clone(),
+                    path: None,
                 }),
             },
             can: SphereAction::Fetch,
@@ -188,63 +297,185 @@ where
         let (token, ucan_headers) = Self::make_bearer_token(
             &self.session.gateway_identity,
             &self.author,
-            &capability,
+            &[capability],
+            BEARER_TOKEN_LIFETIME,
             &self.store,
         )
         .await?;
 
-        let bytes = self
+        let response = self
             .client
             .get(url)
             .bearer_auth(token)
             .headers(ucan_headers)
             .send()
-            .await?
-            .bytes()
             .await?;
 
-        block_deserialize::<DagCborCodec, _>(&bytes)
+        match response.status() {
+            status if !status.is_success() => {
+                return Err(anyhow!("Gateway refused the fetch: {}", status))
+            }
+            _ => (),
+        };
+
+        // NOTE: Blocks are read out of the response as they arrive, but the
+        // wasm32 flavor of reqwest cannot stream response bodies
+        #[cfg(not(target_arch = "wasm32"))]
+        let reader = response
+            .bytes_stream()
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
+            .into_async_read();
+
+        #[cfg(target_arch = "wasm32")]
+        let reader = futures::io::Cursor::new(response.bytes().await?.to_vec());
+
+        Ok(reader)
     }
 
-    pub async fn push(&self, push_body: &PushBody) -> Result<PushResponse> {
+    pub async fn push(&self, push_body: PushBody) -> Result<PushResponse> {
+        let (header, blocks) = push_body.into_header_and_blocks()?;
+
+        self.push_stream(header, stream::iter(blocks.into_iter().map(Ok)))
+            .await
+    }
+
+    /// Same as [Client::push], except that the blocks of the push are read
+    /// from a [Stream] as they are sent, so that they never need to be held
+    /// in memory all at once (see
+    /// [noosphere_core::view::Sphere::stream_blocks_until_ancestor])
+    pub async fn push_stream<B>(&self, header: PushBodyHeader, blocks: B) -> Result<PushResponse>
+    where
+        B: Stream<Item = Result<(Cid, Vec<u8>)>> + BlockStoreSend + 'static,
+    {
         let url = Url::try_from(RouteUrl::<()>(&self.api_base, Route::Push, None))?;
+        debug!("Client pushing sphere {} to {}", header.sphere, url);
+        let capabilities = self.push_capabilities().await?;
+
+        let (token, ucan_headers) = Self::make_bearer_token(
+            &self.session.gateway_identity,
+            &self.author,
+            &capabilities,
+            BEARER_TOKEN_LIFETIME,
+            &self.store,
+        )
+        .await?;
+
+        let push_body_stream = header.into_car_stream(blocks)?;
+
+        // NOTE: The wasm32 flavor of reqwest cannot stream request bodies, and
+        // the native flavor requires a streamed body to be Sync; the CAR is
+        // encoded by a task of its own and handed over through a channel
+        #[cfg(not(target_arch = "wasm32"))]
+        let body = {
+            use futures::{pin_mut, SinkExt, StreamExt};
+
+            let (mut sender, receiver) = futures::channel::mpsc::channel(1);
+
+            tokio::spawn(async move {
+                pin_mut!(push_body_stream);
+
+                while let Some(frame) = push_body_stream.next().await {
+                    if sender.send(frame).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            Body::wrap_stream(receiver)
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        let body = Body::from(push_body_stream.try_concat().await?);
+
+        let response = self
+            .client
+            .put(url)
+            .bearer_auth(token)
+            .headers(ucan_headers)
+            .header("Content-Type", CAR_CONTENT_TYPE)
+            .body(body)
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::CONFLICT => {
+                return Err(anyhow!(
+                    "Gateway has revisions of the sphere that are not included in the pushed history; fetch them before pushing again"
+                ))
+            }
+            status if !status.is_success() => {
+                return Err(anyhow!("Gateway refused the push: {}", status))
+            }
+            _ => (),
+        };
+
+        let bytes = response.bytes().await?;
+
+        block_deserialize::<DagCborCodec, _>(bytes.as_ref())
+    }
+
+    pub async fn publish(&self, publish_body: &PublishBody) -> Result<PublishResponse> {
+        let url = Url::try_from(RouteUrl::<()>(&self.api_base, Route::Publish, None))?;
         debug!(
-            "Client pushing {} blocks for sphere {} to {}",
-            push_body.blocks.len(),
-            push_body.sphere,
-            url
+            "Client publishing revision {:?} of sphere {} to {}",
+            publish_body.version, publish_body.sphere, url
         );
         let capability = Capability {
             with: With::Resource {
                 kind: Resource::Scoped(SphereReference {
                     did: self.sphere_identity.clone(),
+                    path: None,
                 }),
             },
-            can: SphereAction::Push,
+            can: SphereAction::Publish,
         };
 
         let (token, ucan_headers) = Self::make_bearer_token(
             &self.session.gateway_identity,
             &self.author,
-            &capability,
+            &[capability.clone()],
+            BEARER_TOKEN_LIFETIME,
             &self.store,
         )
         .await?;
 
-        let (_, push_body_bytes) = block_serialize::<DagCborCodec, _>(push_body)?;
+        // The gateway uses the proof as the proof of the name record that it
+        // signs on our behalf, so unlike the bearer token it must live as long
+        // as the record does
+        let proof = match &publish_body.proof {
+            Some(proof) => proof.clone(),
+            None => Jwt(Self::make_bearer_token(
+                &self.session.gateway_identity,
+                &self.author,
+                &[capability],
+                NAME_RECORD_PROOF_LIFETIME,
+                &self.store,
+            )
+            .await?
+            .0),
+        };
 
-        let bytes = self
+        let (_, publish_body_bytes) = block_serialize::<DagCborCodec, _>(&PublishBody {
+            sphere: publish_body.sphere.clone(),
+            version: publish_body.version,
+            proof: Some(proof),
+        })?;
+
+        let response = self
             .client
-            .put(url)
+            .post(url)
             .bearer_auth(token)
             .headers(ucan_headers)
             .header("Content-Type", "application/octet-stream")
-            .body(Body::from(push_body_bytes))
+            .body(Body::from(publish_body_bytes))
             .send()
-            .await?
-            .bytes()
             .await?;
 
-        block_deserialize::<DagCborCodec, _>(bytes.as_ref())
+        match response.status() {
+            StatusCode::OK => (),
+            status => return Err(anyhow!("Gateway refused to publish: {}", status)),
+        };
+
+        block_deserialize::<DagCborCodec, _>(response.bytes().await?.as_ref())
     }
 }
@@ -2,12 +2,14 @@ use std::{fmt::Display, str::FromStr};
 
 use anyhow::{anyhow, Result};
 use cid::Cid;
+use futures::{io::AsyncRead, stream, Stream, StreamExt};
+use libipld_cbor::DagCborCodec;
 use noosphere_core::{
     authority::{SphereAction, SphereReference, SPHERE_SEMANTICS},
-    data::{Bundle, Did},
+    data::{car_stream, car_stream_from_blocks, Bundle, CarReader, Did, Jwt},
 };
-use noosphere_storage::{base64_decode, base64_encode};
-use serde::{Deserialize, Deserializer, Serialize};
+use noosphere_storage::{base64_decode, base64_encode, block_deserialize, block_serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use ucan::{
     capability::{Capability, Resource, With},
     chain::ProofChain,
@@ -77,8 +79,54 @@ pub enum FetchResponse {
     UpToDate,
 }
 
-/// The body payload expected by the "push" API route
+/// The root block of the CAR that a [FetchResponse] is sent as; the blocks of
+/// the response (if any) follow it in the CAR
 #[derive(Debug, Serialize, Deserialize)]
+pub enum FetchResponseHeader {
+    NewChanges { tip: Cid },
+    UpToDate,
+}
+
+impl FetchResponse {
+    /// Encode the response as a stream of CARv1 bytes; see [FetchResponseHeader]
+    pub fn into_car_stream(self) -> Result<impl Stream<Item = Result<Vec<u8>>>> {
+        let (header, blocks) = match self {
+            FetchResponse::NewChanges { tip, blocks } => {
+                (FetchResponseHeader::NewChanges { tip }, blocks)
+            }
+            FetchResponse::UpToDate => (FetchResponseHeader::UpToDate, Bundle::default()),
+        };
+
+        header_car_stream(&header, blocks)
+    }
+
+    /// Read the [FetchResponseHeader] from the front of a CAR that was
+    /// produced by [FetchResponse::into_car_stream]. The blocks of the
+    /// response (if any) may then be read incrementally from the [CarReader]
+    /// (for example, with [Bundle::load_car_into]).
+    pub async fn read_car_header<R: AsyncRead + Unpin>(
+        car: &mut CarReader<R>,
+    ) -> Result<FetchResponseHeader> {
+        read_car_header(car).await
+    }
+
+    /// Read a complete response out of a CAR that was produced by
+    /// [FetchResponse::into_car_stream]
+    pub async fn from_car<R: AsyncRead + Unpin>(reader: R) -> Result<FetchResponse> {
+        let mut car = CarReader::new(reader).await?;
+
+        Ok(match FetchResponse::read_car_header(&mut car).await? {
+            FetchResponseHeader::NewChanges { tip } => FetchResponse::NewChanges {
+                tip,
+                blocks: Bundle::try_from_car(&mut car).await?,
+            },
+            FetchResponseHeader::UpToDate => FetchResponse::UpToDate,
+        })
+    }
+}
+
+/// The body payload expected by the "push" API route
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PushBody {
     /// The DID of the local sphere whose revisions are being pushed
     pub sphere: String,
@@ -92,6 +140,107 @@ pub struct PushBody {
     pub blocks: Bundle,
 }
 
+/// The root block of the CAR that a [PushBody] is sent as; the blocks of the
+/// push follow it in the CAR, so that a receiver can decide whether to accept
+/// the push before reading any of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushBodyHeader {
+    pub sphere: String,
+    pub base: Option<Cid>,
+    pub tip: Cid,
+}
+
+impl PushBodyHeader {
+    /// Encode a push as a stream of CARv1 bytes, with this header as its root
+    /// followed by the given blocks as they are yielded
+    pub fn into_car_stream<B>(self, blocks: B) -> Result<impl Stream<Item = Result<Vec<u8>>>>
+    where
+        B: Stream<Item = Result<(Cid, Vec<u8>)>>,
+    {
+        let (header_cid, header_bytes) = block_serialize::<DagCborCodec, _>(&self)?;
+
+        car_stream_from_blocks(
+            &[header_cid],
+            stream::once(async move { Ok((header_cid, header_bytes)) }).chain(blocks),
+        )
+    }
+}
+
+impl PushBody {
+    /// Encode the push body as a stream of CARv1 bytes; see [PushBodyHeader]
+    pub fn into_car_stream(self) -> Result<impl Stream<Item = Result<Vec<u8>>>> {
+        let (header, blocks) = self.into_header_and_blocks()?;
+
+        header.into_car_stream(stream::iter(blocks.into_iter().map(Ok)))
+    }
+
+    /// Split the push body into its [PushBodyHeader] and its blocks
+    pub fn into_header_and_blocks(self) -> Result<(PushBodyHeader, Vec<(Cid, Vec<u8>)>)> {
+        Ok((
+            PushBodyHeader {
+                sphere: self.sphere,
+                base: self.base,
+                tip: self.tip,
+            },
+            self.blocks.into_blocks()?,
+        ))
+    }
+
+    /// Read the [PushBodyHeader] from the front of a CAR that was produced by
+    /// [PushBody::into_car_stream]. The blocks of the push may then be read
+    /// incrementally from the [CarReader] (for example, with
+    /// [Bundle::load_car_into]).
+    pub async fn read_car_header<R: AsyncRead + Unpin>(
+        car: &mut CarReader<R>,
+    ) -> Result<PushBodyHeader> {
+        read_car_header(car).await
+    }
+
+    /// Read a complete push body out of a CAR that was produced by
+    /// [PushBody::into_car_stream]
+    pub async fn from_car<R: AsyncRead + Unpin>(reader: R) -> Result<PushBody> {
+        let mut car = CarReader::new(reader).await?;
+        let header = PushBody::read_car_header(&mut car).await?;
+
+        Ok(PushBody {
+            sphere: header.sphere,
+            base: header.base,
+            tip: header.tip,
+            blocks: Bundle::try_from_car(&mut car).await?,
+        })
+    }
+}
+
+fn header_car_stream<T: Serialize>(
+    header: &T,
+    blocks: Bundle,
+) -> Result<impl Stream<Item = Result<Vec<u8>>>> {
+    let (header_cid, header_bytes) = block_serialize::<DagCborCodec, _>(header)?;
+
+    car_stream(
+        &[header_cid],
+        std::iter::once((header_cid, header_bytes)).chain(blocks.into_blocks()?),
+    )
+}
+
+async fn read_car_header<T, R>(car: &mut CarReader<R>) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: AsyncRead + Unpin,
+{
+    let root = *car
+        .roots()
+        .first()
+        .ok_or_else(|| anyhow!("CAR does not have a root"))?;
+
+    match car.next_block().await? {
+        Some((cid, bytes)) if cid == root => block_deserialize::<DagCborCodec, T>(&bytes),
+        _ => Err(anyhow!(
+            "Expected the first block of the CAR to be its root"
+        )),
+    }
+}
+
 /// The possible responses from the "push" API route
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PushResponse {
@@ -109,6 +258,45 @@ pub enum PushResponse {
     },
     /// The history was already known by the API host, so no changes were made
     NoChange,
+    /// The base of the pushed history is ahead of the API host's record of
+    /// the local sphere's history, so some revisions are missing. The push
+    /// should be retried with a payload that covers all of the revisions that
+    /// come after the one specified here
+    MissingRevisions {
+        /// The latest revision of the local sphere that is known to the API
+        /// host; this should be the base of the retried push
+        since: Cid,
+    },
+}
+
+/// The body payload expected by the "publish" API route
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublishBody {
+    /// The DID of the local sphere whose revision is being published
+    pub sphere: String,
+    /// The revision of the local sphere to publish; if None, the latest
+    /// revision known to the API host will be published
+    pub version: Option<Cid>,
+    /// A UCAN that delegates the capability to publish the local sphere to
+    /// the API host, which it uses as the proof of the name record that it
+    /// signs (so it must not expire before the record does); if None, one is
+    /// made by [crate::client::Client::publish]
+    #[serde(default)]
+    pub proof: Option<Jwt>,
+}
+
+/// The response from the "publish" API route
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishResponse {
+    /// The revision of the local sphere that was published
+    pub version: Cid,
+    /// The name record that was signed by the API host, in the form of a UCAN
+    /// JWT
+    pub record: Jwt,
+    /// Whether the API host put the name record into the Noosphere Name
+    /// System; if false, the record was signed but not propagated (e.g.,
+    /// because the API host is not configured to use a name system)
+    pub propagated: bool,
 }
 
 /// The response from the "identify" API route; this is a signed response that
@@ -182,6 +370,7 @@ impl IdentifyResponse {
             with: With::Resource {
                 kind: Resource::Scoped(SphereReference {
                     did: self.sphere_identity.to_string(),
+                    path: None,
                 }),
             },
             can: SphereAction::Push,
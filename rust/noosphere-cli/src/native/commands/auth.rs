@@ -1,16 +1,29 @@
-use std::{convert::TryFrom, str::FromStr};
+use std::{
+    convert::TryFrom,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{anyhow, Result};
 use cid::Cid;
+use noosphere::{
+    key::KeyStorage,
+    sphere::{AUTHORIZATION, USER_KEY_NAME},
+};
 use noosphere_core::{
-    authority::{SphereAction, SphereReference},
-    data::{CidKey, DelegationIpld, RevocationIpld},
+    authority::{
+        try_collect_proof_cids, Authorization, RevocationReport, SphereAction, SphereReference,
+        SPHERE_SEMANTICS,
+    },
+    data::{CidKey, DelegationIpld},
     view::{Sphere, SphereMutation, SPHERE_LIFETIME},
 };
+use noosphere_storage::KeyValueStore;
 use serde_json::{json, Value};
 use ucan::{
     builder::UcanBuilder,
     capability::{Capability, Resource, With},
+    chain::ProofChain,
     crypto::KeyMaterial,
     store::UcanJwtStore,
     Ucan,
@@ -20,7 +33,74 @@ use tokio_stream::StreamExt;
 
 use crate::native::workspace::Workspace;
 
-pub async fn auth_add(did: &str, name: Option<String>, workspace: &Workspace) -> Result<Cid> {
+/// A capability to delegate to an authorized key, optionally scoped to some
+/// part of the sphere. On the command line it is written as the action,
+/// optionally followed by a path (e.g., `push` or `push#/journal/`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegatedCapability {
+    pub action: SphereAction,
+    pub path: Option<String>,
+}
+
+impl FromStr for DelegatedCapability {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (action, path) = match value.split_once('#') {
+            Some((action, path)) => (action, Some(path)),
+            None => (value, None),
+        };
+
+        let action = match action.strip_prefix("sphere/") {
+            Some(_) => action.to_string(),
+            None => format!("sphere/{}", action),
+        };
+
+        Ok(DelegatedCapability {
+            action: SphereAction::try_from(action)?,
+            path: path.filter(|path| !path.is_empty()).map(String::from),
+        })
+    }
+}
+
+/// Options that constrain an authorization created by [auth_add]
+#[derive(Default, Debug, Clone)]
+pub struct DelegationOptions {
+    /// How long the authorization should be valid for; if not specified, the
+    /// authorization does not expire
+    pub expires_in: Option<Duration>,
+    /// When the authorization should become valid; if not specified, it is
+    /// valid immediately
+    pub not_before: Option<SystemTime>,
+    /// The capabilities to delegate; if none are specified, the authorized key
+    /// is given full authority over the sphere
+    pub capabilities: Vec<DelegatedCapability>,
+}
+
+fn to_unix_seconds(time: SystemTime) -> Result<u64> {
+    Ok(time.duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// Whether or not an authorization is currently usable, according to its
+/// time bounds
+fn authorization_status(ucan: &Ucan) -> Result<&'static str> {
+    let now = to_unix_seconds(SystemTime::now())?;
+
+    Ok(if ucan.is_expired() {
+        "expired"
+    } else if matches!(ucan.not_before(), Some(not_before) if *not_before > now) {
+        "pending"
+    } else {
+        "active"
+    })
+}
+
+pub async fn auth_add(
+    did: &str,
+    name: Option<String>,
+    options: DelegationOptions,
+    workspace: &Workspace,
+) -> Result<Cid> {
     let sphere_did = workspace.sphere_identity().await?;
     let mut db = workspace.db().await?;
 
@@ -77,22 +157,44 @@ You will be able to add a new one after the old one is revoked"#,
     let latest_sphere_cid = db.require_version(&sphere_did).await?;
     let authorization = workspace.authorization().await?;
 
-    let mut signable = UcanBuilder::default()
+    let capabilities = match options.capabilities.is_empty() {
+        true => vec![DelegatedCapability {
+            action: SphereAction::Authorize,
+            path: None,
+        }],
+        false => options.capabilities,
+    };
+
+    let expiration = match options.expires_in {
+        Some(expires_in) => to_unix_seconds(SystemTime::now() + expires_in)?,
+        None => SPHERE_LIFETIME,
+    };
+
+    let mut builder = UcanBuilder::default()
         .issued_by(&my_key)
         .for_audience(did)
-        .claiming_capability(&Capability {
+        .with_expiration(expiration)
+        .with_nonce();
+
+    if let Some(not_before) = options.not_before {
+        builder = builder.not_before(to_unix_seconds(not_before)?);
+    }
+
+    for capability in capabilities {
+        builder = builder.claiming_capability(&Capability {
             with: With::Resource {
                 kind: Resource::Scoped(SphereReference {
                     did: sphere_did.to_string(),
+                    path: capability.path,
                 }),
             },
-            can: SphereAction::Authorize,
-        })
-        .with_expiration(SPHERE_LIFETIME)
-        .with_nonce()
-        // TODO(ucan-wg/rs-ucan#32): Clean this up when we can use a CID as an authorization
-        // .witnessed_by(&authorization)
-        .build()?;
+            can: capability.action,
+        });
+    }
+
+    // TODO(ucan-wg/rs-ucan#32): Clean this up when we can use a CID as an authorization
+    // .witnessed_by(&authorization)
+    let mut signable = builder.build()?;
 
     signable
         .proofs
@@ -148,7 +250,7 @@ pub async fn auth_list(as_json: bool, workspace: &Workspace) -> Result<()> {
 
     let allowed_ucans = authorization.try_get_allowed_ucans().await?;
 
-    let mut authorizations: Vec<(String, String, Cid)> = Vec::new();
+    let mut authorizations: Vec<(String, Ucan, Cid)> = Vec::new();
     let mut delegation_stream = allowed_ucans.stream().await?;
     let mut max_name_length: usize = 7;
 
@@ -158,36 +260,58 @@ pub async fn auth_list(as_json: bool, workspace: &Workspace) -> Result<()> {
         let name = delegation.name.clone();
 
         max_name_length = max_name_length.max(name.len());
-        authorizations.push((
-            delegation.name.clone(),
-            ucan.audience().into(),
-            delegation.jwt,
-        ));
+        authorizations.push((delegation.name.clone(), ucan, delegation.jwt));
     }
 
+    let mut has_expired = false;
+
     if as_json {
-        let authorizations: Vec<Value> = authorizations
-            .into_iter()
-            .map(|(name, did, cid)| {
-                json!({
-                    "name": name,
-                    "did": did,
-                    "cid": cid.to_string()
-                })
-            })
-            .collect();
-        println!("{}", serde_json::to_string_pretty(&json!(authorizations))?);
+        let mut entries: Vec<Value> = Vec::new();
+
+        for (name, ucan, cid) in authorizations {
+            entries.push(json!({
+                "name": name,
+                "did": ucan.audience(),
+                "cid": cid.to_string(),
+                "status": authorization_status(&ucan)?,
+                "expires_at": ucan.expires_at(),
+                "not_before": ucan.not_before()
+            }));
+        }
+
+        println!("{}", serde_json::to_string_pretty(&json!(entries))?);
     } else {
-        println!("{:1$}  AUTHORIZED KEY", "NAME", max_name_length);
-        for (name, did, _) in authorizations {
-            println!("{:1$}  {did}", name, max_name_length);
+        println!("{:1$}  STATUS   AUTHORIZED KEY", "NAME", max_name_length);
+        for (name, ucan, _) in authorizations {
+            let status = authorization_status(&ucan)?;
+
+            has_expired = has_expired || status == "expired";
+
+            println!(
+                "{:1$}  {status:8} {}",
+                name,
+                max_name_length,
+                ucan.audience()
+            );
         }
     }
 
+    if has_expired {
+        println!(
+            r#"
+Some authorizations have expired; you can remove them with:
+
+  orb auth revoke <NAME>"#
+        );
+    }
+
     Ok(())
 }
 
-pub async fn auth_revoke(name: &str, workspace: &Workspace) -> Result<()> {
+/// Revoke the authorization with the given name, along with every
+/// authorization in the sphere that was derived from it (for example, one
+/// that the revoked key issued to another key of its own)
+pub async fn auth_revoke(name: &str, workspace: &Workspace) -> Result<RevocationReport> {
     let sphere_did = workspace.sphere_identity().await?;
     let mut db = workspace.db().await?;
 
@@ -206,30 +330,309 @@ pub async fn auth_revoke(name: &str, workspace: &Workspace) -> Result<()> {
     let allowed_ucans = authorization.try_get_allowed_ucans().await?;
 
     let mut delegation_stream = allowed_ucans.stream().await?;
+    let mut revoked_cid = None;
 
     while let Some(Ok((CidKey(cid), delegation))) = delegation_stream.next().await {
         if delegation.name == name {
-            let revocation = RevocationIpld::try_revoke(cid, &my_key).await?;
+            revoked_cid = Some(*cid);
+            break;
+        }
+    }
+
+    let revoked_cid =
+        revoked_cid.ok_or_else(|| anyhow!("There is no authorization named {:?}", name))?;
+
+    let mut mutation = SphereMutation::new(&my_did);
+    let report = sphere
+        .try_revoke_transitively(&revoked_cid, &my_key, &mut mutation)
+        .await?;
+
+    let mut revision = sphere.try_apply_mutation(&mutation).await?;
+    let ucan = workspace.authorization().await?;
+
+    let sphere_cid = revision.try_sign(&my_key, Some(&ucan)).await?;
+
+    db.set_version(&sphere_did, &sphere_cid).await?;
+
+    println!("The authorization named {:?} has been revoked", name);
+
+    if report.revoked.len() > 1 {
+        let max_name_length = report
+            .revoked
+            .iter()
+            .fold(7, |length, delegation| delegation.name.len().max(length));
+
+        println!("\nAuthorizations derived from it have also been revoked:\n");
+        println!("{:1$}  AUTHORIZED KEY", "NAME", max_name_length);
+
+        for delegation in report.revoked.iter().skip(1) {
+            println!(
+                "{:1$}  {}",
+                delegation.name, max_name_length, delegation.did
+            );
+        }
+    }
+
+    println!(
+        r#"
+IMPORTANT: You MUST sync to enable your gateway to recognize the revocation:
+
+  orb sync"#
+    );
+
+    Ok(report)
+}
+
+/// A summary of what became of the sphere's authorizations after the owner key
+/// was rotated. Each entry is a tuple of the authorization's name, the DID of
+/// the key it authorizes and the CID of the authorization.
+#[derive(Default, Debug)]
+pub struct RotationReport {
+    /// The new authorization of the owner key
+    pub owner: Cid,
+    /// Authorizations that did not depend on the previous owner's authority and
+    /// were left alone
+    pub unchanged: Vec<(String, String, Cid)>,
+    /// Authorizations that were re-delegated by the new owner key; the CID is
+    /// the CID of the replacement authorization
+    pub redelegated: Vec<(String, String, Cid)>,
+    /// Authorizations that could not be re-delegated (for example, because
+    /// they had already expired), and so did not survive the rotation
+    pub lost: Vec<(String, String, Cid)>,
+}
+
+/// Transfer ownership of the sphere to a different key, using the mnemonic
+/// that was produced when the sphere was created (the user is prompted for it
+/// on stdin if it is not given). Authorizations that were
+/// derived from the previous owner's authority are re-delegated by the new
+/// owner, and the workspace is updated to use the new key going forward.
+pub async fn auth_rotate(
+    key_name: &str,
+    create_key: bool,
+    mnemonic: Option<String>,
+    workspace: &Workspace,
+) -> Result<RotationReport> {
+    let sphere_did = workspace.sphere_identity().await?;
+    let mut db = workspace.db().await?;
+
+    let current_authorization = workspace.authorization().await?;
+    let current_authorization_cid = Cid::try_from(&current_authorization)?;
 
-            let mut mutation = SphereMutation::new(&my_did);
+    let next_owner_key = match (
+        create_key,
+        workspace.key_storage().read_key(key_name).await?,
+    ) {
+        (true, Some(_)) => {
+            return Err(anyhow!(
+                "A key named {:?} already exists; omit --create to use it as the new owner",
+                key_name
+            ))
+        }
+        (true, None) => workspace.key_storage().create_key(key_name).await?,
+        (false, Some(key)) => key,
+        (false, None) => {
+            return Err(anyhow!(
+                r#"No key named {:?} found
+To create a new key for the new owner as part of the rotation, add --create:
 
-            let key = CidKey(*cid);
+  orb auth rotate --create {}"#,
+                key_name,
+                key_name
+            ))
+        }
+    };
+    let next_owner_did = next_owner_key.get_did().await?;
 
-            mutation.allowed_ucans_mut().remove(&key);
-            mutation.revoked_ucans_mut().set(&key, &revocation);
+    let mnemonic = match mnemonic {
+        Some(mnemonic) => mnemonic,
+        None => {
+            println!(
+                r#"Ownership of the sphere will be transferred to the key {:?}:
 
-            let mut revision = sphere.try_apply_mutation(&mutation).await?;
-            let ucan = workspace.authorization().await?;
+  {}
 
-            let sphere_cid = revision.try_sign(&my_key, Some(&ucan)).await?;
+Type or paste the sequence of words you were given when the sphere was created and press enter:"#,
+                key_name, next_owner_did
+            );
 
-            db.set_version(&sphere_did, &sphere_cid).await?;
+            let mut mnemonic = String::new();
 
-            println!("The authorization named {:?} has been revoked", name);
+            std::io::stdin().read_line(&mut mnemonic)?;
 
-            return Ok(());
+            mnemonic
         }
+    };
+
+    let latest_sphere_cid = db.require_version(&sphere_did).await?;
+    let sphere = Sphere::at(&latest_sphere_cid, &db);
+
+    let context = workspace.sphere_context().await?;
+    let mut context = context.lock().await;
+    let did_parser = context.did_parser_mut();
+
+    let mut report = RotationReport::default();
+    let mut redelegations = Vec::new();
+
+    let allowed_ucans = sphere
+        .try_get_authority()
+        .await?
+        .try_get_allowed_ucans()
+        .await?;
+    let mut delegation_stream = allowed_ucans.stream().await?;
+
+    while let Some((CidKey(cid), delegation)) = delegation_stream.try_next().await? {
+        if cid == &current_authorization_cid {
+            continue;
+        }
+
+        let ucan = delegation.resolve_ucan(&db).await?;
+        let entry = (delegation.name.clone(), ucan.audience().to_string(), *cid);
+
+        if !try_collect_proof_cids(&ucan, &db)
+            .await?
+            .contains(&current_authorization_cid)
+        {
+            report.unchanged.push(entry);
+            continue;
+        }
+
+        if ucan.is_expired() {
+            report.lost.push(entry);
+            continue;
+        }
+
+        let proof_chain = match ProofChain::from_ucan(ucan.clone(), did_parser, &db).await {
+            Ok(proof_chain) => proof_chain,
+            Err(error) => {
+                warn!("Unable to verify authorization {}: {:?}", cid, error);
+                report.lost.push(entry);
+                continue;
+            }
+        };
+
+        let capabilities: Vec<Capability<SphereReference, SphereAction>> = proof_chain
+            .reduce_capabilities(&SPHERE_SEMANTICS)
+            .into_iter()
+            .filter(|info| info.originators.contains(sphere_did.as_str()))
+            .map(|info| info.capability)
+            .collect();
+
+        if capabilities.is_empty() {
+            report.lost.push(entry);
+            continue;
+        }
+
+        redelegations.push((entry, *ucan.expires_at(), *ucan.not_before(), capabilities));
+    }
+
+    let (sphere, next_authorization) = sphere
+        .try_change_owner(
+            mnemonic.trim(),
+            &next_owner_did,
+            &current_authorization,
+            did_parser,
+        )
+        .await?;
+
+    drop(context);
+
+    let next_authorization_cid = Cid::try_from(&next_authorization)?;
+    let next_authorization = Authorization::Cid(next_authorization_cid);
+
+    let mut mutation = SphereMutation::new(&next_owner_did);
+
+    // The previous owner's authorization has been revoked by the sphere, so it
+    // should no longer be listed among the allowed authorizations
+    mutation
+        .allowed_ucans_mut()
+        .remove(&CidKey(current_authorization_cid));
+
+    for ((name, did, cid), expiration, not_before, capabilities) in redelegations {
+        let mut builder = UcanBuilder::default()
+            .issued_by(&next_owner_key)
+            .for_audience(&did)
+            .with_expiration(expiration)
+            .with_nonce();
+
+        if let Some(not_before) = not_before {
+            builder = builder.not_before(not_before);
+        }
+
+        for capability in capabilities.iter() {
+            builder = builder.claiming_capability(capability);
+        }
+
+        let mut signable = builder.build()?;
+
+        // TODO(ucan-wg/rs-ucan#32): Clean this up when we can use a CID as an authorization
+        signable.proofs.push(next_authorization_cid.to_string());
+
+        let jwt = signable.sign().await?.encode()?;
+        let delegation = DelegationIpld::try_register(&name, &jwt, &mut db).await?;
+
+        mutation.allowed_ucans_mut().remove(&CidKey(cid));
+        mutation
+            .allowed_ucans_mut()
+            .set(&CidKey(delegation.jwt), &delegation);
+
+        report.redelegated.push((name, did, delegation.jwt));
+    }
+
+    let mut revision = sphere.try_apply_mutation(&mutation).await?;
+    let version_cid = revision
+        .try_sign(&next_owner_key, Some(&next_authorization))
+        .await?;
+
+    db.set_version(&sphere_did, &version_cid).await?;
+    db.set_key(USER_KEY_NAME, key_name.to_string()).await?;
+    db.set_key(AUTHORIZATION, next_authorization_cid).await?;
+
+    report.owner = next_authorization_cid;
+
+    println!(
+        "Ownership of the sphere has been transferred to {:?} ({})\n",
+        key_name, next_owner_did
+    );
+
+    let max_name_length = report
+        .unchanged
+        .iter()
+        .chain(report.redelegated.iter())
+        .chain(report.lost.iter())
+        .fold(7, |length, (name, _, _)| name.len().max(length));
+
+    println!(
+        "{:1$}  STATUS       AUTHORIZED KEY",
+        "NAME", max_name_length
+    );
+    for (status, entries) in [
+        ("unchanged", &report.unchanged),
+        ("re-delegated", &report.redelegated),
+        ("lost", &report.lost),
+    ] {
+        for (name, did, _) in entries {
+            println!("{:1$}  {status:12} {did}", name, max_name_length);
+        }
+    }
+
+    println!(
+        r#"
+IMPORTANT: You MUST sync to enable your gateway to recognize the new owner:
+
+  orb sync
+
+Re-delegated authorizations have new identities; other clients will need to
+re-join the sphere using them (see: orb auth list --as-json)"#
+    );
+
+    if !report.lost.is_empty() {
+        println!(
+            r#"
+Some authorizations could not be re-delegated; you can add them again with:
+
+  orb auth add <DID>"#
+        );
     }
 
-    Err(anyhow!("There is no authorization named {:?}", name))
+    Ok(report)
 }
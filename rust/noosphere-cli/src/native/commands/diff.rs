@@ -0,0 +1,367 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use libipld_cbor::DagCborCodec;
+use noosphere_core::{
+    authority::Author,
+    data::{BodyChunkIpld, ContentType, Header},
+};
+use noosphere_fs::SphereFs;
+use noosphere_storage::{BlockStore, KeyValueStore, MemoryStore};
+use similar::TextDiff;
+use tempfile::TempDir;
+use tokio::{fs, io::AsyncReadExt, process::Command};
+
+use crate::native::{commands::config::DIFFTOOL, workspace::Workspace};
+
+/// One side of a diff: the bytes of some content along with the metadata
+/// needed to render it in a way that makes sense for its content type
+struct DiffSide {
+    content_type: Option<ContentType>,
+    extension: Option<String>,
+    bytes: Vec<u8>,
+}
+
+/// The saved and local sides of the content at a slug whose local version
+/// differs from the saved one
+struct SlugDiff {
+    slug: String,
+    saved: Option<DiffSide>,
+    local: Option<DiffSide>,
+}
+
+/// Show the difference between files on disk and the versions of those files
+/// that were saved in the sphere (at the latest revision, or else at the
+/// specified base revision). If a difftool is configured, it is invoked once
+/// for each changed file; otherwise, a unified diff is printed for Subtext
+/// files and a short summary is printed for everything else.
+pub async fn diff(paths: Vec<PathBuf>, base: Option<Cid>, workspace: &Workspace) -> Result<()> {
+    let db = workspace.db().await?;
+    let sphere_identity = workspace.sphere_identity().await?;
+
+    let base = match base {
+        Some(base) => base,
+        None => db.require_version(&sphere_identity).await?,
+    };
+
+    let diffs = collect_diffs(&paths, &base, workspace).await?;
+
+    if diffs.is_empty() {
+        println!("No differences from sphere revision {}", base);
+        return Ok(());
+    }
+
+    let difftool: Option<String> = db.get_key(DIFFTOOL).await?;
+
+    for SlugDiff { slug, saved, local } in diffs {
+        match &difftool {
+            Some(difftool) => run_difftool(difftool, &slug, saved.as_ref(), local.as_ref()).await?,
+            None => print!(
+                "{}",
+                builtin_diff(&slug, &base, saved.as_ref(), local.as_ref())
+            ),
+        };
+    }
+
+    Ok(())
+}
+
+/// Gather both sides of every slug that differs between the workspace and the
+/// base revision (limited to the slugs of the given paths, if any). All of
+/// the given paths are checked before anything is returned, so that a path
+/// with neither saved nor local content fails the whole diff up front.
+async fn collect_diffs(
+    paths: &[PathBuf],
+    base: &Cid,
+    workspace: &Workspace,
+) -> Result<Vec<SlugDiff>> {
+    let db = workspace.db().await?;
+    let sphere_identity = workspace.sphere_identity().await?;
+
+    // NOTE: Reading the workspace stores the body of every local file in
+    // memory, so the local side of each diff is loaded from there rather than
+    // being read from disk a second time
+    let mut memory_store = MemoryStore::default();
+    let content = workspace.read_file_content(&mut memory_store).await?;
+
+    let sphere_fs = SphereFs::at(&sphere_identity, base, &Author::anonymous(), &db).await?;
+
+    let slugs: BTreeSet<String> = if paths.is_empty() {
+        let mut slugs = sphere_fs.list().await;
+        slugs.extend(content.matched.keys().cloned());
+        slugs
+    } else {
+        paths
+            .iter()
+            .map(|path| workspace.slug_for_path(path))
+            .collect::<Result<_>>()?
+    };
+
+    let mut diffs = Vec::new();
+
+    for slug in slugs {
+        if content.is_ignored(&slug) {
+            continue;
+        }
+
+        let local_file = content.matched.get(&slug);
+
+        let saved = match sphere_fs.read(&slug).await? {
+            Some(mut file) => {
+                if local_file.map(|file| file.cid) == Some(file.memo.body) {
+                    continue;
+                }
+
+                let mut bytes = Vec::new();
+                file.contents.read_to_end(&mut bytes).await?;
+
+                Some(DiffSide {
+                    content_type: file.memo.content_type(),
+                    extension: file
+                        .memo
+                        .get_first_header(&Header::FileExtension.to_string()),
+                    bytes,
+                })
+            }
+            None => None,
+        };
+
+        let local = match local_file {
+            Some(file) => {
+                let body = memory_store
+                    .load::<DagCborCodec, BodyChunkIpld>(&file.cid)
+                    .await?;
+
+                Some(DiffSide {
+                    content_type: Some(file.content_type.clone()),
+                    extension: file.extension.clone(),
+                    bytes: body.load_all_bytes(&memory_store).await?,
+                })
+            }
+            None => None,
+        };
+
+        if saved.is_none() && local.is_none() {
+            return Err(anyhow!("There is no saved or local content for {:?}", slug));
+        }
+
+        diffs.push(SlugDiff { slug, saved, local });
+    }
+
+    Ok(diffs)
+}
+
+/// Write both sides of the diff to temporary files and invoke the configured
+/// difftool with the paths to those files as its final two arguments
+async fn run_difftool(
+    difftool: &str,
+    slug: &str,
+    saved: Option<&DiffSide>,
+    local: Option<&DiffSide>,
+) -> Result<()> {
+    let mut parts = difftool.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow!("The configured difftool is empty"))?;
+
+    let temporary_directory = TempDir::new()?;
+    let file_name = slug.replace('/', "_");
+
+    let mut side_paths = Vec::new();
+
+    for (label, side) in [("saved", saved), ("local", local)] {
+        let path = match side.and_then(|side| side.extension.as_ref()) {
+            Some(extension) => temporary_directory
+                .path()
+                .join(format!("{label}.{file_name}.{extension}")),
+            None => temporary_directory
+                .path()
+                .join(format!("{label}.{file_name}")),
+        };
+
+        let bytes = side.map(|side| side.bytes.as_slice()).unwrap_or_default();
+
+        fs::write(&path, bytes).await?;
+        side_paths.push(path);
+    }
+
+    let status = Command::new(program)
+        .args(parts)
+        .args(&side_paths)
+        .status()
+        .await?;
+
+    // NOTE: Many difftools (including `diff` itself) exit with a non-zero
+    // status when they find differences, so we only treat a missing exit
+    // code (e.g., the tool was killed by a signal) as a failure
+    if status.code().is_none() {
+        return Err(anyhow!("Difftool {:?} exited unexpectedly", program));
+    }
+
+    Ok(())
+}
+
+/// Render a diff for one slug without the help of an external tool; Subtext
+/// is rendered as a unified text diff, and other content is summarized
+fn builtin_diff(
+    slug: &str,
+    base: &Cid,
+    saved: Option<&DiffSide>,
+    local: Option<&DiffSide>,
+) -> String {
+    let is_subtext = |side: Option<&DiffSide>| {
+        side.map(|side| side.content_type == Some(ContentType::Subtext))
+            .unwrap_or(true)
+    };
+
+    if !is_subtext(saved) || !is_subtext(local) {
+        let content_type = local
+            .or(saved)
+            .and_then(|side| side.content_type.as_ref())
+            .map(|content_type| content_type.to_string())
+            .unwrap_or_else(|| "Unknown".into());
+
+        let change = match (saved, local) {
+            (None, _) => "is new",
+            (_, None) => "was removed",
+            _ => "has changed",
+        };
+
+        return format!("Content at {} ({}) {}\n", slug, content_type, change);
+    }
+
+    let saved_text = saved
+        .map(|side| String::from_utf8_lossy(&side.bytes).to_string())
+        .unwrap_or_default();
+    let local_text = local
+        .map(|side| String::from_utf8_lossy(&side.bytes).to_string())
+        .unwrap_or_default();
+
+    let saved_header = match saved {
+        Some(_) => format!("{} ({})", slug, base),
+        None => String::from("/dev/null"),
+    };
+    let local_header = match local {
+        Some(_) => format!("{} (local)", slug),
+        None => String::from("/dev/null"),
+    };
+
+    TextDiff::from_lines(&saved_text, &local_text)
+        .unified_diff()
+        .context_radius(3)
+        .header(&saved_header, &local_header)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use libipld_cbor::DagCborCodec;
+    use noosphere_core::data::ContentType;
+    use noosphere_storage::derive_cid;
+    use tokio::fs;
+
+    use super::{builtin_diff, collect_diffs, DiffSide};
+    use crate::native::{
+        commands::{key, save::save_changes, sphere},
+        workspace::Workspace,
+    };
+
+    fn subtext(text: &str) -> DiffSide {
+        DiffSide {
+            content_type: Some(ContentType::Subtext),
+            extension: Some("subtext".into()),
+            bytes: text.as_bytes().to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn it_renders_a_unified_diff_against_the_saved_revision() {
+        let (workspace, _temporary_directories) = Workspace::temporary().unwrap();
+
+        key::key_create("FOO", &workspace).await.unwrap();
+        sphere::sphere_create("FOO", &workspace).await.unwrap();
+
+        let root = workspace.root_directory();
+
+        fs::write(root.join("hello.subtext"), "# Hello\nThis is a memo\n")
+            .await
+            .unwrap();
+
+        let revision = save_changes(&workspace).await.unwrap().unwrap().revision;
+
+        fs::write(
+            root.join("hello.subtext"),
+            "# Hello\nThis is a changed memo\n",
+        )
+        .await
+        .unwrap();
+
+        let diffs = collect_diffs(&[], &revision, &workspace).await.unwrap();
+
+        assert_eq!(diffs.len(), 1);
+
+        let hello = diffs.first().unwrap();
+        let diff = builtin_diff(
+            &hello.slug,
+            &revision,
+            hello.saved.as_ref(),
+            hello.local.as_ref(),
+        );
+
+        assert!(diff.contains(&format!("--- hello ({})", revision)));
+        assert!(diff.contains("+++ hello (local)"));
+        assert!(diff.contains("-This is a memo"));
+        assert!(diff.contains("+This is a changed memo"));
+        assert!(diff.contains(" # Hello"));
+    }
+
+    #[tokio::test]
+    async fn it_refuses_a_named_path_with_no_saved_or_local_content() {
+        let (workspace, _temporary_directories) = Workspace::temporary().unwrap();
+
+        key::key_create("FOO", &workspace).await.unwrap();
+        sphere::sphere_create("FOO", &workspace).await.unwrap();
+
+        let root = workspace.root_directory();
+
+        fs::write(root.join("hello.subtext"), "Hello\n")
+            .await
+            .unwrap();
+
+        let revision = save_changes(&workspace).await.unwrap().unwrap().revision;
+
+        fs::write(root.join("hello.subtext"), "Hello again\n")
+            .await
+            .unwrap();
+
+        let paths = vec![root.join("hello.subtext"), root.join("missing.subtext")];
+
+        assert!(collect_diffs(&paths, &revision, &workspace).await.is_err());
+    }
+
+    #[test]
+    fn it_diffs_new_subtext_against_nothing() {
+        let base = derive_cid::<DagCborCodec>(b"base");
+        let local = subtext("Brand new\n");
+
+        let diff = builtin_diff("new", &base, None, Some(&local));
+
+        assert!(diff.contains("--- /dev/null"));
+        assert!(diff.contains("+Brand new"));
+    }
+
+    #[test]
+    fn it_summarizes_changes_to_content_that_is_not_subtext() {
+        let base = derive_cid::<DagCborCodec>(b"base");
+        let saved = DiffSide {
+            content_type: Some(ContentType::Bytes),
+            extension: None,
+            bytes: vec![1, 2, 3],
+        };
+
+        let diff = builtin_diff("data", &base, Some(&saved), None);
+
+        assert_eq!(diff, "Content at data (raw/bytes) was removed\n");
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Result};
+use noosphere_core::{authority::SUPPORTED_KEYS, data::Did};
+use noosphere_ns::{resolve_petnames, server::HTTPClient, PetnameResolution};
+use serde_json::{json, Value};
+use ucan::crypto::did::DidParser;
+use url::Url;
+
+use crate::native::workspace::Workspace;
+
+/// Assign a petname to the sphere with the given identity in the local
+/// sphere's address book
+pub async fn follow(petname: &str, did: &Did, workspace: &Workspace) -> Result<()> {
+    let context = workspace.sphere_context().await?;
+    let mut context = context.lock().await;
+
+    if let Some(address) = context.list_petnames().await?.get(petname) {
+        if &address.identity == did {
+            println!("You are already following {} as @{}", did, petname);
+            return Ok(());
+        }
+
+        println!(
+            "Note: @{} used to refer to {}; it will now refer to {}",
+            petname, address.identity, did
+        );
+    }
+
+    context.set_petname(petname, did).await?;
+
+    println!(
+        r#"You are now following {} as @{}
+
+Sync to share the change with your gateway:
+
+  orb sync"#,
+        did, petname
+    );
+
+    Ok(())
+}
+
+/// Remove a petname from the local sphere's address book
+pub async fn unfollow(petname: &str, workspace: &Workspace) -> Result<Did> {
+    let context = workspace.sphere_context().await?;
+    let mut context = context.lock().await;
+
+    let did = context
+        .unset_petname(petname)
+        .await?
+        .ok_or_else(|| anyhow!("You are not following anyone as @{}", petname))?;
+
+    println!("You are no longer following {} as @{}", did, petname);
+
+    Ok(did)
+}
+
+/// Print the petnames in the local sphere's address book. If the URL of a name
+/// system API is given, each followed sphere is resolved through it first, and
+/// any newer (valid) records that are found are saved to the address book.
+pub async fn follows(as_json: bool, resolve: Option<Url>, workspace: &Workspace) -> Result<()> {
+    let context = workspace.sphere_context().await?;
+    let mut context = context.lock().await;
+
+    let mut petnames = context.list_petnames().await?;
+    let mut statuses = Vec::new();
+
+    if let Some(api_url) = resolve {
+        let client = HTTPClient::new(api_url).await?;
+        let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+        let resolved = resolve_petnames(&client, &petnames, context.db(), &mut did_parser).await?;
+        let mut updated_records = Vec::new();
+
+        for entry in resolved {
+            let status = match entry.resolution {
+                PetnameResolution::Updated(record) => {
+                    if let Some(address) = petnames.get_mut(&entry.petname) {
+                        address.last_known_record = Some(record.clone());
+                    }
+                    updated_records.push((entry.petname.clone(), record));
+                    "updated".to_string()
+                }
+                PetnameResolution::Unchanged => "unchanged".to_string(),
+                PetnameResolution::Outdated => "outdated".to_string(),
+                PetnameResolution::NotFound => "not found".to_string(),
+                PetnameResolution::Invalid(reason) => format!("invalid ({})", reason),
+            };
+
+            statuses.push((entry.petname, status));
+        }
+
+        context.set_petname_records(&updated_records).await?;
+    }
+
+    if as_json {
+        let mut entries: Vec<Value> = Vec::new();
+
+        for (petname, address) in petnames {
+            let status = statuses
+                .iter()
+                .find(|(name, _)| name == &petname)
+                .map(|(_, status)| status.clone());
+
+            entries.push(json!({
+                "petname": petname,
+                "did": address.identity,
+                "last_known_record": address.last_known_record,
+                "status": status
+            }));
+        }
+
+        println!("{}", serde_json::to_string_pretty(&json!(entries))?);
+        return Ok(());
+    }
+
+    if petnames.is_empty() {
+        println!(
+            "You are not following anyone yet; you can follow a sphere with:
+
+  orb follow <PETNAME> <DID>"
+        );
+        return Ok(());
+    }
+
+    let max_name_length = petnames
+        .keys()
+        .map(|petname| petname.len() + 1)
+        .max()
+        .unwrap_or_default()
+        .max(7);
+
+    println!("{:1$}  IDENTITY", "PETNAME", max_name_length);
+
+    for (petname, address) in petnames {
+        let status = statuses
+            .iter()
+            .find(|(name, _)| name == &petname)
+            .map(|(_, status)| format!("  [{}]", status))
+            .unwrap_or_default();
+
+        println!(
+            "{:1$}  {}{}",
+            format!("@{}", petname),
+            max_name_length,
+            address.identity,
+            status
+        );
+    }
+
+    Ok(())
+}
@@ -1,8 +1,12 @@
 pub mod auth;
 pub mod config;
+pub mod diff;
+pub mod follow;
 pub mod key;
+pub mod publish;
 pub mod save;
 pub mod serve;
 pub mod sphere;
 pub mod status;
 pub mod sync;
+pub mod watch;
@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use noosphere_api::data::{PublishBody, PublishResponse};
+
+use crate::native::workspace::Workspace;
+
+pub async fn publish(version: Option<Cid>, workspace: &Workspace) -> Result<PublishResponse> {
+    let context = workspace.sphere_context().await?;
+    let mut context = context.lock().await;
+
+    let sphere_identity = context.identity().clone();
+    let version = match version {
+        Some(version) => version,
+        None => context.db().require_version(&sphere_identity).await?,
+    };
+
+    let client = context.client().await?;
+
+    println!(
+        "Asking gateway {} to publish revision {}...",
+        client.session.gateway_identity, version
+    );
+
+    let response = client
+        .publish(&PublishBody {
+            sphere: sphere_identity.to_string(),
+            version: Some(version),
+            proof: None,
+        })
+        .await
+        .map_err(|error| {
+            anyhow!(
+                r#"{}
+
+Make sure that the revision has been synced to the gateway before publishing it:
+
+  orb sync"#,
+                error
+            )
+        })?;
+
+    if response.propagated {
+        println!(
+            r#"Revision {} of sphere {} has been published
+
+This is the name record that the gateway put into the Noosphere Name System:
+
+  {}"#,
+            response.version, sphere_identity, response.record
+        );
+    } else {
+        println!(
+            r#"The gateway signed a name record for revision {} of sphere {}, but it did not put the record into the Noosphere Name System (it may not be configured to use one), so the revision has not been published
+
+This is the name record that the gateway signed:
+
+  {}"#,
+            response.version, sphere_identity, response.record
+        );
+    }
+
+    Ok(response)
+}
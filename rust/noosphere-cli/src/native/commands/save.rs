@@ -7,9 +7,33 @@ use noosphere_storage::{BlockStore, MemoryStore};
 
 use crate::native::workspace::{FileReference, Workspace};
 
+/// A summary of a revision that was created by saving changes in the
+/// workspace to the sphere
+pub struct SaveSummary {
+    pub revision: Cid,
+    pub new: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
 /// TODO(#105): We may want to change this to take an optional list of paths to
 /// consider, and allow the user to rely on their shell for glob filtering
 pub async fn save(workspace: &Workspace) -> Result<()> {
+    match save_changes(workspace).await? {
+        Some(summary) => {
+            println!(
+                "Save complete!\nThe latest sphere revision is {}",
+                summary.revision
+            );
+            Ok(())
+        }
+        None => Err(anyhow!("No changes to save")),
+    }
+}
+
+/// Save any changes to files in the workspace to the sphere, producing a new
+/// revision; returns `None` if there were no changes to save
+pub async fn save_changes(workspace: &Workspace) -> Result<Option<SaveSummary>> {
     let mut memory_store = MemoryStore::default();
     let mut db = workspace.db().await?;
 
@@ -21,7 +45,7 @@ pub async fn save(workspace: &Workspace) -> Result<()> {
             (content, content_changes)
         }
         _ => {
-            return Err(anyhow!("No changes to save"));
+            return Ok(None);
         }
     };
 
@@ -47,18 +71,22 @@ pub async fn save(workspace: &Workspace) -> Result<()> {
         .iter()
         .chain(content_changes.updated.iter())
     {
-        if let Some(FileReference {
-            cid,
-            content_type,
-            extension,
-        }) = content.matched.get(slug)
-        {
+        if let Some(file_reference) = content.matched.get(slug) {
+            let FileReference {
+                cid,
+                content_type,
+                extension,
+                ..
+            } = file_reference;
+
             println!("Saving {}...", slug);
-            let headers = extension
-                .as_ref()
-                .map(|extension| vec![(Header::FileExtension.to_string(), extension.clone())]);
+            let mut headers = vec![(Header::FilePath.to_string(), file_reference.path_header())];
+
+            if let Some(extension) = extension {
+                headers.push((Header::FileExtension.to_string(), extension.clone()));
+            }
 
-            fs.link(slug, &content_type.to_string(), cid, headers)
+            fs.link(slug, &content_type.to_string(), cid, Some(headers))
                 .await?;
         }
     }
@@ -70,6 +98,10 @@ pub async fn save(workspace: &Workspace) -> Result<()> {
 
     let cid = fs.save(None).await?;
 
-    println!("Save complete!\nThe latest sphere revision is {}", cid);
-    Ok(())
+    Ok(Some(SaveSummary {
+        revision: cid,
+        new: content_changes.new.len(),
+        updated: content_changes.updated.len(),
+        removed: content_changes.removed.len(),
+    }))
 }
@@ -1,18 +1,37 @@
 use anyhow::Result;
 
 use std::net::{IpAddr, TcpListener};
+use std::sync::Arc;
 
 use url::Url;
 
 use crate::native::workspace::Workspace;
 
-use noosphere_gateway::{start_gateway, GatewayScope};
+use noosphere_gateway::{start_gateway, GatewayFeeds, GatewayNameSystem, GatewayScope};
+use noosphere_ns::{
+    server::HTTPClient, DHTConfig, Multiaddr, NameSystem, NameSystemClient, BOOTSTRAP_PEERS,
+};
+
+/// The ways that a gateway may be connected to the Noosphere Name System
+pub enum NameSystemOptions {
+    /// The gateway does not publish or resolve records
+    None,
+    /// The gateway talks to a name system node through its HTTP API
+    Api(Url),
+    /// The gateway runs its own name system node
+    Embedded {
+        listening_address: Multiaddr,
+        peers: Vec<Multiaddr>,
+    },
+}
 
 pub async fn serve(
     interface: IpAddr,
     port: u16,
     ipfs_api: Url,
+    name_system: NameSystemOptions,
     cors_origin: Option<Url>,
+    feeds: Option<GatewayFeeds>,
     workspace: &Workspace,
 ) -> Result<()> {
     let listener = TcpListener::bind(&(interface, port))?;
@@ -26,6 +45,8 @@ pub async fn serve(
         counterpart,
     };
 
+    let name_system = initialize_name_system(name_system, workspace).await?;
+
     let sphere_context = workspace.sphere_context().await?;
 
     start_gateway(
@@ -33,7 +54,43 @@ pub async fn serve(
         gateway_scope,
         sphere_context,
         ipfs_api,
+        name_system,
         cors_origin,
+        feeds,
     )
     .await
 }
+
+async fn initialize_name_system(
+    options: NameSystemOptions,
+    workspace: &Workspace,
+) -> Result<Option<GatewayNameSystem>> {
+    Ok(match options {
+        NameSystemOptions::None => None,
+        NameSystemOptions::Api(api_url) => {
+            let client = HTTPClient::new(api_url.clone()).await?;
+
+            println!("Using the name system API at {}", api_url);
+
+            Some(Arc::new(client))
+        }
+        NameSystemOptions::Embedded {
+            listening_address,
+            mut peers,
+        } => {
+            let key = workspace.key().await?;
+            let db = workspace.db().await?;
+            let name_system = NameSystem::new(&key, db, DHTConfig::default())?;
+
+            let address = name_system.listen(listening_address).await?;
+
+            peers.extend_from_slice(&BOOTSTRAP_PEERS[..]);
+            name_system.add_peers(peers).await?;
+            name_system.bootstrap().await?;
+
+            println!("Running a name system node at {}", address);
+
+            Some(Arc::new(name_system))
+        }
+    })
+}
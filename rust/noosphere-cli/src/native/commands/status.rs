@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use crate::native::Workspace;
+use crate::native::workspace::{Content, Workspace, SPHERE_IGNORE_FILE};
 use anyhow::Result;
 use noosphere_core::data::ContentType;
 use noosphere_storage::MemoryStore;
@@ -25,7 +25,7 @@ pub fn status_section(
     }
 }
 
-pub async fn status(workspace: &Workspace) -> Result<()> {
+pub async fn status(ignored: bool, workspace: &Workspace) -> Result<()> {
     let identity = workspace.sphere_identity().await?;
 
     println!("This sphere's identity is {identity}");
@@ -33,14 +33,23 @@ pub async fn status(workspace: &Workspace) -> Result<()> {
 
     let mut memory_store = MemoryStore::default();
 
-    let (_, mut changes) = match workspace
+    let (local_content, mut changes) = match workspace
         .get_file_content_changes(&mut memory_store)
         .await?
     {
         Some((content, content_changes)) if !content_changes.is_empty() => {
             (content, content_changes)
         }
-        _ => {
+        Some((content, _)) => {
+            println!("No new changes to sphere content!");
+
+            if ignored {
+                ignored_section(&content);
+            }
+
+            return Ok(());
+        }
+        None => {
             println!("No new changes to sphere content!");
             return Ok(());
         }
@@ -87,5 +96,25 @@ pub async fn status(workspace: &Workspace) -> Result<()> {
         );
     }
 
+    if ignored {
+        ignored_section(&local_content);
+    }
+
     Ok(())
 }
+
+fn ignored_section(content: &Content) {
+    if content.ignored_paths.is_empty() {
+        println!("\nNo files or directories are being ignored");
+        return;
+    }
+
+    println!(
+        "\nIgnored files and directories (see {}):",
+        SPHERE_IGNORE_FILE
+    );
+
+    for path in content.ignored_paths.iter() {
+        println!("  {}", path.display());
+    }
+}
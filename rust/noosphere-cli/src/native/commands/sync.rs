@@ -1,9 +1,39 @@
+use std::sync::Arc;
+
 use crate::native::workspace::Workspace;
 use anyhow::{anyhow, Result};
-use noosphere_core::tracing::initialize_tracing;
+use cid::Cid;
+use clap::ValueEnum;
+use noosphere_core::{
+    tracing::initialize_tracing,
+    view::{ConflictResolution, ResolvedConflict},
+};
 use noosphere_storage::MemoryStore;
 
-pub async fn sync(workspace: &Workspace) -> Result<()> {
+/// How conflicting changes to the same file should be resolved when syncing
+/// without clobbering local changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConflictStrategy {
+    /// Keep the local version of the file
+    KeepMine,
+    /// Keep the version of the file that was synced from elsewhere
+    KeepTheirs,
+    /// Keep the version that was synced from elsewhere, and move the local
+    /// version to a new file alongside it
+    KeepBoth,
+}
+
+impl From<ConflictStrategy> for ConflictResolution {
+    fn from(strategy: ConflictStrategy) -> Self {
+        match strategy {
+            ConflictStrategy::KeepMine => ConflictResolution::KeepMine,
+            ConflictStrategy::KeepTheirs => ConflictResolution::KeepTheirs,
+            ConflictStrategy::KeepBoth => ConflictResolution::KeepBoth,
+        }
+    }
+}
+
+pub async fn sync(no_clobber: Option<ConflictStrategy>, workspace: &Workspace) -> Result<()> {
     initialize_tracing();
 
     let mut memory_store = MemoryStore::default();
@@ -20,12 +50,25 @@ pub async fn sync(workspace: &Workspace) -> Result<()> {
         _ => (),
     };
 
-    {
+    let resolved_conflicts = {
         let context = workspace.sphere_context().await?;
         let mut context = context.lock().await;
 
-        context.sync().await?;
-    }
+        match no_clobber {
+            Some(strategy) => {
+                let resolution = ConflictResolution::from(strategy);
+                context
+                    .sync_with_conflict_resolver(Arc::new(move |_| resolution))
+                    .await?
+            }
+            None => {
+                context.sync().await?;
+                Vec::new()
+            }
+        }
+    };
+
+    report_conflicts(&resolved_conflicts);
 
     println!("Sync complete, rendering updated workspace...");
 
@@ -35,3 +78,41 @@ pub async fn sync(workspace: &Workspace) -> Result<()> {
 
     Ok(())
 }
+
+fn report_conflicts(resolved_conflicts: &[ResolvedConflict]) {
+    if resolved_conflicts.is_empty() {
+        return;
+    }
+
+    println!(
+        "{} file(s) were changed both locally and elsewhere:\n",
+        resolved_conflicts.len()
+    );
+
+    let display = |cid: &Option<Cid>| match cid {
+        Some(cid) => cid.to_string(),
+        None => String::from("(removed)"),
+    };
+
+    for ResolvedConflict {
+        conflict,
+        resolution,
+        kept_mine_as,
+    } in resolved_conflicts
+    {
+        println!("  {}", conflict.slug);
+        println!("    mine:   {}", display(&conflict.mine));
+        println!("    theirs: {}", display(&conflict.theirs));
+
+        match (resolution, kept_mine_as) {
+            (ConflictResolution::KeepMine, _) => println!("    kept mine"),
+            (ConflictResolution::KeepTheirs, _) => println!("    kept theirs"),
+            (ConflictResolution::KeepBoth, Some(slug)) => {
+                println!("    kept theirs; mine was kept as {:?}", slug)
+            }
+            (ConflictResolution::KeepBoth, None) => println!("    kept the remaining version"),
+        };
+
+        println!();
+    }
+}
@@ -0,0 +1,190 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
+use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Watcher};
+use pathdiff::diff_paths;
+use tokio::{
+    sync::mpsc::unbounded_channel,
+    time::{sleep_until, Instant},
+};
+
+use crate::native::workspace::Workspace;
+
+use super::{
+    save::{save_changes, SaveSummary},
+    sync::{sync, ConflictStrategy},
+};
+
+/// The longest that a failed sync will be retried after
+pub const MAX_SYNC_BACKOFF: Duration = Duration::from_secs(300);
+
+/// When (if ever) the workspace should be synced with the gateway while it
+/// is being watched
+pub struct WatchSyncOptions {
+    /// Sync after every revision that is saved
+    pub after_save: bool,
+    /// Sync at this interval, whether or not anything was saved
+    pub interval: Option<Duration>,
+    /// How conflicts are resolved when syncing; see [sync]
+    pub no_clobber: Option<ConflictStrategy>,
+}
+
+/// Tracks consecutive failures to sync, so that an unreachable gateway is
+/// retried with an exponentially increasing delay
+#[derive(Default)]
+pub struct SyncBackoff {
+    failures: u32,
+}
+
+impl SyncBackoff {
+    /// Record a failure, and get the delay before the next attempt
+    pub fn fail(&mut self) -> Duration {
+        let delay = Duration::from_secs(1 << self.failures.min(16)).min(MAX_SYNC_BACKOFF);
+        self.failures = self.failures.saturating_add(1);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+/// Watch the workspace for changes to files, and save them to the sphere once
+/// they have settled for the debounce period. If sync options are given, the
+/// sphere is also synced with the gateway. Runs until interrupted.
+pub async fn watch(
+    debounce: Duration,
+    sync_options: Option<WatchSyncOptions>,
+    workspace: &Workspace,
+) -> Result<()> {
+    let root_directory = workspace.root_directory().to_path_buf();
+    let ignore_rules = workspace.get_ignored_patterns().await?;
+    let (tx, mut rx) = unbounded_channel::<PathBuf>();
+
+    let mut watcher = recommended_watcher(move |result: notify::Result<Event>| match result {
+        Ok(event) => {
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+
+            for path in event.paths {
+                let _ = tx.send(path);
+            }
+        }
+        Err(error) => warn!("Error while watching the workspace: {}", error),
+    })?;
+
+    watcher.watch(&root_directory, RecursiveMode::Recursive)?;
+
+    println!(
+        "Watching {} for changes (press Ctrl+C to stop)...",
+        root_directory.display()
+    );
+
+    let mut save_due: Option<Instant> = None;
+    let mut sync_due: Option<Instant> = sync_options
+        .as_ref()
+        .and_then(|options| options.interval)
+        .map(|interval| Instant::now() + interval);
+    let mut backoff = SyncBackoff::default();
+
+    loop {
+        tokio::select! {
+            path = rx.recv() => {
+                let path = match path {
+                    Some(path) => path,
+                    None => break,
+                };
+
+                // Changes to the .sphere directory (among other things) are
+                // made by saving and syncing, so they must not cause a save
+                let is_content = match diff_paths(&path, &root_directory) {
+                    Some(relative_path) => !ignore_rules.is_builtin_ignored_path(&relative_path),
+                    None => false,
+                };
+
+                if is_content {
+                    save_due = Some(Instant::now() + debounce);
+                }
+            }
+            _ = sleep_until(save_due.unwrap_or_else(Instant::now)), if save_due.is_some() => {
+                save_due = None;
+
+                match save_changes(workspace).await {
+                    Ok(Some(summary)) => {
+                        report_revision(&summary);
+
+                        if let Some(WatchSyncOptions { after_save: true, .. }) = &sync_options {
+                            sync_due = Some(Instant::now());
+                        }
+                    }
+                    Ok(None) => debug!("No changes to save"),
+                    Err(error) => println!("Failed to save changes: {}", error),
+                }
+            }
+            _ = sleep_until(sync_due.unwrap_or_else(Instant::now)), if sync_due.is_some() => {
+                let options = match &sync_options {
+                    Some(options) => options,
+                    None => {
+                        sync_due = None;
+                        continue;
+                    }
+                };
+
+                sync_due = match sync(options.no_clobber, workspace).await {
+                    Ok(_) => {
+                        backoff.reset();
+                        options.interval.map(|interval| Instant::now() + interval)
+                    }
+                    Err(error) => {
+                        let delay = backoff.fail();
+                        println!(
+                            "Failed to sync with the gateway ({}); retrying in {}s",
+                            error,
+                            delay.as_secs()
+                        );
+                        Some(Instant::now() + delay)
+                    }
+                };
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    println!("Stopped watching {}", root_directory.display());
+
+    Ok(())
+}
+
+fn report_revision(summary: &SaveSummary) {
+    println!(
+        "Saved revision {} ({} new, {} updated, {} removed)",
+        summary.revision, summary.new, summary.updated, summary.removed
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{SyncBackoff, MAX_SYNC_BACKOFF};
+
+    #[test]
+    fn it_backs_off_exponentially_up_to_a_limit() {
+        let mut backoff = SyncBackoff::default();
+
+        assert_eq!(backoff.fail(), Duration::from_secs(1));
+        assert_eq!(backoff.fail(), Duration::from_secs(2));
+        assert_eq!(backoff.fail(), Duration::from_secs(4));
+
+        for _ in 0..32 {
+            backoff.fail();
+        }
+
+        assert_eq!(backoff.fail(), MAX_SYNC_BACKOFF);
+
+        backoff.reset();
+
+        assert_eq!(backoff.fail(), Duration::from_secs(1));
+    }
+}
@@ -4,10 +4,13 @@ pub mod workspace;
 use anyhow::Result;
 
 use noosphere_core::data::Did;
+use noosphere_gateway::GatewayFeeds;
+use noosphere_ns::Multiaddr;
 use std::ffi::OsString;
 
 use std::net::IpAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use cid::Cid;
 
@@ -24,12 +27,18 @@ use workspace::Workspace;
 use self::commands::auth::auth_add;
 use self::commands::auth::auth_list;
 use self::commands::auth::auth_revoke;
+use self::commands::auth::auth_rotate;
+use self::commands::auth::{DelegatedCapability, DelegationOptions};
 use self::commands::config::config_get;
 use self::commands::config::config_set;
+use self::commands::diff::diff;
+use self::commands::follow::{follow, follows, unfollow};
+use self::commands::publish::publish;
 use self::commands::save::save;
-use self::commands::serve::serve;
+use self::commands::serve::{serve, NameSystemOptions};
 use self::commands::status::status;
-use self::commands::sync::sync;
+use self::commands::sync::{sync, ConflictStrategy};
+use self::commands::watch::{watch, WatchSyncOptions};
 
 #[derive(Debug, Parser)]
 #[clap(name = "orb")]
@@ -88,13 +97,47 @@ pub enum OrbCommand {
         /// The port that the gateway should listen on
         #[clap(short, long, default_value = "4433")]
         port: u16,
+
+        /// URL of a Noosphere Name System API (such as one served by `orb-ns
+        /// run`) to publish and resolve records through
+        #[clap(long, conflicts_with = "name_system_listen")]
+        name_system_api: Option<Url>,
+
+        /// Run a Noosphere Name System node within the gateway to publish and
+        /// resolve records through, listening for peers on this address
+        #[clap(long)]
+        name_system_listen: Option<Multiaddr>,
+
+        /// A peer for the embedded name system node to bootstrap from, in
+        /// addition to the default bootstrap peers (may be specified multiple
+        /// times)
+        #[clap(long = "name-system-peer", requires = "name_system_listen")]
+        name_system_peers: Vec<Multiaddr>,
+
+        /// A directory to write the counterpart sphere's static HTML site,
+        /// along with Atom and JSON feeds of its changes, to whenever it is
+        /// published
+        #[clap(long)]
+        feed_directory: Option<PathBuf>,
+
+        /// The URL that the counterpart sphere's static site is served from,
+        /// used to make the permalinks in its feeds absolute
+        #[clap(long, requires = "feed_directory")]
+        feed_base_url: Option<Url>,
     },
 
     /// Show details about files in the sphere directory that have changed since
     /// the last time the sphere was saved
-    Status,
+    Status {
+        /// Also list the files and directories that were skipped because of
+        /// patterns in a .sphereignore file
+        #[clap(long)]
+        ignored: bool,
+    },
 
-    /// If a difftool is configured, show a diff between files on disk and saved versions in the sphere
+    /// Show a diff between files on disk and saved versions in the sphere;
+    /// if a difftool is configured it will be used, otherwise a unified diff
+    /// will be printed for Subtext files
     Diff {
         /// The specific file or files to show a diff of
         paths: Vec<PathBuf>,
@@ -107,13 +150,49 @@ pub enum OrbCommand {
     /// Saves changed files to a sphere, creating and signing a new revision in
     /// the process; does nothing if there have been no changes to the files
     /// since the last revision
-    Save,
+    Save {
+        /// Keep running, and save again whenever files change (equivalent to
+        /// `orb watch` without syncing)
+        #[clap(long)]
+        watch: bool,
+    },
 
     /// Synchronizes the local sphere with the copy in a configured gateway;
-    /// note that this is a "conflict-free" sync that may cause local changes
-    /// to be overwritten in cases where two or more clients have made changes
-    /// to the same files
-    Sync,
+    /// note that by default this is a "conflict-free" sync that may cause
+    /// local changes to be overwritten in cases where two or more clients have
+    /// made changes to the same files
+    Sync {
+        /// Merge local changes with changes made elsewhere instead of
+        /// overwriting them, and report any files that were changed in both
+        /// places; conflicts are resolved with the given strategy (keep-both,
+        /// if none is specified)
+        #[clap(long, value_enum, num_args = 0..=1, default_missing_value = "keep-both")]
+        no_clobber: Option<ConflictStrategy>,
+    },
+
+    /// Watch the sphere directory for changes to files, and save them to the
+    /// sphere automatically once they settle; optionally, also sync with the
+    /// configured gateway
+    Watch {
+        /// How long to wait after the most recent change to a file before
+        /// saving (e.g., "500ms" or "2s")
+        #[clap(long, default_value = "1s", value_parser = humantime::parse_duration)]
+        debounce: std::time::Duration,
+
+        /// Sync with the gateway after every save
+        #[clap(long)]
+        sync: bool,
+
+        /// Sync with the gateway at this interval (e.g., "5m"), even if there
+        /// were no changes to save
+        #[clap(long, value_parser = humantime::parse_duration)]
+        sync_interval: Option<std::time::Duration>,
+
+        /// Merge local changes with changes made elsewhere when syncing; see
+        /// `orb sync --no-clobber`
+        #[clap(long, value_enum, num_args = 0..=1, default_missing_value = "keep-both")]
+        no_clobber: Option<ConflictStrategy>,
+    },
 
     /// Tell a configured gateway to update the published version of the sphere
     /// in the Noosphere name system
@@ -123,6 +202,36 @@ pub enum OrbCommand {
         #[clap(value_name = "CID")]
         version: Option<Cid>,
     },
+
+    /// Follow another sphere by assigning it a petname in the local sphere's
+    /// address book; the sphere can then be referred to as @<PETNAME>
+    Follow {
+        /// The petname to refer to the sphere by
+        petname: String,
+
+        /// The identity (as a DID) of the sphere to follow
+        did: Did,
+    },
+
+    /// Stop following the sphere assigned to a petname, removing the petname
+    /// from the local sphere's address book
+    Unfollow {
+        /// The petname of the sphere to stop following
+        petname: String,
+    },
+
+    /// Print the petname and DID of every sphere that is being followed
+    Follows {
+        /// Output the list of followed spheres as formatted JSON
+        #[clap(short = 'j', long)]
+        as_json: bool,
+
+        /// The URL of a Noosphere Name System API to resolve the followed
+        /// spheres through; newly resolved records are saved to the local
+        /// sphere's address book
+        #[clap(short, long, value_name = "NAME_SYSTEM_API")]
+        resolve: Option<Url>,
+    },
 }
 
 /// Read and manage configuration values for a local sphere
@@ -243,9 +352,27 @@ pub enum AuthCommand {
         /// one will be assigned
         #[clap(short = 'n', long)]
         name: Option<String>,
+
+        /// How long the authorization should be valid for (e.g., "30days" or
+        /// "12h"); if not specified, the authorization does not expire
+        #[clap(long, value_parser = humantime::parse_duration)]
+        expires_in: Option<std::time::Duration>,
+
+        /// The time at which the authorization becomes valid, as an RFC 3339
+        /// timestamp (e.g., "2023-01-01 09:00:00"); if not specified, the
+        /// authorization is valid immediately
+        #[clap(long, value_parser = humantime::parse_rfc3339_weak)]
+        not_before: Option<std::time::SystemTime>,
+
+        /// A capability to delegate, optionally scoped to a path within the
+        /// sphere (e.g., "push" or "push#/journal/"); may be specified more
+        /// than once. If no capabilities are specified, the key is given full
+        /// authority over the sphere
+        #[clap(long = "capability")]
+        capabilities: Vec<DelegatedCapability>,
     },
 
-    /// Print the name and DID for all keys that the owner has authorized
+    /// Print the name, status and DID for all keys that the owner has authorized
     /// to work on this sphere
     List {
         /// Output the list of authorized keys as formatted JSON
@@ -259,8 +386,19 @@ pub enum AuthCommand {
         name: String,
     },
 
-    /// Rotate key authority from one key to another
-    Rotate {},
+    /// Transfer ownership of the sphere to a different key using the
+    /// mnemonic that was given when the sphere was created; existing
+    /// authorizations are re-delegated by the new owner. You will be prompted
+    /// to enter the mnemonic, which may also be piped in through stdin
+    Rotate {
+        /// The name of the key that should become the new owner
+        key: String,
+
+        /// Create a new key with the given name, rather than using an
+        /// existing key from key storage
+        #[clap(short, long)]
+        create: bool,
+    },
 }
 
 pub async fn main() -> Result<()> {
@@ -299,25 +437,100 @@ pub async fn main() -> Result<()> {
                 sphere_join(&local_key, authorization, &id, &workspace).await?;
             }
         },
-        OrbCommand::Status => status(&workspace).await?,
-        OrbCommand::Diff { paths: _, base: _ } => todo!(),
-        OrbCommand::Save => save(&workspace).await?,
-        OrbCommand::Sync => sync(&workspace).await?,
-        OrbCommand::Publish { version: _ } => todo!(),
+        OrbCommand::Status { ignored } => status(ignored, &workspace).await?,
+        OrbCommand::Diff { paths, base } => diff(paths, base, &workspace).await?,
+        OrbCommand::Save { watch: false } => save(&workspace).await?,
+        OrbCommand::Save { watch: true } => watch(Duration::from_secs(1), None, &workspace).await?,
+        OrbCommand::Sync { no_clobber } => sync(no_clobber, &workspace).await?,
+        OrbCommand::Watch {
+            debounce,
+            sync,
+            sync_interval,
+            no_clobber,
+        } => {
+            let sync_options = match sync || sync_interval.is_some() {
+                true => Some(WatchSyncOptions {
+                    after_save: sync,
+                    interval: sync_interval,
+                    no_clobber,
+                }),
+                false => None,
+            };
+
+            watch(debounce, sync_options, &workspace).await?
+        }
+        OrbCommand::Publish { version } => {
+            publish(version, &workspace).await?;
+        }
+        OrbCommand::Follow { petname, did } => follow(&petname, &did, &workspace).await?,
+        OrbCommand::Unfollow { petname } => {
+            unfollow(&petname, &workspace).await?;
+        }
+        OrbCommand::Follows { as_json, resolve } => follows(as_json, resolve, &workspace).await?,
         OrbCommand::Auth { command } => match command {
-            AuthCommand::Add { did, name } => {
-                auth_add(&did, name, &workspace).await?;
+            AuthCommand::Add {
+                did,
+                name,
+                expires_in,
+                not_before,
+                capabilities,
+            } => {
+                auth_add(
+                    &did,
+                    name,
+                    DelegationOptions {
+                        expires_in,
+                        not_before,
+                        capabilities,
+                    },
+                    &workspace,
+                )
+                .await?;
             }
             AuthCommand::List { as_json } => auth_list(as_json, &workspace).await?,
-            AuthCommand::Revoke { name } => auth_revoke(&name, &workspace).await?,
-            AuthCommand::Rotate {} => todo!(),
+            AuthCommand::Revoke { name } => {
+                auth_revoke(&name, &workspace).await?;
+            }
+            AuthCommand::Rotate { key, create } => {
+                auth_rotate(&key, create, None, &workspace).await?;
+            }
         },
         OrbCommand::Serve {
             cors_origin,
             ipfs_api,
             interface,
             port,
-        } => serve(interface, port, ipfs_api, cors_origin, &workspace).await?,
+            name_system_api,
+            name_system_listen,
+            name_system_peers,
+            feed_directory,
+            feed_base_url,
+        } => {
+            let name_system = match (name_system_api, name_system_listen) {
+                (Some(api_url), _) => NameSystemOptions::Api(api_url),
+                (None, Some(listening_address)) => NameSystemOptions::Embedded {
+                    listening_address,
+                    peers: name_system_peers,
+                },
+                (None, None) => NameSystemOptions::None,
+            };
+
+            let feeds = feed_directory.map(|directory| GatewayFeeds {
+                directory,
+                base_url: feed_base_url,
+            });
+
+            serve(
+                interface,
+                port,
+                ipfs_api,
+                name_system,
+                cors_origin,
+                feeds,
+                &workspace,
+            )
+            .await?
+        }
     };
 
     Ok(())
@@ -1,6 +1,9 @@
 use anyhow::{anyhow, Result};
 use cid::Cid;
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
 use libipld_cbor::DagCborCodec;
 use noosphere_core::{
     authority::{Author, Authorization},
@@ -8,13 +11,11 @@ use noosphere_core::{
     view::Sphere,
 };
 use noosphere_fs::SphereFs;
-use noosphere_storage::{
-    BlockStore, SphereDb, KeyValueStore, NativeStorage, Store,
-};
+use noosphere_storage::{BlockStore, KeyValueStore, NativeStorage, SphereDb, Store};
 use pathdiff::diff_paths;
 use std::{
     collections::{BTreeMap, BTreeSet},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     str::FromStr,
     sync::Arc,
 };
@@ -36,6 +37,17 @@ use tempfile::TempDir;
 
 const SPHERE_DIRECTORY: &str = ".sphere";
 const NOOSPHERE_DIRECTORY: &str = ".noosphere";
+
+/// The name of a file containing gitignore-style patterns for paths that
+/// should not be considered part of the sphere's content. It may appear at
+/// the root of the workspace as well as in any directory within it; patterns
+/// are relative to the directory the file is in.
+pub const SPHERE_IGNORE_FILE: &str = ".sphereignore";
+
+/// Patterns that are always ignored, regardless of any [SPHERE_IGNORE_FILE]:
+/// petname links to other spheres, and dotfiles (which includes the `.sphere`
+/// directory itself)
+const BUILTIN_IGNORE_PATTERNS: &[&str] = &["@*", ".*"];
 // const STORAGE_DIRECTORY: &str = "storage";
 
 pub type CliSphereContext = SphereContext<Ed25519KeyMaterial, NativeStorage>;
@@ -59,13 +71,116 @@ impl ContentChanges {
 #[derive(Default)]
 pub struct Content {
     pub matched: BTreeMap<String, FileReference>,
+    /// The slugs of files that were skipped because they are ignored
     pub ignored: BTreeSet<String>,
+    /// The paths (relative to the workspace root) of files and directories
+    /// that were skipped because of patterns in a [SPHERE_IGNORE_FILE]
+    pub ignored_paths: BTreeSet<PathBuf>,
 }
 
 impl Content {
     pub fn is_empty(&self) -> bool {
         self.matched.is_empty()
     }
+
+    /// Whether the slug corresponds to an ignored file, or to a file inside an
+    /// ignored directory (and not also to some file that was not ignored)
+    pub fn is_ignored(&self, slug: &str) -> bool {
+        if self.matched.contains_key(slug) {
+            return false;
+        }
+
+        self.ignored.contains(slug)
+            || self
+                .ignored_paths
+                .iter()
+                .any(|path| slug.starts_with(&format!("{}/", path.to_string_lossy())))
+    }
+}
+
+/// Derive the slug that a file is saved to in the sphere from its path relative
+/// to the workspace root: every directory in the path (as well as the name of
+/// the file, minus its extension) is converted to a slug, and these are joined
+/// with `/`. Returns `None` if any part of the path cannot be made into a slug.
+fn slug_for_relative_path(relative_path: &Path) -> Option<String> {
+    let name = relative_path.with_extension("");
+    let mut parts = Vec::new();
+
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => parts.push(to_slug(&part.to_string_lossy()).ok()?),
+            _ => return None,
+        }
+    }
+
+    match parts.is_empty() {
+        true => None,
+        false => Some(parts.join("/")),
+    }
+}
+
+/// The ignore rules that apply to the entries of some directory in the
+/// workspace: the built-in patterns, plus the patterns from every
+/// [SPHERE_IGNORE_FILE] between the workspace root and that directory
+#[derive(Clone)]
+pub struct IgnoreRules {
+    builtin: Arc<Gitignore>,
+    user: Vec<Arc<Gitignore>>,
+}
+
+impl IgnoreRules {
+    /// Whether the path is always ignored; built-in patterns cannot be
+    /// overridden by negated patterns in a [SPHERE_IGNORE_FILE]
+    pub fn is_builtin_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.builtin.matched(path, is_dir).is_ignore()
+    }
+
+    /// Whether the path (relative to the workspace root), or any directory
+    /// that contains it, is always ignored
+    pub fn is_builtin_ignored_path(&self, relative_path: &Path) -> bool {
+        let mut path = PathBuf::new();
+
+        relative_path.components().any(|component| {
+            path.push(component);
+            self.builtin.matched(&path, false).is_ignore()
+        })
+    }
+
+    /// Whether the path is ignored by a [SPHERE_IGNORE_FILE]. As with git, the
+    /// file closest to the path takes precedence, and within a file the last
+    /// matching pattern wins (so a negated pattern can re-include a path).
+    pub fn is_user_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for matcher in self.user.iter().rev() {
+            match matcher.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => continue,
+            }
+        }
+
+        false
+    }
+
+    /// Produce the rules that apply to the entries of a directory, given that
+    /// these are the rules that apply to the directory itself
+    pub async fn descend(&self, directory: &Path) -> Result<IgnoreRules> {
+        let mut rules = self.clone();
+        let ignore_file = directory.join(SPHERE_IGNORE_FILE);
+
+        if !ignore_file.is_file() {
+            return Ok(rules);
+        }
+
+        let mut builder = GitignoreBuilder::new(directory);
+
+        for line in fs::read_to_string(&ignore_file).await?.lines() {
+            builder.add_line(Some(ignore_file.clone()), line)?;
+        }
+
+        rules.user.push(Arc::new(builder.build()?));
+
+        Ok(rules)
+    }
 }
 
 /// Metadata that identifies some sphere content that is present on the file
@@ -74,6 +189,23 @@ pub struct FileReference {
     pub cid: Cid,
     pub content_type: ContentType,
     pub extension: Option<String>,
+    /// The path to the file, relative to the workspace root
+    pub path: PathBuf,
+}
+
+impl FileReference {
+    /// The path to the file as it is recorded in the [Header::FilePath] header
+    /// of a memo (always using `/` as the separator)
+    pub fn path_header(&self) -> String {
+        path_to_header(&self.path)
+    }
+}
+
+fn path_to_header(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 use super::commands::config::COUNTERPART;
@@ -202,16 +334,18 @@ impl Workspace {
         let mut changes = ContentChanges::default();
 
         while let Some(Ok((slug, cid))) = stream.next().await {
-            if content.ignored.contains(slug) {
+            if content.is_ignored(slug) {
                 continue;
             }
 
             match content.matched.get(slug) {
-                Some(FileReference {
-                    cid: body_cid,
-                    content_type,
-                    extension: _,
-                }) => {
+                Some(file_reference) => {
+                    let FileReference {
+                        cid: body_cid,
+                        content_type,
+                        ..
+                    } = file_reference;
+
                     let sphere_file = sphere_fs.read(slug).await?.ok_or_else(|| {
                         anyhow!(
                             "Expected sphere file at slug {:?} but it was missing!",
@@ -219,7 +353,24 @@ impl Workspace {
                         )
                     })?;
 
-                    if &sphere_file.memo.body == body_cid {
+                    // Memos saved before paths were recorded are assumed to
+                    // have been at the path that the slug implies
+                    let saved_path = sphere_file
+                        .memo
+                        .get_first_header(&Header::FilePath.to_string())
+                        .unwrap_or_else(|| {
+                            match sphere_file
+                                .memo
+                                .get_first_header(&Header::FileExtension.to_string())
+                            {
+                                Some(extension) => format!("{}.{}", slug, extension),
+                                None => slug.clone(),
+                            }
+                        });
+
+                    if &sphere_file.memo.body == body_cid
+                        && saved_path == file_reference.path_header()
+                    {
                         changes.unchanged.insert(slug.clone());
                         continue;
                     }
@@ -258,54 +409,62 @@ impl Workspace {
     /// consider, and allow the user to rely on their shell for glob filtering
     pub async fn read_file_content<S: BlockStore>(&self, store: &mut S) -> Result<Content> {
         let root_path = &self.root_directory;
-        let mut directories = vec![(None, tokio::fs::read_dir(root_path).await?)];
-
-        let ignore_patterns = self.get_ignored_patterns().await?;
+        let ignore_rules = self.get_ignored_patterns().await?;
+        let mut directories = vec![(tokio::fs::read_dir(root_path).await?, ignore_rules)];
 
         let mut content = Content::default();
 
-        while let Some((slug_prefix, mut directory)) = directories.pop() {
+        while let Some((mut directory, ignore_rules)) = directories.pop() {
             while let Some(entry) = directory.next_entry().await? {
                 let path = entry.path();
                 let relative_path = diff_paths(&path, root_path)
                     .ok_or_else(|| anyhow!("Could not determine relative path to {:?}", path))?;
+                let is_dir = path.is_dir();
 
-                if ignore_patterns.is_match(&relative_path) {
+                if ignore_rules.is_builtin_ignored(&path, is_dir) {
                     continue;
                 }
 
-                if path.is_dir() {
-                    let slug_prefix = relative_path.to_string_lossy().to_string();
+                let ignored = ignore_rules.is_user_ignored(&path, is_dir);
+
+                if ignored {
+                    content.ignored_paths.insert(relative_path.clone());
+                }
+
+                if is_dir {
+                    if ignored {
+                        continue;
+                    }
+
+                    let ignore_rules = ignore_rules.descend(&path).await?;
 
-                    directories.push((Some(slug_prefix), tokio::fs::read_dir(path).await?));
+                    directories.push((tokio::fs::read_dir(path).await?, ignore_rules));
 
                     // TODO: Limit the depth of the directory traversal to some reasonable number
 
                     continue;
                 }
 
-                let ignored = false;
-
-                let name = match path.file_stem() {
-                    Some(name) => name.to_string_lossy(),
+                let slug = match slug_for_relative_path(&relative_path) {
+                    Some(slug) => slug,
                     None => continue,
                 };
 
-                let name = match &slug_prefix {
-                    Some(prefix) => format!("{}/{}", prefix, name),
-                    None => name.to_string(),
-                };
-
-                let slug = match to_slug(&name) {
-                    Ok(slug) if slug == name => slug,
-                    _ => continue,
-                };
-
                 if ignored {
                     content.ignored.insert(slug);
                     continue;
                 }
 
+                if let Some(existing) = content.matched.get(&slug) {
+                    return Err(anyhow!(
+                        "Both {:?} and {:?} would be saved to the sphere as {:?}; rename one of them, or add one of them to {}",
+                        existing.path,
+                        relative_path,
+                        slug,
+                        SPHERE_IGNORE_FILE
+                    ));
+                }
+
                 let extension = path
                     .extension()
                     .map(|extension| String::from(extension.to_string_lossy()));
@@ -324,6 +483,7 @@ impl Workspace {
                         cid: body_cid,
                         content_type,
                         extension,
+                        path: relative_path,
                     },
                 );
             }
@@ -378,7 +538,22 @@ impl Workspace {
                 None => slug.into(),
             };
 
-            let file_path = self.root_directory.join(file_fragment);
+            let file_path = match sphere_file
+                .memo
+                .get_first_header(&Header::FilePath.to_string())
+            {
+                Some(path) => match self.resolve_workspace_path(&path) {
+                    Some(file_path) => file_path,
+                    None => {
+                        println!(
+                            "Warning: the recorded path of {} ({:?}) is outside of the workspace; it will be rendered as {}",
+                            slug, path, file_fragment
+                        );
+                        self.root_directory.join(file_fragment)
+                    }
+                },
+                None => self.root_directory.join(file_fragment),
+            };
 
             let file_directory = file_path
                 .parent()
@@ -394,19 +569,54 @@ impl Workspace {
         Ok(())
     }
 
-    /// Produce a matcher that will match any path that should be ignored when
-    /// considering the files that make up the local workspace
-    async fn get_ignored_patterns(&self) -> Result<GlobSet> {
-        // TODO(#82): User-specified ignore patterns
-        let ignored_patterns = vec!["@*", ".*"];
+    /// Given a path to a file in the workspace (either absolute or relative to
+    /// the current working directory), derive the slug that the file's content
+    /// is saved to in the sphere
+    pub fn slug_for_path(&self, path: &Path) -> Result<String> {
+        let path = std::env::current_dir()?.join(path);
+        let relative_path = diff_paths(&path, &self.root_directory)
+            .ok_or_else(|| anyhow!("Could not determine relative path to {:?}", path))?;
+
+        if relative_path.starts_with("..") {
+            return Err(anyhow!("{:?} is not inside the sphere workspace", path));
+        }
+
+        slug_for_relative_path(&relative_path)
+            .ok_or_else(|| anyhow!("{:?} does not correspond to a valid slug", path))
+    }
+
+    /// Resolve a path that was recorded in a [Header::FilePath] header to a
+    /// location in the workspace. Paths that are absolute or that contain
+    /// components such as `..` are refused, so that the content of a sphere
+    /// can never cause files to be written outside of the workspace.
+    pub fn resolve_workspace_path(&self, path: &str) -> Option<PathBuf> {
+        let path = Path::new(path);
+
+        match path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            true => Some(self.root_directory.join(path)),
+            false => None,
+        }
+    }
 
-        let mut builder = GlobSetBuilder::new();
+    /// Produce the rules that determine which paths should be ignored when
+    /// considering the files that make up the local workspace, starting from
+    /// the workspace root
+    pub async fn get_ignored_patterns(&self) -> Result<IgnoreRules> {
+        let mut builder = GitignoreBuilder::new(&self.root_directory);
 
-        for pattern in ignored_patterns {
-            builder.add(Glob::new(pattern)?);
+        for pattern in BUILTIN_IGNORE_PATTERNS {
+            builder.add_line(None, pattern)?;
         }
 
-        Ok(builder.build()?)
+        let rules = IgnoreRules {
+            builtin: Arc::new(builder.build()?),
+            user: Vec::new(),
+        };
+
+        rules.descend(&self.root_directory).await
     }
 
     /// Given a file extension, infer its mime
@@ -517,10 +727,13 @@ impl Workspace {
 
 #[cfg(test)]
 mod tests {
-    use crate::native::commands::{key, sphere};
+    use std::path::PathBuf;
+
+    use crate::native::commands::{key, save::save, sphere};
+    use noosphere_storage::MemoryStore;
     use tokio::fs;
 
-    use super::Workspace;
+    use super::{Workspace, SPHERE_IGNORE_FILE};
 
     #[tokio::test]
     async fn it_chooses_an_ancestor_sphere_directory_as_root_if_one_exists() {
@@ -539,4 +752,137 @@ mod tests {
 
         assert_eq!(workspace.root_directory(), new_workspace.root_directory());
     }
+
+    #[tokio::test]
+    async fn it_skips_paths_that_match_sphereignore_patterns() {
+        let (workspace, _temporary_directories) = Workspace::temporary().unwrap();
+
+        key::key_create("FOO", &workspace).await.unwrap();
+
+        sphere::sphere_create("FOO", &workspace).await.unwrap();
+
+        let root = workspace.root_directory();
+
+        fs::create_dir_all(root.join("build")).await.unwrap();
+        fs::create_dir_all(root.join("notes")).await.unwrap();
+
+        fs::write(root.join(SPHERE_IGNORE_FILE), "*.swp\nbuild/\n!keep.swp\n")
+            .await
+            .unwrap();
+        fs::write(root.join("notes").join(SPHERE_IGNORE_FILE), "draft*\n")
+            .await
+            .unwrap();
+
+        for path in [
+            "cats.subtext",
+            "cats.swp",
+            "keep.swp",
+            "build/out.subtext",
+            "notes/draft.subtext",
+            "notes/dogs.swp",
+        ] {
+            fs::write(root.join(path), b"meow").await.unwrap();
+        }
+
+        let content = workspace
+            .read_file_content(&mut MemoryStore::default())
+            .await
+            .unwrap();
+
+        assert!(content.matched.contains_key("cats"));
+        assert!(content.matched.contains_key("keep"));
+        assert!(!content.matched.contains_key("build/out"));
+
+        assert_eq!(
+            content.ignored_paths.into_iter().collect::<Vec<PathBuf>>(),
+            vec![
+                PathBuf::from("build"),
+                PathBuf::from("cats.swp"),
+                PathBuf::from("notes/dogs.swp"),
+                PathBuf::from("notes/draft.subtext"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_round_trips_nested_directories_through_the_sphere() {
+        let (workspace, _temporary_directories) = Workspace::temporary().unwrap();
+
+        key::key_create("FOO", &workspace).await.unwrap();
+
+        sphere::sphere_create("FOO", &workspace).await.unwrap();
+
+        let root = workspace.root_directory();
+
+        fs::create_dir_all(root.join("notes/2024")).await.unwrap();
+        fs::write(root.join("jan.subtext"), b"Top").await.unwrap();
+        fs::write(root.join("notes/2024/jan.subtext"), b"Nested")
+            .await
+            .unwrap();
+
+        let content = workspace
+            .read_file_content(&mut MemoryStore::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            content.matched.get("notes/2024/jan").unwrap().path,
+            PathBuf::from("notes/2024/jan.subtext")
+        );
+        assert_eq!(
+            content.matched.get("jan").unwrap().path,
+            PathBuf::from("jan.subtext")
+        );
+
+        save(&workspace).await.unwrap();
+
+        fs::remove_dir_all(root.join("notes")).await.unwrap();
+        fs::remove_file(root.join("jan.subtext")).await.unwrap();
+
+        workspace.render().await.unwrap();
+
+        assert_eq!(
+            fs::read_to_string(root.join("notes/2024/jan.subtext"))
+                .await
+                .unwrap(),
+            "Nested"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("jan.subtext")).await.unwrap(),
+            "Top"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_refuses_to_read_files_that_would_share_a_slug() {
+        let (workspace, _temporary_directories) = Workspace::temporary().unwrap();
+
+        key::key_create("FOO", &workspace).await.unwrap();
+
+        sphere::sphere_create("FOO", &workspace).await.unwrap();
+
+        let root = workspace.root_directory();
+
+        fs::create_dir_all(root.join("notes")).await.unwrap();
+        fs::write(root.join("notes/jan.subtext"), b"One")
+            .await
+            .unwrap();
+        fs::write(root.join("notes/jan.md"), b"Two").await.unwrap();
+
+        assert!(workspace
+            .read_file_content(&mut MemoryStore::default())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn it_refuses_recorded_paths_that_escape_the_workspace() {
+        let (workspace, _temporary_directories) = Workspace::temporary().unwrap();
+
+        assert!(workspace
+            .resolve_workspace_path("notes/jan.subtext")
+            .is_some());
+        assert!(workspace.resolve_workspace_path("../jan.subtext").is_none());
+        assert!(workspace.resolve_workspace_path("/etc/passwd").is_none());
+    }
 }
@@ -1,36 +1,44 @@
 #![cfg(not(target_arch = "wasm32"))]
 
 use anyhow::anyhow;
-use noosphere::key::KeyStorage;
-use noosphere_storage::BlockStore;
+use noosphere::{key::KeyStorage, sphere::SphereContextBuilder};
+use noosphere_storage::{derive_cid, BlockStore, MemoryStorage, SphereDb};
 use std::net::TcpListener;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::io::AsyncReadExt;
 use tokio_stream::StreamExt;
 use url::Url;
 
 use noosphere_api::{
-    data::{FetchParameters, FetchResponse, PushBody, PushResponse},
+    data::{FetchParameters, FetchResponse, PublishBody, PushBody, PushResponse},
     route::Route,
 };
 use noosphere_core::{
-    authority::Authorization,
-    data::{ContentType, MemoIpld},
-    view::{Sphere, SphereMutation},
+    authority::{generate_ed25519_key, Authorization},
+    data::{ContentType, Did, Jwt, MemoIpld},
+    view::{ConflictResolution, Sphere, SphereMutation},
 };
 
 use libipld_cbor::DagCborCodec;
-use ucan::crypto::KeyMaterial;
+use ucan::{crypto::KeyMaterial, Ucan};
 
 use noosphere_cli::native::{
     commands::{
-        auth::auth_add,
+        auth::{auth_add, auth_rotate, DelegationOptions},
         key::key_create,
         sphere::{sphere_create, sphere_join},
     },
     workspace::Workspace,
 };
 use noosphere_core::tracing::initialize_tracing;
-use noosphere_gateway::{start_gateway, GatewayScope};
+use noosphere_gateway::{
+    start_gateway, start_gateway_with_clock, GatewayClock, GatewayFeeds, GatewayScope,
+};
+use noosphere_ns::{
+    utils::wait_for_peers, NSRecord, NameSystem, NameSystemBuilder, NameSystemClient,
+};
 
 #[tokio::test]
 async fn gateway_tells_you_its_identity() {
@@ -74,6 +82,8 @@ async fn gateway_tells_you_its_identity() {
                 gateway_sphere_context,
                 Url::parse("http://127.0.0.1:5001").unwrap(),
                 None,
+                None,
+                None,
             )
             .await
             .unwrap()
@@ -149,6 +159,8 @@ async fn gateway_identity_can_be_verified_by_the_client_of_its_owner() {
                 gateway_sphere_context,
                 Url::parse("http://127.0.0.1:5001").unwrap(),
                 None,
+                None,
+                None,
             )
             .await
             .unwrap()
@@ -232,6 +244,8 @@ async fn gateway_receives_a_newly_initialized_sphere_from_the_client() {
                 gateway_sphere_context,
                 Url::parse("http://127.0.0.1:5001").unwrap(),
                 None,
+                None,
+                None,
             )
             .await
             .unwrap()
@@ -263,7 +277,7 @@ async fn gateway_receives_a_newly_initialized_sphere_from_the_client() {
         let client = client_sphere_context.client().await.unwrap();
 
         let push_result = client
-            .push(&PushBody {
+            .push(PushBody {
                 sphere: client_sphere_identity.to_string(),
                 base: None,
                 tip: *sphere.cid(),
@@ -347,6 +361,8 @@ async fn gateway_updates_an_existing_sphere_with_changes_from_the_client() {
                 gateway_sphere_context,
                 Url::parse("http://127.0.0.1:5001").unwrap(),
                 None,
+                None,
+                None,
             )
             .await
             .unwrap()
@@ -381,7 +397,7 @@ async fn gateway_updates_an_existing_sphere_with_changes_from_the_client() {
         let bundle = sphere.try_bundle_until_ancestor(None).await.unwrap();
 
         let push_result = client
-            .push(&PushBody {
+            .push(PushBody {
                 sphere: client_sphere_identity.to_string(),
                 base: None,
                 tip: *sphere.cid(),
@@ -430,7 +446,7 @@ async fn gateway_updates_an_existing_sphere_with_changes_from_the_client() {
             .unwrap();
 
         let push_result = client
-            .push(&PushBody {
+            .push(PushBody {
                 sphere: client_sphere_identity.to_string(),
                 base: Some(sphere_cid),
                 tip: *sphere.cid(),
@@ -513,6 +529,8 @@ async fn gateway_serves_sphere_revisions_to_a_client() {
                 gateway_sphere_context,
                 Url::parse("http://127.0.0.1:5001").unwrap(),
                 None,
+                None,
+                None,
             )
             .await
             .unwrap()
@@ -578,7 +596,7 @@ async fn gateway_serves_sphere_revisions_to_a_client() {
         let bundle = sphere.try_bundle_until_ancestor(None).await.unwrap();
 
         let push_result = client
-            .push(&PushBody {
+            .push(PushBody {
                 sphere: client_sphere_identity.to_string(),
                 base: None,
                 tip: *sphere.cid(),
@@ -662,6 +680,8 @@ async fn gateway_can_sync_an_authorized_sphere_across_multiple_replicas() {
                 gateway_sphere_context,
                 Url::parse("http://127.0.0.1:5001").unwrap(),
                 None,
+                None,
+                None,
             )
             .await
             .unwrap()
@@ -678,6 +698,7 @@ async fn gateway_can_sync_an_authorized_sphere_across_multiple_replicas() {
         auth_add(
             &client_replica_key.get_did().await.unwrap(),
             None,
+            DelegationOptions::default(),
             &client_workspace,
         )
         .await
@@ -747,3 +768,1081 @@ async fn gateway_can_sync_an_authorized_sphere_across_multiple_replicas() {
 
     client_task.await.unwrap();
 }
+
+#[tokio::test]
+async fn gateway_publishes_a_name_record_for_a_synced_revision() {
+    // initialize_tracing();
+
+    let (gateway_workspace, _gateway_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_workspace, _client_temporary_directories) = Workspace::temporary().unwrap();
+
+    let gateway_key_name = "GATEWAY_KEY";
+    let client_key_name = "CLIENT_KEY";
+
+    key_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    key_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    sphere_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    sphere_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let gateway_address = listener.local_addr().unwrap();
+
+    let gateway_sphere_identity = gateway_workspace.sphere_identity().await.unwrap();
+    let client_sphere_identity = client_workspace.sphere_identity().await.unwrap();
+
+    let gateway_sphere_context = gateway_workspace.sphere_context().await.unwrap();
+
+    let server_task = {
+        let gateway_sphere_context = gateway_sphere_context.clone();
+        let client_sphere_identity = client_sphere_identity.clone();
+        tokio::spawn(async move {
+            start_gateway(
+                listener,
+                GatewayScope {
+                    identity: gateway_sphere_identity,
+                    counterpart: client_sphere_identity,
+                },
+                gateway_sphere_context,
+                Url::parse("http://127.0.0.1:5001").unwrap(),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+        })
+    };
+
+    let client_sphere_context = client_workspace.sphere_context().await.unwrap();
+
+    let client_task = tokio::spawn(async move {
+        let mut client_sphere_context = client_sphere_context.lock().await;
+
+        client_sphere_context
+            .configure_gateway_url(Some(
+                &format!("http://{}:{}", gateway_address.ip(), gateway_address.port())
+                    .parse()
+                    .unwrap(),
+            ))
+            .await
+            .unwrap();
+
+        let mut fs = client_sphere_context.fs().await.unwrap();
+
+        fs.write(
+            "hello",
+            &ContentType::Subtext.to_string(),
+            "Hello, world!".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let synced_version = fs.save(None).await.unwrap();
+
+        client_sphere_context.sync().await.unwrap();
+
+        let mut fs = client_sphere_context.fs().await.unwrap();
+
+        fs.write(
+            "goodbye",
+            &ContentType::Subtext.to_string(),
+            "Goodbye, world!".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let unsynced_version = fs.save(None).await.unwrap();
+
+        let client = client_sphere_context.client().await.unwrap();
+
+        let publish_response = client
+            .publish(&PublishBody {
+                sphere: client_sphere_identity.to_string(),
+                version: Some(synced_version),
+                proof: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(publish_response.version, synced_version);
+        assert!(!publish_response.propagated);
+
+        let record = Ucan::from_str(&publish_response.record).unwrap();
+
+        assert_eq!(record.audience(), client_sphere_identity.as_str());
+        assert_eq!(record.issuer(), client.session.gateway_identity.as_str());
+
+        let publish_result = client
+            .publish(&PublishBody {
+                sphere: client_sphere_identity.to_string(),
+                version: Some(unsynced_version),
+                proof: None,
+            })
+            .await;
+
+        assert!(publish_result.is_err());
+
+        server_task.abort();
+        let _ = server_task.await;
+    });
+
+    client_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn gateway_writes_feeds_for_a_published_revision() {
+    // initialize_tracing();
+
+    let (gateway_workspace, _gateway_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_workspace, _client_temporary_directories) = Workspace::temporary().unwrap();
+    let feed_directory = tempfile::TempDir::new().unwrap();
+
+    let gateway_key_name = "GATEWAY_KEY";
+    let client_key_name = "CLIENT_KEY";
+
+    key_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    key_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    sphere_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    sphere_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let gateway_address = listener.local_addr().unwrap();
+
+    let gateway_sphere_identity = gateway_workspace.sphere_identity().await.unwrap();
+    let client_sphere_identity = client_workspace.sphere_identity().await.unwrap();
+
+    let gateway_sphere_context = gateway_workspace.sphere_context().await.unwrap();
+
+    let server_task = {
+        let client_sphere_identity = client_sphere_identity.clone();
+        let feeds = GatewayFeeds {
+            directory: feed_directory.path().to_path_buf(),
+            base_url: Some(Url::parse("https://example.com/").unwrap()),
+        };
+        tokio::spawn(async move {
+            start_gateway(
+                listener,
+                GatewayScope {
+                    identity: gateway_sphere_identity,
+                    counterpart: client_sphere_identity,
+                },
+                gateway_sphere_context,
+                Url::parse("http://127.0.0.1:5001").unwrap(),
+                None,
+                None,
+                Some(feeds),
+            )
+            .await
+            .unwrap()
+        })
+    };
+
+    let client_sphere_context = client_workspace.sphere_context().await.unwrap();
+    let feed_path = feed_directory.path().join("feed.json");
+
+    let client_task = tokio::spawn(async move {
+        let mut client_sphere_context = client_sphere_context.lock().await;
+
+        client_sphere_context
+            .configure_gateway_url(Some(
+                &format!("http://{}:{}", gateway_address.ip(), gateway_address.port())
+                    .parse()
+                    .unwrap(),
+            ))
+            .await
+            .unwrap();
+
+        let mut fs = client_sphere_context.fs().await.unwrap();
+
+        fs.write(
+            "hello",
+            &ContentType::Subtext.to_string(),
+            "Hello, world!".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        fs.save(None).await.unwrap();
+
+        client_sphere_context.sync().await.unwrap();
+
+        let client = client_sphere_context.client().await.unwrap();
+
+        client
+            .publish(&PublishBody {
+                sphere: client_sphere_identity.to_string(),
+                version: None,
+                proof: None,
+            })
+            .await
+            .unwrap();
+
+        let started_at = Instant::now();
+
+        let feed: serde_json::Value = loop {
+            if let Ok(bytes) = tokio::fs::read(&feed_path).await {
+                break serde_json::from_slice(&bytes).unwrap();
+            }
+
+            if started_at.elapsed() > Duration::from_secs(30) {
+                panic!("The gateway did not write feeds for the published revision in time");
+            }
+
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        };
+
+        let items = feed["items"].as_array().unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["title"], "/hello");
+        assert_eq!(items[0]["summary"], "Hello, world!");
+        assert!(items[0]["url"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://example.com/permalink/"));
+
+        server_task.abort();
+        let _ = server_task.await;
+    });
+
+    client_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn gateway_accepts_changes_from_a_client_after_owner_key_rotation() {
+    // initialize_tracing();
+
+    let (gateway_workspace, _gateway_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_workspace, (client_root, client_global_root)) = Workspace::temporary().unwrap();
+
+    let gateway_key_name = "GATEWAY_KEY";
+    let client_key_name = "CLIENT_KEY";
+    let next_client_key_name = "NEXT_CLIENT_KEY";
+
+    key_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    key_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    let mnemonic = SphereContextBuilder::default()
+        .create_sphere()
+        .at_storage_path(client_workspace.root_directory())
+        .reading_keys_from(client_workspace.key_storage().clone())
+        .using_key(client_key_name)
+        .build()
+        .await
+        .unwrap()
+        .require_mnemonic()
+        .unwrap()
+        .to_string();
+
+    sphere_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    let replica_key = client_workspace
+        .key_storage()
+        .create_key("REPLICA_KEY")
+        .await
+        .unwrap();
+    let replica_did = replica_key.get_did().await.unwrap();
+
+    let replica_authorization = auth_add(
+        &replica_did,
+        Some("replica".into()),
+        DelegationOptions::default(),
+        &client_workspace,
+    )
+    .await
+    .unwrap();
+
+    let report = auth_rotate(
+        next_client_key_name,
+        true,
+        Some(mnemonic),
+        &client_workspace,
+    )
+    .await
+    .unwrap();
+
+    assert!(report.unchanged.is_empty());
+    assert!(report.lost.is_empty());
+    assert_eq!(report.redelegated.len(), 1);
+
+    let (name, did, cid) = report.redelegated.first().unwrap();
+
+    assert_eq!(name, "replica");
+    assert_eq!(did, &replica_did);
+    assert_ne!(cid, &replica_authorization);
+
+    // The workspace caches its sphere context (and the author key within it),
+    // so re-open it to pick up the rotated configuration
+    drop(client_workspace);
+    let client_workspace =
+        Workspace::new(client_root.path(), Some(client_global_root.path())).unwrap();
+
+    let next_client_did = client_workspace
+        .key()
+        .await
+        .unwrap()
+        .get_did()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        next_client_did,
+        client_workspace
+            .key_storage()
+            .require_key(next_client_key_name)
+            .await
+            .unwrap()
+            .get_did()
+            .await
+            .unwrap()
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let gateway_address = listener.local_addr().unwrap();
+
+    let gateway_sphere_identity = gateway_workspace.sphere_identity().await.unwrap();
+    let client_sphere_identity = client_workspace.sphere_identity().await.unwrap();
+
+    let gateway_sphere_context = gateway_workspace.sphere_context().await.unwrap();
+
+    let server_task = {
+        let gateway_sphere_context = gateway_sphere_context.clone();
+        let client_sphere_identity = client_sphere_identity.clone();
+        tokio::spawn(async move {
+            start_gateway(
+                listener,
+                GatewayScope {
+                    identity: gateway_sphere_identity,
+                    counterpart: client_sphere_identity,
+                },
+                gateway_sphere_context,
+                Url::parse("http://127.0.0.1:5001").unwrap(),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+        })
+    };
+
+    let client_sphere_context = client_workspace.sphere_context().await.unwrap();
+
+    let client_task = tokio::spawn(async move {
+        let mut client_sphere_context = client_sphere_context.lock().await;
+
+        client_sphere_context
+            .configure_gateway_url(Some(
+                &format!("http://{}:{}", gateway_address.ip(), gateway_address.port())
+                    .parse()
+                    .unwrap(),
+            ))
+            .await
+            .unwrap();
+
+        let mut fs = client_sphere_context.fs().await.unwrap();
+
+        fs.write(
+            "hello",
+            &ContentType::Subtext.to_string(),
+            "Hello, world!".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        fs.save(None).await.unwrap();
+
+        client_sphere_context.sync().await.unwrap();
+
+        server_task.abort();
+        let _ = server_task.await;
+    });
+
+    client_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn gateway_sync_can_merge_conflicting_changes_from_multiple_replicas() {
+    // initialize_tracing();
+
+    let (gateway_workspace, _gateway_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_workspace, _client_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_replica_workspace, _client_replica_temporary_directories) =
+        Workspace::temporary().unwrap();
+
+    let gateway_key_name = "GATEWAY_KEY";
+    let client_key_name = "CLIENT_KEY";
+    let client_replica_key_name = "CLIENT_REPLICA_KEY";
+
+    key_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    key_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+    key_create(client_replica_key_name, &client_replica_workspace)
+        .await
+        .unwrap();
+
+    sphere_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    sphere_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let gateway_address = listener.local_addr().unwrap();
+
+    let gateway_sphere_identity = gateway_workspace.sphere_identity().await.unwrap();
+    let client_sphere_identity = client_workspace.sphere_identity().await.unwrap();
+
+    let gateway_sphere_context = gateway_workspace.sphere_context().await.unwrap();
+
+    let server_task = {
+        let gateway_sphere_context = gateway_sphere_context.clone();
+        let client_sphere_identity = client_sphere_identity.clone();
+        tokio::spawn(async move {
+            start_gateway(
+                listener,
+                GatewayScope {
+                    identity: gateway_sphere_identity,
+                    counterpart: client_sphere_identity,
+                },
+                gateway_sphere_context,
+                Url::parse("http://127.0.0.1:5001").unwrap(),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+        })
+    };
+
+    let client_replica_key = client_replica_workspace
+        .key_storage()
+        .require_key(client_replica_key_name)
+        .await
+        .unwrap();
+
+    let client_replica_authorization = Authorization::Cid(
+        auth_add(
+            &client_replica_key.get_did().await.unwrap(),
+            None,
+            DelegationOptions::default(),
+            &client_workspace,
+        )
+        .await
+        .unwrap(),
+    );
+
+    sphere_join(
+        client_replica_key_name,
+        Some(client_replica_authorization.to_string()),
+        &client_sphere_identity,
+        &client_replica_workspace,
+    )
+    .await
+    .unwrap();
+
+    let client_sphere_context = client_workspace.sphere_context().await.unwrap();
+    let client_replica_sphere_context = client_replica_workspace.sphere_context().await.unwrap();
+
+    let client_task = tokio::spawn(async move {
+        let mut client_sphere_context = client_sphere_context.lock().await;
+        let mut client_replica_sphere_context = client_replica_sphere_context.lock().await;
+        let gateway_url: Url =
+            format!("http://{}:{}", gateway_address.ip(), gateway_address.port())
+                .parse()
+                .unwrap();
+
+        client_sphere_context
+            .configure_gateway_url(Some(&gateway_url))
+            .await
+            .unwrap();
+        client_replica_sphere_context
+            .configure_gateway_url(Some(&gateway_url))
+            .await
+            .unwrap();
+
+        let mut fs = client_sphere_context.fs().await.unwrap();
+        fs.write(
+            "notes",
+            &ContentType::Subtext.to_string(),
+            "Original".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.save(None).await.unwrap();
+
+        client_sphere_context.sync().await.unwrap();
+        client_replica_sphere_context.sync().await.unwrap();
+
+        for (context, value) in [
+            (&mut client_sphere_context, "Changed on the client"),
+            (&mut client_replica_sphere_context, "Changed on the replica"),
+        ] {
+            let mut fs = context.fs().await.unwrap();
+            fs.write(
+                "notes",
+                &ContentType::Subtext.to_string(),
+                value.as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+            fs.save(None).await.unwrap();
+        }
+
+        let conflicts = client_sphere_context
+            .sync_with_conflict_resolver(Arc::new(|_| ConflictResolution::KeepBoth))
+            .await
+            .unwrap();
+
+        assert!(conflicts.is_empty());
+
+        let conflicts = client_replica_sphere_context
+            .sync_with_conflict_resolver(Arc::new(|_| ConflictResolution::KeepBoth))
+            .await
+            .unwrap();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].conflict.slug, "notes");
+        assert_eq!(conflicts[0].kept_mine_as.as_deref(), Some("notes-conflict"));
+
+        let fs = client_replica_sphere_context.fs().await.unwrap();
+
+        for (slug, expected) in [
+            ("notes", "Changed on the client"),
+            ("notes-conflict", "Changed on the replica"),
+        ] {
+            let mut file = fs.read(slug).await.unwrap().unwrap();
+            let mut contents = String::new();
+            file.contents.read_to_string(&mut contents).await.unwrap();
+            assert_eq!(expected, &contents);
+        }
+
+        server_task.abort();
+        let _ = server_task.await;
+    });
+
+    client_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn gateway_asks_for_missing_revisions_and_fast_forwards_a_push_that_is_ahead() {
+    // initialize_tracing();
+
+    let (gateway_workspace, _gateway_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_workspace, _client_temporary_directories) = Workspace::temporary().unwrap();
+
+    let gateway_key_name = "GATEWAY_KEY";
+    let client_key_name = "CLIENT_KEY";
+
+    key_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    key_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    sphere_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    sphere_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let gateway_address = listener.local_addr().unwrap();
+
+    let gateway_sphere_identity = gateway_workspace.sphere_identity().await.unwrap();
+    let client_sphere_identity = client_workspace.sphere_identity().await.unwrap();
+
+    let gateway_sphere_context = gateway_workspace.sphere_context().await.unwrap();
+
+    let server_task = {
+        let gateway_sphere_context = gateway_sphere_context.clone();
+        let client_sphere_identity = client_sphere_identity.clone();
+        tokio::spawn(async move {
+            start_gateway(
+                listener,
+                GatewayScope {
+                    identity: gateway_sphere_identity,
+                    counterpart: client_sphere_identity,
+                },
+                gateway_sphere_context,
+                Url::parse("http://127.0.0.1:5001").unwrap(),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+        })
+    };
+
+    let client_sphere_context = client_workspace.sphere_context().await.unwrap();
+
+    let client_task = tokio::spawn(async move {
+        let mut client_sphere_context = client_sphere_context.lock().await;
+
+        client_sphere_context
+            .configure_gateway_url(Some(
+                &format!("http://{}:{}", gateway_address.ip(), gateway_address.port())
+                    .parse()
+                    .unwrap(),
+            ))
+            .await
+            .unwrap();
+
+        let client = client_sphere_context.client().await.unwrap();
+
+        let sphere_cid = client_sphere_context
+            .db()
+            .require_version(&client_sphere_identity)
+            .await
+            .unwrap();
+
+        let mut sphere = Sphere::at(&sphere_cid, client_sphere_context.db());
+        let bundle = sphere.try_bundle_until_ancestor(None).await.unwrap();
+
+        let push_result = client
+            .push(PushBody {
+                sphere: client_sphere_identity.to_string(),
+                base: None,
+                tip: *sphere.cid(),
+                blocks: bundle,
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(push_result, PushResponse::Accepted { .. }));
+
+        let mut revisions = Vec::new();
+
+        for value in ["one", "two", "three"] {
+            let memo = MemoIpld::for_body(client_sphere_context.db_mut(), vec![value])
+                .await
+                .unwrap();
+            let memo_cid = client_sphere_context
+                .db_mut()
+                .save::<DagCborCodec, _>(&memo)
+                .await
+                .unwrap();
+
+            let mut mutation =
+                SphereMutation::new(&client_sphere_context.author().identity().await.unwrap());
+            mutation.links_mut().set(&value.into(), &memo_cid);
+
+            let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+            let revision_cid = revision
+                .try_sign(
+                    &client_sphere_context.author().key,
+                    client_sphere_context.author().authorization.as_ref(),
+                )
+                .await
+                .unwrap();
+
+            revisions.push(revision_cid);
+            sphere = Sphere::at(&revision_cid, client_sphere_context.db());
+        }
+
+        // Push only the latest revision, as though the gateway had already
+        // received the first one
+        let bundle = sphere
+            .try_bundle_until_ancestor(Some(&revisions[1]))
+            .await
+            .unwrap();
+
+        let push_result = client
+            .push(PushBody {
+                sphere: client_sphere_identity.to_string(),
+                base: Some(revisions[1]),
+                tip: *sphere.cid(),
+                blocks: bundle,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            push_result,
+            PushResponse::MissingRevisions { since: sphere_cid }
+        );
+
+        // The blocks of a push that was not accepted are not kept
+        {
+            let gateway_sphere_context = gateway_sphere_context.lock().await;
+
+            assert!(gateway_sphere_context
+                .db()
+                .get_block(sphere.cid())
+                .await
+                .unwrap()
+                .is_none());
+        }
+
+        // Push the whole history without a base; the gateway should recognize
+        // that it descends from the revision it already has
+        let bundle = sphere.try_bundle_until_ancestor(None).await.unwrap();
+
+        let push_result = client
+            .push(PushBody {
+                sphere: client_sphere_identity.to_string(),
+                base: None,
+                tip: *sphere.cid(),
+                blocks: bundle,
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(push_result, PushResponse::Accepted { .. }));
+
+        let gateway_db = {
+            let gateway_sphere_context = gateway_sphere_context.lock().await;
+            gateway_sphere_context.db().clone()
+        };
+
+        assert_eq!(
+            gateway_db
+                .require_version(&client_sphere_identity)
+                .await
+                .unwrap(),
+            *sphere.cid()
+        );
+
+        // A push that is based on a revision the gateway has already moved
+        // past is a genuine conflict
+        let push_result = client
+            .push(PushBody {
+                sphere: client_sphere_identity.to_string(),
+                base: Some(revisions[0]),
+                tip: revisions[1],
+                blocks: Sphere::at(&revisions[1], client_sphere_context.db())
+                    .try_bundle_until_ancestor(Some(&revisions[0]))
+                    .await
+                    .unwrap(),
+            })
+            .await;
+
+        assert!(push_result.is_err());
+
+        server_task.abort();
+        let _ = server_task.await;
+    });
+
+    client_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn gateway_rejects_a_replica_whose_authorization_has_expired() {
+    // initialize_tracing();
+
+    let (gateway_workspace, _gateway_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_workspace, _client_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_replica_workspace, _client_replica_temporary_directories) =
+        Workspace::temporary().unwrap();
+
+    let gateway_key_name = "GATEWAY_KEY";
+    let client_key_name = "CLIENT_KEY";
+    let client_replica_key_name = "CLIENT_REPLICA_KEY";
+
+    key_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    key_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+    key_create(client_replica_key_name, &client_replica_workspace)
+        .await
+        .unwrap();
+
+    sphere_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    sphere_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let gateway_address = listener.local_addr().unwrap();
+
+    let gateway_sphere_identity = gateway_workspace.sphere_identity().await.unwrap();
+    let client_sphere_identity = client_workspace.sphere_identity().await.unwrap();
+
+    let gateway_sphere_context = gateway_workspace.sphere_context().await.unwrap();
+    let gateway_clock = GatewayClock::default();
+
+    let server_task = {
+        let gateway_sphere_context = gateway_sphere_context.clone();
+        let gateway_clock = gateway_clock.clone();
+        let client_sphere_identity = client_sphere_identity.clone();
+        tokio::spawn(async move {
+            start_gateway_with_clock(
+                listener,
+                GatewayScope {
+                    identity: gateway_sphere_identity,
+                    counterpart: client_sphere_identity,
+                },
+                gateway_sphere_context,
+                Url::parse("http://127.0.0.1:5001").unwrap(),
+                None,
+                None,
+                None,
+                gateway_clock,
+            )
+            .await
+            .unwrap()
+        })
+    };
+
+    let client_replica_key = client_replica_workspace
+        .key_storage()
+        .require_key(client_replica_key_name)
+        .await
+        .unwrap();
+
+    let lifetime = Duration::from_secs(5);
+
+    let client_replica_authorization = Authorization::Cid(
+        auth_add(
+            &client_replica_key.get_did().await.unwrap(),
+            Some("contractor".into()),
+            DelegationOptions {
+                expires_in: Some(lifetime),
+                ..Default::default()
+            },
+            &client_workspace,
+        )
+        .await
+        .unwrap(),
+    );
+
+    sphere_join(
+        client_replica_key_name,
+        Some(client_replica_authorization.to_string()),
+        &client_sphere_identity,
+        &client_replica_workspace,
+    )
+    .await
+    .unwrap();
+
+    let client_sphere_context = client_workspace.sphere_context().await.unwrap();
+    let client_replica_sphere_context = client_replica_workspace.sphere_context().await.unwrap();
+
+    let client_task = tokio::spawn(async move {
+        let mut client_sphere_context = client_sphere_context.lock().await;
+        let mut client_replica_sphere_context = client_replica_sphere_context.lock().await;
+        let gateway_url: Url =
+            format!("http://{}:{}", gateway_address.ip(), gateway_address.port())
+                .parse()
+                .unwrap();
+
+        client_sphere_context
+            .configure_gateway_url(Some(&gateway_url))
+            .await
+            .unwrap();
+        client_replica_sphere_context
+            .configure_gateway_url(Some(&gateway_url))
+            .await
+            .unwrap();
+
+        client_sphere_context.sync().await.unwrap();
+        client_replica_sphere_context.sync().await.unwrap();
+
+        let mut fs = client_replica_sphere_context.fs().await.unwrap();
+        fs.write(
+            "notes",
+            &ContentType::Subtext.to_string(),
+            "Synced after the authorization expired".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.save(None).await.unwrap();
+
+        gateway_clock.advance(lifetime + Duration::from_secs(1));
+
+        assert!(client_replica_sphere_context.sync().await.is_err());
+
+        // The owner of the sphere is unaffected by the replica's expiry
+        client_sphere_context.sync().await.unwrap();
+
+        server_task.abort();
+        let _ = server_task.await;
+    });
+
+    client_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn gateway_resolves_the_petnames_followed_by_its_counterpart() {
+    // initialize_tracing();
+
+    let (gateway_workspace, _gateway_temporary_directories) = Workspace::temporary().unwrap();
+    let (client_workspace, _client_temporary_directories) = Workspace::temporary().unwrap();
+
+    let gateway_key_name = "GATEWAY_KEY";
+    let client_key_name = "CLIENT_KEY";
+
+    key_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    key_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    sphere_create(client_key_name, &client_workspace)
+        .await
+        .unwrap();
+    sphere_create(gateway_key_name, &gateway_workspace)
+        .await
+        .unwrap();
+
+    let bootstrap_name_system = NameSystemBuilder::default()
+        .key_material(&generate_ed25519_key())
+        .store(&SphereDb::new(&MemoryStorage::default()).await.unwrap())
+        .listening_port(0)
+        .build()
+        .await
+        .unwrap();
+    let bootstrap_address = bootstrap_name_system.address().await.unwrap().unwrap();
+
+    let gateway_name_system = NameSystemBuilder::default()
+        .key_material(&gateway_workspace.key().await.unwrap())
+        .store(&gateway_workspace.db().await.unwrap())
+        .listening_port(0)
+        .bootstrap_peers(&[bootstrap_address])
+        .build()
+        .await
+        .unwrap();
+    gateway_name_system.bootstrap().await.unwrap();
+    wait_for_peers::<NameSystem>(&gateway_name_system, 1)
+        .await
+        .unwrap();
+
+    // Some other sphere publishes a record that the client's sphere will
+    // eventually refer to by petname
+    let friend_key = generate_ed25519_key();
+    let friend_identity = Did(friend_key.get_did().await.unwrap());
+    let friend_version = derive_cid::<DagCborCodec>(b"friend");
+    let friend_record = NSRecord::from_issuer(&friend_key, &friend_identity, &friend_version, None)
+        .await
+        .unwrap();
+
+    bootstrap_name_system
+        .put_record(friend_record.clone())
+        .await
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let gateway_address = listener.local_addr().unwrap();
+
+    let gateway_sphere_identity = gateway_workspace.sphere_identity().await.unwrap();
+    let client_sphere_identity = client_workspace.sphere_identity().await.unwrap();
+
+    let gateway_sphere_context = gateway_workspace.sphere_context().await.unwrap();
+
+    let server_task = {
+        let gateway_sphere_context = gateway_sphere_context.clone();
+        let client_sphere_identity = client_sphere_identity.clone();
+        tokio::spawn(async move {
+            start_gateway(
+                listener,
+                GatewayScope {
+                    identity: gateway_sphere_identity,
+                    counterpart: client_sphere_identity,
+                },
+                gateway_sphere_context,
+                Url::parse("http://127.0.0.1:5001").unwrap(),
+                Some(Arc::new(gateway_name_system)),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+        })
+    };
+
+    let client_sphere_context = client_workspace.sphere_context().await.unwrap();
+
+    let client_task = tokio::spawn(async move {
+        let mut client_sphere_context = client_sphere_context.lock().await;
+
+        client_sphere_context
+            .configure_gateway_url(Some(
+                &format!("http://{}:{}", gateway_address.ip(), gateway_address.port())
+                    .parse()
+                    .unwrap(),
+            ))
+            .await
+            .unwrap();
+
+        client_sphere_context
+            .set_petname("friend", &friend_identity)
+            .await
+            .unwrap();
+
+        client_sphere_context.sync().await.unwrap();
+
+        // The push prompts the gateway to resolve the client's petnames
+        let expected_record = Jwt(friend_record.try_to_string().unwrap());
+        let started_at = Instant::now();
+
+        loop {
+            let petnames = {
+                let gateway_sphere_context = gateway_sphere_context.lock().await;
+                gateway_sphere_context.list_petnames().await.unwrap()
+            };
+
+            if let Some(address) = petnames.get("friend") {
+                assert_eq!(address.identity, friend_identity);
+
+                if address.last_known_record.as_ref() == Some(&expected_record) {
+                    break;
+                }
+            }
+
+            if started_at.elapsed() > Duration::from_secs(30) {
+                panic!("The gateway did not resolve the followed sphere in time");
+            }
+
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+
+        server_task.abort();
+        let _ = server_task.await;
+    });
+
+    client_task.await.unwrap();
+}
@@ -16,13 +16,31 @@ use ucan_key_support::ed25519::Ed25519KeyMaterial;
 
 /// The level of access that a given user has to a related resource. Broadly,
 /// a user will always have either read/write access (to their own sphere) or
-/// else read-only access (to all other spheres).
+/// else read-only access (to all other spheres). A user who has been delegated
+/// write access to only some parts of a sphere has read/write access that is
+/// scoped to those parts.
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Access {
     ReadWrite,
+    ScopedReadWrite(Vec<SphereReference>),
     ReadOnly,
 }
 
+impl Access {
+    /// Returns true if this level of access allows the content at the given
+    /// slug to be changed; if no slug is given, returns true only if any part
+    /// of the sphere may be changed
+    pub fn can_write(&self, slug: Option<&str>) -> bool {
+        match (self, slug) {
+            (Access::ReadWrite, _) => true,
+            (Access::ScopedReadWrite(scopes), Some(slug)) => {
+                scopes.iter().any(|scope| scope.contains_slug(slug))
+            }
+            _ => false,
+        }
+    }
+}
+
 /// An author is a user or program who is reading content from and/or writing
 /// content to a sphere. This construct collects the identity and the
 /// authorization of that entity to make it easier to determine their level of
@@ -83,6 +101,7 @@ where
                 with: With::Resource {
                     kind: Resource::Scoped(SphereReference {
                         did: sphere_identity.to_string(),
+                        path: None,
                     }),
                 },
                 can: SphereAction::Push,
@@ -91,13 +110,33 @@ where
             let proof_chain = ProofChain::from_ucan(ucan, &mut did_parser, db).await?;
 
             let capability_infos = proof_chain.reduce_capabilities(&SPHERE_SEMANTICS);
+            let mut scopes = Vec::new();
 
             for info in capability_infos {
-                if info.originators.contains(sphere_identity.as_str())
-                    && info.capability.enables(&read_write_capability)
-                {
+                if !info.originators.contains(sphere_identity.as_str()) {
+                    continue;
+                }
+
+                if info.capability.enables(&read_write_capability) {
                     return Ok(Access::ReadWrite);
                 }
+
+                if info.capability.can < SphereAction::Push {
+                    continue;
+                }
+
+                if let With::Resource {
+                    kind: Resource::Scoped(scope),
+                } = &info.capability.with
+                {
+                    if scope.did == sphere_identity.as_str() && scope.path.is_some() {
+                        scopes.push(scope.clone());
+                    }
+                }
+            }
+
+            if !scopes.is_empty() {
+                return Ok(Access::ScopedReadWrite(scopes));
             }
         }
 
@@ -107,10 +146,20 @@ where
 
 #[cfg(test)]
 mod tests {
+    use cid::Cid;
     use noosphere_storage::{MemoryStorage, SphereDb};
-    use ucan::crypto::KeyMaterial;
+    use ucan::{
+        builder::UcanBuilder,
+        capability::{Capability, Resource, With},
+        crypto::KeyMaterial,
+        store::UcanJwtStore,
+    };
 
-    use crate::{authority::generate_ed25519_key, data::Did, view::Sphere};
+    use crate::{
+        authority::{generate_ed25519_key, Authorization, SphereAction, SphereReference},
+        data::Did,
+        view::Sphere,
+    };
 
     use super::{Access, Author};
 
@@ -156,4 +205,59 @@ mod tests {
 
         assert_eq!(access, Access::ReadWrite);
     }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_gives_scoped_read_write_access_if_the_key_is_authorized_for_a_path() {
+        let owner_key = generate_ed25519_key();
+        let owner_did = Did(owner_key.get_did().await.unwrap());
+        let collaborator_key = generate_ed25519_key();
+        let collaborator_did = Did(collaborator_key.get_did().await.unwrap());
+        let mut db = SphereDb::new(&MemoryStorage::default()).await.unwrap();
+
+        let (sphere, authorization, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+
+        db.write_token(
+            &authorization
+                .resolve_ucan(&db)
+                .await
+                .unwrap()
+                .encode()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let mut signable = UcanBuilder::default()
+            .issued_by(&owner_key)
+            .for_audience(&collaborator_did)
+            .with_lifetime(120)
+            .claiming_capability(&Capability {
+                with: With::Resource {
+                    kind: Resource::Scoped(SphereReference {
+                        did: sphere_identity.to_string(),
+                        path: Some("/journal/".into()),
+                    }),
+                },
+                can: SphereAction::Push,
+            })
+            .build()
+            .unwrap();
+        signable
+            .proofs
+            .push(Cid::try_from(&authorization).unwrap().to_string());
+
+        let author = Author {
+            key: collaborator_key,
+            authorization: Some(Authorization::Ucan(signable.sign().await.unwrap())),
+        };
+
+        let access = author.access_to(&sphere_identity, &db).await.unwrap();
+
+        assert!(matches!(access, Access::ScopedReadWrite(_)));
+        assert!(access.can_write(Some("journal/today")));
+        assert!(!access.can_write(Some("recipes/pie")));
+        assert!(!access.can_write(None));
+    }
 }
@@ -4,6 +4,9 @@ use url::Url;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
 pub enum SphereAction {
+    /// May read the content of a sphere (including sealed content that has
+    /// been shared with the holder of the capability), but may not change it
+    Read,
     /// May read information about a sphere from a counterpart
     Fetch,
     /// May push an updated sphere lineage to a counterpart
@@ -16,6 +19,14 @@ pub enum SphereAction {
 
 impl Action for SphereAction {}
 
+impl SphereAction {
+    /// Returns true if the action only allows the content of a sphere to be
+    /// read, and does not allow it to be changed
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, SphereAction::Read | SphereAction::Fetch)
+    }
+}
+
 impl ToString for SphereAction {
     fn to_string(&self) -> String {
         match self {
@@ -23,6 +34,7 @@ impl ToString for SphereAction {
             SphereAction::Publish => "sphere/publish",
             SphereAction::Push => "sphere/push",
             SphereAction::Fetch => "sphere/fetch",
+            SphereAction::Read => "sphere/read",
         }
         .into()
     }
@@ -37,25 +49,73 @@ impl TryFrom<String> for SphereAction {
             "sphere/publish" => SphereAction::Publish,
             "sphere/push" => SphereAction::Push,
             "sphere/fetch" => SphereAction::Fetch,
+            "sphere/read" => SphereAction::Read,
             _ => return Err(anyhow!("Unrecognized action: {:?}", value)),
         })
     }
 }
 
+/// A reference to a sphere, or to some part of a sphere, that capabilities
+/// may be scoped to. A reference with a path (e.g., `sphere:did:key:...#/journal/`)
+/// only refers to the slugs in the sphere that start with that path; a
+/// reference without a path refers to the whole sphere. Paths are matched
+/// segment by segment, so `/journal` refers to `journal` and `journal/today`,
+/// but not to `journalism`.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SphereReference {
     pub did: String,
+    pub path: Option<String>,
+}
+
+impl SphereReference {
+    /// Returns true if the slug falls within the part of the sphere that this
+    /// reference refers to
+    pub fn contains_slug(&self, slug: &str) -> bool {
+        match &self.path {
+            Some(path) => {
+                let path = normalize_path(path);
+
+                match normalize_path(slug).strip_prefix(path) {
+                    Some(rest) => {
+                        path.is_empty()
+                            || path.ends_with('/')
+                            || rest.is_empty()
+                            || rest.starts_with('/')
+                    }
+                    None => false,
+                }
+            }
+            None => true,
+        }
+    }
+}
+
+/// Paths in sphere references are written with a leading slash, but slugs are
+/// not, so the leading slash is ignored when comparing the two
+fn normalize_path(path: &str) -> &str {
+    path.trim_start_matches('/')
 }
 
 impl Scope for SphereReference {
     fn contains(&self, other: &Self) -> bool {
-        other.did == self.did
+        if other.did != self.did {
+            return false;
+        }
+
+        match (&self.path, &other.path) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(_), Some(other_path)) => self.contains_slug(other_path),
+        }
     }
 }
 
 impl ToString for SphereReference {
     fn to_string(&self) -> String {
-        format!("sphere:{}", self.did)
+        match &self.path {
+            Some(path) => format!("sphere:{}#{}", self.did, path),
+            None => format!("sphere:{}", self.did),
+        }
     }
 }
 
@@ -66,6 +126,10 @@ impl TryFrom<Url> for SphereReference {
         match value.scheme() {
             "sphere" => Ok(SphereReference {
                 did: String::from(value.path()),
+                path: value
+                    .fragment()
+                    .filter(|path| !path.is_empty())
+                    .map(String::from),
             }),
             _ => Err(anyhow!(
                 "Could not interpret URI as a sphere reference: {:?}",
@@ -80,3 +144,112 @@ pub struct SphereSemantics {}
 impl CapabilitySemantics<SphereReference, SphereAction> for SphereSemantics {}
 
 pub const SPHERE_SEMANTICS: SphereSemantics = SphereSemantics {};
+
+#[cfg(test)]
+mod tests {
+    use ucan::capability::{Capability, Resource, Scope, With};
+    use url::Url;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::{SphereAction, SphereReference};
+
+    fn reference(path: Option<&str>) -> SphereReference {
+        SphereReference {
+            did: "did:key:z6MkfooBar".into(),
+            path: path.map(String::from),
+        }
+    }
+
+    fn capability(
+        path: Option<&str>,
+        can: SphereAction,
+    ) -> Capability<SphereReference, SphereAction> {
+        Capability {
+            with: With::Resource {
+                kind: Resource::Scoped(reference(path)),
+            },
+            can,
+        }
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn it_round_trips_a_path_scoped_reference_through_a_url() {
+        let url = Url::parse("sphere:did:key:z6MkfooBar#/journal/").unwrap();
+        let reference = SphereReference::try_from(url).unwrap();
+
+        assert_eq!(reference.did, "did:key:z6MkfooBar");
+        assert_eq!(reference.path, Some("/journal/".into()));
+        assert_eq!(reference.to_string(), "sphere:did:key:z6MkfooBar#/journal/");
+
+        let url = Url::parse("sphere:did:key:z6MkfooBar").unwrap();
+        let reference = SphereReference::try_from(url).unwrap();
+
+        assert_eq!(reference.path, None);
+        assert_eq!(reference.to_string(), "sphere:did:key:z6MkfooBar");
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn it_only_contains_slugs_within_its_path() {
+        let journal = reference(Some("/journal/"));
+
+        assert!(journal.contains_slug("journal/2023-01-01"));
+        assert!(!journal.contains_slug("recipes/pie"));
+        assert!(!journal.contains_slug("journal"));
+        assert!(reference(None).contains_slug("recipes/pie"));
+        assert!(reference(Some("/")).contains_slug("recipes/pie"));
+
+        let journal = reference(Some("/journal"));
+
+        assert!(journal.contains_slug("journal"));
+        assert!(journal.contains_slug("journal/2023-01-01"));
+        assert!(!journal.contains_slug("journalism"));
+        assert!(!journal.contains_slug("journal-private/secrets"));
+        assert!(!journal.contains(&reference(Some("/journalism/"))));
+
+        let journal = reference(Some("/journal/"));
+
+        assert!(reference(None).contains(&journal));
+        assert!(journal.contains(&reference(Some("/journal/2023/"))));
+        assert!(!journal.contains(&reference(None)));
+        assert!(!journal.contains(&reference(Some("/recipes/"))));
+
+        let other_sphere = SphereReference {
+            did: "did:key:z6MkbazQux".into(),
+            path: None,
+        };
+
+        assert!(!reference(None).contains(&other_sphere));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn it_does_not_let_read_only_capabilities_enable_writes() {
+        let read = capability(None, SphereAction::Read);
+        let fetch = capability(None, SphereAction::Fetch);
+        let push = capability(None, SphereAction::Push);
+
+        assert!(SphereAction::Read.is_read_only());
+        assert!(SphereAction::Fetch.is_read_only());
+        assert!(!SphereAction::Push.is_read_only());
+
+        assert!(!read.enables(&push));
+        assert!(!fetch.enables(&push));
+        assert!(fetch.enables(&read));
+        assert!(push.enables(&fetch));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn it_lets_a_path_scoped_capability_enable_writes_within_its_path() {
+        let journal_push = capability(Some("/journal/"), SphereAction::Push);
+
+        assert!(journal_push.enables(&capability(Some("journal/today"), SphereAction::Push)));
+        assert!(!journal_push.enables(&capability(Some("recipes/pie"), SphereAction::Push)));
+        assert!(!journal_push.enables(&capability(None, SphereAction::Push)));
+        assert!(!journal_push.enables(&capability(Some("/journal/"), SphereAction::Authorize)));
+    }
+}
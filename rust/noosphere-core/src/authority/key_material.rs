@@ -1,11 +1,14 @@
 use anyhow::{anyhow, Result};
 use bip39::{Language, Mnemonic};
+use curve25519_dalek::edwards::CompressedEdwardsY;
 use ed25519_zebra::{SigningKey as Ed25519PrivateKey, VerificationKey as Ed25519PublicKey};
-use ucan::crypto::did::KeyConstructorSlice;
+use sha2::{Digest, Sha512};
+use ucan::crypto::{did::KeyConstructorSlice, KeyMaterial};
 use ucan_key_support::{
     ed25519::{bytes_to_ed25519_key, Ed25519KeyMaterial, ED25519_MAGIC_BYTES},
     rsa::{bytes_to_rsa_key, RSA_MAGIC_BYTES},
 };
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret as X25519Secret};
 
 // TODO: Conditional web crypto support
 pub const SUPPORTED_KEYS: &KeyConstructorSlice = &[
@@ -52,3 +55,52 @@ pub fn ed25519_key_to_bytes(
     bytes[ED25519_KEY_LENGTH..].copy_from_slice(public_key.as_ref());
     Ok(bytes)
 }
+
+/// Key material that can take part in an X25519 key agreement, which allows
+/// content to be sealed so that only the holder of the key may open it
+pub trait SealingKey: KeyMaterial {
+    /// Derive the X25519 secret that corresponds to this key material
+    fn try_sealing_secret(&self) -> Result<X25519Secret>;
+}
+
+impl SealingKey for Ed25519KeyMaterial {
+    fn try_sealing_secret(&self) -> Result<X25519Secret> {
+        let private_key = self
+            .1
+            .as_ref()
+            .ok_or_else(|| anyhow!("A private key is required in order to open sealed content"))?;
+
+        // The X25519 secret is derived from the Ed25519 seed in the same way
+        // as the Ed25519 signing scalar (per RFC 8032); clamping is applied by
+        // the X25519 implementation
+        let hash = Sha512::digest(private_key.as_ref());
+        let mut secret = [0u8; ED25519_KEY_LENGTH];
+        secret.copy_from_slice(&hash[..ED25519_KEY_LENGTH]);
+
+        Ok(X25519Secret::from(secret))
+    }
+}
+
+/// Derive the X25519 public key that corresponds to the Ed25519 key embodied
+/// by a `did:key` DID, so that content may be sealed for that key's holder
+pub fn did_to_sealing_public_key(did: &str) -> Result<X25519PublicKey> {
+    let encoded = did
+        .strip_prefix("did:key:z")
+        .ok_or_else(|| anyhow!("Only did:key DIDs may be used to seal content: {}", did))?;
+    let bytes = bs58::decode(encoded).into_vec()?;
+
+    let public_key = bytes
+        .strip_prefix(ED25519_MAGIC_BYTES)
+        .ok_or_else(|| anyhow!("Only Ed25519 keys may be used to seal content: {}", did))?;
+
+    if public_key.len() != ED25519_KEY_LENGTH {
+        return Err(anyhow!("Unexpected Ed25519 public key length in {}", did));
+    }
+
+    let montgomery_point = CompressedEdwardsY::from_slice(public_key)
+        .decompress()
+        .ok_or_else(|| anyhow!("Invalid Ed25519 public key in {}", did))?
+        .to_montgomery();
+
+    Ok(X25519PublicKey::from(montgomery_point.to_bytes()))
+}
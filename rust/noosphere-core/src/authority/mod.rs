@@ -2,10 +2,12 @@ mod author;
 mod authorization;
 mod capability;
 mod key_material;
+mod revocation;
 mod verification;
 
 pub use author::*;
 pub use authorization::*;
 pub use capability::*;
 pub use key_material::*;
+pub use revocation::*;
 pub use verification::*;
@@ -0,0 +1,63 @@
+use std::{collections::BTreeSet, str::FromStr};
+
+use anyhow::Result;
+use cid::Cid;
+use ucan::{store::UcanJwtStore, Ucan};
+
+/// Collect the CIDs of every UCAN that the given [Ucan] relies on as proof,
+/// directly or indirectly. Proofs that are not available in the store are
+/// still included, but their own proofs cannot be followed.
+pub async fn try_collect_proof_cids<S: UcanJwtStore>(
+    ucan: &Ucan,
+    store: &S,
+) -> Result<BTreeSet<Cid>> {
+    let mut proof_cids = BTreeSet::new();
+    let mut proofs_to_search: Vec<String> = ucan.proofs().clone();
+
+    while let Some(cid_string) = proofs_to_search.pop() {
+        let cid = Cid::from_str(cid_string.as_str())?;
+
+        if !proof_cids.insert(cid) {
+            continue;
+        }
+
+        if let Some(jwt) = store.read_token(&cid).await? {
+            proofs_to_search.extend(Ucan::from_str(&jwt)?.proofs().clone().into_iter());
+        }
+    }
+
+    Ok(proof_cids)
+}
+
+/// A delegation that was revoked as part of a revocation sweep
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevokedDelegation {
+    /// The name that the delegation was registered with
+    pub name: String,
+    /// The DID of the key that the delegation was issued to
+    pub did: String,
+    /// The CID of the delegation's UCAN
+    pub cid: Cid,
+    /// The CID of the revoked delegation that this delegation was derived
+    /// from, if it was not the delegation being revoked directly
+    pub derived_from: Option<Cid>,
+}
+
+/// A summary of all of the delegations that were revoked when a delegation
+/// (and transitively, everything derived from it) was revoked
+#[derive(Debug, Clone, Default)]
+pub struct RevocationReport {
+    /// Every delegation that was revoked, starting with the one that was
+    /// revoked directly
+    pub revoked: Vec<RevokedDelegation>,
+}
+
+impl RevocationReport {
+    /// The DIDs of all keys that lost access as a result of the revocation
+    pub fn affected_dids(&self) -> BTreeSet<&str> {
+        self.revoked
+            .iter()
+            .map(|delegation| delegation.did.as_str())
+            .collect()
+    }
+}
@@ -2,6 +2,7 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Result};
 use cid::Cid;
+use futures::TryStreamExt;
 use libipld_cbor::DagCborCodec;
 use ucan::{
     capability::{Capability, Resource, With},
@@ -11,13 +12,16 @@ use ucan::{
     ucan::Ucan,
 };
 
-use crate::data::{ContentType, Header, MemoIpld, SphereIpld};
+use crate::{
+    data::{CidKey, ContentType, Header, MapOperation, MemoIpld, SphereIpld},
+    view::{Authority, RevokedUcans, Sphere},
+};
 
 use noosphere_storage::{base64_decode, BlockStore, SphereDb, Storage, UcanStore};
 
 use crate::authority::SPHERE_SEMANTICS;
 
-use super::{SphereAction, SphereReference};
+use super::{try_collect_proof_cids, SphereAction, SphereReference};
 
 pub async fn verify_sphere_cid<S: Storage>(
     cid: &Cid,
@@ -44,6 +48,23 @@ pub async fn verify_sphere_cid<S: Storage>(
     // Load up the sphere being verified
     let sphere = store.load::<DagCborCodec, SphereIpld>(&memo.body).await?;
 
+    // The revision was written by its signer, so only its parent can be
+    // trusted to say which authorizations have been revoked
+    let revoked_ucans = match Sphere::at(cid, store).try_get_parent().await? {
+        Some(parent) => {
+            let revoked_ucans = parent
+                .try_get_authority()
+                .await?
+                .try_get_revoked_ucans()
+                .await?;
+
+            verify_revocations_are_kept(&revoked_ucans, &sphere, store).await?;
+
+            Some(revoked_ucans)
+        }
+        None => None,
+    };
+
     // If we have an authorizing proof...
     if let Some(proof_header) = memo.get_header(&Header::Proof.to_string()).first() {
         let ucan_store = UcanStore(store.clone());
@@ -59,30 +80,56 @@ pub async fn verify_sphere_cid<S: Storage>(
         // Verify the audience signature of the body CID
         credential.verify(&memo.body.to_bytes(), &signature).await?;
 
-        // Check the proof's provenance and that it enables the signer to sign
-        let proof = ProofChain::from_ucan(ucan, did_parser, &ucan_store).await?;
+        // Refuse the revision if the proof, or anything it was derived from,
+        // had been revoked by the sphere as of the parent revision
+        if let Some(revoked_ucans) = &revoked_ucans {
+            let mut proof_cids = try_collect_proof_cids(&ucan, &ucan_store).await?;
+            proof_cids.insert(ucan_cid);
+
+            for proof_cid in proof_cids {
+                if revoked_ucans.get(&CidKey(proof_cid)).await?.is_some() {
+                    return Err(anyhow!(
+                        "Proof relies on a revoked authorization ({})",
+                        proof_cid
+                    ));
+                }
+            }
+        }
 
-        let desired_capability = Capability {
-            with: With::Resource {
-                kind: Resource::Scoped(SphereReference {
-                    did: sphere.identity.to_string(),
-                }),
-            },
-            can: SphereAction::Push,
+        // Check the proof's provenance and that it enables the signer to make
+        // each of the changes in this revision of the sphere
+        let proof = ProofChain::from_ucan(ucan, did_parser, &ucan_store).await?;
+        let capability_infos = proof.reduce_capabilities(&SPHERE_SEMANTICS);
+
+        let enables = |path: Option<&str>| {
+            let desired_capability = Capability {
+                with: With::Resource {
+                    kind: Resource::Scoped(SphereReference {
+                        did: sphere.identity.to_string(),
+                        path: path.map(String::from),
+                    }),
+                },
+                can: SphereAction::Push,
+            };
+
+            capability_infos.iter().any(|capability_info| {
+                capability_info
+                    .originators
+                    .contains(sphere.identity.as_str())
+                    && capability_info.capability.enables(&desired_capability)
+            })
         };
 
-        for capability_info in proof.reduce_capabilities(&SPHERE_SEMANTICS) {
-            let capability = capability_info.capability;
-            if capability_info
-                .originators
-                .contains(sphere.identity.as_str())
-                && capability.enables(&desired_capability)
-            {
-                return Ok(());
+        for path in required_write_paths(cid, store).await? {
+            if !enables(path.as_deref()) {
+                return Err(match path {
+                    Some(slug) => anyhow!("Proof did not enable signer to write to {:?}", slug),
+                    None => anyhow!("Proof did not enable signer to sign this sphere"),
+                });
             }
         }
 
-        Err(anyhow!("Proof did not enable signer to sign this sphere"))
+        Ok(())
     } else {
         // Assume the identity is the signer
         let credential = did_parser.parse(&sphere.identity)?;
@@ -93,3 +140,76 @@ pub async fn verify_sphere_cid<S: Storage>(
         Ok(())
     }
 }
+
+/// Ensure that a revision of a sphere keeps every revocation that was made
+/// before it; a revoked authorization may never be restored.
+async fn verify_revocations_are_kept<S: Storage>(
+    parent_revoked_ucans: &RevokedUcans<SphereDb<S>>,
+    sphere: &SphereIpld,
+    store: &SphereDb<S>,
+) -> Result<()> {
+    let revoked_ucans = match &sphere.authorization {
+        Some(authority_cid) => Some(
+            Authority::at(authority_cid, store)
+                .try_get_revoked_ucans()
+                .await?,
+        ),
+        None => None,
+    };
+
+    if let Some(revoked_ucans) = &revoked_ucans {
+        if revoked_ucans.cid() == parent_revoked_ucans.cid() {
+            return Ok(());
+        }
+    }
+
+    let mut parent_revocations = parent_revoked_ucans.stream().await?;
+
+    while let Some((key, _)) = parent_revocations.try_next().await? {
+        let is_kept = match &revoked_ucans {
+            Some(revoked_ucans) => revoked_ucans.get(key).await?.is_some(),
+            None => false,
+        };
+
+        if !is_kept {
+            return Err(anyhow!(
+                "Revision restores a revoked authorization ({})",
+                key.0
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Determine which parts of a sphere the signer of a revision must be allowed
+/// to write to in order for the revision to be valid. Changes to links only
+/// require access to the changed slugs (`Some(slug)`); every other kind of
+/// change (as well as a revision that changes nothing but its headers)
+/// requires access to the whole sphere (`None`).
+async fn required_write_paths<S: Storage>(
+    cid: &Cid,
+    store: &SphereDb<S>,
+) -> Result<Vec<Option<String>>> {
+    let mutation = Sphere::at(cid, store).try_derive_mutation().await?;
+    let mut paths = Vec::new();
+
+    for operation in mutation.links().changes() {
+        let slug = match operation {
+            MapOperation::Add { key, .. } => key,
+            MapOperation::Remove { key } => key,
+        };
+        paths.push(Some(slug.clone()));
+    }
+
+    let changes_more_than_links = !mutation.names().changes().is_empty()
+        || !mutation.sealed().changes().is_empty()
+        || !mutation.allowed_ucans().changes().is_empty()
+        || !mutation.revoked_ucans().changes().is_empty();
+
+    if changes_more_than_links || paths.is_empty() {
+        paths.push(None);
+    }
+
+    Ok(paths)
+}
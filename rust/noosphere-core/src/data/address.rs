@@ -1,4 +1,17 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use noosphere_storage::{SphereDb, Storage};
 use serde::{Deserialize, Serialize};
+use ucan::{
+    capability::{Capability, Resource, With},
+    chain::ProofChain,
+    crypto::did::DidParser,
+    Ucan,
+};
+
+use crate::authority::{SphereAction, SphereReference, SPHERE_SEMANTICS};
 
 use super::{Did, Jwt};
 
@@ -12,3 +25,72 @@ pub struct AddressIpld {
     pub identity: Did,
     pub last_known_record: Option<Jwt>,
 }
+
+impl AddressIpld {
+    /// The sphere revision that the last known record for this address links
+    /// to, if a record has ever been resolved. The record is verified before
+    /// its link is trusted: it must be an unexpired UCAN for this address's
+    /// identity, signed by a key that the identity has authorized to publish
+    /// the sphere. Any proofs that the record relies on must be available in
+    /// the given [SphereDb].
+    pub async fn verify_linked_version<S: Storage>(
+        &self,
+        db: &SphereDb<S>,
+        did_parser: &mut DidParser,
+    ) -> Result<Option<Cid>> {
+        let record = match &self.last_known_record {
+            Some(record) => Ucan::from_str(record)?,
+            None => return Ok(None),
+        };
+
+        if record.audience() != self.identity.as_str() {
+            return Err(anyhow!(
+                "Record is for {} rather than {}",
+                record.audience(),
+                self.identity
+            ));
+        }
+
+        if record.is_expired() {
+            return Err(anyhow!("Record for {} has expired", self.identity));
+        }
+
+        record.check_signature(did_parser).await?;
+
+        let link = record
+            .facts()
+            .iter()
+            .find_map(|fact| fact.get("link").and_then(|link| link.as_str()))
+            .map(Cid::from_str)
+            .transpose()?;
+
+        let desired_capability = Capability {
+            with: With::Resource {
+                kind: Resource::Scoped(SphereReference {
+                    did: self.identity.to_string(),
+                    path: None,
+                }),
+            },
+            can: SphereAction::Publish,
+        };
+
+        let proof = ProofChain::from_ucan(record, did_parser, db).await?;
+
+        let is_authorized = proof
+            .reduce_capabilities(&SPHERE_SEMANTICS)
+            .into_iter()
+            .any(|capability_info| {
+                capability_info.originators.contains(self.identity.as_str())
+                    && capability_info.capability.enables(&desired_capability)
+            });
+
+        if !is_authorized {
+            return Err(anyhow!(
+                "Record is not authorized to publish {}",
+                self.identity
+            ));
+        }
+
+        Ok(link)
+    }
+}
@@ -4,10 +4,13 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use cid::Cid;
 
-use futures::{pin_mut, StreamExt};
+use futures::{io::AsyncRead, pin_mut, Stream, StreamExt};
 use libipld_cbor::DagCborCodec;
 use libipld_core::{raw::RawCodec, serde::to_ipld};
-use noosphere_storage::{block_deserialize, block_serialize, BlockStore};
+use noosphere_storage::{
+    block_deserialize, block_serialize, verify_blocks, BlockIntegrityError, BlockIntegrityFailure,
+    BlockStore,
+};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
@@ -19,11 +22,12 @@ use crate::{
 };
 
 use super::{
-    AllowedIpld, AuthorityIpld, NamesIpld, RevokedIpld, VersionedMapKey, VersionedMapValue,
+    car_stream, AllowedIpld, AuthorityIpld, CarReader, NamesIpld, RevokedIpld, SealedIpld,
+    VersionedMapKey, VersionedMapValue,
 };
 
-// TODO: This should maybe only collect CIDs, and then streaming-serialize to
-// a CAR (https://ipld.io/specs/transport/car/carv2/)
+// TODO: This should maybe only collect CIDs, and then stream the blocks out of
+// the store as the CAR is serialized
 #[derive(PartialEq, Eq, Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Bundle(BTreeMap<String, Vec<u8>>);
 
@@ -36,27 +40,77 @@ impl Bundle {
         self.0.contains_key(&cid.to_string())
     }
 
+    /// Load all the blocks of the [Bundle] into the store. Every block is
+    /// verified against its CID first, and if any of them fail verification
+    /// then nothing is stored and a [BlockIntegrityError] naming all of the
+    /// offending CIDs is returned.
     pub async fn load_into<S: BlockStore>(&self, store: &mut S) -> Result<()> {
+        let blocks = self
+            .0
+            .iter()
+            .map(|(cid_string, block_bytes)| Ok((Cid::from_str(cid_string)?, block_bytes)))
+            .collect::<Result<Vec<_>>>()?;
+
+        verify_blocks(
+            blocks
+                .iter()
+                .map(|(cid, block_bytes)| (cid, block_bytes.as_slice())),
+        )?;
+
         // TODO: Parrallelize this
-        for (cid_string, block_bytes) in self.0.iter() {
-            let cid = Cid::from_str(cid_string)?;
+        for (cid, block_bytes) in blocks {
+            put_block_with_links(store, &cid, block_bytes).await?;
+        }
 
-            store.put_block(&cid, block_bytes).await?;
+        Ok(())
+    }
 
-            match cid.codec() {
-                codec_id if codec_id == u64::from(DagCborCodec) => {
-                    store.put_links::<DagCborCodec>(&cid, block_bytes).await?;
-                }
-                codec_id if codec_id == u64::from(RawCodec) => {
-                    store.put_links::<RawCodec>(&cid, block_bytes).await?;
-                }
-                codec_id => warn!("Unrecognized codec {}; skipping...", codec_id),
-            }
+    /// Incrementally load the remaining blocks of a CAR into the store as
+    /// they are read, without first collecting them into a [Bundle]. Returns
+    /// the number of blocks that were loaded. Each block is verified as it is
+    /// read, so loading stops at the first block that fails verification
+    /// (blocks that were read before it have already been stored).
+    pub async fn load_car_into<R, S>(car: &mut CarReader<R>, store: &mut S) -> Result<usize>
+    where
+        R: AsyncRead + Unpin,
+        S: BlockStore,
+    {
+        let mut count = 0;
+
+        while let Some((cid, block_bytes)) = car.next_block().await? {
+            put_block_with_links(store, &cid, &block_bytes).await?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
 
-            // TODO: Verify CID is correct, maybe?
+    /// Collect the remaining blocks of a CAR into a [Bundle]
+    pub async fn try_from_car<R>(car: &mut CarReader<R>) -> Result<Bundle>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut bundle = Bundle::default();
+
+        while let Some((cid, block_bytes)) = car.next_block().await? {
+            bundle.add(cid, block_bytes);
         }
 
-        Ok(())
+        Ok(bundle)
+    }
+
+    /// Serialize the blocks in this [Bundle] as a stream of CARv1 bytes with
+    /// the given roots
+    pub fn into_car_stream(self, roots: &[Cid]) -> Result<impl Stream<Item = Result<Vec<u8>>>> {
+        car_stream(roots, self.into_blocks()?)
+    }
+
+    /// Consume the [Bundle], producing its blocks paired with their CIDs
+    pub fn into_blocks(self) -> Result<Vec<(Cid, Vec<u8>)>> {
+        self.0
+            .into_iter()
+            .map(|(cid_string, block_bytes)| Ok((Cid::from_str(&cid_string)?, block_bytes)))
+            .collect()
     }
 
     pub async fn try_from_timeslice<'a, S: BlockStore>(
@@ -105,6 +159,33 @@ impl Bundle {
     }
 }
 
+// NOTE: Blocks are expected to have been verified before they get here
+async fn put_block_with_links<S: BlockStore>(
+    store: &mut S,
+    cid: &Cid,
+    block_bytes: &[u8],
+) -> Result<()> {
+    store.put_block(cid, block_bytes).await?;
+
+    match cid.codec() {
+        codec_id if codec_id == u64::from(DagCborCodec) => {
+            store.put_links::<DagCborCodec>(cid, block_bytes).await?;
+        }
+        codec_id if codec_id == u64::from(RawCodec) => {
+            store.put_links::<RawCodec>(cid, block_bytes).await?;
+        }
+        codec_id => {
+            return Err(BlockIntegrityError::from((
+                *cid,
+                BlockIntegrityFailure::UnsupportedCodec(codec_id),
+            ))
+            .into())
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub trait TryBundleSendSync: Send + Sync {}
 
@@ -351,8 +432,8 @@ impl TryBundle for SphereIpld {
         }
 
         match sphere.sealed {
-            Some(_cid) => {
-                todo!();
+            Some(cid) => {
+                SealedIpld::try_extend_bundle_with_cid(&cid, bundle, store).await?;
             }
             _ => (),
         }
@@ -385,7 +466,7 @@ impl TryBundle for AuthorityIpld {
 mod tests {
     use libipld_cbor::DagCborCodec;
     use libipld_core::{ipld::Ipld, raw::RawCodec};
-    use noosphere_storage::{BlockStore, MemoryStore};
+    use noosphere_storage::{derive_cid, BlockIntegrityError, BlockStore, MemoryStore};
     use serde_bytes::Bytes;
     use ucan::crypto::KeyMaterial;
 
@@ -397,10 +478,29 @@ mod tests {
 
     use crate::{
         authority::generate_ed25519_key,
-        data::{Bundle, LinksIpld, MemoIpld, TryBundle},
+        data::{Bundle, EnvelopeIpld, LinksIpld, MemoIpld, SealedMemoIpld, TryBundle},
         view::{Sphere, SphereMutation, Timeline},
     };
 
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_refuses_to_load_a_bundle_with_mismatched_blocks() {
+        let mut store = MemoryStore::default();
+        let foo_cid = derive_cid::<RawCodec>(b"foo");
+        let bar_cid = derive_cid::<RawCodec>(b"bar");
+
+        let mut bundle = Bundle::default();
+        bundle.add(foo_cid, b"foo".to_vec());
+        bundle.add(bar_cid, b"baz".to_vec());
+
+        let error = bundle.load_into(&mut store).await.unwrap_err();
+        let error = error.downcast::<BlockIntegrityError>().unwrap();
+
+        assert_eq!(error.cids(), vec![&bar_cid]);
+        assert!(store.get_block(&foo_cid).await.unwrap().is_none());
+        assert!(store.get_block(&bar_cid).await.unwrap().is_none());
+    }
+
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
     async fn it_bundles_an_empty_sphere() {
@@ -507,6 +607,45 @@ mod tests {
         assert!(bundle.contains(&body_cid));
     }
 
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_bundles_a_sphere_with_sealed_content() {
+        let mut store = MemoryStore::default();
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let (sphere, ucan, _) = Sphere::try_generate(&owner_did, &mut store).await.unwrap();
+
+        let envelope = EnvelopeIpld::try_seal(
+            &SealedMemoIpld {
+                slug: "secret".into(),
+                headers: Vec::new(),
+                body: b"foo".to_vec(),
+            },
+            &[owner_did.clone()],
+        )
+        .unwrap();
+        let envelope_cid = store.save::<DagCborCodec, _>(&envelope).await.unwrap();
+
+        let mut mutation = SphereMutation::new(&owner_did);
+        mutation
+            .sealed_mut()
+            .set(&String::from("abc123"), &envelope_cid);
+
+        let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+        let new_cid = revision.try_sign(&owner_key, Some(&ucan)).await.unwrap();
+
+        let bundle = MemoIpld::try_bundle_with_cid(&new_cid, &store)
+            .await
+            .unwrap();
+
+        let sphere_ipld = Sphere::at(&new_cid, &store).try_as_body().await.unwrap();
+        let sealed_cid = sphere_ipld.sealed.unwrap();
+
+        assert!(bundle.contains(&sealed_cid));
+        assert!(bundle.contains(&envelope_cid));
+    }
+
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
     async fn it_only_bundles_the_revision_delta() {
@@ -0,0 +1,425 @@
+use std::io::Cursor;
+
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use futures::{
+    io::{AsyncRead, AsyncReadExt},
+    stream, Stream, StreamExt,
+};
+use libipld_cbor::DagCborCodec;
+use noosphere_storage::{block_deserialize, block_serialize, verify_block};
+use serde::{Deserialize, Serialize};
+
+#[cfg(doc)]
+use noosphere_storage::BlockIntegrityError;
+
+/// The MIME type that CAR files are served with
+pub const CAR_CONTENT_TYPE: &str = "application/vnd.ipld.car";
+
+/// The largest single frame (CID plus block bytes) that a [CarReader] will
+/// accept; this keeps a malformed or hostile length prefix from provoking an
+/// arbitrarily large allocation.
+pub const MAX_CAR_FRAME_SIZE: u64 = 1024 * 1024 * 8;
+
+/// The fixed-size header that follows the pragma of a CARv2, containing the
+/// characteristics bitfield followed by the data offset, data size and index
+/// offset (all little-endian u64)
+const CAR_V2_HEADER_SIZE: usize = 40;
+
+#[derive(Serialize, Deserialize)]
+struct CarHeader {
+    #[serde(default)]
+    roots: Option<Vec<Cid>>,
+    version: u64,
+}
+
+/// Encode a varint-prefixed CARv1 header that lists the given roots
+pub fn encode_car_header(roots: &[Cid]) -> Result<Vec<u8>> {
+    let (_, header) = block_serialize::<DagCborCodec, _>(&CarHeader {
+        roots: Some(roots.to_vec()),
+        version: 1,
+    })?;
+
+    let mut bytes = encode_varint(header.len() as u64);
+    bytes.extend(header);
+
+    Ok(bytes)
+}
+
+/// Encode a single varint-prefixed CARv1 frame containing a block and its CID
+pub fn encode_car_block(cid: &Cid, block: &[u8]) -> Vec<u8> {
+    let cid_bytes = cid.to_bytes();
+    let mut bytes = encode_varint((cid_bytes.len() + block.len()) as u64);
+
+    bytes.extend(cid_bytes);
+    bytes.extend_from_slice(block);
+
+    bytes
+}
+
+/// Produce a stream of CARv1 bytes with the given roots, containing the given
+/// blocks in the order they are yielded. Each item in the stream is a single
+/// frame, so the blocks are only encoded as the stream is consumed.
+pub fn car_stream<I>(roots: &[Cid], blocks: I) -> Result<impl Stream<Item = Result<Vec<u8>>>>
+where
+    I: IntoIterator<Item = (Cid, Vec<u8>)>,
+{
+    let header = encode_car_header(roots)?;
+
+    Ok(stream::iter(
+        std::iter::once(Ok(header)).chain(
+            blocks
+                .into_iter()
+                .map(|(cid, block)| Ok(encode_car_block(&cid, &block))),
+        ),
+    ))
+}
+
+/// Same as [car_stream], except that the blocks are yielded by a [Stream], so
+/// that they can be read out of storage as the CAR is consumed
+pub fn car_stream_from_blocks<B>(
+    roots: &[Cid],
+    blocks: B,
+) -> Result<impl Stream<Item = Result<Vec<u8>>>>
+where
+    B: Stream<Item = Result<(Cid, Vec<u8>)>>,
+{
+    let header = encode_car_header(roots)?;
+
+    Ok(
+        stream::once(async { Ok(header) }).chain(blocks.map(|block| {
+            let (cid, block) = block?;
+            Ok(encode_car_block(&cid, &block))
+        })),
+    )
+}
+
+fn encode_varint(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// A [CarReader] incrementally reads blocks out of a CAR (either
+/// [CARv1](https://ipld.io/specs/transport/car/carv1/) or
+/// [CARv2](https://ipld.io/specs/transport/car/carv2/)) from any
+/// [AsyncRead], so that a large CAR never needs to be held in memory all at
+/// once. Every block is verified against its CID as it is read (see
+/// [verify_block]), and an error is produced for any that does not match.
+pub struct CarReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    reader: R,
+    roots: Vec<Cid>,
+    remaining: Option<u64>,
+}
+
+impl<R> CarReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Read the header of the CAR, leaving the reader positioned at the first
+    /// block. In the case of a CARv2, the characteristics and index are
+    /// ignored and only the inner CARv1 payload is read.
+    pub async fn new(reader: R) -> Result<Self> {
+        let mut car = CarReader {
+            reader,
+            roots: Vec::new(),
+            remaining: None,
+        };
+
+        let (header, header_size) = car.read_header().await?;
+
+        let header = match header.version {
+            1 => header,
+            2 => {
+                let mut v2_header = [0u8; CAR_V2_HEADER_SIZE];
+                car.read_exact(&mut v2_header).await?;
+
+                let data_offset = read_u64_le(&v2_header[16..24]);
+                let data_size = read_u64_le(&v2_header[24..32]);
+                let consumed = header_size + CAR_V2_HEADER_SIZE as u64;
+
+                if data_offset < consumed {
+                    return Err(anyhow!("CARv2 data offset overlaps its header"));
+                }
+
+                car.skip(data_offset - consumed).await?;
+                car.remaining = Some(data_size);
+
+                let (inner_header, _) = car.read_header().await?;
+
+                if inner_header.version != 1 {
+                    return Err(anyhow!(
+                        "Expected CARv2 payload to be a CARv1, but it was version {}",
+                        inner_header.version
+                    ));
+                }
+
+                inner_header
+            }
+            version => return Err(anyhow!("Unsupported CAR version {}", version)),
+        };
+
+        car.roots = header
+            .roots
+            .ok_or_else(|| anyhow!("CAR header does not specify any roots"))?;
+
+        Ok(car)
+    }
+
+    /// The roots declared in the header of the CAR
+    pub fn roots(&self) -> &[Cid] {
+        &self.roots
+    }
+
+    /// Read the next block from the CAR, returning `None` once there are no
+    /// more blocks to read. If the block does not match its CID, the error
+    /// that is returned can be downcast to a [BlockIntegrityError].
+    pub async fn next_block(&mut self) -> Result<Option<(Cid, Vec<u8>)>> {
+        let frame_size = match self.read_varint().await? {
+            Some(frame_size) => frame_size,
+            None => return Ok(None),
+        };
+
+        if frame_size > MAX_CAR_FRAME_SIZE {
+            return Err(anyhow!(
+                "CAR frame of {} bytes exceeds the maximum of {} bytes",
+                frame_size,
+                MAX_CAR_FRAME_SIZE
+            ));
+        }
+
+        let mut frame = vec![0u8; frame_size as usize];
+        self.read_exact(&mut frame).await?;
+
+        let mut cursor = Cursor::new(&frame);
+        let cid = Cid::read_bytes(&mut cursor)?;
+        let block = frame[cursor.position() as usize..].to_vec();
+
+        verify_block(&cid, &block)?;
+
+        Ok(Some((cid, block)))
+    }
+
+    async fn read_header(&mut self) -> Result<(CarHeader, u64)> {
+        let header_length = self
+            .read_varint()
+            .await?
+            .ok_or_else(|| anyhow!("CAR is empty"))?;
+
+        if header_length > MAX_CAR_FRAME_SIZE {
+            return Err(anyhow!("CAR header is too large"));
+        }
+
+        let mut header_bytes = vec![0u8; header_length as usize];
+        self.read_exact(&mut header_bytes).await?;
+
+        let header = block_deserialize::<DagCborCodec, CarHeader>(&header_bytes)?;
+        let header_size = encode_varint(header_length).len() as u64 + header_length;
+
+        Ok((header, header_size))
+    }
+
+    /// Reads an unsigned LEB128 varint; returns `None` if the end of the
+    /// CAR is reached before the first byte.
+    async fn read_varint(&mut self) -> Result<Option<u64>> {
+        let mut value = 0u64;
+
+        for (index, shift) in (0..64).step_by(7).enumerate() {
+            let mut byte = [0u8; 1];
+
+            if self.remaining == Some(0) {
+                return match index {
+                    0 => Ok(None),
+                    _ => Err(anyhow!("CAR ended in the middle of a varint")),
+                };
+            }
+
+            if self.reader.read(&mut byte).await? == 0 {
+                return match index {
+                    0 => Ok(None),
+                    _ => Err(anyhow!("CAR ended in the middle of a varint")),
+                };
+            }
+
+            self.consume(1)?;
+
+            value |= ((byte[0] & 0x7f) as u64) << shift;
+
+            if byte[0] & 0x80 == 0 {
+                return Ok(Some(value));
+            }
+        }
+
+        Err(anyhow!("CAR contains a varint that is too long"))
+    }
+
+    async fn read_exact(&mut self, buffer: &mut [u8]) -> Result<()> {
+        self.consume(buffer.len() as u64)?;
+        self.reader.read_exact(buffer).await?;
+        Ok(())
+    }
+
+    async fn skip(&mut self, length: u64) -> Result<()> {
+        let skipped =
+            futures::io::copy((&mut self.reader).take(length), &mut futures::io::sink()).await?;
+
+        if skipped != length {
+            return Err(anyhow!("CAR ended before the expected offset"));
+        }
+
+        Ok(())
+    }
+
+    fn consume(&mut self, length: u64) -> Result<()> {
+        if let Some(remaining) = self.remaining {
+            self.remaining = Some(
+                remaining
+                    .checked_sub(length)
+                    .ok_or_else(|| anyhow!("CAR frame exceeds the declared data size"))?,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn read_u64_le(bytes: &[u8]) -> u64 {
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(bytes);
+    u64::from_le_bytes(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+    use futures::{io::Cursor, TryStreamExt};
+    use libipld_cbor::DagCborCodec;
+    use libipld_core::raw::RawCodec;
+    use noosphere_storage::{block_serialize, derive_cid};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    use super::{
+        car_stream, car_stream_from_blocks, encode_car_block, encode_car_header, CarReader,
+    };
+
+    fn make_blocks() -> Vec<(Cid, Vec<u8>)> {
+        vec![
+            (derive_cid::<RawCodec>(b"foo"), b"foo".to_vec()),
+            block_serialize::<DagCborCodec, _>(&vec!["bar", "baz"]).unwrap(),
+        ]
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_round_trip_blocks_through_a_car_stream() {
+        let blocks = make_blocks();
+        let roots = vec![blocks[1].0];
+
+        let bytes = car_stream(&roots, blocks.clone())
+            .unwrap()
+            .try_concat()
+            .await
+            .unwrap();
+
+        let mut car = CarReader::new(Cursor::new(bytes)).await.unwrap();
+
+        assert_eq!(car.roots(), &roots[..]);
+
+        for expected in blocks {
+            assert_eq!(car.next_block().await.unwrap(), Some(expected));
+        }
+
+        assert_eq!(car.next_block().await.unwrap(), None);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_read_the_payload_of_a_car_v2() {
+        let blocks = make_blocks();
+        let roots = vec![blocks[0].0];
+
+        let mut payload = encode_car_header(&roots).unwrap();
+        for (cid, block) in blocks.iter() {
+            payload.extend(encode_car_block(cid, block));
+        }
+
+        let pragma: [u8; 11] = [
+            0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+        ];
+        let padding = 5u64;
+        let data_offset = pragma.len() as u64 + 40 + padding;
+
+        let mut bytes = pragma.to_vec();
+        bytes.extend([0u8; 16]);
+        bytes.extend(data_offset.to_le_bytes());
+        bytes.extend((payload.len() as u64).to_le_bytes());
+        bytes.extend(0u64.to_le_bytes());
+        bytes.extend(vec![0u8; padding as usize]);
+        bytes.extend(payload);
+        // Trailing bytes (e.g., an index) must not be read as blocks
+        bytes.extend([0xffu8; 7]);
+
+        let mut car = CarReader::new(Cursor::new(bytes)).await.unwrap();
+
+        assert_eq!(car.roots(), &roots[..]);
+
+        for expected in blocks {
+            assert_eq!(car.next_block().await.unwrap(), Some(expected));
+        }
+
+        assert_eq!(car.next_block().await.unwrap(), None);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_refuses_blocks_that_do_not_match_their_cid() {
+        let cid = derive_cid::<RawCodec>(b"foo");
+
+        let mut bytes = encode_car_header(&[cid]).unwrap();
+        bytes.extend(encode_car_block(&cid, b"bar"));
+
+        let mut car = CarReader::new(Cursor::new(bytes)).await.unwrap();
+
+        assert!(car.next_block().await.is_err());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_encodes_the_same_car_from_a_stream_of_blocks() {
+        let blocks = make_blocks();
+        let roots = vec![blocks[0].0];
+
+        let expected = car_stream(&roots, blocks.clone())
+            .unwrap()
+            .try_concat()
+            .await
+            .unwrap();
+
+        let actual =
+            car_stream_from_blocks(&roots, futures::stream::iter(blocks.into_iter().map(Ok)))
+                .unwrap()
+                .try_concat()
+                .await
+                .unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
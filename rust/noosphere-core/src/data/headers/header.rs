@@ -8,6 +8,7 @@ pub enum Header {
     Signature,
     Version,
     FileExtension,
+    FilePath,
     Unknown(String),
 }
 
@@ -21,6 +22,7 @@ impl Display for Header {
             Header::Signature => "Signature",
             Header::Version => "Version",
             Header::FileExtension => "File-Extension",
+            Header::FilePath => "File-Path",
             Header::Unknown(name) => name,
         };
 
@@ -35,6 +37,7 @@ impl FromStr for Header {
         Ok(match s.to_lowercase().as_str() {
             "content-type" => Header::ContentType,
             "file-extension" => Header::FileExtension,
+            "file-path" => Header::FilePath,
             "proof" => Header::Proof,
             "author" => Header::Author,
             "title" => Header::Title,
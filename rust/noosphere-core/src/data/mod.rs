@@ -2,12 +2,14 @@ mod address;
 mod authority;
 mod body_chunk;
 mod bundle;
+mod car;
 mod changelog;
 mod headers;
 mod links;
 mod memo;
 mod names;
 mod reference;
+mod sealed;
 mod sphere;
 mod strings;
 mod versioned_map;
@@ -16,12 +18,14 @@ pub use address::*;
 pub use authority::*;
 pub use body_chunk::*;
 pub use bundle::*;
+pub use car::*;
 pub use changelog::*;
 pub use headers::*;
 pub use links::*;
 pub use memo::*;
 pub use names::*;
 pub use reference::*;
+pub use sealed::*;
 pub use sphere::*;
 pub use strings::*;
 pub use versioned_map::*;
@@ -0,0 +1,275 @@
+use anyhow::{anyhow, Result};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use cid::Cid;
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret as X25519Secret};
+
+use crate::{
+    authority::{did_to_sealing_public_key, SealingKey},
+    data::VersionedMapIpld,
+};
+
+/// The sealed content of a sphere. Keys are opaque (randomly generated) so
+/// that they reveal nothing about the slugs they stand in for, and values are
+/// the CIDs of [EnvelopeIpld]s. The real slug of each entry is only stored
+/// inside of its envelope's ciphertext.
+pub type SealedIpld = VersionedMapIpld<String, Cid>;
+
+const CONTENT_KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const KEY_WRAPPING_INFO: &[u8] = b"noosphere-sealed-key";
+
+/// The plaintext that is sealed inside of an [EnvelopeIpld]; it carries the
+/// details of a memo (including the slug it is stored at) and its body
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SealedMemoIpld {
+    /// The slug that the sealed memo was written to
+    pub slug: String,
+    /// The headers of the sealed memo
+    pub headers: Vec<(String, String)>,
+    /// The (unchunked) body of the sealed memo
+    #[serde(with = "serde_bytes")]
+    pub body: Vec<u8>,
+}
+
+/// A copy of an envelope's content key, wrapped so that only one specific
+/// recipient is able to unwrap it
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RecipientIpld {
+    /// The DID of the key that is able to unwrap the content key
+    pub did: String,
+    /// The public half of the ephemeral X25519 key used to wrap the content key
+    #[serde(with = "serde_bytes")]
+    pub ephemeral_key: Vec<u8>,
+    /// The nonce used when wrapping the content key
+    #[serde(with = "serde_bytes")]
+    pub nonce: Vec<u8>,
+    /// The content key, encrypted for the recipient
+    #[serde(with = "serde_bytes")]
+    pub wrapped_key: Vec<u8>,
+}
+
+/// Encrypted content that may be stored in a sphere (and replicated to a
+/// gateway or IPFS) without revealing anything but its size and the DIDs of
+/// the keys that are able to open it. Content is encrypted with a random key
+/// using XChaCha20-Poly1305, and that key is wrapped once per recipient using
+/// an X25519 key agreement with the recipient's Ed25519 key.
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct EnvelopeIpld {
+    /// The nonce used when encrypting the content
+    #[serde(with = "serde_bytes")]
+    pub nonce: Vec<u8>,
+    /// The encrypted DAG-CBOR encoding of a [SealedMemoIpld]
+    #[serde(with = "serde_bytes")]
+    pub ciphertext: Vec<u8>,
+    /// The keys that are able to open the envelope
+    pub recipients: Vec<RecipientIpld>,
+}
+
+impl EnvelopeIpld {
+    /// Seal a [SealedMemoIpld] so that it may only be opened by the holders of
+    /// the keys referred to by the given DIDs
+    pub fn try_seal(memo: &SealedMemoIpld, recipients: &[String]) -> Result<Self> {
+        if recipients.is_empty() {
+            return Err(anyhow!("Sealed content must have at least one recipient"));
+        }
+
+        let mut content_key = [0u8; CONTENT_KEY_LENGTH];
+        rand::thread_rng().fill_bytes(&mut content_key);
+
+        let plaintext = serde_ipld_dagcbor::to_vec(memo)?;
+        let (nonce, ciphertext) = encrypt(&content_key, &plaintext)?;
+
+        let mut sealed_recipients = Vec::new();
+
+        for did in recipients {
+            let recipient_key = did_to_sealing_public_key(did)?;
+
+            let mut ephemeral_bytes = [0u8; CONTENT_KEY_LENGTH];
+            rand::thread_rng().fill_bytes(&mut ephemeral_bytes);
+            let ephemeral_secret = X25519Secret::from(ephemeral_bytes);
+            let ephemeral_key = X25519PublicKey::from(&ephemeral_secret);
+
+            let shared_secret = ephemeral_secret.diffie_hellman(&recipient_key);
+            let wrapping_key =
+                derive_wrapping_key(shared_secret.as_bytes(), &ephemeral_key, &recipient_key)?;
+            let (nonce, wrapped_key) = encrypt(&wrapping_key, &content_key)?;
+
+            sealed_recipients.push(RecipientIpld {
+                did: did.clone(),
+                ephemeral_key: ephemeral_key.as_bytes().to_vec(),
+                nonce,
+                wrapped_key,
+            });
+        }
+
+        Ok(EnvelopeIpld {
+            nonce,
+            ciphertext,
+            recipients: sealed_recipients,
+        })
+    }
+
+    /// Returns true if the key referred to by the given DID is able to open
+    /// this envelope
+    pub fn is_recipient(&self, did: &str) -> bool {
+        self.recipients.iter().any(|recipient| recipient.did == did)
+    }
+
+    /// The DIDs of all of the keys that are able to open this envelope
+    pub fn recipient_dids(&self) -> Vec<String> {
+        self.recipients
+            .iter()
+            .map(|recipient| recipient.did.clone())
+            .collect()
+    }
+
+    /// Open the envelope with the given key, producing the [SealedMemoIpld]
+    /// that was sealed inside of it
+    pub async fn try_open<K: SealingKey>(&self, key: &K) -> Result<SealedMemoIpld> {
+        let did = key.get_did().await?;
+        let recipient = self
+            .recipients
+            .iter()
+            .find(|recipient| recipient.did == did)
+            .ok_or_else(|| anyhow!("{} is not a recipient of the sealed content", did))?;
+
+        let secret = key.try_sealing_secret()?;
+        let public_key = X25519PublicKey::from(&secret);
+        let ephemeral_key =
+            X25519PublicKey::from(to_array::<CONTENT_KEY_LENGTH>(&recipient.ephemeral_key)?);
+
+        let shared_secret = secret.diffie_hellman(&ephemeral_key);
+        let wrapping_key =
+            derive_wrapping_key(shared_secret.as_bytes(), &ephemeral_key, &public_key)?;
+        let content_key = decrypt(&wrapping_key, &recipient.nonce, &recipient.wrapped_key)?;
+        let plaintext = decrypt(
+            &to_array::<CONTENT_KEY_LENGTH>(&content_key)?,
+            &self.nonce,
+            &self.ciphertext,
+        )?;
+
+        Ok(serde_ipld_dagcbor::from_slice(&plaintext)?)
+    }
+}
+
+/// Derive a key for wrapping (or unwrapping) a content key from the secret
+/// that is shared via X25519 key agreement between an ephemeral key and a
+/// recipient's key
+fn derive_wrapping_key(
+    shared_secret: &[u8],
+    ephemeral_key: &X25519PublicKey,
+    recipient_key: &X25519PublicKey,
+) -> Result<[u8; CONTENT_KEY_LENGTH]> {
+    let mut salt = ephemeral_key.as_bytes().to_vec();
+    salt.extend_from_slice(recipient_key.as_bytes());
+
+    let mut wrapping_key = [0u8; CONTENT_KEY_LENGTH];
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+        .expand(KEY_WRAPPING_INFO, &mut wrapping_key)
+        .map_err(|error| anyhow!("Failed to derive wrapping key: {}", error))?;
+
+    Ok(wrapping_key)
+}
+
+fn encrypt(key: &[u8; CONTENT_KEY_LENGTH], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut nonce = [0u8; NONCE_LENGTH];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|error| anyhow!("Failed to encrypt sealed content: {}", error))?;
+
+    Ok((nonce.to_vec(), ciphertext))
+}
+
+fn decrypt(key: &[u8; CONTENT_KEY_LENGTH], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    if nonce.len() != NONCE_LENGTH {
+        return Err(anyhow!("Sealed content has an invalid nonce"));
+    }
+
+    XChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|error| anyhow!("Failed to decrypt sealed content: {}", error))
+}
+
+fn to_array<const N: usize>(bytes: &[u8]) -> Result<[u8; N]> {
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Expected {} bytes but got {}", N, bytes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ucan::crypto::KeyMaterial;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    use crate::authority::generate_ed25519_key;
+
+    use super::{EnvelopeIpld, SealedMemoIpld};
+
+    fn memo() -> SealedMemoIpld {
+        SealedMemoIpld {
+            slug: "diary".into(),
+            headers: vec![("Content-Type".into(), "text/subtext".into())],
+            body: b"Dear diary...".to_vec(),
+        }
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_be_opened_by_each_of_its_recipients() {
+        let alice = generate_ed25519_key();
+        let bob = generate_ed25519_key();
+
+        let envelope = EnvelopeIpld::try_seal(
+            &memo(),
+            &[alice.get_did().await.unwrap(), bob.get_did().await.unwrap()],
+        )
+        .unwrap();
+
+        assert_eq!(envelope.try_open(&alice).await.unwrap(), memo());
+        assert_eq!(envelope.try_open(&bob).await.unwrap(), memo());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_cannot_be_opened_by_a_key_that_is_not_a_recipient() {
+        let alice = generate_ed25519_key();
+        let mallory = generate_ed25519_key();
+
+        let envelope = EnvelopeIpld::try_seal(&memo(), &[alice.get_did().await.unwrap()]).unwrap();
+
+        assert!(envelope.try_open(&mallory).await.is_err());
+
+        // Even if the recipient list is tampered with, the wrapped key is
+        // useless to anyone but its intended recipient
+        let mut tampered = envelope.clone();
+        tampered.recipients[0].did = mallory.get_did().await.unwrap();
+
+        assert!(tampered.try_open(&mallory).await.is_err());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_does_not_include_the_plaintext_in_the_envelope() {
+        let alice = generate_ed25519_key();
+        let envelope = EnvelopeIpld::try_seal(&memo(), &[alice.get_did().await.unwrap()]).unwrap();
+        let bytes = serde_ipld_dagcbor::to_vec(&envelope).unwrap();
+
+        let needle = b"Dear diary";
+        assert!(!bytes.windows(needle.len()).any(|window| window == needle));
+        let needle = b"diary";
+        assert!(!bytes.windows(needle.len()).any(|window| window == needle));
+    }
+}
@@ -87,6 +87,7 @@ mod tests {
             with: With::Resource {
                 kind: Resource::Scoped(SphereReference {
                     did: identity_did.to_string(),
+                    path: None,
                 }),
             },
             can: SphereAction::Authorize,
@@ -164,6 +165,7 @@ mod tests {
             with: With::Resource {
                 kind: Resource::Scoped(SphereReference {
                     did: identity_did.to_string(),
+                    path: None,
                 }),
             },
             can: SphereAction::Authorize,
@@ -0,0 +1,49 @@
+use cid::Cid;
+
+/// A [LinkConflict] describes a slug whose link was changed in two divergent
+/// lineages of the same sphere since their common ancestor, where the two
+/// lineages do not agree on what the slug should now point to. A value of
+/// `None` means that the slug was unassigned (or never assigned) at that point
+/// in the respective lineage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkConflict {
+    /// The slug that both lineages have changed
+    pub slug: String,
+    /// The memo CID the slug pointed to in the common ancestor
+    pub ancestor: Option<Cid>,
+    /// The memo CID the slug points to in the local ("mine") lineage
+    pub mine: Option<Cid>,
+    /// The memo CID the slug points to in the counterpart ("theirs") lineage
+    pub theirs: Option<Cid>,
+}
+
+/// The ways in which a [LinkConflict] may be resolved during a merge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Keep the local version of the content at the slug
+    KeepMine,
+    /// Keep the counterpart's version of the content at the slug
+    KeepTheirs,
+    /// Keep the counterpart's version of the content at the slug, and keep
+    /// the local version at a new slug derived from the original one
+    KeepBoth,
+}
+
+/// The outcome of resolving one [LinkConflict] during a merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedConflict {
+    pub conflict: LinkConflict,
+    pub resolution: ConflictResolution,
+    /// If the local version was kept under a different slug, this is that slug
+    pub kept_mine_as: Option<String>,
+}
+
+/// Produce a slug for the local version of conflicted content when both
+/// versions are kept; the `attempt` distinguishes successive candidates in
+/// case an earlier one is already taken
+pub fn conflict_slug(slug: &str, attempt: usize) -> String {
+    match attempt {
+        0 | 1 => format!("{}-conflict", slug),
+        attempt => format!("{}-conflict-{}", slug, attempt),
+    }
+}
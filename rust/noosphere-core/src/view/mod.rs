@@ -1,10 +1,12 @@
 mod authority;
+mod merge;
 mod mutation;
 mod sphere;
 mod timeline;
 mod versioned_map;
 
 pub use authority::*;
+pub use merge::*;
 pub use mutation::*;
 pub use sphere::*;
 pub use timeline::*;
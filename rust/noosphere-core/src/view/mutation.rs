@@ -35,6 +35,7 @@ pub struct SphereMutation {
     did: String,
     links: LinksMutation,
     names: NamesMutation,
+    sealed: SealedMutation,
     allowed_ucans: AllowedUcansMutation,
     revoked_ucans: RevokedUcansMutation,
 }
@@ -45,6 +46,7 @@ impl<'a> SphereMutation {
             did: did.into(),
             links: LinksMutation::new(did),
             names: NamesMutation::new(did),
+            sealed: SealedMutation::new(did),
             allowed_ucans: AllowedUcansMutation::new(did),
             revoked_ucans: RevokedUcansMutation::new(did),
         }
@@ -70,6 +72,14 @@ impl<'a> SphereMutation {
         &self.names
     }
 
+    pub fn sealed_mut(&mut self) -> &mut SealedMutation {
+        &mut self.sealed
+    }
+
+    pub fn sealed(&self) -> &SealedMutation {
+        &self.sealed
+    }
+
     pub fn allowed_ucans_mut(&mut self) -> &mut AllowedUcansMutation {
         &mut self.allowed_ucans
     }
@@ -88,6 +98,8 @@ impl<'a> SphereMutation {
 
     pub fn is_empty(&self) -> bool {
         self.links.changes.len() == 0
+            && self.names.changes.len() == 0
+            && self.sealed.changes.len() == 0
             && self.allowed_ucans.changes.len() == 0
             && self.revoked_ucans.changes.len() == 0
     }
@@ -95,6 +107,7 @@ impl<'a> SphereMutation {
 
 pub type LinksMutation = VersionedMapMutation<String, Cid>;
 pub type NamesMutation = VersionedMapMutation<String, AddressIpld>;
+pub type SealedMutation = VersionedMapMutation<String, Cid>;
 pub type AllowedUcansMutation = VersionedMapMutation<CidKey, DelegationIpld>;
 pub type RevokedUcansMutation = VersionedMapMutation<CidKey, RevocationIpld>;
 
@@ -1,7 +1,9 @@
+use std::collections::BTreeSet;
+
 use anyhow::{anyhow, Result};
 use async_stream::try_stream;
 use cid::Cid;
-use futures::Stream;
+use futures::{pin_mut, Stream, TryStreamExt};
 use libipld_cbor::DagCborCodec;
 
 use ucan::{
@@ -14,19 +16,23 @@ use ucan::{
 
 use crate::{
     authority::{
-        ed25519_key_to_mnemonic, generate_ed25519_key, restore_ed25519_key, Authorization,
-        SphereAction, SphereReference, SPHERE_SEMANTICS,
+        ed25519_key_to_mnemonic, generate_ed25519_key, restore_ed25519_key, try_collect_proof_cids,
+        Authorization, RevocationReport, RevokedDelegation, SphereAction, SphereReference,
+        SPHERE_SEMANTICS,
     },
     data::{
         AuthorityIpld, Bundle, ChangelogIpld, CidKey, ContentType, DelegationIpld, Did, Header,
         MapOperation, MemoIpld, RevocationIpld, SphereIpld, TryBundle, Version,
     },
-    view::{Links, SphereMutation, SphereRevision, Timeline},
+    view::{
+        conflict_slug, ConflictResolution, LinkConflict, Links, ResolvedConflict, SphereMutation,
+        SphereRevision, Timeline,
+    },
 };
 
 use noosphere_storage::{BlockStore, UcanStore};
 
-use super::{AllowedUcans, Authority, Names, RevokedUcans};
+use super::{AllowedUcans, Authority, Names, RevokedUcans, Sealed};
 
 pub const SPHERE_LIFETIME: u64 = 315360000000; // 10,000 years (arbitrarily high)
 
@@ -81,6 +87,34 @@ impl<S: BlockStore> Sphere<S> {
         .await
     }
 
+    /// Same as [Sphere::try_bundle_until_ancestor], except that the blocks
+    /// are streamed one revision at a time rather than collected into a
+    /// [Bundle]; a block that is shared by several revisions is only yielded
+    /// once.
+    pub fn stream_blocks_until_ancestor(
+        &self,
+        cid: Option<&Cid>,
+    ) -> impl Stream<Item = Result<(Cid, Vec<u8>)>> {
+        let store = self.store.clone();
+        let future = self.cid;
+        let past = cid.cloned();
+
+        try_stream! {
+            let timeline = Timeline::new(&store);
+            let mut yielded = BTreeSet::new();
+
+            for await revision in timeline.try_stream(&future, past.as_ref()) {
+                let (_, memo) = revision?;
+
+                for (cid, block) in memo.try_bundle(&store).await?.into_blocks()? {
+                    if yielded.insert(cid) {
+                        yield (cid, block);
+                    }
+                }
+            }
+        }
+    }
+
     /// Get a [Sphere] view over the parent revision of the sphere relative to
     /// this revision, if one exists
     pub async fn try_get_parent(&self) -> Result<Option<Sphere<S>>> {
@@ -117,6 +151,15 @@ impl<S: BlockStore> Sphere<S> {
         Names::try_at_or_empty(sphere.names.as_ref(), &mut self.store.clone()).await
     }
 
+    /// Attempt to load the [Sealed] content of this sphere. If no sealed
+    /// content has been added to this sphere yet, this initializes an empty
+    /// [Sealed] and returns it for the caller to populate.
+    pub async fn try_get_sealed(&self) -> Result<Sealed<S>> {
+        let sphere = self.try_as_body().await?;
+
+        Sealed::try_at_or_empty(sphere.sealed.as_ref(), &mut self.store.clone()).await
+    }
+
     /// Get the [Did] identity of the sphere
     pub async fn try_get_identity(&self) -> Result<Did> {
         let sphere = self.try_as_body().await?;
@@ -167,6 +210,21 @@ impl<S: BlockStore> Sphere<S> {
             mutation.names_mut().try_apply_changelog(changelog)?;
         }
 
+        let parent_sealed = parent.try_get_sealed().await?;
+        let sealed = self.try_get_sealed().await?;
+
+        if sealed.cid() != parent_sealed.cid() {
+            let changelog = sealed.try_get_changelog().await?;
+
+            if changelog.is_empty() {
+                return Err(anyhow!(
+                    "Sealed content has changed but the changelog is empty"
+                ));
+            }
+
+            mutation.sealed_mut().try_apply_changelog(changelog)?;
+        }
+
         let parent_authorization = parent.try_get_authority().await?;
         let authorization = self.try_get_authority().await?;
 
@@ -220,6 +278,7 @@ impl<S: BlockStore> Sphere<S> {
     ) -> Result<SphereRevision<S>> {
         let links_mutation = mutation.links();
         let names_mutation = mutation.names();
+        let sealed_mutation = mutation.sealed();
 
         let mut memo = MemoIpld::branch_from(cid, store).await?;
         let mut sphere = store.load::<DagCborCodec, SphereIpld>(&memo.body).await?;
@@ -238,6 +297,13 @@ impl<S: BlockStore> Sphere<S> {
             false => sphere.names,
         };
 
+        sphere.sealed = match !sealed_mutation.changes().is_empty() {
+            true => Some(
+                Sealed::try_apply_with_cid(sphere.sealed.as_ref(), sealed_mutation, store).await?,
+            ),
+            false => sphere.sealed,
+        };
+
         let allowed_ucans_mutation = mutation.allowed_ucans();
         let revoked_ucans_mutation = mutation.revoked_ucans();
 
@@ -377,6 +443,187 @@ impl<S: BlockStore> Sphere<S> {
         Ok(next_base)
     }
 
+    /// Find the most recent revision that is shared by the lineage of this
+    /// sphere revision and the lineage of another revision of the same
+    /// sphere, if there is one.
+    pub async fn try_find_common_ancestor(&self, other: &Cid) -> Result<Option<Cid>> {
+        let timeline = Timeline::new(&self.store);
+        let mut lineage = BTreeSet::new();
+
+        {
+            let stream = timeline.try_stream(self.cid(), None);
+            pin_mut!(stream);
+
+            while let Some((cid, _)) = stream.try_next().await? {
+                lineage.insert(cid);
+            }
+        }
+
+        let stream = timeline.try_stream(other, None);
+        pin_mut!(stream);
+
+        while let Some((cid, _)) = stream.try_next().await? {
+            if lineage.contains(&cid) {
+                return Ok(Some(cid));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find all slugs that were changed both in the lineage leading up to this
+    /// revision and in the lineage leading up to some other revision since the
+    /// given common ancestor, where the two lineages disagree about what the
+    /// slug now refers to. A slug that one lineage changed and then changed
+    /// back is still a conflict, since replaying that lineage over the other
+    /// would undo the other's change.
+    pub async fn try_find_link_conflicts(
+        &self,
+        ancestor: &Cid,
+        theirs: &Cid,
+    ) -> Result<Vec<LinkConflict>> {
+        let changed_slugs = |sphere: Sphere<S>| async move {
+            let mut slugs = BTreeSet::new();
+            let stream = sphere.into_link_changelog_stream(Some(ancestor));
+            pin_mut!(stream);
+
+            while let Some((_, changelog)) = stream.try_next().await? {
+                for operation in changelog.changes {
+                    match operation {
+                        MapOperation::Add { key, .. } => slugs.insert(key),
+                        MapOperation::Remove { key } => slugs.insert(key),
+                    };
+                }
+            }
+
+            Ok(slugs) as Result<BTreeSet<String>>
+        };
+
+        let their_sphere = Sphere::at(theirs, &self.store);
+
+        let my_changes = changed_slugs(self.clone()).await?;
+        let their_changes = changed_slugs(their_sphere.clone()).await?;
+
+        let ancestor_links = Sphere::at(ancestor, &self.store).try_get_links().await?;
+        let my_links = self.try_get_links().await?;
+        let their_links = their_sphere.try_get_links().await?;
+
+        let mut conflicts = Vec::new();
+
+        for slug in my_changes.intersection(&their_changes) {
+            let ancestor = ancestor_links.get(slug).await?.cloned();
+            let mine = my_links.get(slug).await?.cloned();
+            let theirs = their_links.get(slug).await?.cloned();
+
+            if mine != theirs {
+                conflicts.push(LinkConflict {
+                    slug: slug.clone(),
+                    ancestor,
+                    mine,
+                    theirs,
+                });
+            }
+        }
+
+        Ok(conflicts)
+    }
+
+    /// Attempt to linearize the canonical history of the sphere in the same
+    /// way as [Sphere::try_sync], but without silently clobbering changes
+    /// that were made to the same slug in both lineages. Each conflicting slug
+    /// is passed to the `resolve` callback, and the chosen resolutions are
+    /// recorded in an additional revision on top of the rebased lineage. The
+    /// returned tuple includes the new tip of the lineage as well as a report
+    /// of every conflict and how it was resolved.
+    pub async fn try_merge<Credential, Resolve>(
+        &self,
+        theirs: &Cid,
+        credential: &Credential,
+        authorization: Option<&Authorization>,
+        resolve: Resolve,
+    ) -> Result<(Cid, Vec<ResolvedConflict>)>
+    where
+        Credential: KeyMaterial,
+        Resolve: Fn(&LinkConflict) -> ConflictResolution,
+    {
+        let ancestor = self
+            .try_find_common_ancestor(theirs)
+            .await?
+            .ok_or_else(|| anyhow!("Lineages to be merged have no common ancestor"))?;
+
+        let conflicts = self.try_find_link_conflicts(&ancestor, theirs).await?;
+
+        // NOTE: After the rebase the local changes have been replayed on top
+        // of the counterpart's changes, so the local version of every
+        // conflicting slug is the one that is assigned in the new tip
+        let rebased_tip = self
+            .try_sync(&ancestor, theirs, credential, authorization)
+            .await?;
+
+        if conflicts.is_empty() {
+            return Ok((rebased_tip, Vec::new()));
+        }
+
+        let rebased_links = Sphere::at(&rebased_tip, &self.store)
+            .try_get_links()
+            .await?;
+
+        let mut mutation = SphereMutation::new(&credential.get_did().await?);
+        let mut resolved_conflicts = Vec::new();
+
+        for conflict in conflicts {
+            let resolution = resolve(&conflict);
+            let mut kept_mine_as = None;
+
+            let keep_theirs = |mutation: &mut SphereMutation| match &conflict.theirs {
+                Some(cid) => mutation.links_mut().set(&conflict.slug, cid),
+                None => mutation.links_mut().remove(&conflict.slug),
+            };
+
+            match resolution {
+                ConflictResolution::KeepMine => (),
+                ConflictResolution::KeepTheirs => keep_theirs(&mut mutation),
+                ConflictResolution::KeepBoth => match (&conflict.mine, &conflict.theirs) {
+                    (Some(mine), Some(_)) => {
+                        let mut attempt = 0;
+                        let slug = loop {
+                            attempt += 1;
+                            let slug = conflict_slug(&conflict.slug, attempt);
+                            if rebased_links.get(&slug).await?.is_none() {
+                                break slug;
+                            }
+                        };
+
+                        keep_theirs(&mut mutation);
+                        mutation.links_mut().set(&slug, mine);
+                        kept_mine_as = Some(slug);
+                    }
+                    // One side removed the content and the other changed it,
+                    // so keep whichever version still exists
+                    (None, Some(_)) => keep_theirs(&mut mutation),
+                    _ => (),
+                },
+            };
+
+            resolved_conflicts.push(ResolvedConflict {
+                conflict,
+                resolution,
+                kept_mine_as,
+            });
+        }
+
+        if mutation.is_empty() {
+            return Ok((rebased_tip, resolved_conflicts));
+        }
+
+        let mut revision = Sphere::at(&rebased_tip, &self.store)
+            .try_apply_mutation(&mutation)
+            .await?;
+        let merged_tip = revision.try_sign(credential, authorization).await?;
+
+        Ok((merged_tip, resolved_conflicts))
+    }
+
     /// Generate a new sphere and assign a DID as its owner. The returned tuple
     /// includes the UCAN authorization that enables the owner to manage the
     /// the sphere, as well as a mnemonic string that should be stored side-band
@@ -413,6 +660,7 @@ impl<S: BlockStore> Sphere<S> {
             with: With::Resource {
                 kind: Resource::Scoped(SphereReference {
                     did: sphere_did.to_string(),
+                    path: None,
                 }),
             },
             can: SphereAction::Authorize,
@@ -493,6 +741,7 @@ impl<S: BlockStore> Sphere<S> {
             with: With::Resource {
                 kind: Resource::Scoped(SphereReference {
                     did: sphere_did.to_string(),
+                    path: None,
                 }),
             },
             can: SphereAction::Authorize,
@@ -547,6 +796,70 @@ impl<S: BlockStore> Sphere<S> {
         ))
     }
 
+    /// Revoke the delegation identified by the given [Cid], as well as every
+    /// allowed delegation in the sphere that was derived from it. The
+    /// revocations are added to the given [SphereMutation], and a report of
+    /// every delegation that was revoked is returned.
+    pub async fn try_revoke_transitively<K: KeyMaterial>(
+        &self,
+        cid: &Cid,
+        issuer: &K,
+        mutation: &mut SphereMutation,
+    ) -> Result<RevocationReport> {
+        let ucan_store = UcanStore(self.store.clone());
+        let allowed_ucans = self
+            .try_get_authority()
+            .await?
+            .try_get_allowed_ucans()
+            .await?;
+
+        let mut revoked_directly = None;
+        let mut revoked_transitively = Vec::new();
+
+        {
+            let mut stream = allowed_ucans.stream().await?;
+
+            while let Some((CidKey(delegation_cid), delegation)) = stream.try_next().await? {
+                let ucan = delegation.resolve_ucan(&self.store).await?;
+                let entry = RevokedDelegation {
+                    name: delegation.name.clone(),
+                    did: ucan.audience().to_string(),
+                    cid: *delegation_cid,
+                    derived_from: None,
+                };
+
+                if delegation_cid == cid {
+                    revoked_directly = Some(entry);
+                } else if try_collect_proof_cids(&ucan, &ucan_store)
+                    .await?
+                    .contains(cid)
+                {
+                    revoked_transitively.push(RevokedDelegation {
+                        derived_from: Some(*cid),
+                        ..entry
+                    });
+                }
+            }
+        }
+
+        let revoked_directly = revoked_directly
+            .ok_or_else(|| anyhow!("{} is not an allowed delegation of the sphere", cid))?;
+
+        let mut report = RevocationReport::default();
+
+        for delegation in std::iter::once(revoked_directly).chain(revoked_transitively) {
+            let key = CidKey(delegation.cid);
+            let revocation = RevocationIpld::try_revoke(&delegation.cid, issuer).await?;
+
+            mutation.allowed_ucans_mut().remove(&key);
+            mutation.revoked_ucans_mut().set(&key, &revocation);
+
+            report.revoked.push(delegation);
+        }
+
+        Ok(report)
+    }
+
     /// Consume the [Sphere] and get a [Stream] that yields the [ChangelogIpld]
     /// for content slugs at each version of the sphere.
     pub fn into_link_changelog_stream(
@@ -580,6 +893,8 @@ impl<S: BlockStore> Sphere<S> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+
     use cid::Cid;
     use libipld_core::raw::RawCodec;
     use serde_bytes::Bytes;
@@ -598,14 +913,16 @@ mod tests {
 
     use crate::{
         authority::{
-            ed25519_key_to_mnemonic, generate_ed25519_key, Authorization, SphereAction,
-            SphereReference, SUPPORTED_KEYS,
+            ed25519_key_to_mnemonic, generate_ed25519_key, verify_sphere_cid, Authorization,
+            SphereAction, SphereReference, SUPPORTED_KEYS,
         },
         data::{AddressIpld, Bundle, CidKey, DelegationIpld, RevocationIpld},
-        view::{Sphere, SphereMutation, Timeline, SPHERE_LIFETIME},
+        view::{
+            ConflictResolution, LinkConflict, Sphere, SphereMutation, Timeline, SPHERE_LIFETIME,
+        },
     };
 
-    use noosphere_storage::{BlockStore, MemoryStore, Store, UcanStore};
+    use noosphere_storage::{BlockStore, MemoryStorage, MemoryStore, SphereDb, Store, UcanStore};
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
@@ -781,6 +1098,7 @@ mod tests {
                     with: With::Resource {
                         kind: Resource::Scoped(SphereReference {
                             did: sphere.try_get_identity().await.unwrap().to_string(),
+                            path: None,
                         }),
                     },
                     can: SphereAction::Publish,
@@ -1088,6 +1406,45 @@ mod tests {
         store.expect_replica_in(&other_store).await.unwrap();
     }
 
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_streams_the_same_blocks_that_it_bundles() {
+        let mut store = MemoryStore::default();
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let (mut sphere, ucan, _) = Sphere::try_generate(&owner_did, &mut store).await.unwrap();
+        let mut revisions = Vec::new();
+
+        for i in 0..8u8 {
+            let mut mutation = SphereMutation::new(&owner_did);
+
+            mutation.links_mut().set(
+                &format!("key{}", i % 3),
+                &store.save::<RawCodec, _>(Bytes::new(&[i])).await.unwrap(),
+            );
+            let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+            let next_cid = revision.try_sign(&owner_key, Some(&ucan)).await.unwrap();
+            revisions.push(next_cid);
+            sphere = Sphere::at(&next_cid, &store);
+        }
+
+        for ancestor in [None, Some(&revisions[3])] {
+            let bundle = sphere.try_bundle_until_ancestor(ancestor).await.unwrap();
+            let mut stream = Box::pin(sphere.stream_blocks_until_ancestor(ancestor));
+            let mut streamed = BTreeSet::new();
+
+            while let Some(block) = stream.next().await {
+                let (cid, block) = block.unwrap();
+
+                assert!(streamed.insert(cid));
+                assert_eq!(bundle.map().get(&cid.to_string()), Some(&block));
+            }
+
+            assert_eq!(streamed.len(), bundle.len());
+        }
+    }
+
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
     async fn it_can_hydrate_revisions_of_authorization_changes() {
@@ -1268,4 +1625,484 @@ mod tests {
             .await
             .unwrap();
     }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_reports_a_conflict_when_a_local_change_was_reverted() {
+        let mut store = MemoryStore::default();
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let foo_cid = store.save::<RawCodec, _>(Bytes::new(b"foo")).await.unwrap();
+        let bar_cid = store.save::<RawCodec, _>(Bytes::new(b"bar")).await.unwrap();
+        let flurb_cid = store
+            .save::<RawCodec, _>(Bytes::new(b"flurb"))
+            .await
+            .unwrap();
+
+        let (sphere, authorization, _) =
+            Sphere::try_generate(&owner_did, &mut store).await.unwrap();
+
+        let set_foo = |base_cid: Cid, cid: Cid| {
+            let mut store = store.clone();
+            let owner_did = owner_did.clone();
+            let owner_key = owner_key.clone();
+            let authorization = authorization.clone();
+
+            async move {
+                let mut mutation = SphereMutation::new(&owner_did);
+                mutation.links_mut().set(&"foo".into(), &cid);
+
+                let mut revision =
+                    Sphere::try_apply_mutation_with_cid(&base_cid, &mutation, &mut store)
+                        .await
+                        .unwrap();
+
+                revision
+                    .try_sign(&owner_key, Some(&authorization))
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let base_cid = set_foo(*sphere.cid(), foo_cid).await;
+        let external_cid = set_foo(base_cid, bar_cid).await;
+
+        // The local lineage changes the slug and then changes it back
+        let local_cid = set_foo(base_cid, flurb_cid).await;
+        let local_cid = set_foo(local_cid, foo_cid).await;
+
+        let local_sphere = Sphere::at(&local_cid, &store);
+
+        let conflicts = local_sphere
+            .try_find_link_conflicts(&base_cid, &external_cid)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            conflicts,
+            vec![LinkConflict {
+                slug: "foo".into(),
+                ancestor: Some(foo_cid),
+                mine: Some(foo_cid),
+                theirs: Some(bar_cid),
+            }]
+        );
+
+        let (merged_cid, resolved) = local_sphere
+            .try_merge(&external_cid, &owner_key, Some(&authorization), |_| {
+                ConflictResolution::KeepTheirs
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.len(), 1);
+
+        let links = Sphere::at(&merged_cid, &store)
+            .try_get_links()
+            .await
+            .unwrap();
+
+        assert_eq!(links.get(&"foo".into()).await.unwrap(), Some(&bar_cid));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_reports_and_resolves_conflicts_when_merging_a_lineage() {
+        let mut store = MemoryStore::default();
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        async fn make_revision<Credential: KeyMaterial, Storage: Store>(
+            base_cid: &Cid,
+            author_did: &str,
+            credential: &Credential,
+            authorization: &Authorization,
+            store: &mut Storage,
+            (change_key, change_cid): (&str, &Cid),
+        ) -> anyhow::Result<Cid> {
+            let mut mutation = SphereMutation::new(author_did);
+            mutation.links_mut().set(&change_key.into(), change_cid);
+
+            let mut base_revision =
+                Sphere::try_apply_mutation_with_cid(base_cid, &mutation, store).await?;
+
+            base_revision
+                .try_sign(credential, Some(authorization))
+                .await
+        }
+
+        let foo_cid = store.save::<RawCodec, _>(Bytes::new(b"foo")).await.unwrap();
+        let bar_cid = store.save::<RawCodec, _>(Bytes::new(b"bar")).await.unwrap();
+        let baz_cid = store.save::<RawCodec, _>(Bytes::new(b"baz")).await.unwrap();
+        let flurb_cid = store
+            .save::<RawCodec, _>(Bytes::new(b"flurb"))
+            .await
+            .unwrap();
+
+        let (sphere, authorization, _) =
+            Sphere::try_generate(&owner_did, &mut store).await.unwrap();
+
+        let base_cid = make_revision(
+            sphere.cid(),
+            &owner_did,
+            &owner_key,
+            &authorization,
+            &mut store,
+            ("foo", &foo_cid),
+        )
+        .await
+        .unwrap();
+
+        let external_cid_a = make_revision(
+            &base_cid,
+            &owner_did,
+            &owner_key,
+            &authorization,
+            &mut store,
+            ("foo", &bar_cid),
+        )
+        .await
+        .unwrap();
+
+        let external_cid_b = make_revision(
+            &external_cid_a,
+            &owner_did,
+            &owner_key,
+            &authorization,
+            &mut store,
+            ("baz", &baz_cid),
+        )
+        .await
+        .unwrap();
+
+        let local_cid = make_revision(
+            &base_cid,
+            &owner_did,
+            &owner_key,
+            &authorization,
+            &mut store,
+            ("foo", &flurb_cid),
+        )
+        .await
+        .unwrap();
+
+        let local_sphere = Sphere::at(&local_cid, &store);
+
+        assert_eq!(
+            local_sphere
+                .try_find_common_ancestor(&external_cid_b)
+                .await
+                .unwrap(),
+            Some(base_cid)
+        );
+
+        let expected_conflict = LinkConflict {
+            slug: "foo".into(),
+            ancestor: Some(foo_cid),
+            mine: Some(flurb_cid),
+            theirs: Some(bar_cid),
+        };
+
+        let (merged_cid, resolved) = local_sphere
+            .try_merge(&external_cid_b, &owner_key, Some(&authorization), |_| {
+                ConflictResolution::KeepBoth
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].conflict, expected_conflict);
+        assert_eq!(resolved[0].kept_mine_as.as_deref(), Some("foo-conflict"));
+
+        let links = Sphere::at(&merged_cid, &store)
+            .try_get_links()
+            .await
+            .unwrap();
+
+        assert_eq!(links.get(&"foo".into()).await.unwrap(), Some(&bar_cid));
+        assert_eq!(
+            links.get(&"foo-conflict".into()).await.unwrap(),
+            Some(&flurb_cid)
+        );
+        assert_eq!(links.get(&"baz".into()).await.unwrap(), Some(&baz_cid));
+
+        let (merged_cid, resolved) = local_sphere
+            .try_merge(&external_cid_b, &owner_key, Some(&authorization), |_| {
+                ConflictResolution::KeepTheirs
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(resolved[0].kept_mine_as, None);
+
+        let links = Sphere::at(&merged_cid, &store)
+            .try_get_links()
+            .await
+            .unwrap();
+
+        assert_eq!(links.get(&"foo".into()).await.unwrap(), Some(&bar_cid));
+        assert_eq!(links.get(&"foo-conflict".into()).await.unwrap(), None);
+
+        let (merged_cid, _) = local_sphere
+            .try_merge(&external_cid_b, &owner_key, Some(&authorization), |_| {
+                ConflictResolution::KeepMine
+            })
+            .await
+            .unwrap();
+
+        let links = Sphere::at(&merged_cid, &store)
+            .try_get_links()
+            .await
+            .unwrap();
+
+        assert_eq!(links.get(&"foo".into()).await.unwrap(), Some(&flurb_cid));
+        assert_eq!(links.get(&"baz".into()).await.unwrap(), Some(&baz_cid));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_revokes_delegations_derived_from_a_revoked_delegation() {
+        let mut db = SphereDb::new(&MemoryStorage::default()).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+        let device_key = generate_ed25519_key();
+        let device_did = device_key.get_did().await.unwrap();
+        let child_key = generate_ed25519_key();
+        let child_did = child_key.get_did().await.unwrap();
+
+        let (sphere, authorization, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+        let owner_ucan = authorization.resolve_ucan(&db).await.unwrap();
+
+        let capability = Capability {
+            with: With::Resource {
+                kind: Resource::Scoped(SphereReference {
+                    did: sphere.try_get_identity().await.unwrap().to_string(),
+                    path: None,
+                }),
+            },
+            can: SphereAction::Authorize,
+        };
+
+        let device_ucan = UcanBuilder::default()
+            .issued_by(&owner_key)
+            .for_audience(&device_did)
+            .with_lifetime(SPHERE_LIFETIME)
+            .claiming_capability(&capability)
+            .witnessed_by(&owner_ucan)
+            .build()
+            .unwrap()
+            .sign()
+            .await
+            .unwrap();
+        let device_delegation =
+            DelegationIpld::try_register("device", &device_ucan.encode().unwrap(), &db)
+                .await
+                .unwrap();
+
+        // The device mints a delegation of its own for another key
+        let child_ucan = UcanBuilder::default()
+            .issued_by(&device_key)
+            .for_audience(&child_did)
+            .with_lifetime(SPHERE_LIFETIME)
+            .claiming_capability(&capability)
+            .witnessed_by(&device_ucan)
+            .build()
+            .unwrap()
+            .sign()
+            .await
+            .unwrap();
+        let child_delegation =
+            DelegationIpld::try_register("child", &child_ucan.encode().unwrap(), &db)
+                .await
+                .unwrap();
+
+        let mut mutation = SphereMutation::new(&owner_did);
+        for delegation in [&device_delegation, &child_delegation] {
+            mutation
+                .allowed_ucans_mut()
+                .set(&CidKey(delegation.jwt), delegation);
+        }
+
+        let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+        let sphere_cid = revision
+            .try_sign(&owner_key, Some(&authorization))
+            .await
+            .unwrap();
+        let sphere = Sphere::at(&sphere_cid, &db);
+
+        let foo_cid = db.save::<RawCodec, _>(Bytes::new(b"foo")).await.unwrap();
+        let child_authorization = Authorization::Cid(child_delegation.jwt);
+        let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+
+        let sign_as_child = |sphere: Sphere<SphereDb<MemoryStorage>>| {
+            let child_key = child_key.clone();
+            let child_did = child_did.clone();
+            let child_authorization = child_authorization.clone();
+
+            async move {
+                let mut mutation = SphereMutation::new(&child_did);
+                mutation.links_mut().set(&"foo".into(), &foo_cid);
+
+                let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+                revision
+                    .try_sign(&child_key, Some(&child_authorization))
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let before_revocation = sign_as_child(sphere.clone()).await;
+
+        verify_sphere_cid(&before_revocation, &db, &mut did_parser)
+            .await
+            .unwrap();
+
+        let mut mutation = SphereMutation::new(&owner_did);
+        let report = sphere
+            .try_revoke_transitively(&device_delegation.jwt, &owner_key, &mut mutation)
+            .await
+            .unwrap();
+
+        assert_eq!(report.revoked.len(), 2);
+        assert_eq!(report.revoked[0].cid, device_delegation.jwt);
+        assert_eq!(report.revoked[0].derived_from, None);
+        assert_eq!(report.revoked[1].cid, child_delegation.jwt);
+        assert_eq!(report.revoked[1].derived_from, Some(device_delegation.jwt));
+        assert_eq!(
+            report.affected_dids(),
+            BTreeSet::from([device_did.as_str(), child_did.as_str()])
+        );
+
+        let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+        let sphere_cid = revision
+            .try_sign(&owner_key, Some(&authorization))
+            .await
+            .unwrap();
+        let sphere = Sphere::at(&sphere_cid, &db);
+
+        let allowed_ucans = sphere
+            .try_get_authority()
+            .await
+            .unwrap()
+            .try_get_allowed_ucans()
+            .await
+            .unwrap();
+
+        assert!(allowed_ucans
+            .get(&CidKey(child_delegation.jwt))
+            .await
+            .unwrap()
+            .is_none());
+
+        let after_revocation = sign_as_child(sphere).await;
+
+        assert!(verify_sphere_cid(&after_revocation, &db, &mut did_parser)
+            .await
+            .is_err());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_refuses_a_revision_that_restores_a_revoked_delegation() {
+        let mut db = SphereDb::new(&MemoryStorage::default()).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+        let device_key = generate_ed25519_key();
+        let device_did = device_key.get_did().await.unwrap();
+
+        let (sphere, authorization, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+        let owner_ucan = authorization.resolve_ucan(&db).await.unwrap();
+
+        let capability = Capability {
+            with: With::Resource {
+                kind: Resource::Scoped(SphereReference {
+                    did: sphere.try_get_identity().await.unwrap().to_string(),
+                    path: None,
+                }),
+            },
+            can: SphereAction::Authorize,
+        };
+
+        let device_ucan = UcanBuilder::default()
+            .issued_by(&owner_key)
+            .for_audience(&device_did)
+            .with_lifetime(SPHERE_LIFETIME)
+            .claiming_capability(&capability)
+            .witnessed_by(&owner_ucan)
+            .build()
+            .unwrap()
+            .sign()
+            .await
+            .unwrap();
+        let device_delegation =
+            DelegationIpld::try_register("device", &device_ucan.encode().unwrap(), &db)
+                .await
+                .unwrap();
+
+        let mut mutation = SphereMutation::new(&owner_did);
+        mutation
+            .allowed_ucans_mut()
+            .set(&CidKey(device_delegation.jwt), &device_delegation);
+
+        let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+        let sphere_cid = revision
+            .try_sign(&owner_key, Some(&authorization))
+            .await
+            .unwrap();
+        let sphere = Sphere::at(&sphere_cid, &db);
+
+        let mut mutation = SphereMutation::new(&owner_did);
+        sphere
+            .try_revoke_transitively(&device_delegation.jwt, &owner_key, &mut mutation)
+            .await
+            .unwrap();
+
+        let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+        let sphere_cid = revision
+            .try_sign(&owner_key, Some(&authorization))
+            .await
+            .unwrap();
+        let sphere = Sphere::at(&sphere_cid, &db);
+
+        // The revoked device pushes a revision that un-revokes itself
+        let device_authorization = Authorization::Cid(device_delegation.jwt);
+        let mut mutation = SphereMutation::new(&device_did);
+        mutation
+            .revoked_ucans_mut()
+            .remove(&CidKey(device_delegation.jwt));
+        mutation
+            .allowed_ucans_mut()
+            .set(&CidKey(device_delegation.jwt), &device_delegation);
+
+        let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+        let unrevoked_cid = revision
+            .try_sign(&device_key, Some(&device_authorization))
+            .await
+            .unwrap();
+
+        let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+
+        assert!(verify_sphere_cid(&unrevoked_cid, &db, &mut did_parser)
+            .await
+            .is_err());
+
+        // Nor may the owner of the sphere restore a revoked delegation
+        let mut mutation = SphereMutation::new(&owner_did);
+        mutation
+            .revoked_ucans_mut()
+            .remove(&CidKey(device_delegation.jwt));
+
+        let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+        let unrevoked_cid = revision
+            .try_sign(&owner_key, Some(&authorization))
+            .await
+            .unwrap();
+
+        assert!(verify_sphere_cid(&unrevoked_cid, &db, &mut did_parser)
+            .await
+            .is_err());
+    }
 }
@@ -18,6 +18,7 @@ use super::VersionedMapMutation;
 
 pub type Links<S> = VersionedMap<String, Cid, S>;
 pub type Names<S> = VersionedMap<String, AddressIpld, S>;
+pub type Sealed<S> = VersionedMap<String, Cid, S>;
 pub type AllowedUcans<S> = VersionedMap<CidKey, DelegationIpld, S>;
 pub type RevokedUcans<S> = VersionedMap<CidKey, RevocationIpld, S>;
 
@@ -0,0 +1,166 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+};
+
+use anyhow::Result;
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use subtext::{block::Block, primitive::Entity, Peer, Slashlink};
+
+/// The prefix of the keys in [noosphere_storage::SphereDb] metadata that
+/// backlink indexes are cached under; the full key is the prefix followed by
+/// the CID of the sphere revision that the index describes
+pub const BACKLINK_INDEX_KEY_PREFIX: &str = "backlinks/";
+
+/// The furthest that we will look back through a sphere's history for a
+/// cached [BacklinkIndex] to build upon before indexing the whole sphere
+/// from scratch
+pub const MAX_BACKLINK_INDEX_DISTANCE: usize = 64;
+
+/// Get the metadata key that the [BacklinkIndex] for a given sphere revision
+/// is cached under
+pub fn backlink_index_key(revision: &Cid) -> String {
+    format!("{}{}", BACKLINK_INDEX_KEY_PREFIX, revision)
+}
+
+/// A [BacklinkIndex] records which slugs link to which other slugs within a
+/// single revision of a sphere. Only links to content in the same sphere are
+/// recorded (that is, slashlinks without a peer, and wikilinks).
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BacklinkIndex {
+    /// For each slug, the set of slugs that its content links to
+    pub links_to: BTreeMap<String, BTreeSet<String>>,
+    /// For each slug, the set of slugs whose content links to it
+    pub linked_from: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl BacklinkIndex {
+    /// The slugs whose content links to the given slug
+    pub fn backlinks(&self, slug: &str) -> BTreeSet<String> {
+        self.linked_from.get(slug).cloned().unwrap_or_default()
+    }
+
+    /// The slugs whose backlinks differ between this index and another one
+    pub fn changed_backlinks(&self, other: &BacklinkIndex) -> BTreeSet<String> {
+        self.linked_from
+            .keys()
+            .chain(other.linked_from.keys())
+            .filter(|slug| self.linked_from.get(*slug) != other.linked_from.get(*slug))
+            .cloned()
+            .collect()
+    }
+
+    /// Replace the outgoing links that are recorded for a slug
+    pub fn set_links(&mut self, slug: &str, links: BTreeSet<String>) {
+        self.remove(slug);
+
+        let links: BTreeSet<String> = links.into_iter().filter(|link| link != slug).collect();
+
+        if links.is_empty() {
+            return;
+        }
+
+        for link in links.iter() {
+            self.linked_from
+                .entry(link.clone())
+                .or_default()
+                .insert(slug.to_string());
+        }
+
+        self.links_to.insert(slug.to_string(), links);
+    }
+
+    /// Forget the outgoing links that are recorded for a slug, e.g., because
+    /// its content was removed from the sphere
+    pub fn remove(&mut self, slug: &str) {
+        let links = match self.links_to.remove(slug) {
+            Some(links) => links,
+            None => return,
+        };
+
+        for link in links {
+            if let Some(sources) = self.linked_from.get_mut(&link) {
+                sources.remove(slug);
+
+                if sources.is_empty() {
+                    self.linked_from.remove(&link);
+                }
+            }
+        }
+    }
+}
+
+/// Get the slugs of all the content in the same sphere that is linked to by
+/// some parsed Subtext
+pub fn subtext_links(blocks: &[Block<Entity>]) -> BTreeSet<String> {
+    let mut links = BTreeSet::new();
+
+    for block in blocks {
+        for entity in block.to_content_entities() {
+            let slug = match entity {
+                Entity::SlashLink(text) => match Slashlink::from_str(text.as_ref()) {
+                    Ok(Slashlink {
+                        peer: Peer::None,
+                        slug: Some(slug),
+                    }) => slug,
+                    _ => continue,
+                },
+                Entity::WikiLink(text) => match subtext::util::to_slug(text.as_ref()) {
+                    Ok(slug) => slug,
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+
+            links.insert(slug);
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    use super::BacklinkIndex;
+
+    fn slugs(slugs: &[&str]) -> BTreeSet<String> {
+        slugs.iter().map(|slug| slug.to_string()).collect()
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn it_updates_backlinks_when_outgoing_links_change() {
+        let mut index = BacklinkIndex::default();
+
+        index.set_links("cats", slugs(&["animals", "dogs", "cats"]));
+        index.set_links("dogs", slugs(&["animals"]));
+
+        assert_eq!(index.backlinks("animals"), slugs(&["cats", "dogs"]));
+        assert_eq!(index.backlinks("dogs"), slugs(&["cats"]));
+        assert_eq!(index.backlinks("cats"), slugs(&[]));
+
+        index.set_links("cats", slugs(&["dogs"]));
+
+        assert_eq!(index.backlinks("animals"), slugs(&["dogs"]));
+
+        let before_removal = index.clone();
+
+        index.remove("dogs");
+
+        assert_eq!(index.backlinks("animals"), slugs(&[]));
+        assert!(!index.linked_from.contains_key("animals"));
+        assert_eq!(
+            index.changed_backlinks(&before_removal),
+            slugs(&["animals"])
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use std::{collections::BTreeMap, io::Cursor, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use cid::Cid;
+use libipld_cbor::DagCborCodec;
+use libipld_core::{codec::Codec, ipld::Ipld};
+use noosphere_core::{
+    data::{ContentType, Header, MemoIpld},
+    view::Sphere,
+};
+use noosphere_storage::BlockStore;
+use subtext::{block::Block, primitive::Entity};
+use tokio_stream::StreamExt;
+
+use crate::BodyChunkDecoder;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub trait ContentDecoderConditionalSendSync: Send + Sync {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<D> ContentDecoderConditionalSendSync for D where D: Send + Sync {}
+
+#[cfg(target_arch = "wasm32")]
+pub trait ContentDecoderConditionalSendSync {}
+
+#[cfg(target_arch = "wasm32")]
+impl<D> ContentDecoderConditionalSendSync for D {}
+
+/// The contents of a file in a sphere, decoded according to its content type
+pub enum SphereContent<S: BlockStore> {
+    /// The parsed blocks of a `text/subtext` document
+    Subtext(Vec<Block<Entity>>),
+    /// The parsed value of an `application/json` document
+    Json(serde_json::Value),
+    /// The decoded IPLD of an `application/cbor` document
+    Cbor(Ipld),
+    /// A handle to a `noo/sphere` revision that is linked as a file
+    Sphere(Sphere<S>),
+    /// The raw bytes of any other kind of content
+    Bytes(Vec<u8>),
+}
+
+impl<S: BlockStore> SphereContent<S> {
+    /// The name of the kind of content, for use in error messages
+    fn kind(&self) -> &'static str {
+        match self {
+            SphereContent::Subtext(_) => "Subtext",
+            SphereContent::Json(_) => "JSON",
+            SphereContent::Cbor(_) => "CBOR",
+            SphereContent::Sphere(_) => "a sphere",
+            SphereContent::Bytes(_) => "bytes",
+        }
+    }
+
+    pub fn into_subtext(self) -> Result<Vec<Block<Entity>>> {
+        match self {
+            SphereContent::Subtext(blocks) => Ok(blocks),
+            other => Err(anyhow!("Expected Subtext but found {}", other.kind())),
+        }
+    }
+
+    pub fn into_json(self) -> Result<serde_json::Value> {
+        match self {
+            SphereContent::Json(value) => Ok(value),
+            other => Err(anyhow!("Expected JSON but found {}", other.kind())),
+        }
+    }
+
+    pub fn into_ipld(self) -> Result<Ipld> {
+        match self {
+            SphereContent::Cbor(ipld) => Ok(ipld),
+            other => Err(anyhow!("Expected CBOR but found {}", other.kind())),
+        }
+    }
+
+    pub fn into_sphere(self) -> Result<Sphere<S>> {
+        match self {
+            SphereContent::Sphere(sphere) => Ok(sphere),
+            other => Err(anyhow!("Expected a sphere but found {}", other.kind())),
+        }
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        match self {
+            SphereContent::Bytes(bytes) => Ok(bytes),
+            other => Err(anyhow!("Expected bytes but found {}", other.kind())),
+        }
+    }
+}
+
+/// A [ContentDecoder] turns the memo of a file in a sphere into a
+/// [SphereContent] that is appropriate for the file's content type.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait ContentDecoder<S: BlockStore>: ContentDecoderConditionalSendSync {
+    async fn decode(
+        &self,
+        memo_version: &Cid,
+        memo: &MemoIpld,
+        store: &S,
+    ) -> Result<SphereContent<S>>;
+}
+
+/// Read the whole (chunked) body of a memo into memory
+async fn read_body<S: BlockStore>(memo: &MemoIpld, store: &S) -> Result<Vec<u8>> {
+    let mut stream = BodyChunkDecoder(&memo.body, store).stream();
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = stream.try_next().await? {
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+/// Decodes `text/subtext` bodies into Subtext blocks
+pub struct SubtextDecoder;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<S: BlockStore> ContentDecoder<S> for SubtextDecoder {
+    async fn decode(
+        &self,
+        _memo_version: &Cid,
+        memo: &MemoIpld,
+        store: &S,
+    ) -> Result<SphereContent<S>> {
+        let bytes = read_body(memo, store).await?;
+        let stream = subtext::stream::<Block<Entity>, Entity, _>(Cursor::new(bytes)).await;
+
+        tokio::pin!(stream);
+
+        let mut blocks = Vec::new();
+
+        while let Some(block) = stream.next().await {
+            blocks.push(block.map_err(|error| anyhow!("Invalid Subtext: {:?}", error))?);
+        }
+
+        Ok(SphereContent::Subtext(blocks))
+    }
+}
+
+/// Decodes `application/json` bodies into JSON values
+pub struct JsonDecoder;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<S: BlockStore> ContentDecoder<S> for JsonDecoder {
+    async fn decode(
+        &self,
+        _memo_version: &Cid,
+        memo: &MemoIpld,
+        store: &S,
+    ) -> Result<SphereContent<S>> {
+        let bytes = read_body(memo, store).await?;
+
+        Ok(SphereContent::Json(serde_json::from_slice(&bytes)?))
+    }
+}
+
+/// Decodes `application/cbor` bodies into IPLD
+pub struct CborDecoder;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<S: BlockStore> ContentDecoder<S> for CborDecoder {
+    async fn decode(
+        &self,
+        _memo_version: &Cid,
+        memo: &MemoIpld,
+        store: &S,
+    ) -> Result<SphereContent<S>> {
+        let bytes = read_body(memo, store).await?;
+
+        Ok(SphereContent::Cbor(DagCborCodec.decode(&bytes)?))
+    }
+}
+
+/// Decodes `noo/sphere` memos into a [Sphere] handle; the memo of such a file
+/// is itself a revision of the linked sphere
+pub struct SphereDecoder;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<S: BlockStore> ContentDecoder<S> for SphereDecoder {
+    async fn decode(
+        &self,
+        memo_version: &Cid,
+        _memo: &MemoIpld,
+        store: &S,
+    ) -> Result<SphereContent<S>> {
+        Ok(SphereContent::Sphere(Sphere::at(memo_version, store)))
+    }
+}
+
+/// Reads bodies as raw bytes; this is used for any content type that does not
+/// have a more specific decoder
+pub struct BytesDecoder;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<S: BlockStore> ContentDecoder<S> for BytesDecoder {
+    async fn decode(
+        &self,
+        _memo_version: &Cid,
+        memo: &MemoIpld,
+        store: &S,
+    ) -> Result<SphereContent<S>> {
+        Ok(SphereContent::Bytes(read_body(memo, store).await?))
+    }
+}
+
+/// A registry of [ContentDecoder]s, keyed by the [ContentType] that they
+/// decode. The default registry knows how to decode all of the well-known
+/// content types; decoders may be added (or replaced) for any content type.
+#[derive(Clone)]
+pub struct ContentDecoderRegistry<S: BlockStore> {
+    decoders: BTreeMap<ContentType, Arc<dyn ContentDecoder<S>>>,
+    fallback: Arc<dyn ContentDecoder<S>>,
+}
+
+impl<S: BlockStore> Default for ContentDecoderRegistry<S> {
+    fn default() -> Self {
+        let mut registry = ContentDecoderRegistry {
+            decoders: BTreeMap::new(),
+            fallback: Arc::new(BytesDecoder),
+        };
+
+        registry.register(ContentType::Subtext, SubtextDecoder);
+        registry.register(ContentType::Json, JsonDecoder);
+        registry.register(ContentType::Cbor, CborDecoder);
+        registry.register(ContentType::Sphere, SphereDecoder);
+        registry.register(ContentType::Bytes, BytesDecoder);
+
+        registry
+    }
+}
+
+impl<S: BlockStore> ContentDecoderRegistry<S> {
+    /// Use the given decoder for all content of the given type, replacing any
+    /// decoder that was previously registered for it
+    pub fn register<D>(&mut self, content_type: ContentType, decoder: D)
+    where
+        D: ContentDecoder<S> + 'static,
+    {
+        self.decoders.insert(content_type, Arc::new(decoder));
+    }
+
+    /// Decode the memo found at the given [Cid] according to its content type
+    pub async fn decode(&self, memo_version: &Cid, store: &S) -> Result<SphereContent<S>> {
+        let memo = store.load::<DagCborCodec, MemoIpld>(memo_version).await?;
+        let content_type = memo
+            .get_first_header(&Header::ContentType.to_string())
+            .ok_or_else(|| anyhow!("No content type specified"))?;
+        let content_type = ContentType::from_str(&content_type)?;
+
+        let decoder = self.decoders.get(&content_type).unwrap_or(&self.fallback);
+
+        decoder.decode(memo_version, &memo, store).await
+    }
+}
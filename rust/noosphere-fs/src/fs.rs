@@ -2,21 +2,31 @@ use anyhow::{anyhow, Result};
 use async_stream::try_stream;
 use libipld_cbor::DagCborCodec;
 use noosphere_core::{
-    authority::{Access, Author},
-    data::{BodyChunkIpld, ContentType, Did, Header, MapOperation, MemoIpld},
+    authority::{Access, Author, SealingKey, SUPPORTED_KEYS},
+    data::{
+        AddressIpld, BodyChunkIpld, CidKey, ContentType, Did, EnvelopeIpld, Header, Jwt,
+        MapOperation, MemoIpld, SealedMemoIpld,
+    },
     view::{Sphere, SphereMutation},
 };
-use noosphere_storage::{BlockStore, SphereDb, Storage};
+use noosphere_storage::{BlockStore, KeyValueStore, SphereDb, Storage};
 use once_cell::sync::OnceCell;
-use std::{collections::BTreeSet, str::FromStr};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Cursor,
+    str::FromStr,
+};
 use tokio_stream::{Stream, StreamExt};
 use tokio_util::io::StreamReader;
-use ucan::crypto::KeyMaterial;
+use ucan::crypto::{did::DidParser, KeyMaterial};
 
 use cid::Cid;
 use tokio::io::{AsyncRead, AsyncReadExt};
 
-use crate::{BodyChunkDecoder, SphereFile};
+use crate::{
+    backlink_index_key, subtext_links, BacklinkIndex, BodyChunkDecoder, ContentDecoderRegistry,
+    SphereContent, SphereFile, MAX_BACKLINK_INDEX_DISTANCE,
+};
 
 /// SphereFs: An FS-like abstraction over Noosphere content.
 ///
@@ -37,6 +47,7 @@ where
     sphere_revision: Cid,
     db: SphereDb<S>,
     mutation: OnceCell<SphereMutation>,
+    decoders: ContentDecoderRegistry<SphereDb<S>>,
 }
 
 impl<S, K> Clone for SphereFs<S, K>
@@ -52,6 +63,7 @@ where
             sphere_revision: self.sphere_revision.clone(),
             db: self.db.clone(),
             mutation: OnceCell::new(),
+            decoders: self.decoders.clone(),
         }
     }
 }
@@ -73,6 +85,11 @@ where
         &self.sphere_revision
     }
 
+    /// The [Author] that this FS view reads and writes as
+    pub fn author(&self) -> &Author<K> {
+        &self.author
+    }
+
     /// Get a data view into the sphere at the current revision
     pub fn to_sphere(&self) -> Sphere<S::BlockStore> {
         Sphere::at(self.revision(), &self.db.to_block_store())
@@ -98,6 +115,31 @@ where
             .ok_or_else(|| anyhow!("Failed to initialize sphere mutation"))
     }
 
+    /// Produce an error result if the author's level of access does not allow
+    /// them to change the content at the given slug (or to change the sphere
+    /// in general, if no slug is given)
+    fn require_write_access(&self, slug: Option<&str>) -> Result<()> {
+        if self.access.can_write(slug) {
+            return Ok(());
+        }
+
+        Err(match (&self.access, slug) {
+            (Access::ScopedReadWrite(scopes), Some(slug)) => anyhow!(
+                "Cannot write to {:?}; author may only write to {}",
+                slug,
+                scopes
+                    .iter()
+                    .filter_map(|scope| scope.path.clone())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            (Access::ScopedReadWrite(_), None) => anyhow!(
+                "Cannot mutate sphere; author only has write access to some parts of its contents"
+            ),
+            _ => anyhow!("Cannot mutate sphere; author only has read access to its contents"),
+        })
+    }
+
     async fn get_file(&self, memo_revision: &Cid) -> Result<SphereFile<impl AsyncRead + Unpin>> {
         let memo = self
             .db
@@ -108,8 +150,9 @@ where
             None => None,
         };
 
+        // NOTE: Files are always read as raw body bytes here; use
+        // [SphereFs::read_content] for content-type aware decoding
         let stream = match content_type {
-            // TODO(#86): Content-type aware decoding of body bytes
             Some(_) => BodyChunkDecoder(&memo.body, &self.db).stream(),
             None => return Err(anyhow!("No content type specified")),
         };
@@ -153,6 +196,7 @@ where
             sphere_revision: *sphere_revision,
             access,
             mutation: OnceCell::new(),
+            decoders: ContentDecoderRegistry::default(),
         })
     }
 
@@ -193,6 +237,25 @@ where
         })
     }
 
+    /// Read the content associated with a given slug, decoded according to
+    /// its content type by the decoders registered with this view (see
+    /// [SphereFs::decoders_mut])
+    pub async fn read_content(&self, slug: &str) -> Result<Option<SphereContent<SphereDb<S>>>> {
+        let sphere = Sphere::at(&self.sphere_revision, &self.db);
+        let links = sphere.try_get_links().await?;
+
+        Ok(match links.get(&slug.to_string()).await? {
+            Some(content_cid) => Some(self.decoders.decode(content_cid, &self.db).await?),
+            None => None,
+        })
+    }
+
+    /// The registry of decoders used by [SphereFs::read_content]; decoders
+    /// may be added or replaced here to customize how content is decoded
+    pub fn decoders_mut(&mut self) -> &mut ContentDecoderRegistry<SphereDb<S>> {
+        &mut self.decoders
+    }
+
     /// Write to a slug in the sphere. In order to commit the change to the
     /// sphere, you must call save. You can buffer multiple writes before
     /// saving.
@@ -205,6 +268,7 @@ where
         mut value: R,
         additional_headers: Option<Vec<(String, String)>>,
     ) -> Result<Cid> {
+        self.require_write_access(Some(slug))?;
         self.require_mutation().await?;
 
         let mut bytes = Vec::new();
@@ -230,6 +294,7 @@ where
         body_cid: &Cid,
         additional_headers: Option<Vec<(String, String)>>,
     ) -> Result<Cid> {
+        self.require_write_access(Some(slug))?;
         self.require_mutation().await?;
 
         let current_file = self.read(slug).await?;
@@ -272,6 +337,7 @@ where
     /// The returned value is the CID previously associated with the slug, if
     /// any.
     pub async fn remove(&mut self, slug: &str) -> Result<Option<Cid>> {
+        self.require_write_access(Some(slug))?;
         self.require_mutation().await?;
 
         let current_file = self.read(slug).await?;
@@ -448,29 +514,633 @@ where
             }
         }
     }
+
+    /// Get the slugs of all the content in this revision of the sphere that
+    /// links to the content at the given slug (via a slashlink or wikilink in
+    /// a Subtext document).
+    ///
+    /// The backlinks of a revision are computed once and then cached in the
+    /// [SphereDb] metadata; when possible, the index for a revision is derived
+    /// from that of a recent ancestor by re-parsing only the content that
+    /// changed in between (after which the ancestor's index is discarded, so
+    /// that typically only the index of the latest revision is kept).
+    pub async fn backlinks(&self, slug: &str) -> Result<BTreeSet<String>> {
+        Ok(self.backlink_index().await?.backlinks(slug))
+    }
+
+    /// Get the [BacklinkIndex] for the revision of the sphere that this view
+    /// is pointing to; see [SphereFs::backlinks]
+    pub async fn backlink_index(&self) -> Result<BacklinkIndex> {
+        let key = backlink_index_key(&self.sphere_revision);
+
+        if let Some(index) = self.db.get_key::<_, BacklinkIndex>(&key).await? {
+            return Ok(index);
+        }
+
+        let mut db = self.db.clone();
+
+        let index = match self.find_ancestor_backlink_index().await? {
+            Some((ancestor, mut index)) => {
+                let changed_slugs = self.changes(Some(&ancestor)).await;
+
+                self.update_backlink_index(&mut index, &self.sphere_revision, changed_slugs)
+                    .await?;
+
+                db.unset_key(&backlink_index_key(&ancestor)).await?;
+
+                index
+            }
+            None => {
+                let mut index = BacklinkIndex::default();
+                let sphere = Sphere::at(&self.sphere_revision, &self.db);
+                let links = sphere.try_get_links().await?;
+                let mut stream = links.stream().await?;
+
+                while let Some((slug, content_cid)) = stream.try_next().await? {
+                    index.set_links(slug, self.get_outgoing_links(content_cid).await);
+                }
+
+                index
+            }
+        };
+
+        db.set_key(&key, &index).await?;
+
+        Ok(index)
+    }
+
+    /// Given the [BacklinkIndex] of the revision of the sphere that this view
+    /// is pointing to, derive the index of its parent revision (if it has
+    /// one) by re-parsing only the content that this revision changed. This
+    /// makes it cheap to get the backlinks of each revision while walking
+    /// back through history; the derived index is not cached.
+    pub async fn rewind_backlink_index(
+        &self,
+        mut index: BacklinkIndex,
+    ) -> Result<Option<(Cid, BacklinkIndex)>> {
+        let sphere = Sphere::at(&self.sphere_revision, &self.db);
+        let parent = match sphere.try_as_memo().await?.parent {
+            Some(parent) => parent,
+            None => return Ok(None),
+        };
+
+        let changed_slugs = sphere
+            .try_get_links()
+            .await?
+            .try_get_changelog()
+            .await?
+            .changes
+            .iter()
+            .map(|operation| match operation {
+                MapOperation::Add { key, .. } => key.clone(),
+                MapOperation::Remove { key } => key.clone(),
+            })
+            .collect();
+
+        self.update_backlink_index(&mut index, &parent, changed_slugs)
+            .await?;
+
+        Ok(Some((parent, index)))
+    }
+
+    /// Update a [BacklinkIndex] so that the outgoing links of the given slugs
+    /// are those of their content at the given revision of the sphere
+    async fn update_backlink_index(
+        &self,
+        index: &mut BacklinkIndex,
+        revision: &Cid,
+        changed_slugs: BTreeSet<String>,
+    ) -> Result<()> {
+        let sphere = Sphere::at(revision, &self.db);
+        let links = sphere.try_get_links().await?;
+
+        for slug in changed_slugs {
+            match links.get(&slug).await? {
+                Some(content_cid) => {
+                    index.set_links(&slug, self.get_outgoing_links(content_cid).await)
+                }
+                None => index.remove(&slug),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look back through recent history for a revision whose [BacklinkIndex]
+    /// has already been cached
+    async fn find_ancestor_backlink_index(&self) -> Result<Option<(Cid, BacklinkIndex)>> {
+        let mut sphere = Sphere::at(&self.sphere_revision, &self.db);
+
+        for _ in 0..MAX_BACKLINK_INDEX_DISTANCE {
+            sphere = match sphere.try_get_parent().await? {
+                Some(parent) => parent,
+                None => return Ok(None),
+            };
+
+            let key = backlink_index_key(sphere.cid());
+
+            if let Some(index) = self.db.get_key::<_, BacklinkIndex>(&key).await? {
+                return Ok(Some((*sphere.cid(), index)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get the slugs that the content with the given CID links to. Content
+    /// that is not Subtext (or that cannot be parsed) links to nothing.
+    async fn get_outgoing_links(&self, content_cid: &Cid) -> BTreeSet<String> {
+        let memo = match self.db.load::<DagCborCodec, MemoIpld>(content_cid).await {
+            Ok(memo) => memo,
+            Err(error) => {
+                warn!("Could not load content {}: {}", content_cid, error);
+                return BTreeSet::new();
+            }
+        };
+
+        if memo.content_type() != Some(ContentType::Subtext) {
+            return BTreeSet::new();
+        }
+
+        match self
+            .decoders
+            .decode(content_cid, &self.db)
+            .await
+            .and_then(|content| content.into_subtext())
+        {
+            Ok(blocks) => subtext_links(&blocks),
+            Err(error) => {
+                warn!("Could not parse links in {}: {}", content_cid, error);
+                BTreeSet::new()
+            }
+        }
+    }
+}
+
+/// The petnames of a sphere make up its address book: each petname is a human
+/// readable name for another sphere's identity, along with the last name
+/// system record that was resolved for that identity (if any). Like other
+/// changes, changes to petnames are buffered until [SphereFs::save] is called.
+impl<S, K> SphereFs<S, K>
+where
+    S: Storage,
+    K: KeyMaterial + Clone + 'static,
+{
+    /// Look up the address that is assigned to a petname as of this version of
+    /// the sphere
+    pub async fn get_petname(&self, name: &str) -> Result<Option<AddressIpld>> {
+        let names = self.to_sphere().try_get_names().await?;
+
+        Ok(names.get(&name.to_string()).await?.cloned())
+    }
+
+    /// Assign a petname to the sphere with the given identity. If the petname
+    /// already refers to that identity, its last known record is preserved;
+    /// otherwise the petname is re-assigned and must be resolved again.
+    pub async fn set_petname(&mut self, name: &str, identity: &Did) -> Result<()> {
+        self.require_write_access(None)?;
+
+        if name.is_empty() {
+            return Err(anyhow!("A petname must not be empty"));
+        }
+
+        let last_known_record = match self.get_petname(name).await? {
+            Some(address) if &address.identity == identity => address.last_known_record,
+            _ => None,
+        };
+
+        let mutation = self.require_mutation().await?;
+
+        mutation.names_mut().set(
+            &name.to_string(),
+            &AddressIpld {
+                identity: identity.clone(),
+                last_known_record,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Remove a petname from the address book, returning the identity it used
+    /// to refer to (if it was assigned)
+    pub async fn unset_petname(&mut self, name: &str) -> Result<Option<Did>> {
+        self.require_write_access(None)?;
+
+        Ok(match self.get_petname(name).await? {
+            Some(address) => {
+                let mutation = self.require_mutation().await?;
+                mutation.names_mut().remove(&name.to_string());
+
+                Some(address.identity)
+            }
+            None => None,
+        })
+    }
+
+    /// Record the latest name system record that has been resolved for the
+    /// identity that a petname refers to; the record is expected to have been
+    /// validated by the caller
+    pub async fn set_petname_record(&mut self, name: &str, record: &Jwt) -> Result<()> {
+        self.require_write_access(None)?;
+
+        let address = self
+            .get_petname(name)
+            .await?
+            .ok_or_else(|| anyhow!("There is no petname {:?}", name))?;
+
+        let mutation = self.require_mutation().await?;
+
+        mutation.names_mut().set(
+            &name.to_string(),
+            &AddressIpld {
+                identity: address.identity,
+                last_known_record: Some(record.clone()),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Get a [BTreeMap] of every petname in the address book (as of this
+    /// version of the sphere) and the address it is assigned to
+    pub async fn list_petnames(&self) -> Result<BTreeMap<String, AddressIpld>> {
+        let names = self.to_sphere().try_get_names().await?;
+        let mut entries = names.stream().await?;
+        let mut petnames = BTreeMap::new();
+
+        while let Some((name, address)) = entries.try_next().await? {
+            petnames.insert(name.clone(), address.clone());
+        }
+
+        Ok(petnames)
+    }
+
+    /// Get a read-only view into the sphere that a petname refers to, at the
+    /// revision linked by the last known record for that petname. Returns
+    /// `None` if the petname is not assigned, if it has never been resolved or
+    /// if its last known record does not pass verification.
+    ///
+    /// The blocks of the other sphere are read from the same storage as this
+    /// one. When that storage is a [noosphere_storage::KuboStorage] (see the
+    /// `From<(SphereDb<S>, &Url)>` conversion for [SphereDb]), any blocks that
+    /// are missing locally are fetched from the IPFS API it is configured with
+    /// (typically that of a gateway, which syndicates the blocks of the
+    /// spheres it hosts).
+    pub async fn traverse_by_petname(&self, petname: &str) -> Result<Option<SphereFs<S, K>>> {
+        let address = match self.get_petname(petname).await? {
+            Some(address) => address,
+            None => return Ok(None),
+        };
+
+        let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+        let sphere_revision = match address
+            .verify_linked_version(&self.db, &mut did_parser)
+            .await
+        {
+            Ok(Some(cid)) => cid,
+            Ok(None) => return Ok(None),
+            Err(error) => {
+                warn!(
+                    "Could not verify the last known record for @{}: {}",
+                    petname, error
+                );
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(SphereFs {
+            sphere_identity: address.identity,
+            author: self.author.clone(),
+            db: self.db.clone(),
+            sphere_revision,
+            access: Access::ReadOnly,
+            mutation: OnceCell::new(),
+            decoders: self.decoders.clone(),
+        }))
+    }
+
+    /// Resolve a slashlink-style path such as `@alice/@bob/notes` to the view of
+    /// the sphere that holds the content and the slug of the content within
+    /// it. Each leading `@petname` segment hops from the current sphere into
+    /// the sphere that the petname refers to (in the address book of the
+    /// sphere before it); the rest of the path is the slug. A path with no
+    /// petnames resolves to this view. Returns `None` if any of the petnames
+    /// along the way cannot be traversed.
+    pub async fn resolve_path(&self, path: &str) -> Result<Option<(SphereFs<S, K>, String)>> {
+        let mut segments = path.trim_start_matches('/').split('/').peekable();
+        let mut fs = self.clone();
+
+        while let Some(petname) = segments
+            .peek()
+            .and_then(|segment| segment.strip_prefix('@'))
+        {
+            fs = match fs.traverse_by_petname(petname).await? {
+                Some(fs) => fs,
+                None => return Ok(None),
+            };
+
+            segments.next();
+        }
+
+        let slug = segments.collect::<Vec<&str>>().join("/");
+
+        if slug.is_empty() {
+            return Err(anyhow!("No slug specified in path {:?}", path));
+        }
+
+        Ok(Some((fs, slug)))
+    }
+}
+
+/// The sealed namespace of a sphere works like its public namespace, except
+/// that every memo (including the slug it is stored at) is encrypted to the
+/// keys that are authorized to access the sphere. Only ciphertext is ever
+/// written to block storage, so sealed content may be replicated to a gateway
+/// (and syndicated to IPFS) without revealing anything about it.
+///
+/// Note that because slugs are themselves sealed, looking up sealed content by
+/// slug requires opening the envelopes in the sealed namespace one at a time.
+impl<S, K> SphereFs<S, K>
+where
+    S: Storage,
+    K: SealingKey + Clone + 'static,
+{
+    /// The DIDs of the keys that new sealed content will be encrypted to: the
+    /// author, plus every key with a live delegation in the sphere
+    async fn sealed_recipients(&self) -> Result<Vec<String>> {
+        let mut recipients = BTreeSet::from([self.author.identity().await?.to_string()]);
+
+        let authority = self.to_sphere().try_get_authority().await?;
+        let allowed_ucans = authority.try_get_allowed_ucans().await?;
+        let revoked_ucans = authority.try_get_revoked_ucans().await?;
+        let mut delegations = allowed_ucans.stream().await?;
+
+        while let Some((CidKey(jwt_cid), delegation)) = delegations.try_next().await? {
+            if revoked_ucans.get(&CidKey(*jwt_cid)).await?.is_some() {
+                continue;
+            }
+
+            let ucan = delegation.resolve_ucan(&self.db).await?;
+
+            if ucan.is_expired() {
+                continue;
+            }
+
+            if !ucan.audience().starts_with("did:key:") {
+                warn!(
+                    "Cannot seal content for {}; only did:key DIDs are supported",
+                    ucan.audience()
+                );
+                continue;
+            }
+
+            recipients.insert(ucan.audience().to_string());
+        }
+
+        Ok(recipients.into_iter().collect())
+    }
+
+    /// Open the envelope with the given CID, if the author is one of its
+    /// recipients
+    async fn open_sealed(
+        &self,
+        author_identity: &str,
+        envelope_cid: &Cid,
+    ) -> Result<Option<SealedMemoIpld>> {
+        let envelope = self
+            .db
+            .load::<DagCborCodec, EnvelopeIpld>(envelope_cid)
+            .await?;
+
+        if !envelope.is_recipient(author_identity) {
+            return Ok(None);
+        }
+
+        match envelope.try_open(&self.author.key).await {
+            Ok(memo) => Ok(Some(memo)),
+            Err(error) => {
+                warn!("Could not open sealed content {}: {}", envelope_cid, error);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Find the sealed entry for a slug at the revision of the sphere that
+    /// this view is pointing to, returning its opaque key, the CID of its
+    /// envelope and the opened contents of that envelope. Entries whose keys
+    /// are in `overridden` are skipped.
+    async fn find_sealed(
+        &self,
+        slug: &str,
+        overridden: &BTreeMap<String, Option<Cid>>,
+    ) -> Result<Option<(String, Cid, SealedMemoIpld)>> {
+        let author_identity = self.author.identity().await?;
+        let sealed = self.to_sphere().try_get_sealed().await?;
+        let mut entries = sealed.stream().await?;
+
+        while let Some((key, envelope_cid)) = entries.try_next().await? {
+            if overridden.contains_key(key) {
+                continue;
+            }
+
+            match self.open_sealed(&author_identity, envelope_cid).await? {
+                Some(memo) if memo.slug == slug => {
+                    return Ok(Some((key.clone(), *envelope_cid, memo)));
+                }
+                _ => (),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Same as [SphereFs::find_sealed], except that sealed changes which have
+    /// not been saved yet take precedence over the sphere's revision, so that
+    /// a slug that has been written is found at its pending key, and a slug
+    /// that has been removed is not found at all
+    async fn find_pending_sealed(
+        &self,
+        slug: &str,
+    ) -> Result<Option<(String, Cid, SealedMemoIpld)>> {
+        let mut pending = BTreeMap::new();
+
+        if let Some(mutation) = self.mutation.get() {
+            for change in mutation.sealed().changes() {
+                match change {
+                    MapOperation::Add { key, value } => pending.insert(key.clone(), Some(*value)),
+                    MapOperation::Remove { key } => pending.insert(key.clone(), None),
+                };
+            }
+        }
+
+        let author_identity = self.author.identity().await?;
+
+        for (key, envelope_cid) in pending.iter() {
+            let envelope_cid = match envelope_cid {
+                Some(envelope_cid) => envelope_cid,
+                None => continue,
+            };
+
+            match self.open_sealed(&author_identity, envelope_cid).await? {
+                Some(memo) if memo.slug == slug => {
+                    return Ok(Some((key.clone(), *envelope_cid, memo)));
+                }
+                _ => (),
+            }
+        }
+
+        self.find_sealed(slug, &pending).await
+    }
+
+    /// Read sealed content that is associated with a given slug at the
+    /// revision of the sphere that this view is pointing to. The memo of the
+    /// returned [SphereFile] is reconstructed from the sealed headers, and
+    /// its `memo_version` is the CID of the envelope that held it.
+    pub async fn read_sealed(&self, slug: &str) -> Result<Option<SphereFile<Cursor<Vec<u8>>>>> {
+        Ok(match self.find_sealed(slug, &BTreeMap::new()).await? {
+            Some((_, envelope_cid, sealed_memo)) => Some(SphereFile {
+                sphere_identity: self.sphere_identity.clone(),
+                sphere_version: self.sphere_revision,
+                memo_version: envelope_cid,
+                memo: MemoIpld {
+                    parent: None,
+                    headers: sealed_memo.headers,
+                    body: envelope_cid,
+                },
+                contents: Cursor::new(sealed_memo.body),
+            }),
+            None => None,
+        })
+    }
+
+    /// Write sealed content to a slug in the sphere's sealed namespace. The
+    /// content is encrypted to the author and to every other key that is
+    /// authorized to access the sphere. As with [SphereFs::write], the change
+    /// must be committed by calling save.
+    ///
+    /// The returned CID is a link to the envelope for the newly sealed content.
+    pub async fn write_sealed<R: AsyncRead + std::marker::Unpin>(
+        &mut self,
+        slug: &str,
+        content_type: &str,
+        mut value: R,
+        additional_headers: Option<Vec<(String, String)>>,
+    ) -> Result<Cid> {
+        // Keys in the sealed namespace are opaque, so writes to it cannot be
+        // scoped to a path and require write access to the whole sphere
+        self.require_write_access(None)?;
+        self.require_mutation().await?;
+
+        let mut body = Vec::new();
+        value.read_to_end(&mut body).await?;
+
+        let mut headers = additional_headers.unwrap_or_default();
+        headers.retain(|(name, _)| name != &Header::ContentType.to_string());
+        headers.push((Header::ContentType.to_string(), content_type.to_string()));
+
+        let envelope = EnvelopeIpld::try_seal(
+            &SealedMemoIpld {
+                slug: slug.to_string(),
+                headers,
+                body,
+            },
+            &self.sealed_recipients().await?,
+        )?;
+        let envelope_cid = self.db.save::<DagCborCodec, _>(&envelope).await?;
+
+        // Keys in the sealed namespace must not reveal anything about the
+        // content they refer to; the CID of the first envelope written for a
+        // slug serves as an opaque key that is stable across overwrites
+        let key = match self.find_pending_sealed(slug).await? {
+            Some((key, _, _)) => key,
+            None => envelope_cid.to_string(),
+        };
+
+        let mutation = self.require_mutation().await?;
+        mutation.sealed_mut().set(&key, &envelope_cid);
+
+        Ok(envelope_cid)
+    }
+
+    /// Unlinks a slug from the sealed namespace. In order to commit the
+    /// change, you must save. The returned value is the CID of the envelope
+    /// that was previously associated with the slug, if any.
+    pub async fn remove_sealed(&mut self, slug: &str) -> Result<Option<Cid>> {
+        self.require_write_access(None)?;
+        self.require_mutation().await?;
+
+        Ok(match self.find_pending_sealed(slug).await? {
+            Some((key, envelope_cid, _)) => {
+                let mutation = self.require_mutation().await?;
+                mutation.sealed_mut().remove(&key);
+
+                Some(envelope_cid)
+            }
+            None => None,
+        })
+    }
+
+    /// Get a [BTreeSet] whose members are all the slugs in the sealed
+    /// namespace (as of this version of the sphere) that the author is able
+    /// to open.
+    pub async fn list_sealed(&self) -> Result<BTreeSet<String>> {
+        let author_identity = self.author.identity().await?;
+        let sealed = self.to_sphere().try_get_sealed().await?;
+        let mut entries = sealed.stream().await?;
+        let mut slugs = BTreeSet::new();
+
+        while let Some((_, envelope_cid)) = entries.try_next().await? {
+            if let Some(memo) = self.open_sealed(&author_identity, envelope_cid).await? {
+                slugs.insert(memo.slug);
+            }
+        }
+
+        Ok(slugs)
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use std::collections::BTreeSet;
 
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use cid::Cid;
+    use libipld_cbor::DagCborCodec;
+    use libipld_core::{codec::Codec, ipld::Ipld};
     use noosphere_core::{
-        authority::{generate_ed25519_key, Author},
-        data::{ContentType, Header},
-        view::Sphere,
+        authority::{
+            generate_ed25519_key, verify_sphere_cid, Author, Authorization, SphereAction,
+            SphereReference, SUPPORTED_KEYS,
+        },
+        data::{CidKey, ContentType, DelegationIpld, Did, Header, Jwt, MemoIpld, TryBundle},
+        view::{Sphere, SphereMutation},
+    };
+    use noosphere_storage::{
+        BlockStore, KeyValueStore, MemoryStorage, MemoryStore, SphereDb, Storage, TrackingStorage,
     };
-    use noosphere_storage::MemoryStorage;
-    use noosphere_storage::{SphereDb, TrackingStorage};
+    use subtext::{block::Block, primitive::Entity};
     use tokio::io::AsyncReadExt;
     use tokio_stream::StreamExt;
-    use ucan::crypto::KeyMaterial;
+    use ucan::{
+        builder::UcanBuilder,
+        capability::{Capability, Resource, With},
+        crypto::{did::DidParser, KeyMaterial},
+        store::UcanJwtStore,
+    };
+    use ucan_key_support::ed25519::Ed25519KeyMaterial;
 
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::wasm_bindgen_test;
 
     wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
 
-    use crate::SphereFs;
+    use crate::{
+        backlink_index_key,
+        helpers::{make_name_record, make_sphere_fs},
+        BacklinkIndex, BytesDecoder, ContentDecoder, SphereContent, SphereFs,
+    };
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
@@ -914,6 +1584,29 @@ pub mod tests {
         assert_eq!(result.unwrap_err().to_string(), "No changes to save");
     }
 
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_save_a_mutation_that_only_changes_petnames() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let initial_revision = *fs.revision();
+
+        let alice = Did(generate_ed25519_key().get_did().await.unwrap());
+
+        fs.set_petname("alice", &alice).await.unwrap();
+
+        let new_revision = fs.save(None).await.unwrap();
+
+        assert_ne!(new_revision, initial_revision);
+        assert_eq!(
+            fs.get_petname("alice").await.unwrap().unwrap().identity,
+            alice
+        );
+        assert!(fs.list().await.is_empty());
+    }
+
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
     async fn it_can_stream_the_whole_index() {
@@ -972,4 +1665,963 @@ pub mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_write_sealed_content_and_read_it_back() {
+        let storage = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let (sphere, authorization, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+        let author = Author {
+            key: owner_key,
+            authorization: Some(authorization),
+        };
+
+        db.set_version(&sphere_identity, sphere.cid())
+            .await
+            .unwrap();
+
+        let mut fs = SphereFs::latest(&sphere_identity, &author, &db)
+            .await
+            .unwrap();
+
+        fs.write_sealed(
+            "diary",
+            &ContentType::Subtext.to_string(),
+            b"Dear diary...".as_ref(),
+            Some(vec![("Title".into(), "Secrets".into())]),
+        )
+        .await
+        .unwrap();
+
+        fs.save(None).await.unwrap();
+
+        assert!(fs.read("diary").await.unwrap().is_none());
+        assert!(fs.list().await.is_empty());
+        assert_eq!(
+            fs.list_sealed().await.unwrap(),
+            BTreeSet::from(["diary".into()])
+        );
+
+        let mut file = fs.read_sealed("diary").await.unwrap().unwrap();
+        let mut contents = String::new();
+        file.contents.read_to_string(&mut contents).await.unwrap();
+
+        assert_eq!(contents, "Dear diary...");
+        assert_eq!(
+            file.memo.get_first_header(&Header::ContentType.to_string()),
+            Some(ContentType::Subtext.to_string())
+        );
+        assert_eq!(
+            file.memo.get_first_header("Title"),
+            Some(String::from("Secrets"))
+        );
+
+        fs.write_sealed(
+            "diary",
+            &ContentType::Subtext.to_string(),
+            b"Dear diary, again...".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        fs.save(None).await.unwrap();
+
+        assert_eq!(fs.list_sealed().await.unwrap().len(), 1);
+
+        fs.remove_sealed("diary").await.unwrap();
+        fs.save(None).await.unwrap();
+
+        assert!(fs.read_sealed("diary").await.unwrap().is_none());
+        assert!(fs.list_sealed().await.unwrap().is_empty());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_sees_unsaved_sealed_changes_when_writing_and_removing() {
+        let storage = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+
+        for contents in ["Dear diary...", "Dear diary, again..."] {
+            fs.write_sealed(
+                "diary",
+                &ContentType::Subtext.to_string(),
+                contents.as_bytes(),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let notes_cid = fs
+            .write_sealed(
+                "notes",
+                &ContentType::Subtext.to_string(),
+                b"Never mind".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(fs.remove_sealed("notes").await.unwrap(), Some(notes_cid));
+        assert_eq!(fs.remove_sealed("notes").await.unwrap(), None);
+
+        fs.save(None).await.unwrap();
+
+        let sealed = fs.to_sphere().try_get_sealed().await.unwrap();
+        let entries: Vec<_> = sealed.stream().await.unwrap().collect().await;
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            fs.list_sealed().await.unwrap(),
+            BTreeSet::from(["diary".into()])
+        );
+
+        let mut file = fs.read_sealed("diary").await.unwrap().unwrap();
+        let mut contents = String::new();
+        file.contents.read_to_string(&mut contents).await.unwrap();
+
+        assert_eq!(contents, "Dear diary, again...");
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_only_bundles_ciphertext_for_sealed_content() {
+        let storage = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let (sphere, authorization, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+        let author = Author {
+            key: owner_key,
+            authorization: Some(authorization),
+        };
+
+        db.set_version(&sphere_identity, sphere.cid())
+            .await
+            .unwrap();
+
+        let mut fs = SphereFs::latest(&sphere_identity, &author, &db)
+            .await
+            .unwrap();
+
+        fs.write_sealed(
+            "secret-plans",
+            &ContentType::Subtext.to_string(),
+            b"Take over the world".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let revision = fs.save(None).await.unwrap();
+
+        let bundle = MemoIpld::try_bundle_with_cid(&revision, &db).await.unwrap();
+
+        for needle in [b"secret-plans".as_ref(), b"Take over the world".as_ref()] {
+            for bytes in bundle.map().values() {
+                assert!(!bytes.windows(needle.len()).any(|window| window == needle));
+            }
+        }
+
+        // A reader without a key that the content was sealed to can see that
+        // sealed content exists, but can't open it
+        let stranger_fs = SphereFs::at(&sphere_identity, &revision, &Author::anonymous(), &db)
+            .await
+            .unwrap();
+
+        assert!(stranger_fs.list_sealed().await.unwrap().is_empty());
+        assert!(stranger_fs
+            .read_sealed("secret-plans")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_seals_content_for_authorized_device_keys() {
+        let storage = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+        let device_key = generate_ed25519_key();
+        let device_did = device_key.get_did().await.unwrap();
+
+        let (sphere, authorization, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+        let owner_ucan = authorization.resolve_ucan(&db).await.unwrap();
+
+        let mut signable = UcanBuilder::default()
+            .issued_by(&owner_key)
+            .for_audience(&device_did)
+            .with_lifetime(120)
+            .claiming_capability(&Capability {
+                with: With::Resource {
+                    kind: Resource::Scoped(SphereReference {
+                        did: sphere_identity.to_string(),
+                        path: None,
+                    }),
+                },
+                can: SphereAction::Authorize,
+            })
+            .build()
+            .unwrap();
+        signable
+            .proofs
+            .push(Cid::try_from(&authorization).unwrap().to_string());
+        let device_jwt = signable.sign().await.unwrap().encode().unwrap();
+
+        db.write_token(&owner_ucan.encode().unwrap()).await.unwrap();
+        db.write_token(&device_jwt).await.unwrap();
+
+        let delegation = DelegationIpld::try_register("device", &device_jwt, &db)
+            .await
+            .unwrap();
+        let mut mutation = SphereMutation::new(&owner_did);
+        mutation
+            .allowed_ucans_mut()
+            .set(&CidKey(delegation.jwt), &delegation);
+
+        let mut revision = sphere.try_apply_mutation(&mutation).await.unwrap();
+        let revision = revision
+            .try_sign(&owner_key, Some(&authorization))
+            .await
+            .unwrap();
+
+        db.set_version(&sphere_identity, &revision).await.unwrap();
+
+        let owner = Author {
+            key: owner_key,
+            authorization: Some(authorization),
+        };
+
+        let mut fs = SphereFs::latest(&sphere_identity, &owner, &db)
+            .await
+            .unwrap();
+
+        fs.write_sealed(
+            "shared-draft",
+            &ContentType::Subtext.to_string(),
+            b"Work in progress".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let revision = fs.save(None).await.unwrap();
+
+        let device = Author {
+            key: device_key,
+            authorization: None,
+        };
+
+        let device_fs = SphereFs::at(&sphere_identity, &revision, &device, &db)
+            .await
+            .unwrap();
+
+        let mut file = device_fs
+            .read_sealed("shared-draft")
+            .await
+            .unwrap()
+            .unwrap();
+        let mut contents = String::new();
+        file.contents.read_to_string(&mut contents).await.unwrap();
+
+        assert_eq!(contents, "Work in progress");
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_only_allows_writes_within_the_paths_an_author_is_authorized_for() {
+        let storage = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+        let collaborator_key = generate_ed25519_key();
+        let collaborator_did = collaborator_key.get_did().await.unwrap();
+
+        let (sphere, authorization, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+
+        db.write_token(
+            &authorization
+                .resolve_ucan(&db)
+                .await
+                .unwrap()
+                .encode()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        db.set_version(&sphere_identity, sphere.cid())
+            .await
+            .unwrap();
+
+        let mut signable = UcanBuilder::default()
+            .issued_by(&owner_key)
+            .for_audience(&collaborator_did)
+            .with_lifetime(120)
+            .claiming_capability(&Capability {
+                with: With::Resource {
+                    kind: Resource::Scoped(SphereReference {
+                        did: sphere_identity.to_string(),
+                        path: Some("/journal/".into()),
+                    }),
+                },
+                can: SphereAction::Push,
+            })
+            .build()
+            .unwrap();
+        signable
+            .proofs
+            .push(Cid::try_from(&authorization).unwrap().to_string());
+        let collaborator_jwt = signable.sign().await.unwrap().encode().unwrap();
+        let collaborator_authorization_cid = db.write_token(&collaborator_jwt).await.unwrap();
+
+        let collaborator = Author {
+            key: collaborator_key,
+            authorization: Some(Authorization::Cid(collaborator_authorization_cid)),
+        };
+
+        let mut fs = SphereFs::latest(&sphere_identity, &collaborator, &db)
+            .await
+            .unwrap();
+
+        fs.write(
+            "journal/today",
+            &ContentType::Subtext.to_string(),
+            b"Dear journal".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(fs
+            .write(
+                "recipes/pie",
+                &ContentType::Subtext.to_string(),
+                b"Apples".as_ref(),
+                None,
+            )
+            .await
+            .is_err());
+        assert!(fs.remove("recipes/pie").await.is_err());
+        assert!(fs
+            .write_sealed(
+                "journal/secret",
+                &ContentType::Subtext.to_string(),
+                b"Shh".as_ref(),
+                None,
+            )
+            .await
+            .is_err());
+
+        let revision = fs.save(None).await.unwrap();
+
+        let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+        verify_sphere_cid(&revision, &db, &mut did_parser)
+            .await
+            .unwrap();
+
+        assert!(fs.read("journal/today").await.unwrap().is_some());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_decodes_content_according_to_its_content_type() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let (sphere, proof, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+        let (other_sphere, _, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+        let author = Author {
+            key: owner_key,
+            authorization: Some(proof),
+        };
+
+        db.set_version(&sphere_identity, sphere.cid())
+            .await
+            .unwrap();
+
+        let mut fs = SphereFs::latest(&sphere_identity, &author, &db)
+            .await
+            .unwrap();
+
+        let cbor = DagCborCodec
+            .encode(&Ipld::List(vec![
+                Ipld::Integer(1),
+                Ipld::String("two".into()),
+            ]))
+            .unwrap();
+
+        for (slug, content_type, bytes) in [
+            (
+                "notes",
+                ContentType::Subtext,
+                b"# Cats\n\nCats are great".to_vec(),
+            ),
+            ("config", ContentType::Json, br#"{"cats": true}"#.to_vec()),
+            ("data", ContentType::Cbor, cbor),
+            (
+                "blob",
+                ContentType::Unknown("image/png".into()),
+                vec![1, 2, 3],
+            ),
+        ] {
+            fs.write(slug, &content_type.to_string(), bytes.as_ref(), None)
+                .await
+                .unwrap();
+        }
+
+        let other_sphere_body = other_sphere.try_as_memo().await.unwrap().body;
+        fs.link(
+            "friend",
+            &ContentType::Sphere.to_string(),
+            &other_sphere_body,
+            None,
+        )
+        .await
+        .unwrap();
+
+        fs.save(None).await.unwrap();
+
+        let blocks = fs
+            .read_content("notes")
+            .await
+            .unwrap()
+            .unwrap()
+            .into_subtext()
+            .unwrap();
+        assert!(matches!(blocks.first(), Some(Block::Header(_))));
+
+        let json = fs
+            .read_content("config")
+            .await
+            .unwrap()
+            .unwrap()
+            .into_json()
+            .unwrap();
+        assert_eq!(json, serde_json::json!({ "cats": true }));
+
+        let ipld = fs
+            .read_content("data")
+            .await
+            .unwrap()
+            .unwrap()
+            .into_ipld()
+            .unwrap();
+        assert_eq!(
+            ipld,
+            Ipld::List(vec![Ipld::Integer(1), Ipld::String("two".into())])
+        );
+
+        let friend = fs
+            .read_content("friend")
+            .await
+            .unwrap()
+            .unwrap()
+            .into_sphere()
+            .unwrap();
+        assert_eq!(
+            friend.try_get_identity().await.unwrap(),
+            other_sphere.try_get_identity().await.unwrap()
+        );
+
+        let bytes = fs
+            .read_content("blob")
+            .await
+            .unwrap()
+            .unwrap()
+            .into_bytes()
+            .unwrap();
+        assert_eq!(bytes, vec![1, 2, 3]);
+
+        assert!(fs
+            .read_content("config")
+            .await
+            .unwrap()
+            .unwrap()
+            .into_subtext()
+            .is_err());
+        assert!(fs.read_content("missing").await.unwrap().is_none());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_use_a_custom_decoder_for_a_content_type() {
+        struct ShoutingDecoder;
+
+        #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+        #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+        impl<S: BlockStore> ContentDecoder<S> for ShoutingDecoder {
+            async fn decode(
+                &self,
+                memo_version: &Cid,
+                memo: &MemoIpld,
+                store: &S,
+            ) -> Result<SphereContent<S>> {
+                let bytes = BytesDecoder
+                    .decode(memo_version, memo, store)
+                    .await?
+                    .into_bytes()?;
+
+                Ok(SphereContent::Bytes(bytes.to_ascii_uppercase()))
+            }
+        }
+
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let (sphere, proof, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+        let author = Author {
+            key: owner_key,
+            authorization: Some(proof),
+        };
+
+        db.set_version(&sphere_identity, sphere.cid())
+            .await
+            .unwrap();
+
+        let mut fs = SphereFs::latest(&sphere_identity, &author, &db)
+            .await
+            .unwrap();
+
+        let shouting = ContentType::Unknown("text/x-shouting".into());
+
+        fs.write("hello", &shouting.to_string(), b"hello".as_ref(), None)
+            .await
+            .unwrap();
+        fs.save(None).await.unwrap();
+
+        fs.decoders_mut().register(shouting, ShoutingDecoder);
+
+        let bytes = fs
+            .read_content("hello")
+            .await
+            .unwrap()
+            .unwrap()
+            .into_bytes()
+            .unwrap();
+
+        assert_eq!(bytes, b"HELLO".to_vec());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_set_resolve_and_unset_petnames() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let (sphere, proof, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+        let author = Author {
+            key: owner_key,
+            authorization: Some(proof),
+        };
+
+        db.set_version(&sphere_identity, sphere.cid())
+            .await
+            .unwrap();
+
+        let mut fs = SphereFs::latest(&sphere_identity, &author, &db)
+            .await
+            .unwrap();
+
+        let alice = Did(generate_ed25519_key().get_did().await.unwrap());
+        let bob = Did(generate_ed25519_key().get_did().await.unwrap());
+
+        fs.set_petname("alice", &alice).await.unwrap();
+        fs.set_petname("bob", &bob).await.unwrap();
+        fs.save(None).await.unwrap();
+
+        let petnames = fs.list_petnames().await.unwrap();
+
+        assert_eq!(
+            petnames.keys().cloned().collect::<Vec<String>>(),
+            vec!["alice".to_string(), "bob".to_string()]
+        );
+        assert_eq!(petnames.get("alice").unwrap().identity, alice);
+        assert_eq!(petnames.get("alice").unwrap().last_known_record, None);
+
+        let record = Jwt("eyJhbGciOiJFZERTQSJ9.e30.c2ln".into());
+
+        fs.set_petname_record("alice", &record).await.unwrap();
+        assert!(fs.set_petname_record("carol", &record).await.is_err());
+        fs.save(None).await.unwrap();
+
+        let address = fs.get_petname("alice").await.unwrap().unwrap();
+        assert_eq!(address.last_known_record, Some(record.clone()));
+
+        // Re-assigning a petname to the same identity keeps its record, but
+        // assigning it to a different identity discards it
+        fs.set_petname("alice", &alice).await.unwrap();
+        fs.save(None).await.unwrap();
+        assert_eq!(
+            fs.get_petname("alice")
+                .await
+                .unwrap()
+                .unwrap()
+                .last_known_record,
+            Some(record)
+        );
+
+        fs.set_petname("alice", &bob).await.unwrap();
+        fs.save(None).await.unwrap();
+        assert_eq!(
+            fs.get_petname("alice")
+                .await
+                .unwrap()
+                .unwrap()
+                .last_known_record,
+            None
+        );
+
+        assert_eq!(fs.unset_petname("bob").await.unwrap(), Some(bob));
+        assert_eq!(fs.unset_petname("carol").await.unwrap(), None);
+        fs.save(None).await.unwrap();
+
+        assert!(fs.get_petname("bob").await.unwrap().is_none());
+        assert_eq!(fs.list_petnames().await.unwrap().len(), 1);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_traverse_into_followed_spheres_by_path() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let mut alice_fs = make_sphere_fs(&mut db).await.unwrap();
+        let mut bob_fs = make_sphere_fs(&mut db).await.unwrap();
+
+        bob_fs
+            .write(
+                "notes/today",
+                "text/subtext",
+                b"Hello from Bob".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+        bob_fs.save(None).await.unwrap();
+
+        let bob = Did(bob_fs.identity().to_string());
+        alice_fs.set_petname("bob", &bob).await.unwrap();
+        alice_fs
+            .set_petname_record("bob", &make_name_record(&bob_fs, &db).await.unwrap())
+            .await
+            .unwrap();
+        alice_fs.save(None).await.unwrap();
+
+        let alice = Did(alice_fs.identity().to_string());
+        fs.set_petname("alice", &alice).await.unwrap();
+        fs.set_petname("carol", &bob).await.unwrap();
+        fs.save(None).await.unwrap();
+
+        // A petname that has never been resolved cannot be traversed yet
+        assert!(fs.resolve_path("@alice/notes").await.unwrap().is_none());
+        assert!(fs
+            .resolve_path("@carol/notes/today")
+            .await
+            .unwrap()
+            .is_none());
+
+        let alice_record = make_name_record(&alice_fs, &db).await.unwrap();
+
+        fs.set_petname_record("alice", &alice_record).await.unwrap();
+        // A record that is not for the identity that the petname refers to
+        // does not pass verification, so it cannot be traversed
+        fs.set_petname_record("carol", &alice_record).await.unwrap();
+        fs.save(None).await.unwrap();
+
+        assert!(fs
+            .resolve_path("@carol/notes/today")
+            .await
+            .unwrap()
+            .is_none());
+
+        let (mut peer_fs, slug) = fs
+            .resolve_path("/@alice/@bob/notes/today")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(peer_fs.identity(), bob.as_str());
+        assert_eq!(peer_fs.revision(), bob_fs.revision());
+        assert_eq!(slug, "notes/today");
+
+        {
+            let mut file = peer_fs.read(&slug).await.unwrap().unwrap();
+            let mut contents = String::new();
+            file.contents.read_to_string(&mut contents).await.unwrap();
+
+            assert_eq!(contents, "Hello from Bob");
+        }
+
+        // Followed spheres are always read-only
+        assert!(peer_fs
+            .write("notes/today", "text/subtext", b"Hijacked".as_ref(), None)
+            .await
+            .is_err());
+
+        let (local_fs, slug) = fs.resolve_path("/notes").await.unwrap().unwrap();
+
+        assert_eq!(local_fs.identity(), fs.identity());
+        assert_eq!(slug, "notes");
+        assert!(fs.resolve_path("@alice/@bob").await.is_err());
+    }
+
+    /// A [Storage] whose block stores fail over to those of a "remote"
+    /// [Storage] when blocks are missing locally, keeping a local copy of any
+    /// blocks that are found there; it stands in for a
+    /// [noosphere_storage::KuboStorage] that fetches blocks from a gateway
+    #[derive(Clone)]
+    struct FailoverStorage {
+        local: MemoryStorage,
+        remote: MemoryStorage,
+    }
+
+    #[derive(Clone)]
+    struct FailoverStore {
+        local: MemoryStore,
+        remote: MemoryStore,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+    #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+    impl Storage for FailoverStorage {
+        type BlockStore = FailoverStore;
+
+        type KeyValueStore = MemoryStore;
+
+        async fn get_block_store(&self, name: &str) -> Result<Self::BlockStore> {
+            Ok(FailoverStore {
+                local: self.local.get_block_store(name).await?,
+                remote: self.remote.get_block_store(name).await?,
+            })
+        }
+
+        async fn get_key_value_store(&self, name: &str) -> Result<Self::KeyValueStore> {
+            self.local.get_key_value_store(name).await
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+    #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+    impl BlockStore for FailoverStore {
+        async fn put_block(&mut self, cid: &Cid, block: &[u8]) -> Result<()> {
+            self.local.put_block(cid, block).await
+        }
+
+        async fn get_block(&self, cid: &Cid) -> Result<Option<Vec<u8>>> {
+            if let Some(block) = self.local.get_block(cid).await? {
+                return Ok(Some(block));
+            }
+
+            Ok(match self.remote.get_block(cid).await? {
+                Some(block) => {
+                    self.local.clone().put_block(cid, &block).await?;
+                    Some(block)
+                }
+                None => None,
+            })
+        }
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_can_traverse_into_a_followed_sphere_whose_blocks_are_not_local() {
+        let remote_storage = MemoryStorage::default();
+        let mut remote_db = SphereDb::new(&remote_storage).await.unwrap();
+
+        let mut alice_fs = make_sphere_fs(&mut remote_db).await.unwrap();
+
+        alice_fs
+            .write("notes", "text/subtext", b"Hello from Alice".as_ref(), None)
+            .await
+            .unwrap();
+        alice_fs.save(None).await.unwrap();
+
+        let alice_record = make_name_record(&alice_fs, &remote_db).await.unwrap();
+
+        let local_storage = MemoryStorage::default();
+        let local_db = SphereDb::new(&local_storage).await.unwrap();
+        let mut db = SphereDb::new(&FailoverStorage {
+            local: local_storage,
+            remote: remote_storage,
+        })
+        .await
+        .unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+
+        fs.set_petname("alice", &Did(alice_fs.identity().to_string()))
+            .await
+            .unwrap();
+        fs.set_petname_record("alice", &alice_record).await.unwrap();
+        fs.save(None).await.unwrap();
+
+        assert!(local_db
+            .get_block(alice_fs.revision())
+            .await
+            .unwrap()
+            .is_none());
+
+        let (alice_view, slug) = fs.resolve_path("@alice/notes").await.unwrap().unwrap();
+
+        assert_eq!(alice_view.revision(), alice_fs.revision());
+
+        let mut file = alice_view.read(&slug).await.unwrap().unwrap();
+        let mut contents = String::new();
+        file.contents.read_to_string(&mut contents).await.unwrap();
+
+        assert_eq!(contents, "Hello from Alice");
+        assert!(local_db
+            .get_block(alice_fs.revision())
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_maintains_a_backlink_index_across_revisions() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let owner_key = generate_ed25519_key();
+        let owner_did = owner_key.get_did().await.unwrap();
+
+        let (sphere, proof, _) = Sphere::try_generate(&owner_did, &mut db).await.unwrap();
+
+        let sphere_identity = sphere.try_get_identity().await.unwrap();
+        let author = Author {
+            key: owner_key,
+            authorization: Some(proof),
+        };
+
+        db.set_version(&sphere_identity, sphere.cid())
+            .await
+            .unwrap();
+
+        let mut fs = SphereFs::latest(&sphere_identity, &author, &db)
+            .await
+            .unwrap();
+
+        fs.write(
+            "cats",
+            &ContentType::Subtext.to_string(),
+            b"Cats are /animals and so are [[dogs]]\n\n/@alice/animals".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.write(
+            "dogs",
+            &ContentType::Subtext.to_string(),
+            b"Dogs are /animals too".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.write("animals", "text/plain", b"/cats /dogs".as_ref(), None)
+            .await
+            .unwrap();
+
+        let first_revision = fs.save(None).await.unwrap();
+
+        assert_eq!(
+            fs.backlinks("animals").await.unwrap(),
+            BTreeSet::from(["cats".to_string(), "dogs".to_string()])
+        );
+        assert_eq!(
+            fs.backlinks("dogs").await.unwrap(),
+            BTreeSet::from(["cats".to_string()])
+        );
+        assert!(fs.backlinks("cats").await.unwrap().is_empty());
+
+        fs.write(
+            "cats",
+            &ContentType::Subtext.to_string(),
+            b"Cats are not like [[dogs]]".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.remove("dogs").await.unwrap();
+
+        let second_revision = fs.save(None).await.unwrap();
+
+        assert!(fs.backlinks("animals").await.unwrap().is_empty());
+        assert_eq!(
+            fs.backlinks("dogs").await.unwrap(),
+            BTreeSet::from(["cats".to_string()])
+        );
+
+        // The index of the first revision was only needed to derive that of
+        // the second, so it is no longer kept
+        assert!(db
+            .get_key::<_, BacklinkIndex>(backlink_index_key(&first_revision))
+            .await
+            .unwrap()
+            .is_none());
+
+        let second_index = db
+            .get_key::<_, BacklinkIndex>(backlink_index_key(&second_revision))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let (parent, first_index) = fs
+            .rewind_backlink_index(second_index)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(parent, first_revision);
+        assert_eq!(
+            first_index.backlinks("animals"),
+            BTreeSet::from(["cats".to_string(), "dogs".to_string()])
+        );
+        assert_eq!(
+            first_index,
+            SphereFs::at(&sphere_identity, &first_revision, &author, &db)
+                .await
+                .unwrap()
+                .backlink_index()
+                .await
+                .unwrap()
+        );
+    }
 }
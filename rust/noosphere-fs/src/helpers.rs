@@ -0,0 +1,69 @@
+use anyhow::Result;
+use noosphere_core::{
+    authority::{generate_ed25519_key, Author, SphereAction, SphereReference},
+    data::Jwt,
+    view::Sphere,
+};
+use noosphere_storage::{SphereDb, Storage};
+use ucan::{
+    builder::UcanBuilder,
+    capability::{Capability, Resource, With},
+    crypto::KeyMaterial,
+};
+use ucan_key_support::ed25519::Ed25519KeyMaterial;
+
+use crate::SphereFs;
+
+/// Generate a new sphere owned by a new key, and get a [SphereFs] that is
+/// authorized to write to it as the owner
+pub async fn make_sphere_fs<S: Storage>(
+    db: &mut SphereDb<S>,
+) -> Result<SphereFs<S, Ed25519KeyMaterial>> {
+    let owner_key = generate_ed25519_key();
+    let owner_did = owner_key.get_did().await?;
+
+    let (sphere, proof, _) = Sphere::try_generate(&owner_did, db).await?;
+
+    let sphere_identity = sphere.try_get_identity().await?;
+    let author = Author {
+        key: owner_key,
+        authorization: Some(proof),
+    };
+
+    db.set_version(&sphere_identity, sphere.cid()).await?;
+
+    SphereFs::latest(&sphere_identity, &author, db).await
+}
+
+/// Make a name system record for the sphere behind the given [SphereFs] that
+/// links to the revision it is pointing to, signed by its author (who must be
+/// authorized to publish the sphere, by proofs found in the given [SphereDb])
+pub async fn make_name_record<S, K>(fs: &SphereFs<S, K>, db: &SphereDb<S>) -> Result<Jwt>
+where
+    S: Storage,
+    K: KeyMaterial + Clone + 'static,
+{
+    let author = fs.author();
+    let capability = Capability {
+        with: With::Resource {
+            kind: Resource::Scoped(SphereReference {
+                did: fs.identity().to_string(),
+                path: None,
+            }),
+        },
+        can: SphereAction::Publish,
+    };
+
+    let mut builder = UcanBuilder::default()
+        .issued_by(&author.key)
+        .for_audience(fs.identity())
+        .with_lifetime(120)
+        .claiming_capability(&capability)
+        .with_fact(serde_json::json!({ "link": fs.revision().to_string() }));
+
+    if let Some(authorization) = &author.authorization {
+        builder = builder.witnessed_by(&authorization.resolve_ucan(db).await?);
+    }
+
+    Ok(Jwt(builder.build()?.sign().await?.encode()?))
+}
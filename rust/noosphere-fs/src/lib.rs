@@ -1,10 +1,17 @@
 #[macro_use]
 extern crate tracing;
 
+mod backlinks;
+mod content;
 mod decoder;
 mod file;
 mod fs;
 
+pub use backlinks::*;
+pub use content::*;
 pub use decoder::*;
 pub use file::*;
 pub use fs::*;
+
+#[cfg(any(test, feature = "helpers"))]
+pub mod helpers;
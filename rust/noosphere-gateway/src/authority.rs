@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, sync::Arc};
+use std::{marker::PhantomData, str::FromStr, sync::Arc};
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -11,12 +11,18 @@ use axum::{
 use libipld_core::cid::Cid;
 use noosphere::sphere::SphereContext;
 use noosphere_core::authority::{SphereAction, SphereReference, SPHERE_SEMANTICS};
-use noosphere_storage::NativeStorage;
+use noosphere_storage::{NativeStorage, SphereDb};
 
 use tokio::sync::Mutex;
-use ucan::{capability::Capability, chain::ProofChain, crypto::KeyMaterial, store::UcanJwtStore};
+use ucan::{
+    capability::{Capability, Resource, Scope, With},
+    chain::ProofChain,
+    crypto::KeyMaterial,
+    store::UcanJwtStore,
+    Ucan,
+};
 
-use super::GatewayScope;
+use super::{GatewayClock, GatewayScope};
 
 /// This is a construct that can be generated on a per-request basis and
 /// embodies the authorization status of the request-maker as it is
@@ -36,6 +42,11 @@ impl<K> GatewayAuthority<K>
 where
     K: KeyMaterial + Clone + 'static,
 {
+    /// The bearer [Ucan] that was presented by the maker of the request
+    pub fn ucan(&self) -> &Ucan {
+        self.proof.ucan()
+    }
+
     pub fn try_authorize(
         &self,
         capability: &Capability<SphereReference, SphereAction>,
@@ -56,6 +67,83 @@ where
 
         Err(StatusCode::UNAUTHORIZED)
     }
+
+    /// Like [GatewayAuthority::try_authorize], but also succeeds if the
+    /// capability is only enabled for some part of the sphere that it refers
+    /// to (e.g., a capability to push to the whole sphere is partially
+    /// enabled by a capability to push to `sphere:did:key:...#/journal/`).
+    /// This is useful when the finer-grained authority is checked later on,
+    /// for example against the proofs of the individual revisions in a push.
+    pub fn try_authorize_partially(
+        &self,
+        capability: &Capability<SphereReference, SphereAction>,
+    ) -> Result<(), StatusCode> {
+        if self.try_authorize(capability).is_ok() {
+            return Ok(());
+        }
+
+        let desired_scope = match &capability.with {
+            With::Resource {
+                kind: Resource::Scoped(scope),
+            } => scope,
+            _ => return Err(StatusCode::UNAUTHORIZED),
+        };
+
+        let capability_infos = self.proof.reduce_capabilities(&SPHERE_SEMANTICS);
+
+        for capability_info in capability_infos {
+            if !capability_info
+                .originators
+                .contains(self.scope.counterpart.as_str())
+                || capability_info.capability.can < capability.can
+            {
+                continue;
+            }
+
+            if let With::Resource {
+                kind: Resource::Scoped(granted_scope),
+            } = &capability_info.capability.with
+            {
+                if desired_scope.contains(granted_scope) {
+                    debug!("Partially authorized for {}", granted_scope.to_string());
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// Walk the bearer token and its chain of proofs, looking for a [Ucan] that has
+/// expired or is not valid yet as of `now` (in seconds since the UNIX epoch).
+/// Proofs that are not available locally are skipped here; they will cause the
+/// proof chain to be rejected later on.
+async fn find_untimely_ucan(
+    token: &str,
+    db: &SphereDb<NativeStorage>,
+    now: u64,
+) -> Result<Option<Ucan>> {
+    let mut ucans_to_check = vec![Ucan::from_str(token)?];
+
+    while let Some(ucan) = ucans_to_check.pop() {
+        let is_expired = *ucan.expires_at() < now;
+        let is_too_early = matches!(ucan.not_before(), Some(not_before) if *not_before > now);
+
+        if is_expired || is_too_early {
+            return Ok(Some(ucan));
+        }
+
+        for cid_string in ucan.proofs() {
+            let cid = Cid::try_from(cid_string.as_str())?;
+
+            if let Some(jwt) = db.read_token(&cid).await? {
+                ucans_to_check.push(Ucan::from_str(&jwt)?);
+            }
+        }
+    }
+
+    Ok(None)
 }
 
 #[async_trait]
@@ -87,6 +175,16 @@ where
             })?
             .clone();
 
+        // Get the clock that authorizations are checked against
+        let clock = req
+            .extensions()
+            .get::<GatewayClock>()
+            .ok_or_else(|| {
+                error!("Could not find GatewayClock in extensions");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .clone();
+
         // Extract the bearer token
         let TypedHeader(Authorization(bearer)) =
             TypedHeader::<Authorization<Bearer>>::from_request(req)
@@ -123,6 +221,23 @@ where
             }
         }
 
+        // An authorization is only honored within its time bounds, and the same
+        // goes for every authorization that it was derived from
+        match find_untimely_ucan(bearer.token(), &db, clock.now()).await {
+            Ok(None) => (),
+            Ok(Some(ucan)) => {
+                warn!(
+                    "Rejecting authorization for {} that is expired or not yet valid",
+                    ucan.audience()
+                );
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            Err(error) => {
+                error!("{:?}", error);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+
         let proof_chain = {
             let mut sphere_context = sphere_context.lock().await;
             let did_parser = sphere_context.did_parser_mut();
@@ -0,0 +1,33 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The clock that a gateway checks the time bounds of authorizations against.
+/// It follows the system clock, but tests may move it ahead of it in order to
+/// observe an authorization expire without waiting for it.
+#[derive(Clone, Debug, Default)]
+pub struct GatewayClock {
+    offset: Arc<AtomicU64>,
+}
+
+impl GatewayClock {
+    /// The current time, as seconds since the UNIX epoch
+    pub fn now(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        now + self.offset.load(Ordering::SeqCst)
+    }
+
+    /// Move the clock (and every clone of it) ahead by the given duration
+    #[cfg(any(test, feature = "helpers"))]
+    pub fn advance(&self, duration: Duration) {
+        self.offset.fetch_add(duration.as_secs(), Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,109 @@
+use std::{path::PathBuf, time::SystemTime};
+
+use anyhow::Result;
+use cid::Cid;
+use noosphere_core::data::Did;
+use noosphere_into::{sphere_into_html, sphere_revision_into_feeds, FeedOptions, NativeFs};
+use noosphere_storage::{SphereDb, Storage};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+use url::Url;
+
+/// Where (and how) the gateway writes the static HTML site and the Atom and
+/// JSON feeds of its _counterpart_ sphere
+#[derive(Clone, Debug)]
+pub struct GatewayFeeds {
+    /// The directory that the static site and its feeds are written to (and
+    /// that they are expected to be served from)
+    pub directory: PathBuf,
+    /// The URL that the counterpart sphere's static site is served from, used
+    /// to make the permalinks in the feeds absolute
+    pub base_url: Option<Url>,
+}
+
+/// A [FeedJob] is a request to regenerate the static site and the feeds of the
+/// _counterpart_ sphere as of a revision that has just been published.
+pub struct FeedJob<S>
+where
+    S: Storage,
+{
+    /// The identity of the counterpart sphere
+    pub sphere: Did,
+    /// The published revision of the counterpart sphere
+    pub revision: Cid,
+    /// The [SphereDb] that holds the counterpart sphere's blocks
+    pub db: SphereDb<S>,
+}
+
+/// Start a Tokio task that waits for [FeedJob] messages and regenerates the
+/// static site and the feeds of the counterpart sphere accordingly. The site
+/// is regenerated first, so that the permalinks in the feeds lead to pages
+/// that exist. If no [GatewayFeeds] are configured, jobs are received and
+/// discarded.
+pub fn start_feed_generation<S>(
+    feeds: Option<GatewayFeeds>,
+) -> (UnboundedSender<FeedJob<S>>, JoinHandle<Result<()>>)
+where
+    S: Storage + 'static,
+{
+    let (tx, rx) = unbounded_channel();
+
+    (tx, tokio::task::spawn(feed_generation_task(feeds, rx)))
+}
+
+async fn feed_generation_task<S>(
+    feeds: Option<GatewayFeeds>,
+    mut receiver: UnboundedReceiver<FeedJob<S>>,
+) -> Result<()>
+where
+    S: Storage + 'static,
+{
+    let write_target = feeds.as_ref().map(|feeds| NativeFs {
+        root: feeds.directory.clone(),
+    });
+
+    while let Some(FeedJob {
+        sphere,
+        revision,
+        db,
+    }) = receiver.recv().await
+    {
+        let (feeds, write_target) = match (&feeds, &write_target) {
+            (Some(feeds), Some(write_target)) => (feeds, write_target),
+            _ => {
+                debug!(
+                    "No feed directory configured; skipping feeds for {}",
+                    sphere
+                );
+                continue;
+            }
+        };
+
+        debug!("Generating site for {} at {}...", sphere, revision);
+
+        if let Err(error) = sphere_into_html(&sphere, &db, write_target).await {
+            warn!(
+                "Failed to generate site for {} at {}: {:?}",
+                sphere, revision, error
+            );
+        }
+
+        debug!("Generating feeds for {} at {}...", sphere, revision);
+
+        let mut options = FeedOptions::new(SystemTime::now());
+        options.base_url = feeds.base_url.clone();
+
+        if let Err(error) =
+            sphere_revision_into_feeds(&sphere, &revision, &db, write_target, &options).await
+        {
+            warn!(
+                "Failed to generate feeds for {} at {}: {:?}",
+                sphere, revision, error
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use axum::http::{HeaderValue, Method};
-use axum::routing::{get, put};
+use axum::routing::{get, post, put};
 use axum::{Extension, Router, Server};
 use noosphere::sphere::SphereContext;
 use noosphere_core::data::Did;
@@ -16,8 +16,14 @@ use noosphere_api::route::Route as GatewayRoute;
 use noosphere_storage::NativeStorage;
 
 use crate::{
+    clock::GatewayClock,
+    feed::{start_feed_generation, GatewayFeeds},
     ipfs::start_ipfs_syndication,
-    route::{did_route, fetch_route, identify_route, push_route},
+    nns::{
+        start_name_system, start_periodic_name_resolution, GatewayNameSystem,
+        NAME_RESOLUTION_INTERVAL,
+    },
+    route::{did_route, fetch_route, identify_route, publish_route, push_route},
 };
 
 use noosphere_core::tracing::initialize_tracing;
@@ -33,7 +39,67 @@ pub async fn start_gateway<K>(
     gateway_scope: GatewayScope,
     sphere_context: Arc<Mutex<SphereContext<K, NativeStorage>>>,
     ipfs_api: Url,
+    name_system: Option<GatewayNameSystem>,
     cors_origin: Option<Url>,
+    feeds: Option<GatewayFeeds>,
+) -> Result<()>
+where
+    K: KeyMaterial + Clone + 'static,
+{
+    serve_gateway(
+        listener,
+        gateway_scope,
+        sphere_context,
+        ipfs_api,
+        name_system,
+        cors_origin,
+        feeds,
+        GatewayClock::default(),
+    )
+    .await
+}
+
+/// Start a gateway that checks the time bounds of authorizations against the
+/// given [GatewayClock] rather than the system clock, so that tests can move
+/// it ahead
+#[cfg(any(test, feature = "helpers"))]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_gateway_with_clock<K>(
+    listener: TcpListener,
+    gateway_scope: GatewayScope,
+    sphere_context: Arc<Mutex<SphereContext<K, NativeStorage>>>,
+    ipfs_api: Url,
+    name_system: Option<GatewayNameSystem>,
+    cors_origin: Option<Url>,
+    feeds: Option<GatewayFeeds>,
+    clock: GatewayClock,
+) -> Result<()>
+where
+    K: KeyMaterial + Clone + 'static,
+{
+    serve_gateway(
+        listener,
+        gateway_scope,
+        sphere_context,
+        ipfs_api,
+        name_system,
+        cors_origin,
+        feeds,
+        clock,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn serve_gateway<K>(
+    listener: TcpListener,
+    gateway_scope: GatewayScope,
+    sphere_context: Arc<Mutex<SphereContext<K, NativeStorage>>>,
+    ipfs_api: Url,
+    name_system: Option<GatewayNameSystem>,
+    cors_origin: Option<Url>,
+    feeds: Option<GatewayFeeds>,
+    clock: GatewayClock,
 ) -> Result<()>
 where
     K: KeyMaterial + Clone + 'static,
@@ -67,6 +133,21 @@ where
     }
 
     let (syndication_tx, syndication_task) = start_ipfs_syndication::<K, NativeStorage>(ipfs_api);
+    let (feed_tx, feed_task) = start_feed_generation::<NativeStorage>(feeds);
+    let resolves_names = name_system.is_some();
+    let (name_system_tx, name_system_task) = start_name_system::<K, NativeStorage>(name_system);
+
+    // Only bother resolving the counterpart's petnames if there is a name
+    // system to resolve them through
+    let name_resolution_task = if resolves_names {
+        Some(start_periodic_name_resolution(
+            sphere_context.clone(),
+            name_system_tx.clone(),
+            NAME_RESOLUTION_INTERVAL,
+        ))
+    } else {
+        None
+    };
 
     let app = Router::new()
         .route(&GatewayRoute::Did.to_string(), get(did_route::<K>))
@@ -76,10 +157,14 @@ where
         )
         .route(&GatewayRoute::Push.to_string(), put(push_route::<K>))
         .route(&GatewayRoute::Fetch.to_string(), get(fetch_route::<K>))
+        .route(&GatewayRoute::Publish.to_string(), post(publish_route::<K>))
         .layer(Extension(sphere_context.clone()))
         .layer(Extension(gateway_scope.clone()))
         .layer(Extension(gateway_key_did))
         .layer(Extension(syndication_tx))
+        .layer(Extension(name_system_tx))
+        .layer(Extension(feed_tx))
+        .layer(Extension(clock))
         .layer(cors)
         .layer(TraceLayer::new_for_http());
 
@@ -100,6 +185,12 @@ It awaits updates from sphere {}..."#,
         .await?;
 
     syndication_task.abort();
+    name_system_task.abort();
+    feed_task.abort();
+
+    if let Some(name_resolution_task) = name_resolution_task {
+        name_resolution_task.abort();
+    }
 
     Ok(())
 }
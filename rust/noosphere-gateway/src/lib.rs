@@ -5,12 +5,21 @@ extern crate tracing;
 #[cfg(not(target_arch = "wasm32"))]
 mod authority;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod clock;
+
 #[cfg(not(target_arch = "wasm32"))]
 mod extractor;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod feed;
+
 #[cfg(not(target_arch = "wasm32"))]
 mod ipfs;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod nns;
+
 #[cfg(not(target_arch = "wasm32"))]
 mod route;
 
@@ -19,3 +28,12 @@ mod gateway;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use gateway::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use clock::GatewayClock;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use feed::GatewayFeeds;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use nns::GatewayNameSystem;
@@ -0,0 +1,305 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use noosphere::sphere::{SphereContext, COUNTERPART};
+use noosphere_core::{
+    authority::SUPPORTED_KEYS,
+    data::{AddressIpld, Did},
+    view::{Sphere, SphereMutation},
+};
+use noosphere_ns::{resolve_petnames, NSRecord, NameSystemClient, PetnameResolution};
+use noosphere_storage::{KeyValueStore, Storage};
+use tokio::{
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        oneshot, Mutex,
+    },
+    task::JoinHandle,
+};
+use tokio_stream::StreamExt;
+use ucan::crypto::{did::DidParser, KeyMaterial};
+
+/// How often the gateway resolves the petnames in its counterpart sphere's
+/// address book through the name system
+pub const NAME_RESOLUTION_INTERVAL: Duration = Duration::from_secs(300);
+
+/// A shareable reference to whatever [NameSystemClient] implementation the
+/// gateway has been configured to use (e.g., an embedded name system node, or
+/// an HTTP client for a name system API running elsewhere).
+pub type GatewayNameSystem = Arc<dyn NameSystemClient + Send + Sync>;
+
+/// A [NameSystemJob] is a request for the gateway to do some work in the
+/// Noosphere Name System on behalf of its _counterpart_ sphere.
+pub enum NameSystemJob<K, S>
+where
+    K: KeyMaterial + Clone + 'static,
+    S: Storage,
+{
+    /// Put a freshly signed [NSRecord] for the counterpart sphere into the name
+    /// system, so that the revision it links to becomes the one the rest of
+    /// the network sees. Whether the record was put into the name system is
+    /// reported back through `propagated`.
+    Publish {
+        record: NSRecord,
+        propagated: oneshot::Sender<bool>,
+    },
+    /// Resolve every petname in the counterpart sphere's address book, and
+    /// record the resolved records in the address book of the gateway's own
+    /// sphere (the [SphereContext] that corresponds to the _local_ sphere).
+    ResolveAll {
+        context: Arc<Mutex<SphereContext<K, S>>>,
+    },
+}
+
+/// Start a Tokio task that waits for [NameSystemJob] messages and performs them
+/// against the configured name system. If no name system is configured, jobs
+/// are received and discarded (with a warning, in the case of records that
+/// were meant to be published).
+pub fn start_name_system<K, S>(
+    name_system: Option<GatewayNameSystem>,
+) -> (UnboundedSender<NameSystemJob<K, S>>, JoinHandle<Result<()>>)
+where
+    K: KeyMaterial + Clone + 'static,
+    S: Storage + 'static,
+{
+    let (tx, rx) = unbounded_channel();
+
+    (tx, tokio::task::spawn(name_system_task(name_system, rx)))
+}
+
+/// Start a Tokio task that queues a [NameSystemJob::ResolveAll] for the given
+/// [SphereContext] at a regular interval, starting immediately.
+pub fn start_periodic_name_resolution<K, S>(
+    context: Arc<Mutex<SphereContext<K, S>>>,
+    sender: UnboundedSender<NameSystemJob<K, S>>,
+    interval: Duration,
+) -> JoinHandle<Result<()>>
+where
+    K: KeyMaterial + Clone + 'static,
+    S: Storage + 'static,
+{
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+
+        loop {
+            interval.tick().await;
+
+            if sender
+                .send(NameSystemJob::ResolveAll {
+                    context: context.clone(),
+                })
+                .is_err()
+            {
+                // The name system task has stopped, so there is no longer
+                // anything to do
+                return Ok(());
+            }
+        }
+    })
+}
+
+async fn name_system_task<K, S>(
+    name_system: Option<GatewayNameSystem>,
+    mut receiver: UnboundedReceiver<NameSystemJob<K, S>>,
+) -> Result<()>
+where
+    K: KeyMaterial + Clone + 'static,
+    S: Storage + 'static,
+{
+    while let Some(job) = receiver.recv().await {
+        match job {
+            NameSystemJob::Publish { record, propagated } => {
+                let identity = record.identity().to_owned();
+
+                let was_propagated = match &name_system {
+                    Some(name_system) => match name_system.put_record(record).await {
+                        Ok(_) => {
+                            debug!("Published name record for {}", identity);
+                            true
+                        }
+                        Err(error) => {
+                            warn!(
+                                "Failed to publish name record for {}: {:?}",
+                                identity, error
+                            );
+                            false
+                        }
+                    },
+                    None => {
+                        warn!(
+                            "No name system is configured; the record for {} will not be propagated",
+                            identity
+                        );
+                        false
+                    }
+                };
+
+                // The requester may no longer be waiting for the outcome
+                let _ = propagated.send(was_propagated);
+            }
+            NameSystemJob::ResolveAll { context } => {
+                let name_system = match &name_system {
+                    Some(name_system) => name_system,
+                    None => continue,
+                };
+
+                if let Err(error) = resolve_counterpart_petnames(name_system, context).await {
+                    warn!("Failed to resolve counterpart petnames: {:?}", error);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve the petnames in the counterpart sphere's address book, and mirror
+/// the address book into the gateway's sphere, including any records that
+/// were resolved.
+async fn resolve_counterpart_petnames<K, S>(
+    name_system: &GatewayNameSystem,
+    context: Arc<Mutex<SphereContext<K, S>>>,
+) -> Result<()>
+where
+    K: KeyMaterial + Clone + 'static,
+    S: Storage + 'static,
+{
+    // Gather the address books while holding the lock, but release it while
+    // talking to the name system so that pushes are not held up meanwhile
+    let (db, petnames) = {
+        let context = context.lock().await;
+        let db = context.db().clone();
+
+        match mirrored_petnames(&context).await? {
+            Some((_, petnames)) => (db, petnames),
+            None => return Ok(()),
+        }
+    };
+
+    let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+    let resolved = resolve_petnames(name_system.as_ref(), &petnames, &db, &mut did_parser).await?;
+    let mut resolved_records = BTreeMap::new();
+
+    for entry in resolved {
+        match entry.resolution {
+            PetnameResolution::Updated(record) => {
+                debug!("Resolved a new record for @{}", entry.petname);
+                resolved_records.insert(entry.petname, (entry.identity, record));
+            }
+            PetnameResolution::Invalid(reason) => {
+                warn!(
+                    "Resolved an invalid record for @{}: {}",
+                    entry.petname, reason
+                );
+            }
+            _ => (),
+        }
+    }
+
+    // Either address book may have changed while the lock was released, so
+    // they are read again before the resolved records are applied; a record
+    // is only applied if its petname still refers to the identity that was
+    // resolved
+    let context = context.lock().await;
+    let mut db = context.db().clone();
+
+    let (gateway_petnames, mut petnames) = match mirrored_petnames(&context).await? {
+        Some(petnames) => petnames,
+        None => return Ok(()),
+    };
+
+    for (name, (identity, record)) in resolved_records {
+        if let Some(address) = petnames.get_mut(&name) {
+            if address.identity == identity {
+                address.last_known_record = Some(record);
+            }
+        }
+    }
+
+    let author = context.author();
+    let my_did = author.key.get_did().await?;
+
+    let mut mutation = SphereMutation::new(&my_did);
+
+    for (name, address) in petnames.iter() {
+        if gateway_petnames.get(name) != Some(address) {
+            mutation.names_mut().set(name, address);
+        }
+    }
+
+    for name in gateway_petnames.keys() {
+        if !petnames.contains_key(name) {
+            mutation.names_mut().remove(name);
+        }
+    }
+
+    if mutation.is_empty() {
+        return Ok(());
+    }
+
+    let authorization = author
+        .require_authorization()
+        .map_err(|error| anyhow!("{:?}", error))?;
+    let my_sphere_cid = db.require_version(context.identity()).await?;
+
+    let mut revision = Sphere::at(&my_sphere_cid, &db)
+        .try_apply_mutation(&mutation)
+        .await?;
+    let my_updated_sphere_cid = revision.try_sign(&author.key, Some(authorization)).await?;
+
+    db.set_version(context.identity(), &my_updated_sphere_cid)
+        .await?;
+
+    debug!(
+        "Updated the address book of {} at {}",
+        context.identity(),
+        my_updated_sphere_cid
+    );
+
+    Ok(())
+}
+
+/// Read the gateway's own address book, along with the counterpart sphere's
+/// address book as it should be mirrored into the gateway's sphere. Each
+/// mirrored petname keeps the record the gateway already holds for it, as
+/// long as the petname still refers to the same identity. Returns `None` if
+/// no revisions of the counterpart sphere have been pushed yet.
+async fn mirrored_petnames<K, S>(
+    context: &SphereContext<K, S>,
+) -> Result<Option<(BTreeMap<String, AddressIpld>, BTreeMap<String, AddressIpld>)>>
+where
+    K: KeyMaterial + Clone + 'static,
+    S: Storage + 'static,
+{
+    let db = context.db();
+    let counterpart = db.require_key::<_, Did>(COUNTERPART).await?;
+
+    let counterpart_version = match db.get_version(&counterpart).await? {
+        Some(version) => version,
+        None => {
+            debug!("No revisions of {} have been pushed yet", counterpart);
+            return Ok(None);
+        }
+    };
+
+    let gateway_petnames = context.list_petnames().await?;
+    let counterpart_names = Sphere::at(&counterpart_version, db).try_get_names().await?;
+
+    let mut petnames = BTreeMap::new();
+    let stream = counterpart_names.stream().await?;
+
+    tokio::pin!(stream);
+
+    while let Some((name, address)) = stream.try_next().await? {
+        let address = match gateway_petnames.get(name) {
+            Some(gateway_address) if gateway_address.identity == address.identity => {
+                gateway_address.clone()
+            }
+            _ => address.clone(),
+        };
+
+        petnames.insert(name.clone(), address);
+    }
+
+    Ok(Some((gateway_petnames, petnames)))
+}
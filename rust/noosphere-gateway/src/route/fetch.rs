@@ -2,13 +2,19 @@ use std::sync::Arc;
 
 use anyhow::Result;
 
-use axum::{extract::Query, http::StatusCode, response::IntoResponse, Extension};
+use axum::{
+    body::StreamBody,
+    extract::Query,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Extension,
+};
 use cid::Cid;
 use noosphere::sphere::SphereContext;
 use noosphere_api::data::{FetchParameters, FetchResponse};
 use noosphere_core::{
     authority::{SphereAction, SphereReference},
-    data::Bundle,
+    data::{Bundle, CAR_CONTENT_TYPE},
     view::Sphere,
 };
 use noosphere_storage::{NativeStorage, SphereDb};
@@ -18,7 +24,7 @@ use ucan::{
     crypto::KeyMaterial,
 };
 
-use crate::{authority::GatewayAuthority, extractor::Cbor, GatewayScope};
+use crate::{authority::GatewayAuthority, GatewayScope};
 
 pub async fn fetch_route<K>(
     authority: GatewayAuthority<K>,
@@ -33,6 +39,7 @@ where
         with: With::Resource {
             kind: Resource::Scoped(SphereReference {
                 did: scope.counterpart.to_string(),
+                path: None,
             }),
         },
         can: SphereAction::Fetch,
@@ -53,7 +60,15 @@ where
         None => FetchResponse::UpToDate,
     };
 
-    Ok(Cbor(response))
+    let stream = response.into_car_stream().map_err(|error| {
+        error!("{:?}", error);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((
+        [(header::CONTENT_TYPE, CAR_CONTENT_TYPE)],
+        StreamBody::new(stream),
+    ))
 }
 
 pub async fn generate_fetch_bundle(
@@ -24,6 +24,7 @@ pub async fn identify_route<K: KeyMaterial + Clone>(
         with: With::Resource {
             kind: Resource::Scoped(SphereReference {
                 did: scope.counterpart.to_string(),
+                path: None,
             }),
         },
         can: SphereAction::Fetch,
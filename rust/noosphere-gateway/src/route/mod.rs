@@ -1,9 +1,11 @@
 mod did;
 mod fetch;
 mod identify;
+mod publish;
 mod push;
 
 pub use did::*;
 pub use fetch::*;
 pub use identify::*;
+pub use publish::*;
 pub use push::*;
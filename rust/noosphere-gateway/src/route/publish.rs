@@ -0,0 +1,211 @@
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::Result;
+
+use axum::{http::StatusCode, Extension};
+
+use cid::Cid;
+use noosphere::sphere::SphereContext;
+use noosphere_api::data::{PublishBody, PublishResponse};
+use noosphere_core::{
+    authority::{SphereAction, SphereReference},
+    data::Jwt,
+    view::Timeline,
+};
+use noosphere_ns::NSRecord;
+use noosphere_storage::{NativeStorage, SphereDb};
+use tokio::sync::{mpsc::UnboundedSender, oneshot, Mutex};
+use tokio_stream::StreamExt;
+use ucan::{
+    capability::{Capability, Resource, With},
+    crypto::KeyMaterial,
+    store::UcanJwtStore,
+    ucan::Ucan,
+};
+
+use crate::{
+    authority::GatewayAuthority, extractor::Cbor, feed::FeedJob, ipfs::SyndicationJob,
+    nns::NameSystemJob, GatewayScope,
+};
+
+// #[debug_handler]
+pub async fn publish_route<K>(
+    authority: GatewayAuthority<K>,
+    Cbor(publish_body): Cbor<PublishBody>,
+    Extension(sphere_context_mutex): Extension<Arc<Mutex<SphereContext<K, NativeStorage>>>>,
+    Extension(scope): Extension<GatewayScope>,
+    Extension(syndication_tx): Extension<UnboundedSender<SyndicationJob<K, NativeStorage>>>,
+    Extension(name_system_tx): Extension<UnboundedSender<NameSystemJob<K, NativeStorage>>>,
+    Extension(feed_tx): Extension<UnboundedSender<FeedJob<NativeStorage>>>,
+) -> Result<Cbor<PublishResponse>, StatusCode>
+where
+    K: KeyMaterial + Clone + 'static,
+{
+    debug!("Invoking publish route...");
+
+    if publish_body.sphere != scope.counterpart {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    authority.try_authorize(&Capability {
+        with: With::Resource {
+            kind: Resource::Scoped(SphereReference {
+                did: scope.counterpart.to_string(),
+                path: None,
+            }),
+        },
+        can: SphereAction::Publish,
+    })?;
+
+    let mut sphere_context = sphere_context_mutex.lock().await;
+    let mut db = sphere_context.db().clone();
+
+    let latest_counterpart_version = db
+        .get_version(&scope.counterpart)
+        .await
+        .map_err(|error| {
+            error!("{:?}", error);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            warn!("No revisions of {} have been pushed yet", scope.counterpart);
+            StatusCode::NOT_FOUND
+        })?;
+
+    let version = match publish_body.version {
+        Some(version) => {
+            let is_known = is_in_lineage(&version, &latest_counterpart_version, &db)
+                .await
+                .map_err(|error| {
+                    error!("{:?}", error);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
+            if !is_known {
+                warn!(
+                    "Revision {} is not in the known lineage of {}",
+                    version, scope.counterpart
+                );
+                return Err(StatusCode::UNPROCESSABLE_ENTITY);
+            }
+
+            version
+        }
+        None => latest_counterpart_version,
+    };
+
+    debug!(
+        "Signing name record for {} at {}...",
+        scope.counterpart, version
+    );
+
+    // The proof presented with this request delegates the capability to
+    // publish the counterpart sphere to the gateway's key for as long as the
+    // record that the gateway signs lives (the bearer token that authorized
+    // the request is much shorter-lived, so it cannot serve as the proof)
+    let proof_jwt = publish_body.proof.ok_or_else(|| {
+        warn!("No proof was presented for the name record");
+        StatusCode::BAD_REQUEST
+    })?;
+    let proof = Ucan::from_str(&proof_jwt).map_err(|error| {
+        warn!("Could not read the proof for the name record: {:?}", error);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    if proof.audience() != authority.ucan().audience() {
+        warn!("The proof for the name record was not issued to the gateway");
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    db.write_token(&proof_jwt.0).await.map_err(|error| {
+        error!("{:?}", error);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let record = NSRecord::from_issuer(
+        &sphere_context.author().key,
+        &scope.counterpart,
+        &version,
+        Some(&vec![proof]),
+    )
+    .await
+    .map_err(|error| {
+        error!("{:?}", error);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    record
+        .validate(&db, sphere_context.did_parser_mut())
+        .await
+        .map_err(|error| {
+            warn!("Signed an invalid name record: {:?}", error);
+            StatusCode::UNAUTHORIZED
+        })?;
+
+    let record_jwt = Jwt(record.try_to_string().map_err(|error| {
+        error!("{:?}", error);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?);
+
+    let (propagated_tx, propagated_rx) = oneshot::channel();
+
+    if let Err(error) = name_system_tx.send(NameSystemJob::Publish {
+        record,
+        propagated: propagated_tx,
+    }) {
+        warn!("Failed to queue name system publish job: {}", error);
+    };
+
+    let gateway_version = db.require_version(&scope.identity).await.map_err(|error| {
+        error!("{:?}", error);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if let Err(error) = syndication_tx.send(SyndicationJob {
+        revision: gateway_version,
+        context: sphere_context_mutex.clone(),
+    }) {
+        warn!("Failed to queue IPFS syndication job: {}", error);
+    };
+
+    if let Err(error) = feed_tx.send(FeedJob {
+        sphere: scope.counterpart.clone(),
+        revision: version,
+        db: db.clone(),
+    }) {
+        warn!("Failed to queue feed generation job: {}", error);
+    };
+
+    // NOTE: The name system task may need the sphere context for other jobs
+    // that are queued ahead of this one, so it is released before waiting
+    drop(sphere_context);
+
+    let propagated = propagated_rx.await.unwrap_or(false);
+
+    Ok(Cbor(PublishResponse {
+        version,
+        record: record_jwt,
+        propagated,
+    }))
+}
+
+/// Returns true if the given revision is the tip, or one of the ancestors of
+/// the tip, of a sphere's history
+pub(crate) async fn is_in_lineage(
+    revision: &Cid,
+    tip: &Cid,
+    db: &SphereDb<NativeStorage>,
+) -> Result<bool> {
+    let timeline = Timeline::new(db);
+    let stream = timeline.try_stream(tip, None);
+
+    tokio::pin!(stream);
+
+    while let Some((cid, _)) = stream.try_next().await? {
+        if &cid == revision {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
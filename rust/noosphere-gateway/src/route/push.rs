@@ -2,102 +2,176 @@ use std::sync::Arc;
 
 use anyhow::Result;
 
-use axum::{extract::ContentLengthLimit, http::StatusCode, Extension};
+use axum::{extract::BodyStream, http::StatusCode, Extension};
 
 use cid::Cid;
+use futures::TryStreamExt;
+use libipld_cbor::DagCborCodec;
 use noosphere::sphere::SphereContext;
-use noosphere_api::data::{PushBody, PushResponse};
+use noosphere_api::data::{PushBody, PushBodyHeader, PushResponse};
 use noosphere_core::{
-    authority::{Authorization, SphereAction, SphereReference},
-    data::Bundle,
+    authority::{verify_sphere_cid, Authorization, SphereAction, SphereReference},
+    data::{Bundle, CarReader, MemoIpld},
     view::{Sphere, SphereMutation, Timeline},
 };
-use noosphere_storage::{NativeStorage, SphereDb};
+use noosphere_storage::{
+    block_deserialize, BlockIntegrityError, BlockStore, NativeStorage, SphereDb,
+};
 use tokio::sync::{mpsc::UnboundedSender, Mutex};
 use ucan::capability::{Capability, Resource, With};
-use ucan::crypto::KeyMaterial;
+use ucan::crypto::{did::DidParser, KeyMaterial};
+
+use crate::{authority::GatewayAuthority, extractor::Cbor, nns::NameSystemJob, GatewayScope};
 
-use crate::{authority::GatewayAuthority, extractor::Cbor, ipfs::SyndicationJob, GatewayScope};
+use super::publish::is_in_lineage;
 
 // #[debug_handler]
 pub async fn push_route<K>(
     authority: GatewayAuthority<K>,
-    ContentLengthLimit(Cbor(push_body)): ContentLengthLimit<Cbor<PushBody>, { 1024 * 5000 }>,
     Extension(sphere_context_mutex): Extension<Arc<Mutex<SphereContext<K, NativeStorage>>>>,
     Extension(scope): Extension<GatewayScope>,
-    Extension(syndication_tx): Extension<UnboundedSender<SyndicationJob<K, NativeStorage>>>,
+    Extension(name_system_tx): Extension<UnboundedSender<NameSystemJob<K, NativeStorage>>>,
+    body: BodyStream,
 ) -> Result<Cbor<PushResponse>, StatusCode>
 where
     K: KeyMaterial + Clone + 'static,
 {
     debug!("Invoking push route...");
 
-    let sphere_identity = &push_body.sphere;
+    // The push body is a CAR whose first block is the header of the push; the
+    // rest of the blocks are only read once we have decided to accept them.
+    // NOTE: The body is streamed, and no more than one block of it is held in
+    // memory at a time, so the size of the body as a whole is not limited
+    let mut car = CarReader::new(
+        body.map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
+            .into_async_read(),
+    )
+    .await
+    .map_err(|error| refuse_push_body(error, StatusCode::BAD_REQUEST))?;
+
+    let push_header = PushBody::read_car_header(&mut car)
+        .await
+        .map_err(|error| refuse_push_body(error, StatusCode::BAD_REQUEST))?;
+
+    let sphere_identity = &push_header.sphere;
 
     if sphere_identity != &scope.counterpart {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    authority.try_authorize(&Capability {
+    // NOTE: The requester may only be authorized to push to some parts of the
+    // sphere; the proof of each pushed revision is verified against the
+    // changes in that revision before the revision is accepted
+    authority.try_authorize_partially(&Capability {
         with: With::Resource {
             kind: Resource::Scoped(SphereReference {
                 did: scope.counterpart.to_string(),
+                path: None,
             }),
         },
         can: SphereAction::Push,
     })?;
 
-    let sphere_context = sphere_context_mutex.lock().await;
+    let mut sphere_context = sphere_context_mutex.lock().await;
     let mut db = sphere_context.db().clone();
-    let gateway_key = &sphere_context.author().key;
-    let gateway_authorization =
-        sphere_context
-            .author()
-            .require_authorization()
-            .map_err(|error| {
-                error!("{:?}", error);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+    let gateway_key = sphere_context.author().key.clone();
+    let gateway_authorization = sphere_context
+        .author()
+        .require_authorization()
+        .map_err(|error| {
+            error!("{:?}", error);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .clone();
 
     debug!("Preparing to merge sphere lineage...");
     let local_sphere_base_cid = db.get_version(sphere_identity).await.map_err(|error| {
         error!("{:?}", error);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
-    let request_sphere_base_cid = push_body.base;
+    let request_sphere_base_cid = push_header.base;
 
-    match (local_sphere_base_cid, request_sphere_base_cid) {
+    let base = match (local_sphere_base_cid, request_sphere_base_cid) {
+        (Some(mine), theirs) if theirs == Some(mine) => {
+            if push_header.tip == mine {
+                warn!("No new changes in push body!");
+                return Ok(Cbor(PushResponse::NoChange));
+            }
+
+            Some(mine)
+        }
         (Some(mine), theirs) => {
-            // TODO(#26): Probably should do some diligence here to check if
-            // their base is even in our lineage. Note that this condition
-            // will be hit if theirs is ahead of mine, which actually
-            // should be a "missing revisions" condition.
-            let conflict = match theirs {
-                Some(cid) if cid != mine => true,
-                None => true,
-                _ => false,
-            };
-
-            if conflict {
-                warn!("Conflict!");
-                return Err(StatusCode::CONFLICT);
+            if let Some(theirs) = theirs {
+                let theirs_is_behind =
+                    is_in_lineage(&theirs, &mine, &db).await.map_err(|error| {
+                        error!("{:?}", error);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+
+                if theirs_is_behind {
+                    warn!("Conflict: pushed history is based on an older revision than {mine}");
+                    return Err(StatusCode::CONFLICT);
+                }
             }
 
-            if push_body.tip == mine {
-                warn!("No new changes in push body!");
-                return Ok(Cbor(PushResponse::NoChange));
+            // Their base is not in our lineage, so they may be ahead of us;
+            // if the pushed history descends from our latest revision, we can
+            // fast-forward to it. The pushed blocks are held aside (the size
+            // of the push body is bounded) until we know that, so that a
+            // refused push does not leave its blocks behind in our storage.
+            let pushed_blocks = Bundle::try_from_car(&mut car)
+                .await
+                .map_err(|error| refuse_push_body(error, StatusCode::UNPROCESSABLE_ENTITY))?;
+
+            match search_lineage(&push_header.tip, &mine, &pushed_blocks, &db).await {
+                LineageSearch::Found => {
+                    debug!("Fast-forwarding from {mine} to {}", push_header.tip);
+
+                    pushed_blocks.load_into(&mut db).await.map_err(|error| {
+                        error!("{:?}", error);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+
+                    Some(mine)
+                }
+                LineageSearch::Incomplete => {
+                    debug!("Pushed history is missing revisions since {mine}");
+                    return Ok(Cbor(PushResponse::MissingRevisions { since: mine }));
+                }
+                LineageSearch::Diverged => {
+                    warn!("Conflict: pushed history does not descend from {mine}");
+                    return Err(StatusCode::CONFLICT);
+                }
             }
         }
         (None, Some(_)) => {
             error!("Missing local lineage!");
             return Err(StatusCode::UNPROCESSABLE_ENTITY);
         }
-        _ => (),
+        (None, None) => None,
     };
 
+    debug!("Verifying pushed revisions...");
+
+    // NOTE: If the blocks were already loaded above, the reader is exhausted
+    // and this is a no-op
+    load_pushed_blocks(&mut car, &mut db).await?;
+
+    verify_lineage(
+        &db,
+        base.as_ref(),
+        &push_header,
+        sphere_context.did_parser_mut(),
+    )
+    .await
+    .map_err(|error| {
+        warn!("Refusing push: {:?}", error);
+        StatusCode::FORBIDDEN
+    })?;
+
     debug!("Merging...");
 
-    incorporate_lineage(&scope, &mut db, &push_body)
+    incorporate_lineage(&scope, &mut db, base.as_ref(), &push_header)
         .await
         .map_err(|error| {
             error!("{:?}", error);
@@ -107,10 +181,10 @@ where
     debug!("Updating the gateway's sphere...");
 
     let (new_gateway_tip, new_blocks) = update_gateway_sphere(
-        &push_body.tip,
+        &push_header.tip,
         &scope,
-        gateway_key,
-        gateway_authorization,
+        &gateway_key,
+        &gateway_authorization,
         &mut db,
     )
     .await
@@ -119,15 +193,13 @@ where
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    // TODO(#156): This should not be happening on every push, but rather on
-    // an explicit publish action. Move this to the publish handler when we
-    // have added it to the gateway.
-    if let Err(error) = syndication_tx.send(SyndicationJob {
-        revision: new_gateway_tip,
+    // The pushed revisions may have changed the counterpart's address book, so
+    // resolve its petnames again now rather than waiting for the next interval
+    if let Err(error) = name_system_tx.send(NameSystemJob::ResolveAll {
         context: sphere_context_mutex.clone(),
     }) {
-        warn!("Failed to queue IPFS syndication job: {}", error);
-    };
+        warn!("Failed to queue name system resolve job: {}", error);
+    }
 
     Ok(Cbor(PushResponse::Accepted {
         new_tip: new_gateway_tip,
@@ -135,6 +207,39 @@ where
     }))
 }
 
+/// Incrementally load the blocks that remain in the pushed CAR into the
+/// database; each block is verified against its CID as it is read
+async fn load_pushed_blocks<R>(
+    car: &mut CarReader<R>,
+    db: &mut SphereDb<NativeStorage>,
+) -> Result<(), StatusCode>
+where
+    R: futures::io::AsyncRead + Unpin,
+{
+    let count = Bundle::load_car_into(car, db)
+        .await
+        .map_err(|error| refuse_push_body(error, StatusCode::UNPROCESSABLE_ENTITY))?;
+
+    if count > 0 {
+        debug!("Loaded {} pushed blocks", count);
+    }
+
+    Ok(())
+}
+
+/// Choose the status to respond with when the push body cannot be read; a
+/// body that is too large is always refused as such, regardless of where in
+/// the body the limit was reached
+fn refuse_push_body(error: anyhow::Error, status: StatusCode) -> StatusCode {
+    if let Some(integrity_error) = error.downcast_ref::<BlockIntegrityError>() {
+        warn!("Refusing push: {}", integrity_error);
+        status
+    } else {
+        warn!("{:?}", error);
+        status
+    }
+}
+
 async fn update_gateway_sphere<K>(
     counterpart_sphere_cid: &Cid,
     scope: &GatewayScope,
@@ -169,17 +274,34 @@ where
     Ok((my_updated_sphere_cid, blocks))
 }
 
+/// Verify the signature and proof of every pushed revision, including that
+/// the proof enables the signer to make the changes found in that revision
+async fn verify_lineage(
+    db: &SphereDb<NativeStorage>,
+    base: Option<&Cid>,
+    push_header: &PushBodyHeader,
+    did_parser: &mut DidParser,
+) -> Result<()> {
+    let timeline = Timeline::new(db);
+    let timeslice = timeline.slice(&push_header.tip, base);
+    let steps = timeslice.try_to_chronological().await?;
+
+    for (cid, _) in steps {
+        debug!("Verifying {}", cid);
+        verify_sphere_cid(&cid, db, did_parser).await?;
+    }
+
+    Ok(())
+}
+
 async fn incorporate_lineage(
     scope: &GatewayScope,
     db: &mut SphereDb<NativeStorage>,
-    push_body: &PushBody,
+    base: Option<&Cid>,
+    push_header: &PushBodyHeader,
 ) -> Result<()> {
-    push_body.blocks.load_into(db).await?;
-
-    let PushBody { base, tip, .. } = push_body;
-
     let timeline = Timeline::new(db);
-    let timeslice = timeline.slice(tip, base.as_ref());
+    let timeslice = timeline.slice(&push_header.tip, base);
     let steps = timeslice.try_to_chronological().await?;
 
     for (cid, _) in steps {
@@ -187,7 +309,47 @@ async fn incorporate_lineage(
         Sphere::at(&cid, db).try_hydrate().await?;
     }
 
-    db.set_version(&scope.counterpart, &push_body.tip).await?;
+    db.set_version(&scope.counterpart, &push_header.tip).await?;
 
     Ok(())
 }
+
+/// The outcome of looking for a revision in the history that was pushed by
+/// the client
+enum LineageSearch {
+    /// The revision is an ancestor of the pushed tip
+    Found,
+    /// The pushed history reaches its root without passing the revision
+    Diverged,
+    /// Some revisions between the pushed tip and the root are not available
+    Incomplete,
+}
+
+/// Look for a revision in the pushed history, reading revisions from the
+/// pushed blocks before falling back to the database
+async fn search_lineage(
+    tip: &Cid,
+    revision: &Cid,
+    pushed_blocks: &Bundle,
+    db: &SphereDb<NativeStorage>,
+) -> LineageSearch {
+    let mut next = Some(*tip);
+
+    while let Some(cid) = next {
+        if &cid == revision {
+            return LineageSearch::Found;
+        }
+
+        let memo = match pushed_blocks.map().get(&cid.to_string()) {
+            Some(bytes) => block_deserialize::<DagCborCodec, MemoIpld>(bytes),
+            None => db.load::<DagCborCodec, MemoIpld>(&cid).await,
+        };
+
+        next = match memo {
+            Ok(memo) => memo.parent,
+            Err(_) => return LineageSearch::Incomplete,
+        };
+    }
+
+    LineageSearch::Diverged
+}
@@ -0,0 +1,259 @@
+use std::{io::Cursor, path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use async_stream::stream;
+use futures::Stream;
+use noosphere_core::{
+    authority::Author,
+    data::{ContentType, Did, Header},
+    view::Sphere,
+};
+use noosphere_fs::{SphereFile, SphereFs};
+use noosphere_storage::{SphereDb, Storage};
+use tokio::{io::AsyncRead, sync::Mutex};
+use tokio_stream::StreamExt;
+
+use crate::{
+    body_file_name, document_body_path, document_path, subtext_to_gemtext_stream,
+    subtext_to_markdown_stream, RelativeLinkTransform, TranscludeFormat, Transform,
+    TransformStream, WriteTarget,
+};
+
+/// The formats that a sphere may be rendered to as a set of documents that
+/// link to each other by relative paths
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// Markdown, e.g., for a static site generator such as mkdocs
+    Markdown,
+    /// Gemtext, for a Gemini capsule
+    Gemtext,
+}
+
+impl DocumentFormat {
+    /// The file extension of documents in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocumentFormat::Markdown => "md",
+            DocumentFormat::Gemtext => "gmi",
+        }
+    }
+
+    /// Given a [Transform] and a [SphereFile], produce a stream that yields
+    /// the file content as a document in this format
+    pub fn file_stream<T, R>(&self, transform: T, file: SphereFile<R>) -> impl Stream<Item = String>
+    where
+        T: Transform,
+        R: AsyncRead + Unpin,
+    {
+        let format = *self;
+
+        stream! {
+            match format {
+                DocumentFormat::Markdown => {
+                    for await part in subtext_to_markdown_stream(transform, file) {
+                        yield part;
+                    }
+                }
+                DocumentFormat::Gemtext => {
+                    for await part in subtext_to_gemtext_stream(transform, file) {
+                        yield part;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Produce an index document with the given title that links to each of
+    /// the given slugs
+    fn index(&self, title: &str, slugs: &[String]) -> String {
+        let links = slugs.iter().map(|slug| match self {
+            DocumentFormat::Markdown => format!("- [/{}]({}.md)", slug, slug),
+            DocumentFormat::Gemtext => format!("=> {}.gmi /{}", slug, slug),
+        });
+
+        std::iter::once(format!("# {}\n", title))
+            .chain(links)
+            .collect::<Vec<String>>()
+            .join("\n")
+            + "\n"
+    }
+}
+
+/// Given a sphere [Did], [SphereDb], [WriteTarget] and [DocumentFormat],
+/// write the slug-named content at the latest revision of the sphere as a set
+/// of linked documents: the content at `notes/today` is written to
+/// `notes/today.md` (for Markdown), and so on. An index document that links to
+/// all of the content is written unless the sphere has content of its own at
+/// the `index` slug.
+///
+/// Only Subtext content is rendered at this time; the bodies of media (such as
+/// images) are written alongside the documents so that transcludes can link
+/// to them (see [document_body_path]). Note that documents for content that
+/// has been removed from the sphere are not removed from the [WriteTarget].
+pub async fn sphere_into_documents<S, W>(
+    sphere_identity: &Did,
+    db: &SphereDb<S>,
+    write_target: &W,
+    format: DocumentFormat,
+) -> Result<()>
+where
+    S: Storage + 'static,
+    W: WriteTarget + 'static,
+{
+    let sphere_cid = db
+        .get_version(sphere_identity)
+        .await?
+        .ok_or_else(|| anyhow!("Could not resolve CID for sphere {}", sphere_identity))?;
+
+    let write_target = Arc::new(write_target.clone());
+    let author = Author::anonymous();
+    let sphere = Sphere::at(&sphere_cid, db);
+    let links = sphere.try_get_links().await?;
+    let mut link_stream = links.stream().await?;
+
+    // The slugs of the content that is written as documents
+    let slugs = Arc::new(Mutex::new(Vec::new()));
+    let mut tasks = Vec::new();
+
+    while let Some((slug, _)) = link_stream.try_next().await? {
+        let path = match document_path(slug, format.extension()) {
+            Some(path) => path,
+            None => {
+                warn!("Skipping {}; it cannot be written as a document", slug);
+                continue;
+            }
+        };
+
+        tasks.push(W::spawn({
+            let slug = slug.clone();
+            let slugs = slugs.clone();
+            let write_target = write_target.clone();
+            let sphere_identity = sphere_identity.clone();
+            let db = db.clone();
+            let author = author.clone();
+
+            async move {
+                let fs = SphereFs::at(&sphere_identity, &sphere_cid, &author, &db).await?;
+                let sphere_file = fs
+                    .read(&slug)
+                    .await?
+                    .ok_or_else(|| anyhow!("No file found for {}", slug))?;
+
+                // Media is not rendered, but its body is written out as-is
+                if TranscludeFormat::from_memo(&sphere_file.memo).has_body() {
+                    return match document_body_path(&slug, &body_file_name(&sphere_file.memo)) {
+                        Some(body_path) => {
+                            write_target.write(&body_path, sphere_file.contents).await
+                        }
+                        None => {
+                            warn!("Skipping {}; its body cannot be written", slug);
+                            Ok(())
+                        }
+                    };
+                }
+
+                if sphere_file.memo.content_type() != Some(ContentType::Subtext) {
+                    debug!("Skipping {}; only Subtext is rendered as a document", slug);
+                    return Ok(());
+                }
+
+                let transform = RelativeLinkTransform::new(fs.clone(), &path, format.extension());
+                let reader =
+                    TransformStream(format.file_stream(transform, sphere_file)).into_reader();
+
+                write_target.write(&path, reader).await?;
+                slugs.lock().await.push(slug);
+
+                Ok(())
+            }
+        }));
+    }
+
+    futures::future::try_join_all(tasks).await?;
+
+    let mut slugs = slugs.lock().await.clone();
+
+    if slugs.iter().any(|slug| slug == "index") {
+        return Ok(());
+    }
+
+    slugs.sort();
+
+    let title = sphere
+        .try_as_memo()
+        .await?
+        .get_first_header(&Header::Title.to_string())
+        .unwrap_or_else(|| "My sphere".into());
+
+    write_target
+        .write(
+            &PathBuf::from(format!("index.{}", format.extension())),
+            Cursor::new(format.index(&title, &slugs)),
+        )
+        .await
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::PathBuf;
+
+    use noosphere_core::data::{ContentType, Did, Header};
+    use noosphere_fs::helpers::make_sphere_fs;
+    use noosphere_storage::{MemoryStorage, SphereDb};
+
+    use crate::write::MemoryWriteTarget;
+
+    /// Make a sphere with the content that each [super::DocumentFormat] is
+    /// tested against, and get its identity
+    pub async fn make_document_fixture(db: &mut SphereDb<MemoryStorage>) -> Did {
+        let mut fs = make_sphere_fs(db).await.unwrap();
+
+        fs.write(
+            "cats",
+            &ContentType::Subtext.to_string(),
+            b"# Cats\n\nCats are [[great]]\n\n/animals\n\n- One\n- Two".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.write(
+            "animals",
+            &ContentType::Subtext.to_string(),
+            b"Animals are multicellular.".as_ref(),
+            Some(vec![(Header::Title.to_string(), "Animals".into())]),
+        )
+        .await
+        .unwrap();
+        fs.write(
+            "notes/today",
+            &ContentType::Subtext.to_string(),
+            b"Today I saw /animals".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.write("photo", "image/png", b"Not really a photo".as_ref(), None)
+            .await
+            .unwrap();
+        fs.write(
+            "gallery",
+            &ContentType::Subtext.to_string(),
+            b"/photo".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        fs.save(None).await.unwrap();
+
+        Did(fs.identity().to_string())
+    }
+
+    /// Read a document that has been written to a [MemoryWriteTarget]
+    pub async fn read_document(write_target: &MemoryWriteTarget, path: &str) -> Option<String> {
+        write_target
+            .read(&PathBuf::from(path))
+            .await
+            .map(|bytes| String::from_utf8(bytes).unwrap())
+    }
+}
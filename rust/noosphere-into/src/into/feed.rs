@@ -0,0 +1,701 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Cursor,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use noosphere_core::{
+    authority::Author,
+    data::{ContentType, Did, Header, MapOperation},
+    view::{Sphere, Timeline},
+};
+use noosphere_fs::SphereFs;
+use noosphere_storage::{SphereDb, Storage};
+use serde::{Deserialize, Serialize};
+use subtext::{Peer, Slashlink};
+use tokio_stream::StreamExt;
+use url::Url;
+
+use crate::{ResolvedLink, SphereFsTranscluder, Transclude, Transcluder, WriteTarget};
+
+/// The path within a [WriteTarget] where the Atom feed is written
+pub const ATOM_FEED_PATH: &str = "feed.xml";
+
+/// The path within a [WriteTarget] where the JSON Feed is written
+pub const JSON_FEED_PATH: &str = "feed.json";
+
+/// The number of entries that a feed includes unless otherwise specified
+pub const DEFAULT_MAX_FEED_ENTRIES: usize = 50;
+
+const JSON_FEED_VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+/// Options that control how the feeds of a sphere are generated
+#[derive(Clone, Debug)]
+pub struct FeedOptions {
+    /// The URL that the static site (as rendered by [crate::sphere_into_html])
+    /// is served from. When present, permalinks in the feeds are absolute;
+    /// otherwise they are relative to the root of the site.
+    pub base_url: Option<Url>,
+    /// The maximum number of entries to include in the feeds
+    pub max_entries: usize,
+    /// The time at which the feeds are being generated; this becomes the date
+    /// of any entry that did not appear in an earlier version of the feeds
+    /// (see [sphere_revision_into_feeds])
+    pub generated_at: SystemTime,
+}
+
+impl FeedOptions {
+    pub fn new(generated_at: SystemTime) -> Self {
+        FeedOptions {
+            base_url: None,
+            max_entries: DEFAULT_MAX_FEED_ENTRIES,
+            generated_at,
+        }
+    }
+
+    /// The `href` of a path within the static site
+    fn href(&self, path: &str) -> Result<String> {
+        Ok(match &self.base_url {
+            Some(base_url) => base_url.join(path)?.to_string(),
+            None => format!("/{}", path),
+        })
+    }
+}
+
+/// A [FeedEntry] corresponds to a slug that was changed in some revision of a
+/// sphere
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeedEntry {
+    /// A stable, unique identifier for the entry
+    pub id: String,
+    /// The slug that was changed
+    pub slug: String,
+    /// The revision of the sphere that changed the slug
+    pub revision: Cid,
+    /// The content that the slug was changed to refer to
+    pub content: Cid,
+    /// The `Title` header of the content, if any
+    pub title: Option<String>,
+    /// An excerpt of the content, as it would appear in a transclude
+    pub excerpt: Option<String>,
+    /// The permalink of the content
+    pub url: String,
+    /// When the entry was first included in the feeds, as an RFC 3339 date
+    pub updated: String,
+}
+
+impl FeedEntry {
+    /// The title of the entry, falling back to its slashlink if the content
+    /// does not have a `Title` header
+    pub fn display_title(&self) -> String {
+        self.title
+            .clone()
+            .unwrap_or_else(|| format!("/{}", self.slug))
+    }
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+struct JsonFeed {
+    version: String,
+    title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    home_page_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    feed_url: Option<String>,
+    #[serde(default)]
+    authors: Vec<JsonFeedAuthor>,
+    #[serde(default)]
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonFeedAuthor {
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonFeedItem {
+    id: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_published: Option<String>,
+}
+
+/// Given a sphere [Did], [SphereDb], [WriteTarget] and [FeedOptions], write an
+/// Atom feed (`feed.xml`) and a JSON Feed (`feed.json`) of the most recent
+/// changes to the slug-named content at the latest revision of the sphere; see
+/// [sphere_revision_into_feeds] for details.
+pub async fn sphere_into_feeds<S, W>(
+    sphere_identity: &Did,
+    db: &SphereDb<S>,
+    write_target: &W,
+    options: &FeedOptions,
+) -> Result<()>
+where
+    S: Storage + 'static,
+    W: WriteTarget + 'static,
+{
+    let sphere_cid = db
+        .get_version(sphere_identity)
+        .await?
+        .ok_or_else(|| anyhow!("Could not resolve CID for sphere {}", sphere_identity))?;
+
+    sphere_revision_into_feeds(sphere_identity, &sphere_cid, db, write_target, options).await
+}
+
+/// Same as [sphere_into_feeds], but the feeds are generated as of the given
+/// revision of the sphere. Each entry in the feeds is a slug that was changed
+/// to refer to some Subtext content in a revision, starting with the most
+/// recent; the entry has the `Title` header of the content, an excerpt of the
+/// content and a link to its permalink in the static site.
+///
+/// Sphere history does not record when revisions were made, so entries are
+/// dated by when they first appeared in the feeds: the prior JSON Feed in the
+/// [WriteTarget] is consulted for the dates of known entries, and new entries
+/// are dated by [FeedOptions::generated_at]. Each new entry is dated a second
+/// earlier than the new entry before it, so that readers that order entries by
+/// date keep them in the order of history (even when all of history is new,
+/// as it is when the feeds are first generated).
+pub async fn sphere_revision_into_feeds<S, W>(
+    sphere_identity: &Did,
+    sphere_revision: &Cid,
+    db: &SphereDb<S>,
+    write_target: &W,
+    options: &FeedOptions,
+) -> Result<()>
+where
+    S: Storage + 'static,
+    W: WriteTarget + 'static,
+{
+    let previous_dates = load_previous_dates(write_target).await?;
+    let generated_at = format_rfc3339(options.generated_at);
+    let mut new_entries = 0;
+    let entries = sphere_feed_entries(sphere_identity, sphere_revision, db, options, |id: &str| {
+        previous_dates.get(id).cloned().unwrap_or_else(|| {
+            let date = options
+                .generated_at
+                .checked_sub(Duration::from_secs(new_entries))
+                .unwrap_or(options.generated_at);
+
+            new_entries += 1;
+            format_rfc3339(date)
+        })
+    })
+    .await?;
+
+    let title = Sphere::at(sphere_revision, db)
+        .try_as_memo()
+        .await?
+        .get_first_header(&Header::Title.to_string())
+        .unwrap_or_else(|| "My sphere".into());
+
+    // RFC 3339 dates in UTC sort chronologically as strings
+    let updated = entries
+        .iter()
+        .map(|entry| entry.updated.clone())
+        .max()
+        .unwrap_or(generated_at);
+
+    let atom_feed = entries_to_atom(sphere_identity, &title, &updated, &entries, options)?;
+    let json_feed = entries_to_json_feed(sphere_identity, &title, &entries, options)?;
+
+    write_target
+        .write(&PathBuf::from(ATOM_FEED_PATH), Cursor::new(atom_feed))
+        .await?;
+    write_target
+        .write(
+            &PathBuf::from(JSON_FEED_PATH),
+            Cursor::new(serde_json::to_vec_pretty(&json_feed)?),
+        )
+        .await
+}
+
+/// Walk the history of a sphere from the given revision backwards, producing
+/// a [FeedEntry] for each slug that was changed to refer to Subtext content
+/// (until the maximum number of entries is reached). Content that appears more
+/// than once in history at the same slug is only included as of its most
+/// recent appearance there.
+async fn sphere_feed_entries<S, F>(
+    sphere_identity: &Did,
+    sphere_revision: &Cid,
+    db: &SphereDb<S>,
+    options: &FeedOptions,
+    mut date_for: F,
+) -> Result<Vec<FeedEntry>>
+where
+    S: Storage + 'static,
+    F: FnMut(&str) -> String,
+{
+    let author = Author::anonymous();
+    let timeline = Timeline::new(db);
+    let stream = timeline.try_stream(sphere_revision, None);
+
+    tokio::pin!(stream);
+
+    let mut included_content = BTreeSet::<(String, Cid)>::new();
+    let mut entries = Vec::new();
+
+    'history: while let Some((revision, _)) = stream.try_next().await? {
+        let links = Sphere::at(&revision, db).try_get_links().await?;
+        let changelog = links.try_get_changelog().await?;
+
+        if changelog.changes.is_empty() {
+            continue;
+        }
+
+        let fs = SphereFs::at(sphere_identity, &revision, &author, db).await?;
+
+        for change in changelog.changes.iter() {
+            if entries.len() >= options.max_entries {
+                break 'history;
+            }
+
+            let (slug, content) = match change {
+                MapOperation::Add { key, value } => (key, value),
+                MapOperation::Remove { .. } => continue,
+            };
+
+            if !included_content.insert((slug.clone(), *content)) {
+                continue;
+            }
+
+            match fs.read(slug).await? {
+                Some(file) if file.memo.content_type() == Some(ContentType::Subtext) => (),
+                _ => continue,
+            };
+
+            let url = options.href(&format!("permalink/{}/", content))?;
+            let link = ResolvedLink::Slashlink {
+                link: Slashlink {
+                    peer: Peer::None,
+                    slug: Some(slug.clone()),
+                },
+                href: url.clone(),
+            };
+
+            let (title, excerpt) = match SphereFsTranscluder::new(fs.clone())
+                .transclude(&link)
+                .await?
+            {
+                Some(Transclude::Text(text_transclude)) => {
+                    (text_transclude.title, text_transclude.excerpt)
+                }
+                _ => (None, None),
+            };
+
+            // The same content may be found at more than one slug, and
+            // each of them is an entry of its own
+            let id = format!("urn:cid:{}:{}", content, slug);
+            let updated = date_for(&id);
+
+            entries.push(FeedEntry {
+                id,
+                slug: slug.clone(),
+                revision,
+                content: *content,
+                title,
+                excerpt,
+                url,
+                updated,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Read the dates of the entries in the JSON Feed that was previously written
+/// to the [WriteTarget], keyed by entry ID
+async fn load_previous_dates<W: WriteTarget>(write_target: &W) -> Result<BTreeMap<String, String>> {
+    let bytes = match write_target.read(&PathBuf::from(JSON_FEED_PATH)).await? {
+        Some(bytes) => bytes,
+        None => return Ok(BTreeMap::new()),
+    };
+
+    let feed: JsonFeed = match serde_json::from_slice(&bytes) {
+        Ok(feed) => feed,
+        Err(error) => {
+            warn!("Ignoring unreadable JSON Feed: {}", error);
+            return Ok(BTreeMap::new());
+        }
+    };
+
+    Ok(feed
+        .items
+        .into_iter()
+        .filter_map(|item| Some((item.id, item.date_published?)))
+        .collect())
+}
+
+fn entries_to_atom(
+    sphere_identity: &Did,
+    title: &str,
+    updated: &str,
+    entries: &[FeedEntry],
+    options: &FeedOptions,
+) -> Result<String> {
+    let mut atom = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+
+    atom.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    atom.push_str(&format!("  <id>{}</id>\n", escape_xml(sphere_identity)));
+    atom.push_str(&format!("  <title>{}</title>\n", escape_xml(title)));
+    atom.push_str(&format!("  <updated>{}</updated>\n", updated));
+    atom.push_str(&format!(
+        "  <author><name>{}</name></author>\n",
+        escape_xml(sphere_identity)
+    ));
+    atom.push_str(&format!(
+        "  <link rel=\"alternate\" href=\"{}\"/>\n",
+        escape_xml(&options.href("")?)
+    ));
+    atom.push_str(&format!(
+        "  <link rel=\"self\" href=\"{}\"/>\n",
+        escape_xml(&options.href(ATOM_FEED_PATH)?)
+    ));
+
+    for entry in entries {
+        atom.push_str("  <entry>\n");
+        atom.push_str(&format!("    <id>{}</id>\n", escape_xml(&entry.id)));
+        atom.push_str(&format!(
+            "    <title>{}</title>\n",
+            escape_xml(&entry.display_title())
+        ));
+        atom.push_str(&format!("    <updated>{}</updated>\n", entry.updated));
+        atom.push_str(&format!(
+            "    <link rel=\"alternate\" href=\"{}\"/>\n",
+            escape_xml(&entry.url)
+        ));
+
+        if let Some(excerpt) = &entry.excerpt {
+            atom.push_str(&format!("    <summary>{}</summary>\n", escape_xml(excerpt)));
+        }
+
+        atom.push_str("  </entry>\n");
+    }
+
+    atom.push_str("</feed>\n");
+
+    Ok(atom)
+}
+
+fn entries_to_json_feed(
+    sphere_identity: &Did,
+    title: &str,
+    entries: &[FeedEntry],
+    options: &FeedOptions,
+) -> Result<JsonFeed> {
+    Ok(JsonFeed {
+        version: JSON_FEED_VERSION.into(),
+        title: title.to_owned(),
+        home_page_url: Some(options.href("")?),
+        feed_url: Some(options.href(JSON_FEED_PATH)?),
+        authors: vec![JsonFeedAuthor {
+            name: sphere_identity.to_string(),
+        }],
+        items: entries
+            .iter()
+            .map(|entry| JsonFeedItem {
+                id: entry.id.clone(),
+                url: entry.url.clone(),
+                title: Some(entry.display_title()),
+                summary: entry.excerpt.clone(),
+                date_published: Some(entry.updated.clone()),
+            })
+            .collect(),
+    })
+}
+
+/// Escape text for inclusion in XML character data or attribute values
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+/// Format a [SystemTime] as an RFC 3339 date in UTC (e.g.,
+/// `2001-09-09T01:46:40Z`); times before the Unix epoch are clamped to it
+fn format_rfc3339(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    let days = (seconds / 86400) as i64;
+    let seconds_of_day = seconds % 86400;
+
+    // Convert days since the epoch to a proleptic Gregorian calendar date; see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        path::PathBuf,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use noosphere_core::{
+        data::{ContentType, Did, Header},
+        view::Sphere,
+    };
+    use noosphere_fs::helpers::make_sphere_fs;
+    use noosphere_storage::{MemoryStorage, SphereDb};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::write::MemoryWriteTarget;
+
+    use super::{format_rfc3339, sphere_into_feeds, FeedOptions, JsonFeed};
+
+    async fn read_json_feed(write_target: &MemoryWriteTarget) -> JsonFeed {
+        serde_json::from_slice(
+            &write_target
+                .read(&PathBuf::from("feed.json"))
+                .await
+                .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_writes_feeds_of_changes_from_sphere_history() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let sphere_identity = Did(fs.identity().to_string());
+
+        fs.write(
+            "cats",
+            &ContentType::Subtext.to_string(),
+            b"\nCats are <great>".as_ref(),
+            Some(vec![(Header::Title.to_string(), "Cats & kittens".into())]),
+        )
+        .await
+        .unwrap();
+        fs.write("photo", "image/png", b"Not really a photo".as_ref(), None)
+            .await
+            .unwrap();
+        fs.save(None).await.unwrap();
+
+        fs.write(
+            "dogs",
+            &ContentType::Subtext.to_string(),
+            b"Dogs are also great".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.save(None).await.unwrap();
+
+        let write_target = MemoryWriteTarget::default();
+        let first_generation = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+        sphere_into_feeds(
+            &sphere_identity,
+            &db,
+            &write_target,
+            &FeedOptions::new(first_generation),
+        )
+        .await
+        .unwrap();
+
+        let links = Sphere::at(&db.require_version(&sphere_identity).await.unwrap(), &db)
+            .try_get_links()
+            .await
+            .unwrap();
+        let cats_cid = *links.require(&"cats".to_string()).await.unwrap();
+
+        let json_feed = read_json_feed(&write_target).await;
+
+        assert_eq!(json_feed.items.len(), 2);
+        assert_eq!(json_feed.items[0].title.as_deref(), Some("/dogs"));
+        assert_eq!(
+            json_feed.items[0].date_published.as_deref(),
+            Some("2001-09-09T01:46:40Z")
+        );
+        assert_eq!(
+            json_feed.items[0].summary.as_deref(),
+            Some("Dogs are also great")
+        );
+        assert_eq!(json_feed.items[1].title.as_deref(), Some("Cats & kittens"));
+        assert_eq!(
+            json_feed.items[1].summary.as_deref(),
+            Some("Cats are <great>")
+        );
+        assert_eq!(json_feed.items[1].url, format!("/permalink/{}/", cats_cid));
+        assert_eq!(
+            json_feed.items[1].date_published.as_deref(),
+            Some("2001-09-09T01:46:39Z")
+        );
+
+        let atom_feed =
+            String::from_utf8(write_target.read(&PathBuf::from("feed.xml")).await.unwrap())
+                .unwrap();
+
+        assert!(atom_feed.contains(&format!("<id>{}</id>", sphere_identity)));
+        assert!(atom_feed.contains("<title>Cats &amp; kittens</title>"));
+        assert!(atom_feed.contains("<summary>Cats are &lt;great&gt;</summary>"));
+        assert!(atom_feed.contains(&format!(
+            "<link rel=\"alternate\" href=\"/permalink/{}/\"/>",
+            cats_cid
+        )));
+        assert!(atom_feed.contains("<updated>2001-09-09T01:46:40Z</updated>"));
+
+        fs.write(
+            "cats",
+            &ContentType::Subtext.to_string(),
+            b"Cats are still great".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.save(None).await.unwrap();
+
+        let mut options = FeedOptions::new(first_generation + Duration::from_secs(60));
+        options.base_url = Some("https://example.com/sphere/".parse().unwrap());
+
+        sphere_into_feeds(&sphere_identity, &db, &write_target, &options)
+            .await
+            .unwrap();
+
+        let json_feed = read_json_feed(&write_target).await;
+
+        assert_eq!(json_feed.items.len(), 3);
+        assert_eq!(
+            json_feed.items[0].summary.as_deref(),
+            Some("Cats are still great")
+        );
+        assert_eq!(
+            json_feed.items[0].date_published.as_deref(),
+            Some("2001-09-09T01:47:40Z")
+        );
+        assert_eq!(
+            json_feed.items[2].url,
+            format!("https://example.com/sphere/permalink/{}/", cats_cid)
+        );
+        assert_eq!(
+            json_feed.items[2].date_published.as_deref(),
+            Some("2001-09-09T01:46:39Z")
+        );
+        assert_eq!(
+            json_feed.feed_url.as_deref(),
+            Some("https://example.com/sphere/feed.json")
+        );
+
+        let mut options = FeedOptions::new(first_generation);
+        options.max_entries = 1;
+
+        sphere_into_feeds(&sphere_identity, &db, &write_target, &options)
+            .await
+            .unwrap();
+
+        assert_eq!(read_json_feed(&write_target).await.items.len(), 1);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_includes_the_same_content_at_each_of_its_slugs() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let sphere_identity = Did(fs.identity().to_string());
+
+        for slug in ["cats", "kittens"] {
+            fs.write(
+                slug,
+                &ContentType::Subtext.to_string(),
+                b"Cats are great".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        fs.save(None).await.unwrap();
+
+        let write_target = MemoryWriteTarget::default();
+
+        sphere_into_feeds(
+            &sphere_identity,
+            &db,
+            &write_target,
+            &FeedOptions::new(UNIX_EPOCH + Duration::from_secs(1_000_000_000)),
+        )
+        .await
+        .unwrap();
+
+        let json_feed = read_json_feed(&write_target).await;
+
+        assert_eq!(json_feed.items.len(), 2);
+        assert_eq!(json_feed.items[0].url, json_feed.items[1].url);
+        assert_ne!(json_feed.items[0].id, json_feed.items[1].id);
+        assert_eq!(
+            json_feed
+                .items
+                .iter()
+                .map(|item| item.title.clone().unwrap())
+                .collect::<BTreeSet<String>>(),
+            BTreeSet::from(["/cats".to_string(), "/kittens".to_string()])
+        );
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_formats_times_as_rfc3339_dates() {
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_rfc3339(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(
+            format_rfc3339(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            "2023-11-14T22:13:20Z"
+        );
+    }
+}
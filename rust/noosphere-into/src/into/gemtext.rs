@@ -0,0 +1,104 @@
+use anyhow::Result;
+use noosphere_core::data::Did;
+use noosphere_storage::{SphereDb, Storage};
+
+use crate::{sphere_into_documents, DocumentFormat, WriteTarget};
+
+/// Given a sphere [Did], [SphereDb] and a [WriteTarget], write the latest
+/// revision of the sphere's content as a set of Gemtext documents (suitable
+/// for a Gemini capsule). Slashlinks become relative link lines
+/// between the documents, and transcludes become quotes; see
+/// [sphere_into_documents] for details.
+pub async fn sphere_into_gemtext<S, W>(
+    sphere_identity: &Did,
+    db: &SphereDb<S>,
+    write_target: &W,
+) -> Result<()>
+where
+    S: Storage + 'static,
+    W: WriteTarget + 'static,
+{
+    sphere_into_documents(sphere_identity, db, write_target, DocumentFormat::Gemtext).await
+}
+
+#[cfg(test)]
+mod tests {
+    use noosphere_storage::{MemoryStorage, SphereDb};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::{
+        into::documents::tests::{make_document_fixture, read_document},
+        write::MemoryWriteTarget,
+    };
+
+    use super::sphere_into_gemtext;
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_writes_the_sphere_as_linked_gemtext_documents() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let sphere_identity = make_document_fixture(&mut db).await;
+
+        let write_target = MemoryWriteTarget::default();
+
+        sphere_into_gemtext(&sphere_identity, &db, &write_target)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            read_document(&write_target, "cats.gmi").await.unwrap(),
+            r#"# Cats
+
+Cats are great
+=> great.gmi great
+
+> Animals
+> Animals are multicellular.
+=> animals.gmi /animals
+
+* One
+* Two
+"#
+        );
+
+        assert_eq!(
+            read_document(&write_target, "notes/today.gmi")
+                .await
+                .unwrap(),
+            r#"Today I saw /animals
+=> ../animals.gmi /animals
+> Animals
+> Animals are multicellular.
+=> ../animals.gmi /animals
+"#
+        );
+
+        let index = read_document(&write_target, "index.gmi").await.unwrap();
+
+        assert!(index.ends_with(
+            r#"
+=> animals.gmi /animals
+=> cats.gmi /cats
+=> gallery.gmi /gallery
+=> notes/today.gmi /notes/today
+"#
+        ));
+
+        assert!(read_document(&write_target, "photo.gmi").await.is_none());
+
+        // The photo is not rendered, but its body is written alongside the
+        // documents so that it can be linked to
+        assert_eq!(
+            read_document(&write_target, "gallery.gmi").await.unwrap(),
+            "=> photo.png /photo\n"
+        );
+        assert_eq!(
+            read_document(&write_target, "photo.png").await.unwrap(),
+            "Not really a photo"
+        );
+    }
+}
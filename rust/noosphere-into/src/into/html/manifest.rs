@@ -0,0 +1,92 @@
+use std::{collections::BTreeMap, io::Cursor, path::PathBuf};
+
+use anyhow::Result;
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+
+use crate::WriteTarget;
+
+/// The version of the static HTML renderer. This must be incremented whenever
+/// a change is made that alters the rendered output (for example, changes to
+/// the markup, the stylesheet or the link scheme of [crate::StaticHtmlResolver]),
+/// so that output produced by an earlier version is detected as stale and
+/// regenerated.
+pub const HTML_RENDERER_VERSION: u32 = 3;
+
+/// The path within a [WriteTarget] where the [HtmlManifest] is stored
+pub const HTML_MANIFEST_PATH: &str = "manifest.json";
+
+/// An [HtmlManifest] records which version of the static HTML renderer
+/// produced each file that has been written to a [WriteTarget]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HtmlManifest {
+    /// The renderer version that produced each file, keyed by its path
+    pub files: BTreeMap<PathBuf, u32>,
+    /// The CID of the latest revision of the sphere that has been rendered
+    #[serde(default)]
+    pub latest_revision: Option<String>,
+}
+
+impl HtmlManifest {
+    /// Load the manifest from a [WriteTarget]. If there is no manifest (or it
+    /// cannot be parsed), an empty one is returned, which means that all
+    /// prior output will be considered stale.
+    pub async fn load<W: WriteTarget>(write_target: &W) -> Result<Self> {
+        let bytes = match write_target
+            .read(&PathBuf::from(HTML_MANIFEST_PATH))
+            .await?
+        {
+            Some(bytes) => bytes,
+            None => return Ok(HtmlManifest::default()),
+        };
+
+        Ok(match serde_json::from_slice(&bytes) {
+            Ok(manifest) => manifest,
+            Err(error) => {
+                warn!("Ignoring unreadable HTML manifest: {}", error);
+                HtmlManifest::default()
+            }
+        })
+    }
+
+    /// Write the manifest to a [WriteTarget]
+    pub async fn save<W: WriteTarget>(&self, write_target: &W) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(self)?;
+
+        write_target
+            .write(&PathBuf::from(HTML_MANIFEST_PATH), Cursor::new(bytes))
+            .await
+    }
+
+    /// Returns true if the file at the given path was produced by the current
+    /// [HTML_RENDERER_VERSION]
+    pub fn is_current(&self, path: &PathBuf) -> bool {
+        self.files.get(path) == Some(&HTML_RENDERER_VERSION)
+    }
+
+    /// Returns true if any file was produced by a version of the renderer
+    /// other than the current [HTML_RENDERER_VERSION]
+    pub fn has_stale_files(&self) -> bool {
+        self.files
+            .values()
+            .any(|version| *version != HTML_RENDERER_VERSION)
+    }
+
+    /// The latest revision of the sphere that has been rendered, if known
+    pub fn latest_revision(&self) -> Option<Cid> {
+        self.latest_revision
+            .as_ref()
+            .and_then(|revision| Cid::try_from(revision.as_str()).ok())
+    }
+
+    /// Record the latest revision of the sphere that has been rendered
+    pub fn set_latest_revision(&mut self, revision: &Cid) {
+        self.latest_revision = Some(revision.to_string());
+    }
+
+    /// Record that the file at the given path was produced by the current
+    /// [HTML_RENDERER_VERSION]
+    pub fn record(&mut self, path: &PathBuf) {
+        self.files.insert(path.clone(), HTML_RENDERER_VERSION);
+    }
+}
@@ -1,5 +1,9 @@
 mod envelope;
+mod manifest;
+mod policy;
 mod sphere;
 
 pub use envelope::*;
+pub use manifest::*;
+pub use policy::*;
 pub use sphere::*;
@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+
+use cid::Cid;
+
+/// A [RegenerationPolicy] determines which previously rendered parts of a
+/// sphere's history are rendered again by [crate::sphere_into_html_with_policy].
+/// Output that is missing, or that was produced by a different version of the
+/// renderer (see [crate::HtmlManifest]), is always rendered regardless of the
+/// policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegenerationPolicy {
+    /// Only render output that is missing or stale
+    Stale,
+    /// Render the complete history of the sphere again
+    All,
+    /// Render the given number of most recent revisions of the sphere again
+    LatestRevisions(usize),
+    /// Render the content at the given slugs again, at every revision
+    Slugs(BTreeSet<String>),
+    /// Render every revision of the sphere that came after the given one
+    /// (exclusive of it) again
+    Since(Cid),
+}
+
+impl Default for RegenerationPolicy {
+    fn default() -> Self {
+        RegenerationPolicy::Stale
+    }
+}
+
+impl RegenerationPolicy {
+    /// Returns true if the policy requires the whole of the sphere's history
+    /// to be visited, even where it has already been rendered
+    pub fn visits_all_history(&self) -> bool {
+        matches!(self, RegenerationPolicy::All | RegenerationPolicy::Slugs(_))
+    }
+
+    /// Returns true if the policy requires that all of the content at the
+    /// given revision be rendered again; `depth` is the number of revisions
+    /// between the given one and the latest one, and `reached_since` is true
+    /// if the revision named by [RegenerationPolicy::Since] has been reached
+    pub fn forces_revision(&self, depth: usize, reached_since: bool) -> bool {
+        match self {
+            RegenerationPolicy::Stale | RegenerationPolicy::Slugs(_) => false,
+            RegenerationPolicy::All => true,
+            RegenerationPolicy::LatestRevisions(count) => depth < *count,
+            RegenerationPolicy::Since(_) => !reached_since,
+        }
+    }
+
+    /// Returns true if the policy requires that the content at the given slug
+    /// be rendered again at every revision
+    pub fn forces_slug(&self, slug: &str) -> bool {
+        match self {
+            RegenerationPolicy::Slugs(slugs) => slugs.contains(slug),
+            _ => false,
+        }
+    }
+}
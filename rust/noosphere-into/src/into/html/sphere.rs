@@ -7,17 +7,21 @@ use noosphere_fs::SphereFs;
 use noosphere_storage::{SphereDb, Storage};
 use tokio::sync::Mutex;
 use tokio_stream::StreamExt;
+use ucan_key_support::ed25519::Ed25519KeyMaterial;
 
 use crate::{
-    file_to_html_stream, sphere_to_html_document_stream, HtmlOutput, StaticHtmlTransform,
-    TransformStream, WriteTarget,
+    body_file_name, file_to_html_stream, sphere_to_html_document_stream, HtmlManifest, HtmlOutput,
+    RegenerationPolicy, StaticHtmlResolver, StaticHtmlTransform, TranscludeFormat, TransformStream,
+    WriteTarget,
 };
 
 static DEFAULT_STYLES: &[u8] = include_bytes!("./static/styles.css");
 
 /// Given a sphere [Did], [SphereDb] and a [WriteTarget], produce rendered HTML
 /// output up to and including the complete historical revisions of the
-/// slug-named content of the sphere.
+/// slug-named content of the sphere. Output that has already been rendered by
+/// the current [HTML_RENDERER_VERSION] is not rendered again; see
+/// [sphere_into_html_with_policy] to force some or all of it to be.
 pub async fn sphere_into_html<S, W>(
     sphere_identity: &Did,
     db: &SphereDb<S>,
@@ -27,8 +31,31 @@ where
     S: Storage + 'static,
     W: WriteTarget + 'static,
 {
-    let mut next_sphere_cid = match db.get_version(sphere_identity).await? {
-        Some(link) => Some(link),
+    sphere_into_html_with_policy(
+        sphere_identity,
+        db,
+        write_target,
+        &RegenerationPolicy::default(),
+    )
+    .await
+}
+
+/// Same as [sphere_into_html], but the given [RegenerationPolicy] determines
+/// which previously rendered output is rendered again. Missing or stale
+/// output (according to the [HtmlManifest] kept in the [WriteTarget]) is
+/// always rendered.
+pub async fn sphere_into_html_with_policy<S, W>(
+    sphere_identity: &Did,
+    db: &SphereDb<S>,
+    write_target: &W,
+    policy: &RegenerationPolicy,
+) -> Result<()>
+where
+    S: Storage + 'static,
+    W: WriteTarget + 'static,
+{
+    let latest_sphere_cid = match db.get_version(sphere_identity).await? {
+        Some(link) => link,
         _ => {
             return Err(anyhow!(
                 "Could not resolve CID for sphere {}",
@@ -36,23 +63,85 @@ where
             ))
         }
     };
+    let mut next_sphere_cid = Some(latest_sphere_cid);
 
     let write_target = Arc::new(write_target.clone());
+    let manifest = Arc::new(Mutex::new(HtmlManifest::load(write_target.as_ref()).await?));
+    let write_actions = Arc::new(Mutex::new(BTreeSet::<Cid>::new()));
     let mut latest_revision = true;
+    let mut depth = 0;
+    let mut reached_since = false;
     let author = Author::anonymous();
 
+    let latest_fs = SphereFs::at(sphere_identity, &latest_sphere_cid, &author, db).await?;
+
+    // Pages are only rendered once per content CID, so content that has not
+    // changed since the last render must be rendered again if the set of
+    // content that links to it has changed. The previously rendered
+    // revision's backlinks are read first, as their cached index is dropped
+    // once the latest revision's index is derived from it.
+    let previous_revision = manifest.lock().await.latest_revision();
+    let previous_backlink_index = match previous_revision {
+        Some(previous) if previous != latest_sphere_cid => Some(
+            match SphereFs::at(sphere_identity, &previous, &author, db).await {
+                Ok(fs) => fs.backlink_index().await,
+                Err(error) => Err(error),
+            },
+        ),
+        _ => None,
+    };
+
+    // The backlinks of each revision are derived from those of the revision
+    // after it, as history is rendered from latest to oldest
+    let mut backlink_index = latest_fs.backlink_index().await?;
+
+    // Slugs whose pages must be rendered again as of the latest revision
+    // (where [None] means all of them)
+    let relinked_slugs = match previous_backlink_index {
+        Some(Ok(previous_backlink_index)) => {
+            Some(previous_backlink_index.changed_backlinks(&backlink_index))
+        }
+        Some(Err(error)) => {
+            warn!(
+                "Could not read the backlinks of the previously rendered revision: {:?}",
+                error
+            );
+            None
+        }
+        None => Some(BTreeSet::new()),
+    };
+
+    // Slashlinks into followed spheres lead to their content as of the latest
+    // revision of this sphere (see [followed_sphere_into_html])
+    let followed = find_followed_spheres(&latest_fs).await?;
+    let resolver = StaticHtmlResolver::new(Arc::new(
+        followed
+            .iter()
+            .map(|(petname, _)| petname.clone())
+            .collect(),
+    ));
+
+    // If any output is known to be stale, it may be anywhere in history
+    let visit_all_history = policy.visits_all_history() || manifest.lock().await.has_stale_files();
+
     while let Some(sphere_cid) = next_sphere_cid {
         let sphere_index: PathBuf = format!("permalink/{}/index.html", sphere_cid).into();
 
+        if let RegenerationPolicy::Since(since) = policy {
+            reached_since = reached_since || since == &sphere_cid;
+        }
+
+        let force_revision = policy.forces_revision(depth, reached_since);
+        let index_is_current = is_rendered(&manifest, write_target.as_ref(), &sphere_index).await?;
+
         // We write the sphere index last, so if we already have it we can
-        // assume this revision has been written in the past
-        // TODO(#54): Figure out how to enable forced-regeneration of some-or-all
-        // of history that has been generated before
-        if write_target.exists(&sphere_index).await? {
+        // assume this revision has been written in the past (unless the
+        // policy calls for revisiting it)
+        if index_is_current && !force_revision && !visit_all_history {
             break;
         }
 
-        let write_actions = Arc::new(Mutex::new(BTreeSet::<Cid>::new()));
+        let revision_backlink_index = Arc::new(std::mem::take(&mut backlink_index));
         let sphere = Sphere::at(&sphere_cid, db);
         let links = sphere.try_get_links().await?;
         let mut link_stream = links.stream().await?;
@@ -61,13 +150,16 @@ where
 
         while let Some(Ok((slug, cid))) = link_stream.next().await {
             let file_name: PathBuf = format!("permalink/{}/index.html", cid).into();
-
-            // Skip this write entirely if the content has been written
-            // TODO(#55): This may not hold in a world where there are multiple
-            // files written per slug; an example might be a video file that
-            // needs to be transformed into an HTML document to present the
-            // video, and the video file itself.
-            if write_target.exists(&file_name).await? {
+            let is_relinked = latest_revision
+                && relinked_slugs
+                    .as_ref()
+                    .map_or(true, |relinked_slugs| relinked_slugs.contains(slug));
+            let force_file = force_revision || is_relinked || policy.forces_slug(slug);
+
+            // Skip this write entirely if the content has been written; any
+            // body file for the content is written before its page, so the
+            // presence of the page implies the presence of the body
+            if !force_file && is_rendered(&manifest, write_target.as_ref(), &file_name).await? {
                 continue;
             }
 
@@ -75,10 +167,13 @@ where
                 let slug = slug.clone();
                 let cid = *cid;
                 let write_actions = write_actions.clone();
+                let backlink_index = revision_backlink_index.clone();
+                let manifest = manifest.clone();
                 let write_target = write_target.clone();
                 let sphere_identity = sphere_identity.clone();
                 let db = db.clone();
                 let author = author.clone();
+                let resolver = resolver.clone();
                 let latest_revision = latest_revision;
 
                 async move {
@@ -87,7 +182,8 @@ where
 
                         // Skip this write, to cover the case where we have
                         // multiple slugs referring to the same CID (and the
-                        // write is already being handled by another task).
+                        // write is already being handled by another task), or
+                        // the CID was already written for a later revision
                         if write_actions.contains(&cid) {
                             return Ok(());
                         } else {
@@ -96,20 +192,23 @@ where
                     }
 
                     let fs = SphereFs::at(&sphere_identity, &sphere_cid, &author, &db).await?;
-                    let sphere_file = fs
-                        .read(&slug)
-                        .await?
-                        .ok_or_else(|| anyhow!("No file found for {}", slug))?;
-
-                    let transform = StaticHtmlTransform::new(fs);
-                    let reader = TransformStream(file_to_html_stream(
-                        transform,
-                        sphere_file,
-                        HtmlOutput::Document,
-                    ))
-                    .into_reader();
 
-                    write_target.write(&file_name, reader).await?;
+                    // NOTE: Backlinks are rendered as of the revision that
+                    // first causes this content to be written; since history
+                    // is rendered from latest to oldest, this is the most
+                    // recent revision that includes the content
+                    let backlinks = backlink_index.backlinks(&slug);
+
+                    content_into_html(
+                        fs,
+                        resolver,
+                        &slug,
+                        &cid,
+                        backlinks,
+                        write_target.as_ref(),
+                        &manifest,
+                    )
+                    .await?;
 
                     if latest_revision {
                         write_target
@@ -117,9 +216,6 @@ where
                             .await?;
                     }
 
-                    // TODO(#56): Support backlinks somehow; probably as a dynamic
-                    // widget at the bottom of the HTML document
-
                     Ok(())
                 }
             }));
@@ -130,24 +226,70 @@ where
         // cases where writing content may fail
         futures::future::try_join_all(tasks).await?;
 
-        let fs = SphereFs::at(sphere_identity, &sphere_cid, &author, db).await?;
-        let transform = StaticHtmlTransform::new(fs);
-        let reader = TransformStream(sphere_to_html_document_stream(
-            transform,
-            Sphere::at(&sphere_cid, db),
-        ))
-        .into_reader();
+        if !index_is_current || force_revision {
+            let fs = SphereFs::at(sphere_identity, &sphere_cid, &author, db).await?;
+            let transform = StaticHtmlTransform::with_resolver(fs, resolver.clone());
+            let reader = TransformStream(sphere_to_html_document_stream(
+                transform,
+                Sphere::at(&sphere_cid, db),
+            ))
+            .into_reader();
 
-        write_target.write(&sphere_index, reader).await?;
+            write_target.write(&sphere_index, reader).await?;
 
-        if latest_revision {
-            write_target
-                .symlink(&sphere_index, &PathBuf::from("index.html"))
-                .await?;
+            if latest_revision {
+                write_target
+                    .symlink(&sphere_index, &PathBuf::from("index.html"))
+                    .await?;
+            }
         }
 
-        next_sphere_cid = sphere.try_as_memo().await?.parent;
+        // Record progress as each revision is completed, so that an
+        // interrupted render does not have to start over from scratch
+        {
+            let mut manifest = manifest.lock().await;
+            manifest.record(&sphere_index);
+            if latest_revision {
+                manifest.set_latest_revision(&sphere_cid);
+            }
+            manifest.save(write_target.as_ref()).await?;
+        }
+
+        // All of the tasks that shared this revision's index have completed
+        let revision_backlink_index =
+            Arc::try_unwrap(revision_backlink_index).unwrap_or_else(|index| (*index).clone());
+
+        next_sphere_cid = match SphereFs::at(sphere_identity, &sphere_cid, &author, db)
+            .await?
+            .rewind_backlink_index(revision_backlink_index)
+            .await?
+        {
+            Some((parent, parent_backlink_index)) => {
+                backlink_index = parent_backlink_index;
+                Some(parent)
+            }
+            None => None,
+        };
         latest_revision = false;
+        depth += 1;
+    }
+
+    // Followed spheres may have changed even if this sphere has not, so their
+    // content is always visited (though only missing pages are rendered)
+    for (petname, fs) in followed {
+        followed_sphere_into_html(
+            &petname,
+            fs,
+            write_target.clone(),
+            manifest.clone(),
+            write_actions.clone(),
+            policy.forces_revision(0, false),
+        )
+        .await?;
+    }
+
+    {
+        manifest.lock().await.save(write_target.as_ref()).await?;
     }
 
     // TODO(#57): Writing these static files should be done concurrently
@@ -158,6 +300,12 @@ where
         )
         .await?;
 
+    {
+        let mut manifest = manifest.lock().await;
+        manifest.record(&PathBuf::from("theme/styles.css"));
+        manifest.save(write_target.as_ref()).await?;
+    }
+
     // TODO(#58): Introduce some kind of default logo
     // write_target
     //     .write(
@@ -169,16 +317,182 @@ where
     Ok(())
 }
 
+/// Find the followed spheres (by petname) whose content can be read as of the
+/// revision that the given [SphereFs] is pointing to
+async fn find_followed_spheres<S>(
+    fs: &SphereFs<S, Ed25519KeyMaterial>,
+) -> Result<Vec<(String, SphereFs<S, Ed25519KeyMaterial>)>>
+where
+    S: Storage + 'static,
+{
+    let mut followed = Vec::new();
+
+    for petname in fs.list_petnames().await?.into_keys() {
+        match fs.traverse_by_petname(&petname).await {
+            Ok(Some(peer_fs)) => followed.push((petname, peer_fs)),
+            Ok(None) => debug!("Not rendering @{}; it has not been resolved", petname),
+            Err(error) => warn!("Not rendering @{}: {:?}", petname, error),
+        }
+    }
+
+    Ok(followed)
+}
+
+/// Render the slug-named content of a followed sphere, as of the revision that
+/// the given [SphereFs] is pointing to, so that it is found at
+/// `@<petname>/<slug>`. Slashlinks within that content resolve to other
+/// content of the same followed sphere; slashlinks into any further spheres
+/// are not followed.
+async fn followed_sphere_into_html<S, W>(
+    petname: &str,
+    fs: SphereFs<S, Ed25519KeyMaterial>,
+    write_target: Arc<W>,
+    manifest: Arc<Mutex<HtmlManifest>>,
+    write_actions: Arc<Mutex<BTreeSet<Cid>>>,
+    force: bool,
+) -> Result<()>
+where
+    S: Storage + 'static,
+    W: WriteTarget + 'static,
+{
+    let sphere = fs.to_sphere();
+    let links = sphere.try_get_links().await?;
+    let mut link_stream = links.stream().await?;
+
+    let mut tasks = Vec::new();
+
+    while let Some(Ok((slug, cid))) = link_stream.next().await {
+        tasks.push(W::spawn({
+            let petname = petname.to_owned();
+            let slug = slug.clone();
+            let cid = *cid;
+            let fs = fs.clone();
+            let write_target = write_target.clone();
+            let manifest = manifest.clone();
+            let write_actions = write_actions.clone();
+
+            async move {
+                let file_name: PathBuf = format!("permalink/{}/index.html", cid).into();
+
+                // The same content may already have been written for this
+                // sphere (or for another followed sphere); whichever writes it
+                // first decides how its slashlinks are resolved
+                let is_claimed = !write_actions.lock().await.insert(cid);
+                let is_written = is_claimed
+                    || (!force
+                        && is_rendered(&manifest, write_target.as_ref(), &file_name).await?);
+
+                if !is_written {
+                    content_into_html(
+                        fs,
+                        StaticHtmlResolver::for_peer(&petname),
+                        &slug,
+                        &cid,
+                        BTreeSet::new(),
+                        write_target.as_ref(),
+                        &manifest,
+                    )
+                    .await?;
+                }
+
+                write_target
+                    .symlink(
+                        &format!("permalink/{}", cid).into(),
+                        &PathBuf::from(format!("@{}/{}", petname, slug)),
+                    )
+                    .await
+            }
+        }));
+    }
+
+    futures::future::try_join_all(tasks).await?;
+
+    Ok(())
+}
+
+/// Render the permalink page of the content at the given slug (along with its
+/// body, if the content is presented by embedding it), resolving its links
+/// with the given [StaticHtmlResolver]
+async fn content_into_html<S, W>(
+    fs: SphereFs<S, Ed25519KeyMaterial>,
+    resolver: StaticHtmlResolver,
+    slug: &str,
+    cid: &Cid,
+    backlinks: BTreeSet<String>,
+    write_target: &W,
+    manifest: &Mutex<HtmlManifest>,
+) -> Result<()>
+where
+    S: Storage + 'static,
+    W: WriteTarget + 'static,
+{
+    let file_name: PathBuf = format!("permalink/{}/index.html", cid).into();
+    let mut sphere_file = fs
+        .read(slug)
+        .await?
+        .ok_or_else(|| anyhow!("No file found for {}", slug))?;
+
+    // Content that is presented by embedding it (such as an image) has its
+    // body written alongside its page (and before it, so that the presence of
+    // the page implies the presence of the body)
+    if TranscludeFormat::from_memo(&sphere_file.memo).has_body() {
+        let body_path = permalink_body_path(cid, &body_file_name(&sphere_file.memo));
+
+        write_target.write(&body_path, sphere_file.contents).await?;
+        manifest.lock().await.record(&body_path);
+
+        sphere_file = fs
+            .read(slug)
+            .await?
+            .ok_or_else(|| anyhow!("No file found for {}", slug))?;
+    }
+
+    let reader = TransformStream(file_to_html_stream(
+        StaticHtmlTransform::with_resolver(fs, resolver),
+        sphere_file,
+        HtmlOutput::Document,
+        backlinks,
+    ))
+    .into_reader();
+
+    write_target.write(&file_name, reader).await?;
+    manifest.lock().await.record(&file_name);
+
+    Ok(())
+}
+
+/// The path within a [WriteTarget] that the body of some content (see
+/// [body_file_name]) is written to, alongside the permalink page of the
+/// content with the given memo CID
+pub fn permalink_body_path(content: &Cid, file_name: &str) -> PathBuf {
+    PathBuf::from(format!("permalink/{}/{}", content, file_name))
+}
+
+/// Returns true if the file at the given path has been written by the current
+/// version of the renderer
+async fn is_rendered<W: WriteTarget>(
+    manifest: &Mutex<HtmlManifest>,
+    write_target: &W,
+    path: &PathBuf,
+) -> Result<bool> {
+    let is_current = manifest.lock().await.is_current(path);
+
+    Ok(is_current && write_target.exists(path).await?)
+}
+
 #[cfg(test)]
 pub mod tests {
-    use std::path::PathBuf;
+    use std::{collections::BTreeSet, path::PathBuf};
 
     use noosphere_core::{
         authority::{generate_ed25519_key, Author},
-        data::{ContentType, Header},
+        data::{ContentType, Did, Header},
         view::Sphere,
     };
-    use noosphere_fs::SphereFs;
+    use noosphere_fs::{
+        helpers::{make_name_record, make_sphere_fs},
+        SphereFs,
+    };
     use noosphere_storage::{MemoryStorage, SphereDb};
     use ucan::crypto::KeyMaterial;
 
@@ -187,9 +501,18 @@ pub mod tests {
 
     wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
 
-    use crate::write::MemoryWriteTarget;
+    use crate::{
+        write::MemoryWriteTarget, HtmlManifest, RegenerationPolicy, WriteTarget,
+        HTML_RENDERER_VERSION,
+    };
 
-    use super::sphere_into_html;
+    use super::{sphere_into_html, sphere_into_html_with_policy};
+
+    async fn overwrite_with_stale_output(write_target: &MemoryWriteTarget, paths: &[&PathBuf]) {
+        for path in paths {
+            write_target.write(*path, b"Stale".as_ref()).await.unwrap();
+        }
+    }
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
@@ -226,15 +549,16 @@ pub mod tests {
             .await
             .unwrap();
 
-        fs.write(
-            "animals",
-            &ContentType::Subtext.to_string(),
-            b"Animals are multicellular, eukaryotic organisms in the biological kingdom Animalia."
-                .as_ref(),
-            Some(vec![(Header::Title.to_string(), "Animals".into())]),
-        )
-        .await
-        .unwrap();
+        let animals_cid = fs
+            .write(
+                "animals",
+                &ContentType::Subtext.to_string(),
+                b"Animals are multicellular, eukaryotic organisms in the biological kingdom Animalia."
+                    .as_ref(),
+                Some(vec![(Header::Title.to_string(), "Animals".into())]),
+            )
+            .await
+            .unwrap();
 
         fs.save(None).await.unwrap();
 
@@ -277,6 +601,18 @@ pub mod tests {
 </html>"#;
 
         assert_eq!(html, expected);
+
+        let bytes = write_target
+            .read(&PathBuf::from(format!(
+                "permalink/{}/index.html",
+                animals_cid
+            )))
+            .await
+            .unwrap();
+
+        let html = std::str::from_utf8(&bytes).unwrap();
+
+        assert!(html.contains(r#"<aside class="backlinks"><h2 class="backlinks-title">Linked from</h2><ul class="backlinks-list"><li class="backlink"><a href="/cats" class="slashlink">/cats</a></li></ul></aside>"#));
     }
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
@@ -352,4 +688,348 @@ pub mod tests {
 
         assert_eq!(cats_revised_html, cats_slug_html);
     }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_regenerates_output_according_to_policy_and_manifest() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let sphere_identity = Did(fs.identity().to_string());
+
+        let cats_cid = fs
+            .write(
+                "cats",
+                &ContentType::Subtext.to_string(),
+                b"Cats are great".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+        let dogs_cid = fs
+            .write(
+                "dogs",
+                &ContentType::Subtext.to_string(),
+                b"Dogs are great".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        fs.save(None).await.unwrap();
+
+        let write_target = MemoryWriteTarget::default();
+
+        sphere_into_html(&sphere_identity, &db, &write_target)
+            .await
+            .unwrap();
+
+        let cats_path = PathBuf::from(format!("permalink/{}/index.html", cats_cid));
+        let dogs_path = PathBuf::from(format!("permalink/{}/index.html", dogs_cid));
+        let cats_html = write_target.read(&cats_path).await.unwrap();
+        let dogs_html = write_target.read(&dogs_path).await.unwrap();
+
+        let manifest = HtmlManifest::load(&write_target).await.unwrap();
+
+        assert!(manifest.is_current(&cats_path));
+        assert!(manifest.is_current(&dogs_path));
+
+        // Output that is current is left alone by default...
+        overwrite_with_stale_output(&write_target, &[&cats_path, &dogs_path]).await;
+
+        sphere_into_html(&sphere_identity, &db, &write_target)
+            .await
+            .unwrap();
+
+        assert_eq!(write_target.read(&cats_path).await.unwrap(), b"Stale");
+
+        // ...but may be regenerated for specific slugs...
+        sphere_into_html_with_policy(
+            &sphere_identity,
+            &db,
+            &write_target,
+            &RegenerationPolicy::Slugs(BTreeSet::from(["cats".to_string()])),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(write_target.read(&cats_path).await.unwrap(), cats_html);
+        assert_eq!(write_target.read(&dogs_path).await.unwrap(), b"Stale");
+
+        // ...or for the latest revisions...
+        overwrite_with_stale_output(&write_target, &[&cats_path, &dogs_path]).await;
+
+        sphere_into_html_with_policy(
+            &sphere_identity,
+            &db,
+            &write_target,
+            &RegenerationPolicy::LatestRevisions(1),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(write_target.read(&cats_path).await.unwrap(), cats_html);
+        assert_eq!(write_target.read(&dogs_path).await.unwrap(), dogs_html);
+
+        // ...and output produced by another version of the renderer is always
+        // considered to be stale
+        overwrite_with_stale_output(&write_target, &[&cats_path, &dogs_path]).await;
+
+        let mut manifest = HtmlManifest::load(&write_target).await.unwrap();
+
+        manifest
+            .files
+            .insert(cats_path.clone(), HTML_RENDERER_VERSION - 1);
+        manifest.save(&write_target).await.unwrap();
+
+        sphere_into_html(&sphere_identity, &db, &write_target)
+            .await
+            .unwrap();
+
+        assert_eq!(write_target.read(&cats_path).await.unwrap(), cats_html);
+        assert_eq!(write_target.read(&dogs_path).await.unwrap(), b"Stale");
+        assert!(HtmlManifest::load(&write_target)
+            .await
+            .unwrap()
+            .is_current(&cats_path));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_writes_media_bodies_alongside_their_pages_and_embeds_them() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let sphere_identity = Did(fs.identity().to_string());
+
+        let notes_cid = fs
+            .write(
+                "notes",
+                &ContentType::Subtext.to_string(),
+                b"Here is a diagram:\n\n/diagram\n\n/song".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+        let diagram_cid = fs
+            .write(
+                "diagram",
+                "image/png",
+                b"Not really a PNG".as_ref(),
+                Some(vec![(Header::Title.to_string(), "A diagram".into())]),
+            )
+            .await
+            .unwrap();
+        let song_cid = fs
+            .write("song", "audio/mpeg", b"Not really an MP3".as_ref(), None)
+            .await
+            .unwrap();
+
+        fs.save(None).await.unwrap();
+
+        let write_target = MemoryWriteTarget::default();
+
+        sphere_into_html(&sphere_identity, &db, &write_target)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            write_target
+                .read(&PathBuf::from(format!(
+                    "permalink/{}/content.png",
+                    diagram_cid
+                )))
+                .await
+                .unwrap(),
+            b"Not really a PNG".to_vec()
+        );
+        assert_eq!(
+            write_target
+                .read(&PathBuf::from(format!(
+                    "permalink/{}/content.mp3",
+                    song_cid
+                )))
+                .await
+                .unwrap(),
+            b"Not really an MP3".to_vec()
+        );
+
+        let notes_html = String::from_utf8(
+            write_target
+                .read(&PathBuf::from(format!(
+                    "permalink/{}/index.html",
+                    notes_cid
+                )))
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert!(notes_html.contains(&format!(
+            r#"<figure class="transclude-format-bitmap"><img src="/permalink/{}/content.png" alt="A diagram" loading="lazy">"#,
+            diagram_cid
+        )));
+        assert!(notes_html.contains(&format!(
+            r#"<figure class="transclude-format-audio"><audio src="/permalink/{}/content.mp3" controls="controls" preload="metadata">"#,
+            song_cid
+        )));
+
+        let diagram_html = String::from_utf8(
+            write_target
+                .read(&PathBuf::from(format!(
+                    "permalink/{}/index.html",
+                    diagram_cid
+                )))
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert!(diagram_html.contains(&format!(
+            r#"<article class="media"><img src="/permalink/{}/content.png" alt="A diagram" loading="lazy"></article>"#,
+            diagram_cid
+        )));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_renders_linked_content_of_followed_spheres_under_their_petnames() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let mut alice_fs = make_sphere_fs(&mut db).await.unwrap();
+
+        let notes_cid = alice_fs
+            .write(
+                "notes",
+                &ContentType::Subtext.to_string(),
+                b"See also /more and @bob/notes".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+        alice_fs
+            .write(
+                "more",
+                &ContentType::Subtext.to_string(),
+                b"More notes".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+        alice_fs.save(None).await.unwrap();
+
+        let record = make_name_record(&alice_fs, &db).await.unwrap();
+
+        let cats_cid = fs
+            .write(
+                "cats",
+                &ContentType::Subtext.to_string(),
+                b"Alice writes about @alice/notes\n\nBob writes about @bob/notes".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+        fs.set_petname("alice", &Did(alice_fs.identity().to_string()))
+            .await
+            .unwrap();
+        fs.set_petname_record("alice", &record).await.unwrap();
+        fs.save(None).await.unwrap();
+
+        let write_target = MemoryWriteTarget::default();
+
+        sphere_into_html(&Did(fs.identity().to_string()), &db, &write_target)
+            .await
+            .unwrap();
+
+        let bytes = write_target
+            .read(&PathBuf::from(format!("permalink/{}/index.html", cats_cid)))
+            .await
+            .unwrap();
+        let html = std::str::from_utf8(&bytes).unwrap();
+
+        assert!(html.contains(r#"href="/@alice/notes" class="slashlink""#));
+        assert!(html.contains(r#"<span class="slashlink">"#));
+        assert!(!html.contains(r#"href="/@bob/notes""#));
+
+        assert_eq!(
+            write_target
+                .resolve_symlink(&PathBuf::from("@alice/notes"))
+                .await,
+            Some(PathBuf::from(format!("permalink/{}", notes_cid)))
+        );
+        assert!(write_target
+            .resolve_symlink(&PathBuf::from("@alice/more"))
+            .await
+            .is_some());
+
+        let bytes = write_target
+            .read(&PathBuf::from(format!(
+                "permalink/{}/index.html",
+                notes_cid
+            )))
+            .await
+            .unwrap();
+        let html = std::str::from_utf8(&bytes).unwrap();
+
+        assert!(html.contains(r#"href="/@alice/more" class="slashlink""#));
+        assert!(html.contains(r#"<span class="slashlink">"#));
+        assert!(!html.contains(r#"href="/@bob/notes""#));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_renders_unchanged_content_again_when_its_backlinks_change() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+
+        let animals_cid = fs
+            .write(
+                "animals",
+                &ContentType::Subtext.to_string(),
+                b"All kinds of animals".as_ref(),
+                None,
+            )
+            .await
+            .unwrap();
+        fs.save(None).await.unwrap();
+
+        let sphere_identity = Did(fs.identity().to_string());
+        let write_target = MemoryWriteTarget::default();
+        let animals_page = PathBuf::from(format!("permalink/{}/index.html", animals_cid));
+
+        sphere_into_html(&sphere_identity, &db, &write_target)
+            .await
+            .unwrap();
+
+        let bytes = write_target.read(&animals_page).await.unwrap();
+        let html = std::str::from_utf8(&bytes).unwrap();
+
+        assert!(!html.contains("Linked from"));
+
+        fs.write(
+            "cats",
+            &ContentType::Subtext.to_string(),
+            b"Cats are /animals".as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.save(None).await.unwrap();
+
+        sphere_into_html(&sphere_identity, &db, &write_target)
+            .await
+            .unwrap();
+
+        let bytes = write_target.read(&animals_page).await.unwrap();
+        let html = std::str::from_utf8(&bytes).unwrap();
+
+        assert!(html.contains("Linked from"));
+        assert!(html.contains(r#"<a href="/cats" class="slashlink">/cats</a>"#));
+    }
 }
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use noosphere_core::data::Did;
+use noosphere_storage::{SphereDb, Storage};
+
+use crate::{sphere_into_documents, DocumentFormat, WriteTarget};
+
+/// Given a sphere [Did], [SphereDb] and a [WriteTarget], write the latest
+/// revision of the sphere's content as a set of Markdown documents (suitable
+/// for a static site generator such as mkdocs). Slashlinks become relative
+/// links between the documents, and transcludes become blockquotes; see
+/// [sphere_into_documents] for details.
+pub async fn sphere_into_markdown<S, W>(
+    sphere_identity: &Did,
+    db: &SphereDb<S>,
+    write_target: &W,
+) -> Result<()>
+where
+    S: Storage + 'static,
+    W: WriteTarget + 'static,
+{
+    sphere_into_documents(sphere_identity, db, write_target, DocumentFormat::Markdown).await
+}
+
+#[cfg(test)]
+mod tests {
+    use noosphere_storage::{MemoryStorage, SphereDb};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::{
+        into::documents::tests::{make_document_fixture, read_document},
+        write::MemoryWriteTarget,
+    };
+
+    use super::sphere_into_markdown;
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_writes_the_sphere_as_linked_markdown_documents() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let sphere_identity = make_document_fixture(&mut db).await;
+
+        let write_target = MemoryWriteTarget::default();
+
+        sphere_into_markdown(&sphere_identity, &db, &write_target)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            read_document(&write_target, "cats.md").await.unwrap(),
+            r#"# Cats
+
+Cats are [great](great.md)
+
+> **Animals**
+>
+> Animals are multicellular.
+>
+> [/animals](animals.md)
+
+- One
+- Two
+"#
+        );
+
+        assert_eq!(
+            read_document(&write_target, "notes/today.md")
+                .await
+                .unwrap(),
+            r#"Today I saw [/animals](../animals.md)
+
+> **Animals**
+>
+> Animals are multicellular.
+>
+> [/animals](../animals.md)
+"#
+        );
+
+        let index = read_document(&write_target, "index.md").await.unwrap();
+
+        assert!(index.ends_with(
+            r#"
+- [/animals](animals.md)
+- [/cats](cats.md)
+- [/gallery](gallery.md)
+- [/notes/today](notes/today.md)
+"#
+        ));
+
+        assert!(read_document(&write_target, "photo.md").await.is_none());
+
+        // The photo is not rendered, but its body is written alongside the
+        // documents so that it can be linked to
+        assert_eq!(
+            read_document(&write_target, "gallery.md").await.unwrap(),
+            "> [/photo](photo.png)\n"
+        );
+        assert_eq!(
+            read_document(&write_target, "photo.png").await.unwrap(),
+            "Not really a photo"
+        );
+    }
+}
@@ -1,3 +1,11 @@
+mod documents;
+mod feed;
+mod gemtext;
 mod html;
+mod markdown;
 
+pub use documents::*;
+pub use feed::*;
+pub use gemtext::*;
 pub use html::*;
+pub use markdown::*;
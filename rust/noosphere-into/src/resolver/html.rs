@@ -1,3 +1,5 @@
+use std::{collections::BTreeSet, sync::Arc};
+
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use subtext::{Peer, Slashlink};
@@ -6,22 +8,146 @@ use crate::{ResolvedLink, Resolver};
 
 /// A [Resolver] that is suitable for resolving a [Slashlink] to an `href` for
 /// a basic static website generator.
-#[derive(Clone)]
-pub struct StaticHtmlResolver();
+///
+/// Content of the sphere being rendered is found at `/<slug>`, and content of
+/// a followed sphere is found at `/@<petname>/<slug>`. Slashlinks to spheres
+/// whose content is not rendered cannot be resolved (and are rendered as
+/// plain text instead).
+#[derive(Clone, Default)]
+pub struct StaticHtmlResolver {
+    /// The petname of the followed sphere whose content is being rendered, if
+    /// it is not the content of the sphere being rendered
+    peer: Option<String>,
+    /// The petnames of the followed spheres whose content is rendered
+    followed: Arc<BTreeSet<String>>,
+}
+
+impl StaticHtmlResolver {
+    /// A [StaticHtmlResolver] for the content of the sphere being rendered,
+    /// given the petnames of the followed spheres whose content is rendered
+    /// alongside it
+    pub fn new(followed: Arc<BTreeSet<String>>) -> Self {
+        StaticHtmlResolver {
+            peer: None,
+            followed,
+        }
+    }
+
+    /// A [StaticHtmlResolver] for the content of the followed sphere with the
+    /// given petname; its slashlinks to other spheres cannot be resolved
+    pub fn for_peer(petname: &str) -> Self {
+        StaticHtmlResolver {
+            peer: Some(petname.trim_start_matches('@').to_owned()),
+            followed: Default::default(),
+        }
+    }
+}
 
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 impl Resolver for StaticHtmlResolver {
     async fn resolve(&self, link: &Slashlink) -> Result<ResolvedLink> {
-        match link {
-            Slashlink {
-                slug: Some(slug),
-                peer: Peer::None,
-            } => Ok(ResolvedLink::Slashlink {
-                link: link.clone(),
-                href: format!("/{}", slug),
-            }),
-            _ => Err(anyhow!("Only local slashlinks with slugs are supported")),
-        }
+        let href = match (link, &self.peer) {
+            (
+                Slashlink {
+                    slug: Some(slug),
+                    peer: Peer::None,
+                },
+                None,
+            ) => format!("/{}", slug),
+            (
+                Slashlink {
+                    slug: Some(slug),
+                    peer: Peer::None,
+                },
+                Some(peer),
+            ) => format!("/@{}/{}", peer, slug),
+            (
+                Slashlink {
+                    slug: Some(slug),
+                    peer: Peer::Name(petname),
+                },
+                None,
+            ) if self.followed.contains(petname.trim_start_matches('@')) => {
+                format!("/@{}/{}", petname.trim_start_matches('@'), slug)
+            }
+            (
+                Slashlink {
+                    peer: Peer::Name(_),
+                    ..
+                },
+                _,
+            ) => {
+                return Err(anyhow!(
+                    "The content of the sphere that {} links to is not rendered",
+                    link
+                ))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Only slashlinks with slugs (and, optionally, a peer's petname) are supported"
+                ))
+            }
+        };
+
+        Ok(ResolvedLink::Slashlink {
+            link: link.clone(),
+            href,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Arc};
+
+    use subtext::{Peer, Slashlink};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::Resolver;
+
+    use super::StaticHtmlResolver;
+
+    async fn href(resolver: &StaticHtmlResolver, peer: Peer, slug: &str) -> Option<String> {
+        let link = Slashlink {
+            peer,
+            slug: Some(slug.into()),
+        };
+
+        resolver
+            .resolve(&link)
+            .await
+            .ok()
+            .map(|link| link.to_string())
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_only_resolves_peer_slashlinks_to_rendered_spheres() {
+        let alice = Peer::Name("alice".into());
+        let bob = Peer::Name("bob".into());
+
+        let resolver = StaticHtmlResolver::new(Arc::new(BTreeSet::from(["alice".to_string()])));
+
+        assert_eq!(
+            href(&resolver, Peer::None, "cats").await,
+            Some("/cats".into())
+        );
+        assert_eq!(
+            href(&resolver, alice.clone(), "cats").await,
+            Some("/@alice/cats".into())
+        );
+        assert_eq!(href(&resolver, bob.clone(), "cats").await, None);
+
+        let resolver = StaticHtmlResolver::for_peer("alice");
+
+        assert_eq!(
+            href(&resolver, Peer::None, "dogs").await,
+            Some("/@alice/dogs".into())
+        );
+        assert_eq!(href(&resolver, alice, "dogs").await, None);
+        assert_eq!(href(&resolver, bob, "dogs").await, None);
     }
 }
@@ -1,7 +1,9 @@
 mod html;
 mod link;
+mod relative;
 mod resolver;
 
 pub use html::*;
 pub use link::*;
+pub use relative::*;
 pub use resolver::*;
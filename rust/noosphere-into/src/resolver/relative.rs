@@ -0,0 +1,171 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use subtext::{Peer, Slashlink};
+
+use crate::{ResolvedLink, Resolver};
+
+/// Get the path of the document that the content at a slug is written to when
+/// a sphere is rendered as a set of linked documents with the given file
+/// extension (e.g., `notes/today` becomes `notes/today.md`). Slugs that would
+/// produce a path outside of the document set yield `None`.
+pub fn document_path(slug: &str, extension: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(format!("{}.{}", slug, extension));
+
+    match path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        true => Some(path),
+        false => None,
+    }
+}
+
+/// Get the path that the body of some content at a slug (see
+/// [crate::body_file_name]) is written to alongside a set of linked documents:
+/// the document path of the slug, but with the body's file extension (e.g.,
+/// the body of an image at `photos/cat` becomes `photos/cat.png`)
+pub fn document_body_path(slug: &str, file_name: &str) -> Option<PathBuf> {
+    let extension = Path::new(file_name).extension()?.to_str()?;
+
+    document_path(slug, extension)
+}
+
+/// Given the relative `href` of the document of some content (as resolved by
+/// a [RelativeLinkResolver]), get the relative `href` of its body (as written
+/// to the [document_body_path] of the content)
+pub fn document_body_href(document_href: &str, file_name: &str) -> String {
+    match Path::new(file_name).extension() {
+        Some(extension) => Path::new(document_href)
+            .with_extension(extension)
+            .to_string_lossy()
+            .into_owned(),
+        None => document_href.to_owned(),
+    }
+}
+
+/// A [Resolver] that resolves a [Slashlink] to the relative path of the
+/// document that its content is written to, as seen from some other document
+/// in a set of linked documents (such as Markdown files or a Gemini capsule).
+/// Only the content of the sphere being written is in the document set, so
+/// slashlinks to other spheres cannot be resolved (and are written as plain
+/// text instead).
+#[derive(Clone)]
+pub struct RelativeLinkResolver {
+    /// The path of the document that links are resolved from
+    pub document: PathBuf,
+    /// The file extension of the documents in the document set
+    pub extension: String,
+}
+
+impl RelativeLinkResolver {
+    pub fn new(document: &Path, extension: &str) -> Self {
+        RelativeLinkResolver {
+            document: document.to_path_buf(),
+            extension: extension.to_owned(),
+        }
+    }
+
+    /// Get the path to a document relative to the directory that contains the
+    /// document that links are resolved from
+    fn relative_href(&self, target: &Path) -> String {
+        let depth = match self.document.parent() {
+            Some(parent) => parent.components().count(),
+            None => 0,
+        };
+
+        let mut href = "../".repeat(depth);
+
+        href.push_str(&target.to_string_lossy());
+        href
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Resolver for RelativeLinkResolver {
+    async fn resolve(&self, link: &Slashlink) -> Result<ResolvedLink> {
+        let target = match link {
+            Slashlink {
+                slug: Some(slug),
+                peer: Peer::None,
+            } => slug.to_owned(),
+            Slashlink {
+                peer: Peer::Name(_),
+                ..
+            } => {
+                return Err(anyhow!(
+                    "The content of the sphere that {} links to is not written",
+                    link
+                ))
+            }
+            _ => return Err(anyhow!("Only slashlinks with slugs are supported")),
+        };
+
+        let target = document_path(&target, &self.extension)
+            .ok_or_else(|| anyhow!("Cannot link to {} from a document", link))?;
+
+        Ok(ResolvedLink::Slashlink {
+            link: link.clone(),
+            href: self.relative_href(&target),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use subtext::{Peer, Slashlink};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::{document_body_href, document_body_path, document_path, Resolver};
+
+    use super::RelativeLinkResolver;
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_resolves_slashlinks_relative_to_the_linking_document() {
+        let cats = Slashlink {
+            peer: Peer::None,
+            slug: Some("cats".into()),
+        };
+        let alice_notes = Slashlink {
+            peer: Peer::Name("alice".into()),
+            slug: Some("notes/today".into()),
+        };
+
+        let resolver = RelativeLinkResolver::new(&PathBuf::from("index.md"), "md");
+
+        assert_eq!(
+            resolver.resolve(&cats).await.unwrap().to_string(),
+            "cats.md"
+        );
+        assert!(resolver.resolve(&alice_notes).await.is_err());
+
+        let resolver = RelativeLinkResolver::new(&PathBuf::from("journal/2023/01.gmi"), "gmi");
+
+        assert_eq!(
+            resolver.resolve(&cats).await.unwrap().to_string(),
+            "../../cats.gmi"
+        );
+
+        assert_eq!(document_path("../secrets", "md"), None);
+        assert_eq!(
+            document_path("notes/today", "md"),
+            Some(PathBuf::from("notes/today.md"))
+        );
+
+        assert_eq!(
+            document_body_path("photos/v1.2", "content.png"),
+            Some(PathBuf::from("photos/v1.2.png"))
+        );
+        assert_eq!(
+            document_body_href("../photos/v1.2.gmi", "content.png"),
+            "../photos/v1.2.png"
+        );
+    }
+}
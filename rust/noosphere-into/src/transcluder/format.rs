@@ -0,0 +1,168 @@
+use noosphere_core::data::{ContentType, Header, MemoIpld};
+
+/// The format of a [crate::Transclude], as chosen by the `Content-Type` of the
+/// content being transcluded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranscludeFormat {
+    /// Subtext (or any other content that has no more specific format)
+    Text,
+    /// An image, such as a PNG, JPEG or SVG
+    Bitmap,
+    /// An audio recording
+    Audio,
+    /// A video
+    Video,
+    /// A sphere that has been linked as content
+    Sphere,
+}
+
+impl TranscludeFormat {
+    /// Choose the format that is appropriate for content with the given
+    /// [ContentType]
+    pub fn from_content_type(content_type: Option<&ContentType>) -> Self {
+        let mime_type = match content_type {
+            Some(ContentType::Sphere) => return TranscludeFormat::Sphere,
+            Some(ContentType::Unknown(mime_type)) => mime_type.to_lowercase(),
+            _ => return TranscludeFormat::Text,
+        };
+
+        if mime_type.starts_with("image/") {
+            TranscludeFormat::Bitmap
+        } else if mime_type.starts_with("audio/") {
+            TranscludeFormat::Audio
+        } else if mime_type.starts_with("video/") {
+            TranscludeFormat::Video
+        } else {
+            TranscludeFormat::Text
+        }
+    }
+
+    /// Choose the format that is appropriate for the content of a memo
+    pub fn from_memo(memo: &MemoIpld) -> Self {
+        TranscludeFormat::from_content_type(memo.content_type().as_ref())
+    }
+
+    /// Returns true if content in this format is presented by embedding its
+    /// body (so the body must be written out alongside any rendered pages)
+    pub fn has_body(&self) -> bool {
+        matches!(
+            self,
+            TranscludeFormat::Bitmap | TranscludeFormat::Audio | TranscludeFormat::Video
+        )
+    }
+}
+
+/// The file name that the body of some content is written to when it is
+/// rendered (e.g., `content.png`). The extension comes from the memo's
+/// `File-Extension` header if it has one, otherwise it is derived from the
+/// memo's `Content-Type`.
+pub fn body_file_name(memo: &MemoIpld) -> String {
+    let extension = memo
+        .get_first_header(&Header::FileExtension.to_string())
+        .filter(|extension| {
+            !extension.is_empty()
+                && extension
+                    .chars()
+                    .all(|character| character.is_ascii_alphanumeric())
+        })
+        .or_else(|| {
+            memo.get_first_header(&Header::ContentType.to_string())
+                .and_then(|content_type| extension_for_mime_type(&content_type))
+                .map(String::from)
+        })
+        .unwrap_or_else(|| "bin".into());
+
+    format!("content.{}", extension.to_lowercase())
+}
+
+fn extension_for_mime_type(mime_type: &str) -> Option<&'static str> {
+    let mime_type = mime_type.split(';').next()?.trim().to_lowercase();
+
+    Some(match mime_type.as_str() {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        "image/svg+xml" => "svg",
+        "audio/mpeg" => "mp3",
+        "audio/ogg" => "ogg",
+        "audio/wav" | "audio/x-wav" => "wav",
+        "audio/flac" => "flac",
+        "audio/webm" => "weba",
+        "video/mp4" => "mp4",
+        "video/webm" => "webm",
+        "video/ogg" => "ogv",
+        "video/quicktime" => "mov",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cid::Cid;
+    use noosphere_core::data::{ContentType, Header, MemoIpld};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::{body_file_name, TranscludeFormat};
+
+    fn memo_with_headers(headers: Vec<(&str, &str)>) -> MemoIpld {
+        MemoIpld {
+            parent: None,
+            headers: headers
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: Cid::try_from("bafkqaaa").unwrap(),
+        }
+    }
+
+    fn format_of(content_type: &str) -> TranscludeFormat {
+        TranscludeFormat::from_content_type(Some(&ContentType::from_str(content_type).unwrap()))
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn it_chooses_a_transclude_format_by_content_type() {
+        assert_eq!(format_of("text/subtext"), TranscludeFormat::Text);
+        assert_eq!(format_of("image/svg+xml"), TranscludeFormat::Bitmap);
+        assert_eq!(format_of("audio/mpeg"), TranscludeFormat::Audio);
+        assert_eq!(format_of("video/mp4"), TranscludeFormat::Video);
+        assert_eq!(format_of("noo/sphere"), TranscludeFormat::Sphere);
+        assert_eq!(format_of("application/pdf"), TranscludeFormat::Text);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn it_names_body_files_by_extension_or_content_type() {
+        let content_type = Header::ContentType.to_string();
+        let file_extension = Header::FileExtension.to_string();
+
+        assert_eq!(
+            body_file_name(&memo_with_headers(vec![
+                (&content_type, "image/png"),
+                (&file_extension, "PNG")
+            ])),
+            "content.png"
+        );
+        assert_eq!(
+            body_file_name(&memo_with_headers(vec![(&content_type, "image/jpeg")])),
+            "content.jpg"
+        );
+        assert_eq!(
+            body_file_name(&memo_with_headers(vec![
+                (&content_type, "video/webm"),
+                (&file_extension, "../../evil")
+            ])),
+            "content.webm"
+        );
+        assert_eq!(
+            body_file_name(&memo_with_headers(vec![(&content_type, "image/x-unknown")])),
+            "content.bin"
+        );
+    }
+}
@@ -1,10 +1,13 @@
-use crate::{ResolvedLink, TextTransclude, Transclude, Transcluder};
-use anyhow::Result;
+use crate::{
+    body_file_name, MediaTransclude, ResolvedLink, SphereTransclude, TextTransclude, Transclude,
+    TranscludeFormat, Transcluder,
+};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use noosphere_core::data::Header;
+use noosphere_core::data::{ContentType, Header};
 use noosphere_fs::SphereFs;
 use noosphere_storage::Storage;
-use subtext::{block::Block, primitive::Entity, Peer};
+use subtext::{block::Block, primitive::Entity, Peer, Slashlink};
 use tokio_stream::StreamExt;
 use ucan::crypto::KeyMaterial;
 
@@ -29,6 +32,37 @@ where
     }
 }
 
+impl<S, K> SphereFsTranscluder<S, K>
+where
+    S: Storage,
+    K: KeyMaterial + Clone + 'static,
+{
+    /// The path of the content that a [Slashlink] refers to, suitable for
+    /// [SphereFs::resolve_path]; slashlinks with a peer are prefixed with the
+    /// peer's petname (e.g., `@alice/notes`)
+    fn path_for(link: &Slashlink) -> Option<String> {
+        let slug = link.slug.as_ref()?;
+
+        match &link.peer {
+            Peer::None => Some(slug.to_owned()),
+            Peer::Name(petname) => Some(format!("@{}/{}", petname.trim_start_matches('@'), slug)),
+            _ => None,
+        }
+    }
+
+    /// The identity of the sphere that is linked as content at the given slug
+    async fn linked_sphere_identity(fs: &SphereFs<S, K>, slug: Option<&str>) -> Result<String> {
+        let slug = slug.ok_or_else(|| anyhow!("No slug to read a sphere from"))?;
+        let sphere = fs
+            .read_content(slug)
+            .await?
+            .ok_or_else(|| anyhow!("No sphere found at {}", slug))?
+            .into_sphere()?;
+
+        Ok(sphere.try_get_identity().await?.to_string())
+    }
+}
+
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 impl<S, K> Transcluder for SphereFsTranscluder<S, K>
@@ -43,50 +77,112 @@ where
                 Ok(None)
             }
             ResolvedLink::Slashlink { link, href } => {
-                match link.peer {
-                    Peer::None => {
-                        // TODO(#49): Perhaps this should be sensitive to external content
-                        // e.g., from other spheres
-                        ()
-                    }
-                    _ => return Ok(None),
+                let path = match Self::path_for(link) {
+                    Some(path) => path,
+                    None => return Ok(None),
                 };
 
-                let slug = match &link.slug {
-                    Some(slug) => slug.to_owned(),
-                    None => return Ok(None),
+                // Content in other spheres is read from the revision that was
+                // last resolved for the peer's petname; if the peer has never
+                // been resolved, the link is treated as a "dead" link
+                let (fs, slug) = match self.fs.resolve_path(&path).await? {
+                    Some((fs, slug)) => (fs, Some(slug)),
+                    None => (self.fs.clone(), None),
                 };
-                // TODO(#50): Support content types other than Subtext
 
-                Ok(match self.fs.read(&slug).await? {
+                let link_text = format!("/{}", path);
+
+                let file = match &slug {
+                    Some(slug) => fs.read(slug).await?,
+                    None => None,
+                };
+
+                Ok(match file {
                     Some(file) => {
                         // TODO(#52): Maybe fall back to first heading if present and use
                         // that as a stand-in for title...
                         let title = file.memo.get_first_header(&Header::Title.to_string());
+                        let href = href.to_owned();
 
-                        let subtext_ast_stream =
-                            subtext::stream::<Block<Entity>, _, _>(file.contents).await;
+                        match TranscludeFormat::from_memo(&file.memo) {
+                            TranscludeFormat::Text => {
+                                let mut excerpt = None;
 
-                        tokio::pin!(subtext_ast_stream);
+                                // Other kinds of content are transcluded with
+                                // just a title, as there is no sensible way to
+                                // excerpt them (yet)
+                                if file.memo.content_type() == Some(ContentType::Subtext) {
+                                    let subtext_ast_stream =
+                                        subtext::stream::<Block<Entity>, _, _>(file.contents).await;
 
-                        let mut excerpt = None;
+                                    tokio::pin!(subtext_ast_stream);
 
-                        while let Some(Ok(block)) = subtext_ast_stream.next().await {
-                            match block {
-                                Block::Blank(_) => continue,
-                                any_other => {
-                                    excerpt = Some(any_other.to_text_content());
-                                    break;
+                                    while let Some(Ok(block)) = subtext_ast_stream.next().await {
+                                        match block {
+                                            Block::Blank(_) => continue,
+                                            any_other => {
+                                                excerpt = Some(any_other.to_text_content());
+                                                break;
+                                            }
+                                        }
+                                    }
                                 }
+
+                                Some(Transclude::Text(TextTransclude {
+                                    title,
+                                    excerpt,
+                                    link_text,
+                                    href,
+                                }))
                             }
-                        }
+                            format @ (TranscludeFormat::Bitmap
+                            | TranscludeFormat::Audio
+                            | TranscludeFormat::Video) => {
+                                let media_transclude = MediaTransclude {
+                                    title,
+                                    content_type: file
+                                        .memo
+                                        .get_first_header(&Header::ContentType.to_string())
+                                        .unwrap_or_default(),
+                                    content: file.memo_version,
+                                    file_name: body_file_name(&file.memo),
+                                    link_text,
+                                    href,
+                                };
 
-                        Some(Transclude::Text(TextTransclude {
-                            title,
-                            excerpt,
-                            link_text: format!("/{}", slug),
-                            href: href.to_owned(),
-                        }))
+                                Some(match format {
+                                    TranscludeFormat::Bitmap => {
+                                        Transclude::Bitmap(media_transclude)
+                                    }
+                                    TranscludeFormat::Audio => Transclude::Audio(media_transclude),
+                                    _ => Transclude::Video(media_transclude),
+                                })
+                            }
+                            TranscludeFormat::Sphere => {
+                                match Self::linked_sphere_identity(&fs, slug.as_deref()).await {
+                                    Ok(identity) => Some(Transclude::Sphere(SphereTransclude {
+                                        title,
+                                        identity,
+                                        link_text,
+                                        href,
+                                    })),
+                                    Err(error) => {
+                                        // The blocks of a linked sphere are not
+                                        // necessarily replicated along with the
+                                        // sphere that links to it, in which case
+                                        // it is transcluded like any other text
+                                        debug!("Could not read the sphere at {}: {}", path, error);
+
+                                        Some(Transclude::Text(TextTransclude {
+                                            title,
+                                            excerpt: None,
+                                            link_text,
+                                            href,
+                                        }))
+                                    }
+                                }
+                            }
+                        }
                     }
                     None => {
                         // TODO(#53): Figure out how to treat "dead" links for HTML generation
@@ -95,7 +191,7 @@ where
                         Some(Transclude::Text(TextTransclude {
                             title: None,
                             excerpt: None,
-                            link_text: format!("/{}", slug),
+                            link_text,
                             href: href.to_owned(),
                         }))
                     }
@@ -104,3 +200,212 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Arc};
+
+    use noosphere_core::{
+        data::{ContentType, Did, Header},
+        view::Sphere,
+    };
+    use noosphere_fs::helpers::{make_name_record, make_sphere_fs};
+    use noosphere_storage::{MemoryStorage, SphereDb};
+    use subtext::{Peer, Slashlink};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    use crate::{Resolver, StaticHtmlResolver, Transclude, Transcluder};
+
+    use super::SphereFsTranscluder;
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_transcludes_content_from_a_followed_sphere() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let mut alice_fs = make_sphere_fs(&mut db).await.unwrap();
+
+        alice_fs
+            .write(
+                "notes",
+                &ContentType::Subtext.to_string(),
+                b"Notes from Alice".as_ref(),
+                Some(vec![(Header::Title.to_string(), "Alice's notes".into())]),
+            )
+            .await
+            .unwrap();
+        alice_fs.save(None).await.unwrap();
+
+        let record = make_name_record(&alice_fs, &db).await.unwrap();
+
+        fs.set_petname("alice", &Did(alice_fs.identity().to_string()))
+            .await
+            .unwrap();
+        fs.set_petname_record("alice", &record).await.unwrap();
+        fs.save(None).await.unwrap();
+
+        let slashlink = Slashlink {
+            peer: Peer::Name("alice".into()),
+            slug: Some("notes".into()),
+        };
+
+        let resolver = StaticHtmlResolver::new(Arc::new(BTreeSet::from(["alice".to_string()])));
+        let link = resolver.resolve(&slashlink).await.unwrap();
+        let transclude = SphereFsTranscluder::new(fs)
+            .transclude(&link)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let transclude = match transclude {
+            Transclude::Text(transclude) => transclude,
+            other => panic!("Expected a text transclude, got {:?}", other),
+        };
+
+        assert_eq!(transclude.href, "/@alice/notes");
+        assert_eq!(transclude.link_text, "/@alice/notes");
+        assert_eq!(transclude.title, Some("Alice's notes".into()));
+        assert_eq!(transclude.excerpt, Some("Notes from Alice".into()));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_chooses_a_transclude_by_content_type() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+        let friend_fs = make_sphere_fs(&mut db).await.unwrap();
+
+        let diagram_cid = fs
+            .write(
+                "diagram",
+                "image/svg+xml",
+                b"<svg></svg>".as_ref(),
+                Some(vec![(Header::Title.to_string(), "A diagram".into())]),
+            )
+            .await
+            .unwrap();
+        fs.write("clip", "video/mp4", b"Not really a video".as_ref(), None)
+            .await
+            .unwrap();
+
+        let friend_body = Sphere::at(friend_fs.revision(), &db)
+            .try_as_memo()
+            .await
+            .unwrap()
+            .body;
+        fs.link(
+            "friend",
+            &ContentType::Sphere.to_string(),
+            &friend_body,
+            Some(vec![(Header::Title.to_string(), "My friend".into())]),
+        )
+        .await
+        .unwrap();
+        fs.save(None).await.unwrap();
+
+        let transcluder = SphereFsTranscluder::new(fs);
+
+        let transclude_slug = |slug: &str| {
+            let transcluder = transcluder.clone();
+            let slashlink = Slashlink {
+                peer: Peer::None,
+                slug: Some(slug.into()),
+            };
+
+            async move {
+                let link = StaticHtmlResolver::default()
+                    .resolve(&slashlink)
+                    .await
+                    .unwrap();
+                transcluder.transclude(&link).await.unwrap().unwrap()
+            }
+        };
+
+        match transclude_slug("diagram").await {
+            Transclude::Bitmap(transclude) => {
+                assert_eq!(transclude.title, Some("A diagram".into()));
+                assert_eq!(transclude.content_type, "image/svg+xml");
+                assert_eq!(transclude.content, diagram_cid);
+                assert_eq!(transclude.file_name, "content.svg");
+                assert_eq!(transclude.href, "/diagram");
+            }
+            other => panic!("Expected a bitmap transclude, got {:?}", other),
+        };
+
+        match transclude_slug("clip").await {
+            Transclude::Video(transclude) => {
+                assert_eq!(transclude.file_name, "content.mp4");
+                assert_eq!(transclude.link_text, "/clip");
+            }
+            other => panic!("Expected a video transclude, got {:?}", other),
+        };
+
+        match transclude_slug("friend").await {
+            Transclude::Sphere(transclude) => {
+                assert_eq!(transclude.title, Some("My friend".into()));
+                assert_eq!(transclude.identity, friend_fs.identity().to_string());
+                assert_eq!(transclude.href, "/friend");
+            }
+            other => panic!("Expected a sphere transclude, got {:?}", other),
+        };
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_transcludes_a_linked_sphere_as_text_when_its_blocks_are_missing() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+
+        // The linked sphere is only known to some other replica
+        let mut stranger_db = SphereDb::new(&MemoryStorage::default()).await.unwrap();
+        let stranger_fs = make_sphere_fs(&mut stranger_db).await.unwrap();
+        let stranger_body = Sphere::at(stranger_fs.revision(), &stranger_db)
+            .try_as_memo()
+            .await
+            .unwrap()
+            .body;
+
+        fs.link(
+            "stranger",
+            &ContentType::Sphere.to_string(),
+            &stranger_body,
+            Some(vec![(Header::Title.to_string(), "A stranger".into())]),
+        )
+        .await
+        .unwrap();
+        fs.save(None).await.unwrap();
+
+        let slashlink = Slashlink {
+            peer: Peer::None,
+            slug: Some("stranger".into()),
+        };
+        let link = StaticHtmlResolver::default()
+            .resolve(&slashlink)
+            .await
+            .unwrap();
+
+        match SphereFsTranscluder::new(fs)
+            .transclude(&link)
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            Transclude::Text(transclude) => {
+                assert_eq!(transclude.title, Some("A stranger".into()));
+                assert_eq!(transclude.excerpt, None);
+                assert_eq!(transclude.href, "/stranger");
+            }
+            other => panic!("Expected a text transclude, got {:?}", other),
+        };
+    }
+}
@@ -1,7 +1,9 @@
+mod format;
 mod fs;
 mod transclude;
 mod transcluder;
 
+pub use format::*;
 pub use fs::*;
 pub use transclude::*;
 pub use transcluder::*;
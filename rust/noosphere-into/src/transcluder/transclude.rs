@@ -1,3 +1,5 @@
+use cid::Cid;
+
 #[derive(Clone, Debug)]
 pub struct TextTransclude {
     pub title: Option<String>,
@@ -6,13 +8,42 @@ pub struct TextTransclude {
     pub href: String,
 }
 
+/// A transclude of content that is presented by embedding its body (such as
+/// an image, an audio recording or a video)
+#[derive(Clone, Debug)]
+pub struct MediaTransclude {
+    pub title: Option<String>,
+    /// The `Content-Type` of the content
+    pub content_type: String,
+    /// The CID of the memo of the content
+    pub content: Cid,
+    /// The name of the file that the content's body is written to when it is
+    /// rendered (see [crate::body_file_name])
+    pub file_name: String,
+    pub link_text: String,
+    pub href: String,
+}
+
+/// A transclude of a sphere that has been linked as content
+#[derive(Clone, Debug)]
+pub struct SphereTransclude {
+    pub title: Option<String>,
+    /// The identity of the linked sphere
+    pub identity: String,
+    pub link_text: String,
+    pub href: String,
+}
+
 /// The set of possible transcludes that may need to be rendered to a target
-/// format. At this time, only text transcludes are supported.
+/// format. The variant is chosen by the `Content-Type` of the content being
+/// transcluded (see [crate::TranscludeFormat]).
 #[derive(Clone, Debug)]
 pub enum Transclude {
     // TODO
-    // Rich,
     // Interactive,
-    // Bitmap,
     Text(TextTransclude),
+    Bitmap(MediaTransclude),
+    Audio(MediaTransclude),
+    Video(MediaTransclude),
+    Sphere(SphereTransclude),
 }
@@ -1,10 +1,15 @@
+use std::collections::BTreeSet;
+
 use async_stream::stream;
 use futures::Stream;
-use noosphere_core::data::ContentType;
+use noosphere_core::data::{ContentType, Header};
 use noosphere_fs::SphereFile;
 use tokio::io::AsyncRead;
 
-use crate::{subtext_to_html_document_stream, subtext_to_html_fragment_stream, Transform};
+use crate::{
+    backlinks_to_html, body_file_name, html_document_envelope, media_to_html, permalink_body_path,
+    subtext_to_html_document_stream, subtext_to_html_fragment_stream, TranscludeFormat, Transform,
+};
 
 /// Used to configure the output format of the [file_to_html_stream] transform
 pub enum HtmlOutput {
@@ -16,12 +21,16 @@ pub enum HtmlOutput {
 
 /// Given a [Transform], a [SphereFile] and an [HtmlOutput], perform a streamed
 /// transformation of the [SphereFile] into HTML. The transformation that is
-/// performed may vary depending on content type. At this time, only Subtext
-/// is supported.
+/// performed may vary depending on content type: Subtext is rendered as a
+/// document, and images, audio and video are embedded from their body files
+/// (see [crate::permalink_body_path]). The slugs of any content that links to
+/// the file (see [noosphere_fs::SphereFs::backlinks]) are rendered as a
+/// "Linked from" section following the file content.
 pub fn file_to_html_stream<T, R>(
     transform: T,
     file: SphereFile<R>,
     output: HtmlOutput,
+    backlinks: BTreeSet<String>,
 ) -> impl Stream<Item = String>
 where
     T: Transform,
@@ -32,19 +41,53 @@ where
             Some(ContentType::Subtext) => {
                 match output {
                     HtmlOutput::Document => {
-                        let stream = subtext_to_html_document_stream(transform, file);
+                        let stream = subtext_to_html_document_stream(transform, file, backlinks);
                         for await part in stream {
                             yield part;
                         }
                     },
                     HtmlOutput::Fragment => {
-                        let stream = subtext_to_html_fragment_stream(transform, file);
+                        let stream = subtext_to_html_fragment_stream(transform.clone(), file);
                         for await part in stream {
                             yield part;
                         }
+
+                        match backlinks_to_html(transform, &backlinks).await {
+                            Ok(backlinks_html) => {
+                                yield backlinks_html;
+                            }
+                            Err(error) => warn!("Failed to render backlinks: {:?}", error),
+                        };
                     }
                 };
             }
+            _ if TranscludeFormat::from_memo(&file.memo).has_body() => {
+                let format = TranscludeFormat::from_memo(&file.memo);
+                let src = format!(
+                    "/{}",
+                    permalink_body_path(&file.memo_version, &body_file_name(&file.memo)).display()
+                );
+                let alt = file
+                    .memo
+                    .get_first_header(&Header::Title.to_string())
+                    .unwrap_or_default();
+                let (html_prefix, html_suffix) = match output {
+                    HtmlOutput::Document => html_document_envelope(&file.memo),
+                    HtmlOutput::Fragment => (String::new(), String::new()),
+                };
+
+                yield html_prefix;
+                yield format!("<article class=\"media\">{}</article>", media_to_html(format, &src, &alt));
+
+                match backlinks_to_html(transform, &backlinks).await {
+                    Ok(backlinks_html) => {
+                        yield backlinks_html;
+                    }
+                    Err(error) => warn!("Failed to render backlinks: {:?}", error),
+                };
+
+                yield html_suffix;
+            }
             _ => {
                 yield "<article><p>Format cannot be rendered as HTML</p></article>".into();
             }
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use async_stream::stream;
+use futures::{Future, Stream};
+use noosphere_core::data::ContentType;
+use noosphere_fs::SphereFile;
+use subtext::{block::Block, primitive::Entity};
+use tokio::io::AsyncRead;
+
+use crate::Transform;
+
+/// Given a [Transform], a [SphereFile] and a function that converts a single
+/// [Block] to some plain text document format (such as Markdown or Gemtext),
+/// produce a stream that yields the file content as a document in that
+/// format. Blocks are separated by blank lines, except that consecutive list
+/// items are kept together.
+pub fn subtext_to_document_stream<T, R, F, Fut>(
+    transform: T,
+    file: SphereFile<R>,
+    block_to_document: F,
+) -> impl Stream<Item = String>
+where
+    T: Transform,
+    R: AsyncRead + Unpin,
+    F: Fn(T, Block<Entity>) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    stream! {
+        match file.memo.content_type() {
+            Some(ContentType::Subtext) => (),
+            actual => {
+                warn!("Input did not have the correct content-type; expected {}, got {:?}", ContentType::Subtext, actual.map(|content_type| content_type.to_string()).ok_or("nothing"));
+                return;
+            }
+        };
+
+        let subtext_ast_stream = subtext::stream::<Block<Entity>, Entity, _>(file.contents).await;
+        let mut previous_was_list_item = None;
+
+        for await block in subtext_ast_stream {
+            if let Ok(block) = block {
+                let is_list_item = matches!(block, Block::List(_));
+
+                match block_to_document(transform.clone(), block).await {
+                    Ok(block_document) if block_document.is_empty() => (),
+                    Ok(block_document) => {
+                        // Consecutive list items are kept together so that
+                        // they form a single (tight) list
+                        match previous_was_list_item {
+                            Some(true) if is_list_item => {
+                                yield "\n".into();
+                            }
+                            Some(_) => {
+                                yield "\n\n".into();
+                            }
+                            None => ()
+                        };
+
+                        yield block_document;
+                        previous_was_list_item = Some(is_list_item);
+                    },
+                    Err(error) => {
+                        warn!("Failed to transform subtext block: {:?}", error);
+                    }
+                }
+            }
+        }
+
+        if previous_was_list_item.is_some() {
+            yield "\n".into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use noosphere_core::data::ContentType;
+    use noosphere_fs::helpers::make_sphere_fs;
+    use noosphere_storage::{MemoryStorage, SphereDb};
+    use tokio_stream::StreamExt;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::{
+        subtext_to_gemtext_stream, subtext_to_markdown_stream, DocumentFormat,
+        RelativeLinkTransform,
+    };
+
+    /// Render the same Subtext fixture as a document in the given format
+    async fn render_fixture(format: DocumentFormat) -> String {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let mut fs = make_sphere_fs(&mut db).await.unwrap();
+
+        fs.write(
+            "fixture",
+            &ContentType::Subtext.to_string(),
+            b"# Animals\n\nSome of my favorites:\n- Cats\n- Dogs\n\n\n> Not birds, though\n\nAsk @alice/birds about birds"
+                .as_ref(),
+            None,
+        )
+        .await
+        .unwrap();
+        fs.save(None).await.unwrap();
+
+        let path = PathBuf::from(format!("fixture.{}", format.extension()));
+        let transform = RelativeLinkTransform::new(fs.clone(), &path, format.extension());
+        let file = fs.read("fixture").await.unwrap().unwrap();
+
+        let parts: Vec<String> = match format {
+            DocumentFormat::Markdown => subtext_to_markdown_stream(transform, file).collect().await,
+            DocumentFormat::Gemtext => subtext_to_gemtext_stream(transform, file).collect().await,
+        };
+
+        parts.join("")
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_lays_out_blocks_the_same_way_in_each_document_format() {
+        assert_eq!(
+            render_fixture(DocumentFormat::Markdown).await,
+            "# Animals\n\nSome of my favorites:\n\n- Cats\n- Dogs\n\n> Not birds, though\n\nAsk @alice/birds about birds\n"
+        );
+        assert_eq!(
+            render_fixture(DocumentFormat::Gemtext).await,
+            "# Animals\n\nSome of my favorites:\n\n* Cats\n* Dogs\n\n> Not birds, though\n\nAsk @alice/birds about birds\n"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::str::FromStr;
+
+use crate::{
+    document_body_href, subtext_to_document_stream, Resolver, Transclude, Transcluder, Transform,
+};
+use anyhow::Result;
+use futures::Stream;
+use noosphere_fs::SphereFile;
+use subtext::{block::Block, primitive::Entity, Slashlink};
+use tokio::io::AsyncRead;
+use url::Url;
+
+/// Given a [Transform] and a [SphereFile], produce a stream that yields the
+/// file content as a Gemtext document
+pub fn subtext_to_gemtext_stream<T, R>(
+    transform: T,
+    file: SphereFile<R>,
+) -> impl Stream<Item = String>
+where
+    T: Transform,
+    R: AsyncRead + Unpin,
+{
+    subtext_to_document_stream(transform, file, block_to_gemtext)
+}
+
+/// Given a [Transform] and a [Block], produce a Gemtext string. Gemtext does
+/// not support inline links, so any links in the block are listed as link
+/// lines following its content, and then any transcludes follow as quotes.
+pub async fn block_to_gemtext<T>(transform: T, block: Block<Entity>) -> Result<String>
+where
+    T: Transform,
+{
+    let mut content_gemtext = String::new();
+    let mut link_lines = Vec::new();
+    let mut transclude_gemtext_strings = Vec::new();
+    let content_entities: Vec<Entity> = block.to_content_entities().into_iter().cloned().collect();
+    let is_solo_slashlink = matches!(
+        (content_entities.first(), content_entities.len()),
+        (Some(&Entity::SlashLink(_)), 1)
+    );
+
+    for entity in content_entities {
+        let (entity_gemtext, link_line, transclude_gemtext) =
+            entity_to_gemtext(transform.clone(), entity).await?;
+
+        content_gemtext.push_str(&entity_gemtext);
+        if let Some(link_line) = link_line {
+            link_lines.push(link_line);
+        }
+        if let Some(transclude_gemtext) = transclude_gemtext {
+            transclude_gemtext_strings.push(transclude_gemtext);
+        }
+    }
+
+    let content_gemtext = content_gemtext.trim();
+    let content_gemtext = match block {
+        Block::Header(_) => format!("# {}", content_gemtext),
+        // If this is a slashlink on its own, effectively replace it with its
+        // transclude (which includes a link line of its own)
+        Block::Paragraph(_) if is_solo_slashlink => {
+            link_lines.clear();
+            String::new()
+        }
+        Block::Paragraph(_) => content_gemtext.to_owned(),
+        Block::Quote(_) => format!("> {}", content_gemtext),
+        Block::List(_) => format!("* {}", content_gemtext),
+        Block::Blank(_) => String::new(),
+    };
+
+    Ok(std::iter::once(content_gemtext)
+        .chain(link_lines)
+        .chain(transclude_gemtext_strings)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+/// Given a [Transform] and an [Entity], produce the inline Gemtext for it, a
+/// link line if the entity is a link, and a Gemtext transclude if the entity
+/// is something that may be transcluded
+pub async fn entity_to_gemtext<T>(
+    transform: T,
+    entity: Entity,
+) -> Result<(String, Option<String>, Option<String>)>
+where
+    T: Transform,
+{
+    Ok(match entity {
+        Entity::Sigil(_) => (String::new(), None, None),
+        Entity::TextSpan(content) => (content.to_string(), None, None),
+        Entity::EmptySpace(content) => (content.to_string(), None, None),
+        Entity::SlashLink(text) => {
+            let slashlink = Slashlink::from_str(text.as_ref())?;
+            let content = slashlink.to_string();
+            let link = match transform.resolver().resolve(&slashlink).await {
+                Ok(link) => link,
+                Err(error) => {
+                    // A slashlink that cannot be resolved to a written
+                    // document is written as plain text rather than as a
+                    // dead link
+                    debug!("Not linking {}: {}", content, error);
+
+                    return Ok((content, None, None));
+                }
+            };
+
+            let transclude = transform
+                .transcluder()
+                .transclude(&link)
+                .await?
+                .map(transclude_to_gemtext);
+
+            let link_line = format!("=> {} {}", link, content);
+
+            (content, Some(link_line), transclude)
+        }
+        Entity::HyperLink(text) => {
+            let href = Url::from_str(text.as_ref())?.to_string();
+            let link_line = format!("=> {}", href);
+
+            (href, Some(link_line), None)
+        }
+        Entity::WikiLink(text) => {
+            let slug = subtext::util::to_slug(text.as_ref())?;
+            let slashlink = Slashlink::from_str(&format!("/{slug}"))?;
+            let link = transform.resolver().resolve(&slashlink).await?;
+
+            let text = text
+                .strip_prefix("[[")
+                .unwrap_or_default()
+                .strip_suffix("]]")
+                .unwrap_or_default();
+
+            let link_line = format!("=> {} {}", link, text);
+
+            (text.to_owned(), Some(link_line), None)
+        }
+    })
+}
+
+/// Convert a [Transclude] to Gemtext: quote lines for its title and excerpt,
+/// followed by a link line. Media cannot be embedded in Gemtext, so it is
+/// linked to its body (which is written alongside the documents); spheres are
+/// not written as documents, so they are quoted without a link.
+pub fn transclude_to_gemtext(transclude: Transclude) -> String {
+    let (title, excerpt, link_text, href) = match transclude {
+        Transclude::Text(text_transclude) => (
+            text_transclude.title,
+            text_transclude.excerpt,
+            text_transclude.link_text,
+            Some(text_transclude.href),
+        ),
+        Transclude::Bitmap(media_transclude)
+        | Transclude::Audio(media_transclude)
+        | Transclude::Video(media_transclude) => (
+            media_transclude.title,
+            None,
+            media_transclude.link_text,
+            Some(document_body_href(
+                &media_transclude.href,
+                &media_transclude.file_name,
+            )),
+        ),
+        Transclude::Sphere(sphere_transclude) => (
+            sphere_transclude.title,
+            Some(sphere_transclude.identity),
+            sphere_transclude.link_text,
+            None,
+        ),
+    };
+
+    let mut lines = Vec::new();
+
+    if let Some(title) = &title {
+        lines.push(format!("> {}", title));
+    }
+
+    if let Some(excerpt) = &excerpt {
+        lines.push(format!("> {}", excerpt));
+    }
+
+    lines.push(match href {
+        Some(href) => format!("=> {} {}", href, link_text),
+        None => format!("> {}", link_text),
+    });
+
+    lines.join("\n")
+}
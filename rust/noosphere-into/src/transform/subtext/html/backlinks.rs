@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+
+use crate::{Resolver, Transform};
+use anyhow::Result;
+use horrorshow::{html, Raw};
+use subtext::{Peer, Slashlink};
+
+/// Given a [Transform] and the slugs of the content that links to some file,
+/// produce a "Linked from" section as an HTML string. If there are no
+/// backlinks, the result is an empty string.
+pub async fn backlinks_to_html<T>(transform: T, backlinks: &BTreeSet<String>) -> Result<String>
+where
+    T: Transform,
+{
+    if backlinks.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut item_html_strings = Vec::new();
+
+    for slug in backlinks {
+        let slashlink = Slashlink {
+            peer: Peer::None,
+            slug: Some(slug.clone()),
+        };
+        let content = slashlink.to_string();
+        let href = transform.resolver().resolve(&slashlink).await?.to_string();
+
+        item_html_strings.push(
+            html! {
+                li(class="backlink") {
+                    a(href=href.as_str(), class="slashlink") {
+                        : &content
+                    }
+                }
+            }
+            .to_string(),
+        );
+    }
+
+    let items_html = item_html_strings.join("\n");
+
+    Ok(html! {
+        aside(class="backlinks") {
+            h2(class="backlinks-title") : "Linked from";
+            ul(class="backlinks-list") : Raw(&items_html);
+        }
+    }
+    .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use noosphere_fs::helpers::make_sphere_fs;
+    use noosphere_storage::{MemoryStorage, SphereDb};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::StaticHtmlTransform;
+
+    use super::backlinks_to_html;
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_renders_backlinks_as_a_list_of_slashlinks() {
+        let storage_provider = MemoryStorage::default();
+        let mut db = SphereDb::new(&storage_provider).await.unwrap();
+
+        let fs = make_sphere_fs(&mut db).await.unwrap();
+        let transform = StaticHtmlTransform::new(fs);
+
+        assert_eq!(
+            backlinks_to_html(transform.clone(), &BTreeSet::new())
+                .await
+                .unwrap(),
+            ""
+        );
+
+        let backlinks = BTreeSet::from(["cats".to_string(), "dogs".to_string()]);
+
+        assert_eq!(
+            backlinks_to_html(transform, &backlinks).await.unwrap(),
+            r#"<aside class="backlinks"><h2 class="backlinks-title">Linked from</h2><ul class="backlinks-list"><li class="backlink"><a href="/cats" class="slashlink">/cats</a></li>
+<li class="backlink"><a href="/dogs" class="slashlink">/dogs</a></li></ul></aside>"#
+        );
+    }
+}
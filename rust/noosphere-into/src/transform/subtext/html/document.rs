@@ -1,14 +1,19 @@
-use crate::{html_document_envelope, subtext_to_html_fragment_stream, Transform};
+use std::collections::BTreeSet;
+
+use crate::{
+    backlinks_to_html, html_document_envelope, subtext_to_html_fragment_stream, Transform,
+};
 use async_stream::stream;
 use futures::Stream;
 use noosphere_fs::SphereFile;
 use tokio::io::AsyncRead;
 
-/// Given a [Transform] and a [SphereFile], produce a stream that yields the
-/// file content as an HTML document
+/// Given a [Transform], a [SphereFile] and the slugs of the content that links
+/// to it, produce a stream that yields the file content as an HTML document
 pub fn subtext_to_html_document_stream<T, R>(
     transform: T,
     file: SphereFile<R>,
+    backlinks: BTreeSet<String>,
 ) -> impl Stream<Item = String>
 where
     T: Transform,
@@ -16,7 +21,7 @@ where
 {
     stream! {
       let (html_prefix, html_suffix) = html_document_envelope(&file.memo);
-      let fragment_stream = subtext_to_html_fragment_stream(transform, file);
+      let fragment_stream = subtext_to_html_fragment_stream(transform.clone(), file);
 
       yield html_prefix;
 
@@ -24,6 +29,13 @@ where
         yield fragment_part;
       }
 
+      match backlinks_to_html(transform, &backlinks).await {
+        Ok(backlinks_html) => {
+          yield backlinks_html;
+        }
+        Err(error) => warn!("Failed to render backlinks: {:?}", error),
+      };
+
       yield html_suffix;
     }
 }
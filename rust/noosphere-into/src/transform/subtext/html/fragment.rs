@@ -1,6 +1,9 @@
 use std::str::FromStr;
 
-use crate::{Resolver, Transclude, Transcluder, Transform};
+use crate::{
+    permalink_body_path, MediaTransclude, Resolver, Transclude, TranscludeFormat, Transcluder,
+    Transform,
+};
 use anyhow::Result;
 use async_stream::stream;
 
@@ -145,7 +148,25 @@ where
         Entity::SlashLink(text) => {
             let slashlink = Slashlink::from_str(text.as_ref())?;
             let content = slashlink.to_string();
-            let link = transform.resolver().resolve(&slashlink).await?;
+            let link = match transform.resolver().resolve(&slashlink).await {
+                Ok(link) => link,
+                Err(error) => {
+                    // A slashlink that cannot be resolved to a rendered page
+                    // (such as one into a sphere that is not rendered) is
+                    // presented as plain text rather than as a dead link
+                    debug!("Not linking {}: {}", content, error);
+
+                    return Ok((
+                        html! {
+                            span(class="slashlink") {
+                                : &content
+                            }
+                        }
+                        .to_string(),
+                        None,
+                    ));
+                }
+            };
 
             let transclude = transform.transcluder().transclude(&link).await?;
 
@@ -221,8 +242,8 @@ where
 
 /// Convert a [Transclude] to an HTML string
 pub async fn transclude_to_html(transclude: Transclude) -> Result<String> {
-    match transclude {
-        Transclude::Text(text_transclude) => Ok(html! {
+    Ok(match transclude {
+        Transclude::Text(text_transclude) => html! {
             aside(class="transclude") {
                 a(class="transclude-format-text", href=&text_transclude.href) {
                     @ if let Some(title) = &text_transclude.title {
@@ -237,6 +258,91 @@ pub async fn transclude_to_html(transclude: Transclude) -> Result<String> {
                 }
             }
         }
-        .to_string()),
+        .to_string(),
+        Transclude::Bitmap(media_transclude) => {
+            media_transclude_to_html(TranscludeFormat::Bitmap, &media_transclude)
+        }
+        Transclude::Audio(media_transclude) => {
+            media_transclude_to_html(TranscludeFormat::Audio, &media_transclude)
+        }
+        Transclude::Video(media_transclude) => {
+            media_transclude_to_html(TranscludeFormat::Video, &media_transclude)
+        }
+        Transclude::Sphere(sphere_transclude) => html! {
+            aside(class="transclude") {
+                a(class="transclude-format-sphere", href=&sphere_transclude.href) {
+                    @ if let Some(title) = &sphere_transclude.title {
+                        span(class="title") : title
+                    }
+
+                    span(class="sphere-identity") : &sphere_transclude.identity;
+                    span(class="link-text") : &sphere_transclude.link_text
+                }
+            }
+        }
+        .to_string(),
+    })
+}
+
+/// Convert a [MediaTransclude] to an HTML string: the media itself (loaded
+/// from the body file that is written alongside the content's permalink page),
+/// captioned with a link to the content
+fn media_transclude_to_html(
+    format: TranscludeFormat,
+    media_transclude: &MediaTransclude,
+) -> String {
+    let src = format!(
+        "/{}",
+        permalink_body_path(&media_transclude.content, &media_transclude.file_name).display()
+    );
+    let alt = media_transclude
+        .title
+        .clone()
+        .unwrap_or_else(|| media_transclude.link_text.clone());
+    let format_class = match format {
+        TranscludeFormat::Audio => "transclude-format-audio",
+        TranscludeFormat::Video => "transclude-format-video",
+        _ => "transclude-format-bitmap",
+    };
+
+    html! {
+        aside(class="transclude") {
+            figure(class=format_class) {
+                : Raw(media_to_html(format, &src, &alt));
+                figcaption {
+                    a(href=&media_transclude.href) {
+                        @ if let Some(title) = &media_transclude.title {
+                            span(class="title") : title
+                        }
+
+                        span(class="link-text") : &media_transclude.link_text
+                    }
+                }
+            }
+        }
+    }
+    .to_string()
+}
+
+/// Produce the HTML element that embeds media of the given
+/// [TranscludeFormat] from the given `src`: an `<img>`, `<audio>` or `<video>`
+pub fn media_to_html(format: TranscludeFormat, src: &str, alt: &str) -> String {
+    match format {
+        TranscludeFormat::Audio => html! {
+            audio(src=src, controls="controls", preload="metadata") {
+                a(href=src) : alt
+            }
+        }
+        .to_string(),
+        TranscludeFormat::Video => html! {
+            video(src=src, controls="controls", preload="metadata") {
+                a(href=src) : alt
+            }
+        }
+        .to_string(),
+        _ => html! {
+            img(src=src, alt=alt, loading="lazy");
+        }
+        .to_string(),
     }
 }
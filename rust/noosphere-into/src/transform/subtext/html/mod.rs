@@ -1,5 +1,7 @@
+mod backlinks;
 mod document;
 mod fragment;
 
+pub use backlinks::*;
 pub use document::*;
 pub use fragment::*;
@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+use crate::{
+    document_body_href, subtext_to_document_stream, Resolver, Transclude, Transcluder, Transform,
+};
+use anyhow::Result;
+use futures::Stream;
+use noosphere_fs::SphereFile;
+use subtext::{block::Block, primitive::Entity, Slashlink};
+use tokio::io::AsyncRead;
+use url::Url;
+
+/// Given a [Transform] and a [SphereFile], produce a stream that yields the
+/// file content as a Markdown document
+pub fn subtext_to_markdown_stream<T, R>(
+    transform: T,
+    file: SphereFile<R>,
+) -> impl Stream<Item = String>
+where
+    T: Transform,
+    R: AsyncRead + Unpin,
+{
+    subtext_to_document_stream(transform, file, block_to_markdown)
+}
+
+/// Given a [Transform] and a [Block], produce a Markdown string; any
+/// transcludes in the block follow its content as blockquotes
+pub async fn block_to_markdown<T>(transform: T, block: Block<Entity>) -> Result<String>
+where
+    T: Transform,
+{
+    let mut content_markdown = String::new();
+    let mut transclude_markdown_strings = Vec::new();
+    let content_entities: Vec<Entity> = block.to_content_entities().into_iter().cloned().collect();
+    let is_solo_slashlink = matches!(
+        (content_entities.first(), content_entities.len()),
+        (Some(&Entity::SlashLink(_)), 1)
+    );
+
+    for entity in content_entities {
+        let (entity_markdown, transclude_markdown) =
+            entity_to_markdown(transform.clone(), entity).await?;
+
+        content_markdown.push_str(&entity_markdown);
+        if let Some(transclude_markdown) = transclude_markdown {
+            transclude_markdown_strings.push(transclude_markdown);
+        }
+    }
+
+    let content_markdown = content_markdown.trim();
+    let content_markdown = match block {
+        Block::Header(_) => format!("# {}", content_markdown),
+        // If this is a slashlink on its own, effectively replace it with its transclude
+        Block::Paragraph(_) if is_solo_slashlink => String::new(),
+        Block::Paragraph(_) => content_markdown.to_owned(),
+        Block::Quote(_) => format!("> {}", content_markdown),
+        Block::List(_) => format!("- {}", content_markdown),
+        Block::Blank(_) => String::new(),
+    };
+
+    Ok(std::iter::once(content_markdown)
+        .chain(transclude_markdown_strings)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n\n"))
+}
+
+/// Given a [Transform] and an [Entity], produce a Markdown string (and a
+/// Markdown transclude, if the entity is something that may be transcluded)
+pub async fn entity_to_markdown<T>(transform: T, entity: Entity) -> Result<(String, Option<String>)>
+where
+    T: Transform,
+{
+    Ok(match entity {
+        Entity::Sigil(_) => (String::new(), None),
+        Entity::TextSpan(content) => (escape_markdown(&content.to_string()), None),
+        Entity::EmptySpace(content) => (content.to_string(), None),
+        Entity::SlashLink(text) => {
+            let slashlink = Slashlink::from_str(text.as_ref())?;
+            let content = slashlink.to_string();
+            let link = match transform.resolver().resolve(&slashlink).await {
+                Ok(link) => link,
+                Err(error) => {
+                    // A slashlink that cannot be resolved to a written
+                    // document is written as plain text rather than as a
+                    // dead link
+                    debug!("Not linking {}: {}", content, error);
+
+                    return Ok((escape_markdown(&content), None));
+                }
+            };
+
+            let transclude = transform
+                .transcluder()
+                .transclude(&link)
+                .await?
+                .map(transclude_to_markdown);
+
+            (
+                format!("[{}]({})", escape_markdown(&content), link),
+                transclude,
+            )
+        }
+        Entity::HyperLink(text) => {
+            let href = Url::from_str(text.as_ref())?.to_string();
+
+            (format!("<{}>", href), None)
+        }
+        Entity::WikiLink(text) => {
+            let slug = subtext::util::to_slug(text.as_ref())?;
+            let slashlink = Slashlink::from_str(&format!("/{slug}"))?;
+            let link = transform.resolver().resolve(&slashlink).await?;
+
+            let text = text
+                .strip_prefix("[[")
+                .unwrap_or_default()
+                .strip_suffix("]]")
+                .unwrap_or_default();
+
+            (format!("[{}]({})", escape_markdown(text), link), None)
+        }
+    })
+}
+
+/// Convert a [Transclude] to a Markdown blockquote. Media cannot be embedded
+/// in a set of Markdown documents, so it is quoted as a link to its body
+/// (which is written alongside the documents); spheres are not written as
+/// documents, so they are quoted without a link.
+pub fn transclude_to_markdown(transclude: Transclude) -> String {
+    let (title, excerpt, link_text, href) = match transclude {
+        Transclude::Text(text_transclude) => (
+            text_transclude.title,
+            text_transclude.excerpt,
+            text_transclude.link_text,
+            Some(text_transclude.href),
+        ),
+        Transclude::Bitmap(media_transclude)
+        | Transclude::Audio(media_transclude)
+        | Transclude::Video(media_transclude) => (
+            media_transclude.title,
+            None,
+            media_transclude.link_text,
+            Some(document_body_href(
+                &media_transclude.href,
+                &media_transclude.file_name,
+            )),
+        ),
+        Transclude::Sphere(sphere_transclude) => (
+            sphere_transclude.title,
+            Some(sphere_transclude.identity),
+            sphere_transclude.link_text,
+            None,
+        ),
+    };
+
+    let mut lines = Vec::new();
+
+    if let Some(title) = &title {
+        lines.push(format!("**{}**", escape_markdown(title)));
+    }
+
+    if let Some(excerpt) = &excerpt {
+        lines.push(escape_markdown(excerpt));
+    }
+
+    lines.push(match href {
+        Some(href) => format!("[{}]({})", escape_markdown(&link_text), href),
+        None => escape_markdown(&link_text),
+    });
+
+    lines
+        .iter()
+        .map(|line| format!("> {}", line))
+        .collect::<Vec<String>>()
+        .join("\n>\n")
+}
+
+/// Escape the characters in some text that would otherwise be interpreted
+/// as Markdown syntax
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        if matches!(character, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+
+        escaped.push(character);
+    }
+
+    escaped
+}
@@ -1,3 +1,9 @@
+mod document;
+mod gemtext;
 mod html;
+mod markdown;
 
+pub use document::*;
+pub use gemtext::*;
 pub use html::*;
+pub use markdown::*;
@@ -1,8 +1,10 @@
+use std::path::Path;
+
 use noosphere_fs::SphereFs;
 use noosphere_storage::Storage;
 use ucan::crypto::KeyMaterial;
 
-use crate::{Resolver, SphereFsTranscluder, StaticHtmlResolver, Transcluder};
+use crate::{RelativeLinkResolver, Resolver, SphereFsTranscluder, StaticHtmlResolver, Transcluder};
 
 /// A [Transform] represents the combination of a [Resolver] and a
 /// [Transcluder]. Together these elements form a transformation over
@@ -33,8 +35,15 @@ where
     K: KeyMaterial + Clone + 'static,
 {
     pub fn new(fs: SphereFs<S, K>) -> Self {
+        StaticHtmlTransform::with_resolver(fs, StaticHtmlResolver::default())
+    }
+
+    /// Create a [StaticHtmlTransform] that resolves links with the given
+    /// [StaticHtmlResolver] (for example, one that knows which followed
+    /// spheres are rendered alongside the sphere)
+    pub fn with_resolver(fs: SphereFs<S, K>, resolver: StaticHtmlResolver) -> Self {
         StaticHtmlTransform {
-            resolver: StaticHtmlResolver(),
+            resolver,
             transcluder: SphereFsTranscluder::new(fs),
         }
     }
@@ -57,3 +66,49 @@ where
         &self.transcluder
     }
 }
+
+/// A [Transform] that is suitable for converting Noosphere content to a set of
+/// documents that link to each other by relative paths (for example, Markdown
+/// files or a Gemini capsule).
+#[derive(Clone)]
+pub struct RelativeLinkTransform<S, K>
+where
+    S: Storage,
+    K: KeyMaterial + Clone + 'static,
+{
+    pub resolver: RelativeLinkResolver,
+    pub transcluder: SphereFsTranscluder<S, K>,
+}
+
+impl<S, K> RelativeLinkTransform<S, K>
+where
+    S: Storage,
+    K: KeyMaterial + Clone + 'static,
+{
+    /// Create a [RelativeLinkTransform] for the document at the given path,
+    /// in a document set whose files have the given extension
+    pub fn new(fs: SphereFs<S, K>, document: &Path, extension: &str) -> Self {
+        RelativeLinkTransform {
+            resolver: RelativeLinkResolver::new(document, extension),
+            transcluder: SphereFsTranscluder::new(fs),
+        }
+    }
+}
+
+impl<S, K> Transform for RelativeLinkTransform<S, K>
+where
+    S: Storage,
+    K: KeyMaterial + Clone + 'static,
+{
+    type Resolver = RelativeLinkResolver;
+
+    type Transcluder = SphereFsTranscluder<S, K>;
+
+    fn resolver(&self) -> &Self::Resolver {
+        &self.resolver
+    }
+
+    fn transcluder(&self) -> &Self::Transcluder {
+        &self.transcluder
+    }
+}
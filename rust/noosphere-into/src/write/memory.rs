@@ -43,6 +43,10 @@ impl WriteTarget for MemoryWriteTarget {
         Ok(self.vfs.lock().await.contains_key(path))
     }
 
+    async fn read(&self, path: &PathBuf) -> Result<Option<Vec<u8>>> {
+        Ok(MemoryWriteTarget::read(self, path).await)
+    }
+
     async fn write<R>(&self, path: &PathBuf, mut contents: R) -> Result<()>
     where
         R: AsyncRead + Unpin + WriteTargetConditionalSend,
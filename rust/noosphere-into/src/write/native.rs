@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use std::path::PathBuf;
+use std::{io::ErrorKind, path::PathBuf};
 use tokio::fs::{create_dir_all, File};
 use tokio::io::{copy, AsyncRead, AsyncWriteExt};
 
@@ -34,6 +34,16 @@ impl WriteTarget for NativeFs {
         Ok(self.root.join(path).exists())
     }
 
+    async fn read(&self, path: &PathBuf) -> Result<Option<Vec<u8>>> {
+        NativeFs::assert_relative(path)?;
+
+        match tokio::fs::read(self.root.join(path)).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
     async fn write<R>(&self, path: &PathBuf, mut contents: R) -> Result<()>
     where
         R: AsyncRead + Unpin + WriteTargetConditionalSend,
@@ -58,6 +68,17 @@ impl WriteTarget for NativeFs {
         NativeFs::assert_relative(src)?;
         NativeFs::assert_relative(dst)?;
 
+        // Links are moved to point at newer content as it is rendered, so any
+        // link that already exists at the destination is replaced
+        if tokio::fs::symlink_metadata(self.root.join(dst))
+            .await
+            .is_ok()
+        {
+            tokio::fs::remove_file(self.root.join(dst)).await?;
+        } else if let Some(parent) = dst.parent() {
+            create_dir_all(self.root.join(parent)).await?;
+        }
+
         #[cfg(not(windows))]
         let result = tokio::fs::symlink(self.root.join(src), self.root.join(dst)).await?;
         #[cfg(windows)]
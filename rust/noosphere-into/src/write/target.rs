@@ -25,6 +25,10 @@ pub trait WriteTarget: Clone + WriteTargetConditionalSendSync {
     /// Returns true if a file exists at the provided path
     async fn exists(&self, path: &PathBuf) -> Result<bool>;
 
+    /// Read the full contents of the file at the provided path, if there is
+    /// one
+    async fn read(&self, path: &PathBuf) -> Result<Option<Vec<u8>>>;
+
     /// Given a path and an [AsyncRead], write the contents of the [AsyncRead]
     /// to the path
     async fn write<R>(&self, path: &PathBuf, contents: R) -> Result<()>
@@ -55,6 +55,12 @@ pub enum CLICommand {
         /// the default bootstrap peers.
         #[arg(long, default_value_t = false)]
         no_default_peers: bool,
+
+        /// If no configuration path provided, a directory in which to
+        /// persist DHT records so that they survive a restart. Records are
+        /// only kept in memory when omitted.
+        #[arg(long)]
+        storage_path: Option<PathBuf>,
     },
 
     /// Utility to create keys compatible with Noosphere.
@@ -116,4 +122,6 @@ pub struct CLIConfigFile {
     pub no_default_peers: bool,
     #[serde(default)]
     pub dht_config: DHTConfig,
+    #[serde(default)]
+    pub storage_path: Option<PathBuf>,
 }
@@ -4,6 +4,7 @@ use anyhow::{anyhow, Result};
 use noosphere::key::InsecureKeyStorage;
 use noosphere_ns::{DHTConfig, Multiaddr, BOOTSTRAP_PEERS};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
 use ucan_key_support::ed25519::Ed25519KeyMaterial;
 
@@ -14,6 +15,7 @@ pub struct RunnerNodeConfig {
     pub listening_address: Option<Multiaddr>,
     pub peers: Vec<Multiaddr>,
     pub dht_config: DHTConfig,
+    pub storage_path: Option<PathBuf>,
 }
 
 impl RunnerNodeConfig {
@@ -26,6 +28,7 @@ impl RunnerNodeConfig {
         let dht_config = config.dht_config;
         let listening_address = config.listening_address;
         let api_address = config.api_address;
+        let storage_path = config.storage_path;
         let mut peers = config.peers;
         if !config.no_default_peers {
             peers.extend_from_slice(&BOOTSTRAP_PEERS[..]);
@@ -37,6 +40,7 @@ impl RunnerNodeConfig {
             listening_address,
             peers,
             dht_config,
+            storage_path,
         })
     }
 
@@ -53,6 +57,7 @@ impl RunnerNodeConfig {
                 no_default_peers,
                 listening_address,
                 api_address,
+                storage_path,
             } => match config {
                 Some(config_path) => {
                     let toml_str = tokio::fs::read_to_string(&config_path).await?;
@@ -82,6 +87,7 @@ impl RunnerNodeConfig {
                         peers: bootstrap_peers,
                         no_default_peers,
                         dht_config,
+                        storage_path,
                     };
                     Ok(RunnerNodeConfig::try_from_config(key_storage, config).await?)
                 }
@@ -97,7 +103,6 @@ impl RunnerNodeConfig {
 mod tests {
     use super::*;
     use noosphere::key::KeyStorage;
-    use std::path::PathBuf;
     use tempdir::TempDir;
     use ucan::crypto::KeyMaterial;
 
@@ -152,6 +157,7 @@ mod tests {
                 listening_address: Some("/ip4/127.0.0.1/tcp/6666".parse()?),
                 peers: None,
                 no_default_peers: false,
+                storage_path: None,
             },
             &env.key_storage,
         )
@@ -199,6 +205,7 @@ peers = [
                 listening_address: None,
                 peers: None,
                 no_default_peers: false,
+                storage_path: None,
             },
             &env.key_storage,
         )
@@ -250,6 +257,7 @@ no_default_peers = true
                 listening_address: None,
                 peers: None,
                 no_default_peers: false,
+                storage_path: None,
             },
             &env.key_storage,
         )
@@ -282,6 +290,7 @@ listening_address = 10000
                 listening_address: Some("/ip4/127.0.0.1/tcp/6666".parse()?),
                 peers: None,
                 no_default_peers: false,
+                storage_path: None,
             },
             CLICommand::Run {
                 api_address: None,
@@ -290,6 +299,7 @@ listening_address = 10000
                 listening_address: Some("/ip4/127.0.0.1/tcp/6666".parse()?),
                 peers: None,
                 no_default_peers: false,
+                storage_path: None,
             },
             CLICommand::Run {
                 api_address: None,
@@ -298,6 +308,7 @@ listening_address = 10000
                 listening_address: None,
                 peers: None,
                 no_default_peers: false,
+                storage_path: None,
             },
             CLICommand::Run {
                 api_address: None,
@@ -306,6 +317,7 @@ listening_address = 10000
                 listening_address: None,
                 peers: None,
                 no_default_peers: false,
+                storage_path: None,
             },
         ];
 
@@ -1,7 +1,7 @@
 use crate::runner::config::RunnerNodeConfig;
 use anyhow::Result;
 use noosphere_ns::{Multiaddr, NameSystem, NameSystemClient, PeerId};
-use noosphere_storage::{MemoryStorage, SphereDb};
+use noosphere_storage::{MemoryStorage, NativeStorage, NativeStorageInit, SphereDb};
 use serde::Serialize;
 use std::{
     future::Future,
@@ -44,8 +44,19 @@ pub struct NameSystemRunner {
 
 impl NameSystemRunner {
     pub(crate) async fn try_from_config(mut config: RunnerNodeConfig) -> Result<Self> {
-        let store = SphereDb::new(&MemoryStorage::default()).await?;
-        let node = NameSystem::new(&config.key_material, store, config.dht_config.to_owned())?;
+        // Records are persisted to disk when a storage path is configured, so
+        // that a restarted node can continue to serve the records it held.
+        let node = match config.storage_path.take() {
+            Some(path) => {
+                let store =
+                    SphereDb::new(&NativeStorage::new(NativeStorageInit::Path(path))?).await?;
+                NameSystem::new(&config.key_material, store, config.dht_config.to_owned())?
+            }
+            None => {
+                let store = SphereDb::new(&MemoryStorage::default()).await?;
+                NameSystem::new(&config.key_material, store, config.dht_config.to_owned())?
+            }
+        };
         let peer_id = node.peer_id().to_owned();
 
         let listening_address = if let Some(requested_addr) = config.listening_address.take() {
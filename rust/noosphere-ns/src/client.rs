@@ -52,6 +52,12 @@ pub trait NameSystemClient {
     /// Returns an [NSRecord] for the provided identity if found.
     async fn get_record(&self, identity: &Did) -> Result<Option<NSRecord>>;
 
+    /// Returns the chain of [NSRecord]s that have been seen for the provided
+    /// identity, ordered from oldest to newest. Records that are older than
+    /// the most recently seen record are refused, so the chain only ever
+    /// moves forward.
+    async fn get_record_history(&self, identity: &Did) -> Result<Vec<NSRecord>>;
+
     /* Operator APIs */
 
     /// Connects to peers provided in `add_peers`.
@@ -178,6 +184,10 @@ pub mod test {
 
         assert_eq!(retrieved.identity(), &sphere_id);
         assert_eq!(retrieved.link(), Some(&link));
+
+        let history = client.get_record_history(&sphere_id).await?;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].link(), Some(&link));
         Ok(())
     }
 }
@@ -5,6 +5,7 @@ mod keys;
 mod node;
 mod processor;
 mod rpc;
+mod store;
 mod swarm;
 mod types;
 mod validator;
@@ -13,5 +14,9 @@ pub use config::DHTConfig;
 pub use errors::DHTError;
 pub use keys::DHTKeyMaterial;
 pub use node::DHTNode;
+pub use store::{
+    DHTRecordStore, PersistedChanges, PersistedProviderRecord, PersistedRecord,
+    PersistedRecordIndex, PersistedRecords, RecordPersistence,
+};
 pub use types::{DHTRecord, NetworkInfo, Peer};
 pub use validator::{AllowAllValidator, RecordValidator};
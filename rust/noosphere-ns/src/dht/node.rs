@@ -5,7 +5,7 @@ use crate::dht::{
     processor::DHTProcessor,
     rpc::{DHTMessageClient, DHTRequest, DHTResponse},
     types::{DHTRecord, NetworkInfo, Peer},
-    DHTConfig, RecordValidator,
+    DHTConfig, RecordPersistence, RecordValidator,
 };
 use libp2p::{Multiaddr, PeerId};
 use std::time::Duration;
@@ -68,6 +68,32 @@ impl DHTNode {
         key_material: &K,
         config: DHTConfig,
         validator: Option<V>,
+    ) -> Result<Self, DHTError> {
+        Self::spawn(key_material, config, validator, None)
+    }
+
+    /// Same as [DHTNode::new], except that the records held by the node are
+    /// persisted to the given [RecordPersistence] backend. Records that were
+    /// persisted by a previous node are restored when the node starts, minus
+    /// any that have expired in the meantime.
+    pub fn with_persistence<
+        K: DHTKeyMaterial,
+        V: RecordValidator + 'static,
+        P: RecordPersistence + 'static,
+    >(
+        key_material: &K,
+        config: DHTConfig,
+        validator: Option<V>,
+        persistence: P,
+    ) -> Result<Self, DHTError> {
+        Self::spawn(key_material, config, validator, Some(Box::new(persistence)))
+    }
+
+    fn spawn<K: DHTKeyMaterial, V: RecordValidator + 'static>(
+        key_material: &K,
+        config: DHTConfig,
+        validator: Option<V>,
+        persistence: Option<Box<dyn RecordPersistence>>,
     ) -> Result<Self, DHTError> {
         let keypair = key_material.to_dht_keypair()?;
         let peer_id = PeerId::from(keypair.public());
@@ -77,6 +103,7 @@ impl DHTNode {
             &keypair,
             peer_id,
             validator,
+            persistence,
             config.clone(),
             channels.1,
         )?;
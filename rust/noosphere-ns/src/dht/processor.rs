@@ -3,7 +3,7 @@ use crate::dht::{
     rpc::{DHTMessage, DHTMessageProcessor, DHTRequest, DHTResponse},
     swarm::{build_swarm, DHTEvent, DHTSwarm, DHTSwarmEvent},
     types::{DHTRecord, Peer},
-    DHTConfig, RecordValidator,
+    DHTConfig, RecordPersistence, RecordValidator,
 };
 use libp2p::{
     core::transport::ListenerId,
@@ -68,6 +68,7 @@ where
         keypair: &libp2p::identity::Keypair,
         peer_id: PeerId,
         validator: Option<V>,
+        persistence: Option<Box<dyn RecordPersistence>>,
         config: DHTConfig,
         processor: DHTMessageProcessor,
     ) -> Result<tokio::task::JoinHandle<Result<(), DHTError>>, DHTError> {
@@ -85,7 +86,17 @@ where
             pending_listener_request: None,
         };
 
-        Ok(tokio::spawn(async move { node.process().await }))
+        Ok(tokio::spawn(async move {
+            if let Some(persistence) = persistence {
+                let store = node.swarm.behaviour_mut().kad.store_mut();
+
+                if let Err(error) = store.restore_from(persistence).await {
+                    error!("Failed to restore persisted DHT records: {:?}", error);
+                }
+            }
+
+            node.process().await
+        }))
     }
 
     /// Begin processing requests and connections on the DHT network
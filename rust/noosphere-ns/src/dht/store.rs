@@ -0,0 +1,763 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use libp2p::{
+    kad::record::{
+        store::{MemoryStore, RecordStore, Result as StoreResult},
+        Key, ProviderRecord, Record,
+    },
+    Multiaddr, PeerId,
+};
+use noosphere_storage::{KeyValueStore, SphereDb, Storage};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// The prefix of the keys that persisted DHT records are stored under in a
+/// [KeyValueStore]; the keys are scoped to the [PeerId] of the node that holds
+/// the records, so several nodes may share the same storage
+pub const DHT_RECORDS_KEY_PREFIX: &str = "dht/records";
+
+fn index_key(peer_id: &PeerId) -> String {
+    format!("{}/{}/index", DHT_RECORDS_KEY_PREFIX, peer_id)
+}
+
+fn record_key(peer_id: &PeerId, key: &[u8]) -> String {
+    format!(
+        "{}/{}/record/{}",
+        DHT_RECORDS_KEY_PREFIX,
+        peer_id,
+        to_hex(key)
+    )
+}
+
+fn provider_key(peer_id: &PeerId, key: &[u8], provider: &[u8]) -> String {
+    format!(
+        "{}/{}/provider/{}/{}",
+        DHT_RECORDS_KEY_PREFIX,
+        peer_id,
+        to_hex(key),
+        to_hex(provider)
+    )
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// A change made to a [DHTRecordStore], forwarded to its persistence task.
+#[derive(Debug)]
+pub(crate) enum StoreChange {
+    Put(Record),
+    Remove(Key),
+    AddProvider(ProviderRecord),
+    RemoveProvider(Key, PeerId),
+}
+
+/// A value record in the form that it is persisted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedRecord {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub publisher: Option<Vec<u8>>,
+    /// Expiration as milliseconds since the UNIX epoch, if the record expires.
+    pub expires_at: Option<u64>,
+}
+
+/// A provider record in the form that it is persisted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedProviderRecord {
+    pub key: Vec<u8>,
+    pub provider: Vec<u8>,
+    pub addresses: Vec<Vec<u8>>,
+    /// Expiration as milliseconds since the UNIX epoch, if the record expires.
+    pub expires_at: Option<u64>,
+}
+
+/// All records held by a [DHTRecordStore], as read back from storage.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedRecords {
+    pub records: Vec<PersistedRecord>,
+    pub providers: Vec<PersistedProviderRecord>,
+}
+
+/// The keys of all records held by a [DHTRecordStore]; provider records are
+/// keyed by their record key and the bytes of the provider's [PeerId].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedRecordIndex {
+    pub records: BTreeSet<Vec<u8>>,
+    pub providers: BTreeSet<(Vec<u8>, Vec<u8>)>,
+}
+
+/// A batch of changes to the records held by a [DHTRecordStore].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PersistedChanges {
+    pub records: Vec<PersistedRecord>,
+    pub removed_records: Vec<Vec<u8>>,
+    pub providers: Vec<PersistedProviderRecord>,
+    pub removed_providers: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The index after these changes; `None` if the set of keys is unchanged.
+    pub index: Option<PersistedRecordIndex>,
+}
+
+impl PersistedChanges {
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+            && self.removed_records.is_empty()
+            && self.providers.is_empty()
+            && self.removed_providers.is_empty()
+            && self.index.is_none()
+    }
+}
+
+/// A backend that a [DHTRecordStore] can persist its records to, so that
+/// they survive a restart of the node.
+#[async_trait]
+pub trait RecordPersistence: Send + Sync {
+    /// Read all of the records saved for the node with the given [PeerId].
+    async fn load_records(&self, peer_id: &PeerId) -> Result<PersistedRecords>;
+    /// Write the given changes to the records saved for the node with the
+    /// given [PeerId]; records that did not change are not rewritten.
+    async fn save_changes(&mut self, peer_id: &PeerId, changes: &PersistedChanges) -> Result<()>;
+}
+
+#[async_trait]
+impl<S> RecordPersistence for SphereDb<S>
+where
+    S: Storage,
+{
+    async fn load_records(&self, peer_id: &PeerId) -> Result<PersistedRecords> {
+        let index: PersistedRecordIndex =
+            self.get_key(index_key(peer_id)).await?.unwrap_or_default();
+        let mut persisted = PersistedRecords::default();
+
+        for key in index.records.iter() {
+            if let Some(record) = self.get_key(record_key(peer_id, key)).await? {
+                persisted.records.push(record);
+            }
+        }
+
+        for (key, provider) in index.providers.iter() {
+            if let Some(record) = self.get_key(provider_key(peer_id, key, provider)).await? {
+                persisted.providers.push(record);
+            }
+        }
+
+        Ok(persisted)
+    }
+
+    async fn save_changes(&mut self, peer_id: &PeerId, changes: &PersistedChanges) -> Result<()> {
+        for record in changes.records.iter() {
+            self.set_key(record_key(peer_id, &record.key), record)
+                .await?;
+        }
+
+        for record in changes.providers.iter() {
+            self.set_key(provider_key(peer_id, &record.key, &record.provider), record)
+                .await?;
+        }
+
+        if let Some(index) = &changes.index {
+            self.set_key(index_key(peer_id), index).await?;
+        }
+
+        // Records are unset only after the index no longer refers to them
+        for key in changes.removed_records.iter() {
+            self.unset_key(record_key(peer_id, key)).await?;
+        }
+
+        for (key, provider) in changes.removed_providers.iter() {
+            self.unset_key(provider_key(peer_id, key, provider)).await?;
+        }
+
+        self.flush().await
+    }
+}
+
+fn to_unix_millis(instant: Instant) -> u64 {
+    let now = Instant::now();
+    let time = match instant.checked_duration_since(now) {
+        Some(remaining) => SystemTime::now() + remaining,
+        None => SystemTime::now() - now.duration_since(instant),
+    };
+
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Converts a persisted expiration back into an [Instant]; returns `None` if
+/// the expiration has already passed.
+fn to_instant(unix_millis: u64) -> Option<Instant> {
+    let expires_at = UNIX_EPOCH + Duration::from_millis(unix_millis);
+    let remaining = expires_at.duration_since(SystemTime::now()).ok()?;
+
+    Some(Instant::now() + remaining)
+}
+
+impl From<&Record> for PersistedRecord {
+    fn from(record: &Record) -> Self {
+        PersistedRecord {
+            key: record.key.to_vec(),
+            value: record.value.clone(),
+            publisher: record.publisher.map(|peer_id| peer_id.to_bytes()),
+            expires_at: record.expires.map(to_unix_millis),
+        }
+    }
+}
+
+impl From<&ProviderRecord> for PersistedProviderRecord {
+    fn from(record: &ProviderRecord) -> Self {
+        PersistedProviderRecord {
+            key: record.key.to_vec(),
+            provider: record.provider.to_bytes(),
+            addresses: record
+                .addresses
+                .iter()
+                .map(|address| address.to_vec())
+                .collect(),
+            expires_at: record.expires.map(to_unix_millis),
+        }
+    }
+}
+
+impl PersistedRecord {
+    /// Restore the [Record]; returns `None` if it has expired.
+    fn to_record(&self) -> Result<Option<Record>> {
+        let expires = match self.expires_at {
+            Some(expires_at) => match to_instant(expires_at) {
+                Some(expires) => Some(expires),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+
+        Ok(Some(Record {
+            key: Key::from(self.key.clone()),
+            value: self.value.clone(),
+            publisher: match &self.publisher {
+                Some(bytes) => Some(PeerId::from_bytes(bytes)?),
+                None => None,
+            },
+            expires,
+        }))
+    }
+}
+
+impl PersistedProviderRecord {
+    /// Restore the [ProviderRecord]; returns `None` if it has expired.
+    fn to_provider_record(&self) -> Result<Option<ProviderRecord>> {
+        let expires = match self.expires_at {
+            Some(expires_at) => match to_instant(expires_at) {
+                Some(expires) => Some(expires),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+
+        Ok(Some(ProviderRecord {
+            key: Key::from(self.key.clone()),
+            provider: PeerId::from_bytes(&self.provider)?,
+            addresses: self
+                .addresses
+                .iter()
+                .map(|address| Multiaddr::try_from(address.clone()))
+                .collect::<Result<Vec<Multiaddr>, _>>()?,
+            expires,
+        }))
+    }
+}
+
+/// The changes received by the persistence task since it last saved; only the
+/// latest change to each record is kept.
+#[derive(Default)]
+struct PendingChanges {
+    records: BTreeMap<Vec<u8>, Option<PersistedRecord>>,
+    providers: BTreeMap<(Vec<u8>, Vec<u8>), Option<PersistedProviderRecord>>,
+}
+
+impl PendingChanges {
+    fn apply(&mut self, change: StoreChange) {
+        match change {
+            StoreChange::Put(record) => {
+                self.records
+                    .insert(record.key.to_vec(), Some(PersistedRecord::from(&record)));
+            }
+            StoreChange::Remove(key) => {
+                self.records.insert(key.to_vec(), None);
+            }
+            StoreChange::AddProvider(record) => {
+                self.providers.insert(
+                    (record.key.to_vec(), record.provider.to_bytes()),
+                    Some(PersistedProviderRecord::from(&record)),
+                );
+            }
+            StoreChange::RemoveProvider(key, provider) => {
+                self.providers
+                    .insert((key.to_vec(), provider.to_bytes()), None);
+            }
+        }
+    }
+
+    /// Resolve the pending changes against the given index, updating it to
+    /// reflect them
+    fn into_persisted(self, index: &mut PersistedRecordIndex) -> PersistedChanges {
+        let mut changes = PersistedChanges::default();
+        let mut index_changed = false;
+
+        for (key, record) in self.records {
+            match record {
+                Some(record) => {
+                    index_changed |= index.records.insert(key);
+                    changes.records.push(record);
+                }
+                None => {
+                    if index.records.remove(&key) {
+                        index_changed = true;
+                        changes.removed_records.push(key);
+                    }
+                }
+            }
+        }
+
+        for (key, record) in self.providers {
+            match record {
+                Some(record) => {
+                    index_changed |= index.providers.insert(key);
+                    changes.providers.push(record);
+                }
+                None => {
+                    if index.providers.remove(&key) {
+                        index_changed = true;
+                        changes.removed_providers.push(key);
+                    }
+                }
+            }
+        }
+
+        if index_changed {
+            changes.index = Some(index.clone());
+        }
+
+        changes
+    }
+}
+
+/// A Kademlia [RecordStore] that holds records in memory (via [MemoryStore])
+/// and, if configured with a [RecordPersistence] backend, writes them through
+/// to it so that they are restored when the node restarts.
+pub struct DHTRecordStore {
+    local_peer_id: PeerId,
+    store: MemoryStore,
+    changes: Option<UnboundedSender<StoreChange>>,
+}
+
+impl DHTRecordStore {
+    pub fn new(local_peer_id: PeerId) -> Self {
+        DHTRecordStore {
+            local_peer_id,
+            store: MemoryStore::new(local_peer_id),
+            changes: None,
+        }
+    }
+
+    fn notify(&self, change: StoreChange) {
+        if let Some(changes) = &self.changes {
+            if changes.send(change).is_err() {
+                warn!("DHT record persistence has stopped; changes will not be saved");
+            }
+        }
+    }
+
+    /// Restore the records saved in the given backend (pruning any that have
+    /// expired since they were saved, or that can no longer be read), and begin
+    /// writing all subsequent changes through to it.
+    pub(crate) async fn restore_from(
+        &mut self,
+        mut persistence: Box<dyn RecordPersistence>,
+    ) -> Result<()> {
+        let persisted = persistence.load_records(&self.local_peer_id).await?;
+        let mut index = PersistedRecordIndex::default();
+        let mut pruned = PersistedChanges::default();
+
+        for persisted_record in persisted.records.into_iter() {
+            match persisted_record.to_record() {
+                Ok(Some(record)) => {
+                    self.store.put(record)?;
+                    index.records.insert(persisted_record.key);
+                }
+                Ok(None) => pruned.removed_records.push(persisted_record.key),
+                Err(error) => {
+                    warn!(
+                        "Discarding a persisted DHT record that could not be restored: {:?}",
+                        error
+                    );
+                    pruned.removed_records.push(persisted_record.key);
+                }
+            }
+        }
+
+        for persisted_provider in persisted.providers.into_iter() {
+            let key = (
+                persisted_provider.key.clone(),
+                persisted_provider.provider.clone(),
+            );
+            match persisted_provider.to_provider_record() {
+                Ok(Some(record)) => {
+                    self.store.add_provider(record)?;
+                    index.providers.insert(key);
+                }
+                Ok(None) => pruned.removed_providers.push(key),
+                Err(error) => {
+                    warn!("Discarding a persisted DHT provider record that could not be restored: {:?}", error);
+                    pruned.removed_providers.push(key);
+                }
+            }
+        }
+
+        if !pruned.removed_records.is_empty() || !pruned.removed_providers.is_empty() {
+            debug!(
+                "Pruned {} expired or unreadable DHT record(s) from storage",
+                pruned.removed_records.len() + pruned.removed_providers.len()
+            );
+            pruned.index = Some(index.clone());
+            persistence
+                .save_changes(&self.local_peer_id, &pruned)
+                .await?;
+        }
+
+        let (sender, receiver) = unbounded_channel();
+
+        self.changes = Some(sender);
+
+        tokio::spawn(persist_changes(
+            self.local_peer_id,
+            persistence,
+            index,
+            receiver,
+        ));
+
+        Ok(())
+    }
+}
+
+/// Saves each batch of changes from a [DHTRecordStore], writing only the
+/// records that changed, until the store is dropped.
+async fn persist_changes(
+    peer_id: PeerId,
+    mut persistence: Box<dyn RecordPersistence>,
+    mut index: PersistedRecordIndex,
+    mut changes: UnboundedReceiver<StoreChange>,
+) {
+    while let Some(change) = changes.recv().await {
+        let mut pending = PendingChanges::default();
+
+        pending.apply(change);
+
+        while let Ok(change) = changes.try_recv() {
+            pending.apply(change);
+        }
+
+        let persisted = pending.into_persisted(&mut index);
+
+        if persisted.is_empty() {
+            continue;
+        }
+
+        if let Err(error) = persistence.save_changes(&peer_id, &persisted).await {
+            error!("Failed to persist DHT records: {:?}", error);
+        }
+    }
+}
+
+impl RecordStore for DHTRecordStore {
+    type RecordsIter<'a> = <MemoryStore as RecordStore>::RecordsIter<'a>;
+    type ProvidedIter<'a> = <MemoryStore as RecordStore>::ProvidedIter<'a>;
+
+    fn get(&self, k: &Key) -> Option<Cow<'_, Record>> {
+        self.store.get(k)
+    }
+
+    fn put(&mut self, r: Record) -> StoreResult<()> {
+        self.store.put(r.clone())?;
+        self.notify(StoreChange::Put(r));
+        Ok(())
+    }
+
+    fn remove(&mut self, k: &Key) {
+        self.store.remove(k);
+        self.notify(StoreChange::Remove(k.clone()));
+    }
+
+    fn records(&self) -> Self::RecordsIter<'_> {
+        self.store.records()
+    }
+
+    fn add_provider(&mut self, record: ProviderRecord) -> StoreResult<()> {
+        self.store.add_provider(record.clone())?;
+        self.notify(StoreChange::AddProvider(record));
+        Ok(())
+    }
+
+    fn providers(&self, key: &Key) -> Vec<ProviderRecord> {
+        self.store.providers(key)
+    }
+
+    fn provided(&self) -> Self::ProvidedIter<'_> {
+        self.store.provided()
+    }
+
+    fn remove_provider(&mut self, k: &Key, p: &PeerId) {
+        self.store.remove_provider(k, p);
+        self.notify(StoreChange::RemoveProvider(k.clone(), *p));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noosphere_storage::{MemoryStorage, SphereDb};
+
+    async fn wait_for_persisted_records<S: Storage>(
+        db: &SphereDb<S>,
+        peer_id: &PeerId,
+        count: usize,
+    ) -> Result<PersistedRecords> {
+        for _ in 0..50 {
+            let persisted = db.load_records(peer_id).await?;
+            if persisted.records.len() == count {
+                return Ok(persisted);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        Err(anyhow::anyhow!("Records were not persisted in time"))
+    }
+
+    #[tokio::test]
+    async fn it_restores_persisted_records_after_a_restart() -> Result<()> {
+        let db = SphereDb::new(&MemoryStorage::default()).await?;
+        let peer_id = PeerId::random();
+
+        {
+            let mut store = DHTRecordStore::new(peer_id);
+            store.restore_from(Box::new(db.clone())).await?;
+
+            store.put(Record::new(
+                Key::from(b"removed".to_vec()),
+                b"record".to_vec(),
+            ))?;
+            store.remove(&Key::from(b"removed".to_vec()));
+
+            let mut record = Record::new(Key::from(b"sphere".to_vec()), b"record".to_vec());
+            record.expires = Some(Instant::now() + Duration::from_secs(60));
+            store.put(record)?;
+            store.put(Record::new(
+                Key::from(b"forever".to_vec()),
+                b"record".to_vec(),
+            ))?;
+
+            wait_for_persisted_records(&db, &peer_id, 2).await?;
+        }
+
+        let mut store = DHTRecordStore::new(peer_id);
+        store.restore_from(Box::new(db.clone())).await?;
+
+        let record = store.get(&Key::from(b"sphere".to_vec())).unwrap();
+        assert_eq!(record.value, b"record".to_vec());
+        assert!(record.expires.unwrap() > Instant::now() + Duration::from_secs(50));
+        assert!(store.get(&Key::from(b"forever".to_vec())).is_some());
+        assert!(store.get(&Key::from(b"removed".to_vec())).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_prunes_expired_records_on_startup() -> Result<()> {
+        let mut db = SphereDb::new(&MemoryStorage::default()).await?;
+        let peer_id = PeerId::random();
+        let now = to_unix_millis(Instant::now());
+
+        let expired = PersistedRecord {
+            key: b"expired".to_vec(),
+            value: b"record".to_vec(),
+            publisher: None,
+            expires_at: Some(now - 1000),
+        };
+        let fresh = PersistedRecord {
+            key: b"fresh".to_vec(),
+            value: b"record".to_vec(),
+            publisher: None,
+            expires_at: Some(now + 60_000),
+        };
+        let expired_provider = PersistedProviderRecord {
+            key: b"expired".to_vec(),
+            provider: PeerId::random().to_bytes(),
+            addresses: vec![],
+            expires_at: Some(now - 1000),
+        };
+
+        db.save_changes(
+            &peer_id,
+            &PersistedChanges {
+                index: Some(PersistedRecordIndex {
+                    records: BTreeSet::from([expired.key.clone(), fresh.key.clone()]),
+                    providers: BTreeSet::from([(
+                        expired_provider.key.clone(),
+                        expired_provider.provider.clone(),
+                    )]),
+                }),
+                records: vec![expired, fresh],
+                providers: vec![expired_provider.clone()],
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let mut store = DHTRecordStore::new(peer_id);
+        store.restore_from(Box::new(db.clone())).await?;
+
+        assert!(store.get(&Key::from(b"expired".to_vec())).is_none());
+        assert!(store.get(&Key::from(b"fresh".to_vec())).is_some());
+        assert!(store.providers(&Key::from(b"expired".to_vec())).is_empty());
+
+        let persisted = db.load_records(&peer_id).await?;
+        assert_eq!(persisted.records.len(), 1);
+        assert_eq!(persisted.records[0].key, b"fresh".to_vec());
+        assert!(persisted.providers.is_empty());
+
+        assert!(db
+            .get_key::<_, PersistedRecord>(record_key(&peer_id, b"expired"))
+            .await?
+            .is_none());
+        assert!(db
+            .get_key::<_, PersistedProviderRecord>(provider_key(
+                &peer_id,
+                &expired_provider.key,
+                &expired_provider.provider
+            ))
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_prunes_unreadable_records_on_startup() -> Result<()> {
+        let mut db = SphereDb::new(&MemoryStorage::default()).await?;
+        let peer_id = PeerId::random();
+
+        let unreadable = PersistedRecord {
+            key: b"unreadable".to_vec(),
+            value: b"record".to_vec(),
+            publisher: Some(b"not a peer id".to_vec()),
+            expires_at: None,
+        };
+        let fresh = PersistedRecord {
+            key: b"fresh".to_vec(),
+            value: b"record".to_vec(),
+            publisher: None,
+            expires_at: None,
+        };
+        let unreadable_provider = PersistedProviderRecord {
+            key: b"unreadable".to_vec(),
+            provider: PeerId::random().to_bytes(),
+            addresses: vec![b"not a multiaddr".to_vec()],
+            expires_at: None,
+        };
+
+        db.save_changes(
+            &peer_id,
+            &PersistedChanges {
+                index: Some(PersistedRecordIndex {
+                    records: BTreeSet::from([unreadable.key.clone(), fresh.key.clone()]),
+                    providers: BTreeSet::from([(
+                        unreadable_provider.key.clone(),
+                        unreadable_provider.provider.clone(),
+                    )]),
+                }),
+                records: vec![unreadable, fresh],
+                providers: vec![unreadable_provider],
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let mut store = DHTRecordStore::new(peer_id);
+        store.restore_from(Box::new(db.clone())).await?;
+
+        assert!(store.get(&Key::from(b"unreadable".to_vec())).is_none());
+        assert!(store.get(&Key::from(b"fresh".to_vec())).is_some());
+        assert!(store
+            .providers(&Key::from(b"unreadable".to_vec()))
+            .is_empty());
+
+        let persisted = db.load_records(&peer_id).await?;
+        assert_eq!(persisted.records.len(), 1);
+        assert_eq!(persisted.records[0].key, b"fresh".to_vec());
+        assert!(persisted.providers.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_persists_each_record_under_its_own_key() -> Result<()> {
+        let db = SphereDb::new(&MemoryStorage::default()).await?;
+        let peer_id = PeerId::random();
+
+        let mut store = DHTRecordStore::new(peer_id);
+        store.restore_from(Box::new(db.clone())).await?;
+
+        store.put(Record::new(Key::from(b"foo".to_vec()), b"one".to_vec()))?;
+        store.put(Record::new(Key::from(b"bar".to_vec()), b"one".to_vec()))?;
+
+        wait_for_persisted_records(&db, &peer_id, 2).await?;
+
+        let index: PersistedRecordIndex = db.require_key(index_key(&peer_id)).await?;
+        assert_eq!(
+            index.records,
+            BTreeSet::from([b"foo".to_vec(), b"bar".to_vec()])
+        );
+
+        store.put(Record::new(Key::from(b"foo".to_vec()), b"two".to_vec()))?;
+        store.remove(&Key::from(b"bar".to_vec()));
+
+        let persisted = wait_for_persisted_records(&db, &peer_id, 1).await?;
+        assert_eq!(persisted.records[0].value, b"two".to_vec());
+
+        assert!(db
+            .get_key::<_, PersistedRecord>(record_key(&peer_id, b"bar"))
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_only_updates_the_index_when_the_set_of_keys_changes() {
+        let mut index = PersistedRecordIndex::default();
+        let record = Record::new(Key::from(b"foo".to_vec()), b"one".to_vec());
+
+        let mut pending = PendingChanges::default();
+        pending.apply(StoreChange::Put(record.clone()));
+        let changes = pending.into_persisted(&mut index);
+        assert_eq!(changes.records.len(), 1);
+        assert!(changes.index.is_some());
+
+        let mut pending = PendingChanges::default();
+        pending.apply(StoreChange::Put(record.clone()));
+        let changes = pending.into_persisted(&mut index);
+        assert_eq!(changes.records.len(), 1);
+        assert!(changes.index.is_none());
+
+        let mut pending = PendingChanges::default();
+        pending.apply(StoreChange::Put(record.clone()));
+        pending.apply(StoreChange::Remove(record.key.clone()));
+        let changes = pending.into_persisted(&mut index);
+        assert!(changes.records.is_empty());
+        assert_eq!(changes.removed_records, vec![b"foo".to_vec()]);
+        assert!(index.records.is_empty());
+    }
+}
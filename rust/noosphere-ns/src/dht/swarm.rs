@@ -1,4 +1,5 @@
 use crate::dht::errors::DHTError;
+use crate::dht::store::DHTRecordStore;
 use crate::dht::DHTConfig;
 use libp2p::{
     core::muxing::StreamMuxerBox,
@@ -7,7 +8,7 @@ use libp2p::{
     dns,
     identify::{Behaviour as Identify, Config as IdentifyConfig, Event as IdentifyEvent},
     identity::Keypair,
-    kad::{self, Kademlia, KademliaConfig, KademliaEvent, KademliaStoreInserts},
+    kad::{Kademlia, KademliaConfig, KademliaEvent, KademliaStoreInserts},
     mplex, noise,
     swarm::{self, ConnectionHandler, IntoConnectionHandler, NetworkBehaviour, SwarmEvent},
     tcp, yamux, PeerId, Swarm, Transport,
@@ -41,7 +42,7 @@ pub type DHTSwarmEvent = SwarmEvent<
 #[behaviour(out_event = "DHTEvent", event_process = false)]
 pub struct DHTBehaviour {
     pub identify: Identify,
-    pub kad: Kademlia<kad::record::store::MemoryStore>,
+    pub kad: Kademlia<DHTRecordStore>,
 }
 
 impl DHTBehaviour {
@@ -70,8 +71,10 @@ impl DHTBehaviour {
                 config.publication_interval.into(),
             )));
 
-            // TODO(#99): Use SphereFS storage
-            let store = kad::record::store::MemoryStore::new(local_peer_id.to_owned());
+            // Records are held in memory; if the node was created with a
+            // [crate::dht::RecordPersistence] backend, the store is restored
+            // from it (and begins writing through to it) once the node starts
+            let store = DHTRecordStore::new(local_peer_id.to_owned());
             Kademlia::with_config(local_peer_id.to_owned(), store, cfg)
         };
 
@@ -0,0 +1,263 @@
+use crate::records::NSRecord;
+use anyhow::Result;
+use async_trait::async_trait;
+use cid::Cid;
+use noosphere_core::{data::Did, view::Sphere};
+use noosphere_storage::{KeyValueStore, SphereDb, Storage};
+
+/// The prefix of the key that the history of records for a sphere identity
+/// is stored under in a [KeyValueStore]
+pub const RECORD_HISTORY_KEY_PREFIX: &str = "ns/history";
+
+/// The maximum number of records retained in the history of a single sphere
+/// identity; the oldest records are dropped first.
+pub const MAX_RECORD_HISTORY: usize = 128;
+
+fn history_key(identity: &str) -> String {
+    format!("{}/{}", RECORD_HISTORY_KEY_PREFIX, identity)
+}
+
+/// The [RecordHistory] tracks the chain of [NSRecord]s that have been
+/// accepted for each sphere identity, and enforces that the chain only
+/// ever moves forward: once a record has been accepted, an older record
+/// for the same identity (for example, a stale token that is re-broadcast
+/// by a peer) will be refused.
+///
+/// A record is considered newer than the currently held record if the
+/// revision it links to descends from the currently held revision, or
+/// otherwise if it was not issued before the currently held record (see
+/// [NSRecord::is_issued_before]).
+#[derive(Clone)]
+pub struct RecordHistory<S: Storage> {
+    store: SphereDb<S>,
+}
+
+impl<S> RecordHistory<S>
+where
+    S: Storage,
+{
+    pub fn new(store: SphereDb<S>) -> Self {
+        RecordHistory { store }
+    }
+
+    /// Attempt to append a record to the history of its identity. Returns
+    /// false if the record is older than the currently held record, in which
+    /// case the history is left unchanged. Appending the currently held record
+    /// again is allowed, and does not change the history.
+    ///
+    /// Note that the record is expected to have been validated already.
+    pub async fn try_append(&mut self, record: &NSRecord) -> Result<bool> {
+        let mut history = self.load(record.identity()).await?;
+
+        if let Some(current) = history.last() {
+            if current.try_to_string()? == record.try_to_string()? {
+                return Ok(true);
+            }
+
+            if !self.supersedes(record, current).await {
+                return Ok(false);
+            }
+        }
+
+        history.push(record.clone());
+
+        if history.len() > MAX_RECORD_HISTORY {
+            history.drain(..history.len() - MAX_RECORD_HISTORY);
+        }
+
+        let encoded = history
+            .iter()
+            .map(|record| record.try_to_string())
+            .collect::<Result<Vec<String>>>()?;
+
+        self.store
+            .set_key(history_key(record.identity()), encoded)
+            .await?;
+        self.store.flush().await?;
+
+        Ok(true)
+    }
+
+    async fn load(&self, identity: &str) -> Result<Vec<NSRecord>> {
+        let encoded: Vec<String> = self
+            .store
+            .get_key(&history_key(identity))
+            .await?
+            .unwrap_or_default();
+
+        encoded
+            .into_iter()
+            .map(NSRecord::try_from)
+            .collect::<Result<Vec<NSRecord>>>()
+    }
+
+    async fn supersedes(&self, record: &NSRecord, current: &NSRecord) -> bool {
+        if let (Some(link), Some(current_link)) = (record.link(), current.link()) {
+            if link != current_link {
+                if self.descends_from(link, current_link).await {
+                    return true;
+                }
+
+                if self.descends_from(current_link, link).await {
+                    return false;
+                }
+            }
+        }
+
+        // Records issued within the same second cannot be told apart by
+        // their issuance time, so they are both accepted
+        !record.is_issued_before(current)
+    }
+
+    /// Walks the ancestry of the revision as far as it is available locally,
+    /// looking for the given ancestor revision.
+    async fn descends_from(&self, revision: &Cid, ancestor: &Cid) -> bool {
+        let mut sphere = Sphere::at(revision, &self.store);
+
+        loop {
+            match sphere.try_get_parent().await {
+                Ok(Some(parent)) if parent.cid() == ancestor => return true,
+                Ok(Some(parent)) => sphere = parent,
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// Read access to the history of accepted records, independent of the
+/// [Storage] that backs it.
+#[async_trait]
+pub trait RecordHistoryReader: Send + Sync {
+    /// Returns the records that have been accepted for the identity, ordered
+    /// from oldest to newest.
+    async fn get(&self, identity: &Did) -> Result<Vec<NSRecord>>;
+
+    /// Returns the most recently accepted record for the identity, if any.
+    async fn latest(&self, identity: &Did) -> Result<Option<NSRecord>>;
+}
+
+#[async_trait]
+impl<S> RecordHistoryReader for RecordHistory<S>
+where
+    S: Storage,
+{
+    async fn get(&self, identity: &Did) -> Result<Vec<NSRecord>> {
+        self.load(identity.as_str()).await
+    }
+
+    async fn latest(&self, identity: &Did) -> Result<Option<NSRecord>> {
+        Ok(self.load(identity.as_str()).await?.pop())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libipld_cbor::DagCborCodec;
+    use noosphere_core::authority::generate_ed25519_key;
+    use noosphere_storage::{derive_cid, MemoryStorage};
+    use ucan::{builder::UcanBuilder, crypto::KeyMaterial};
+
+    use crate::utils::{generate_capability, generate_fact};
+
+    async fn make_record<K: KeyMaterial>(
+        key: &K,
+        sphere_id: &Did,
+        link: &Cid,
+        issued_at: Option<u64>,
+        expiration: u64,
+    ) -> Result<NSRecord> {
+        let mut builder = UcanBuilder::default()
+            .issued_by(key)
+            .for_audience(sphere_id)
+            .with_expiration(expiration)
+            .claiming_capability(&generate_capability(sphere_id))
+            .with_fact(generate_fact(&link.to_string()));
+
+        if let Some(issued_at) = issued_at {
+            builder = builder.not_before(issued_at);
+        }
+
+        Ok(builder.build()?.sign().await?.into())
+    }
+
+    #[tokio::test]
+    async fn it_refuses_records_older_than_the_current_record() -> Result<()> {
+        let sphere_key = generate_ed25519_key();
+        let sphere_id = Did::from(sphere_key.get_did().await?);
+        let store = SphereDb::new(&MemoryStorage::default()).await?;
+        let mut history = RecordHistory::new(store);
+
+        let now = ucan::time::now();
+        let older = make_record(
+            &sphere_key,
+            &sphere_id,
+            &derive_cid::<DagCborCodec>(b"00000000"),
+            None,
+            now + 1000,
+        )
+        .await?;
+        let newer = make_record(
+            &sphere_key,
+            &sphere_id,
+            &derive_cid::<DagCborCodec>(b"11111111"),
+            None,
+            now + 2000,
+        )
+        .await?;
+
+        assert!(history.try_append(&older).await?);
+        assert!(history.try_append(&newer).await?);
+        assert!(!history.try_append(&older).await?);
+        assert!(history.try_append(&newer).await?);
+
+        let records = history.get(&sphere_id).await?;
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].link(), older.link());
+        assert_eq!(records[1].link(), newer.link());
+        assert_eq!(
+            history.latest(&sphere_id).await?.unwrap().link(),
+            newer.link()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_accepts_a_newer_record_with_a_shorter_lifetime() -> Result<()> {
+        let sphere_key = generate_ed25519_key();
+        let sphere_id = Did::from(sphere_key.get_did().await?);
+        let store = SphereDb::new(&MemoryStorage::default()).await?;
+        let mut history = RecordHistory::new(store);
+
+        let now = ucan::time::now();
+        let older = make_record(
+            &sphere_key,
+            &sphere_id,
+            &derive_cid::<DagCborCodec>(b"00000000"),
+            Some(now - 10),
+            now + 100000,
+        )
+        .await?;
+        let newer = make_record(
+            &sphere_key,
+            &sphere_id,
+            &derive_cid::<DagCborCodec>(b"11111111"),
+            Some(now),
+            now + 1000,
+        )
+        .await?;
+
+        assert!(history.try_append(&older).await?);
+        assert!(history.try_append(&newer).await?);
+        assert!(!history.try_append(&older).await?);
+
+        assert_eq!(
+            history.latest(&sphere_id).await?.unwrap().link(),
+            newer.link()
+        );
+
+        Ok(())
+    }
+}
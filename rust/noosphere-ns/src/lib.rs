@@ -9,8 +9,10 @@ extern crate lazy_static;
 pub mod builder;
 mod client;
 pub mod dht;
+mod history;
 mod name_system;
 mod records;
+mod resolver;
 pub mod utils;
 mod validator;
 
@@ -20,7 +22,9 @@ pub mod server;
 pub use builder::NameSystemBuilder;
 pub use client::NameSystemClient;
 pub use dht::{DHTConfig, DHTKeyMaterial, NetworkInfo, Peer};
+pub use history::{RecordHistory, RecordHistoryReader};
 pub use libp2p::{multiaddr::Multiaddr, PeerId};
 pub use name_system::{NameSystem, BOOTSTRAP_PEERS};
 pub use records::NSRecord;
+pub use resolver::{resolve_petnames, PetnameResolution, ResolvedPetname};
 pub use validator::Validator;
@@ -1,6 +1,7 @@
 use crate::{
     client::NameSystemClient,
     dht::{DHTConfig, DHTError, DHTKeyMaterial, DHTNode, DHTRecord, NetworkInfo, Peer},
+    history::{RecordHistory, RecordHistoryReader},
     records::NSRecord,
     utils::make_p2p_address,
     validator::Validator,
@@ -44,6 +45,8 @@ pub struct NameSystem {
     hosted_records: Mutex<HashMap<Did, NSRecord>>,
     /// Map of resolved sphere DIDs to resolved [NSRecord].
     resolved_records: Mutex<HashMap<Did, NSRecord>>,
+    /// The chain of [NSRecord]s accepted for each sphere DID.
+    history: Box<dyn RecordHistoryReader>,
 
     #[cfg(feature = "api_server")]
     api_server: Option<APIServer>,
@@ -55,10 +58,18 @@ impl NameSystem {
         store: SphereDb<S>,
         dht_config: DHTConfig,
     ) -> Result<Self> {
+        let history = Box::new(RecordHistory::new(store.clone()));
+
         Ok(NameSystem {
-            dht: DHTNode::new(key_material, dht_config, Some(Validator::new(store)))?,
+            dht: DHTNode::with_persistence(
+                key_material,
+                dht_config,
+                Some(Validator::new(store.clone())),
+                store,
+            )?,
             hosted_records: Mutex::new(HashMap::new()),
             resolved_records: Mutex::new(HashMap::new()),
+            history,
         })
     }
 
@@ -244,9 +255,25 @@ impl NameSystemClient for NameSystem {
                 resolved_records.insert(identity.to_owned(), record.clone());
                 Ok(Some(record))
             }
-            (_, None) => Ok(None),
+            // A record may not have been found because the only records
+            // available on the network are older than one this node has
+            // already accepted; fall back to the latest accepted record.
+            (_, None) => match self.history.latest(identity).await? {
+                Some(record) if !record.is_expired() => {
+                    let mut resolved_records = self.resolved_records.lock().await;
+                    resolved_records.insert(identity.to_owned(), record.clone());
+                    Ok(Some(record))
+                }
+                _ => Ok(None),
+            },
         }
     }
+
+    /// Returns the chain of [NSRecord]s that this node has accepted for the
+    /// provided identity, ordered from oldest to newest.
+    async fn get_record_history(&self, identity: &Did) -> Result<Vec<NSRecord>> {
+        self.history.get(identity).await
+    }
 }
 
 #[cfg(test)]
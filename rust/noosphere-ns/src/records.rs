@@ -39,6 +39,9 @@ use ucan::{chain::ProofChain, crypto::did::DidParser, Ucan};
 ///     "with": "sphere:did:key:z6MkkVfktAC5rVNRmmTjkKPapT3bAyVkYH8ZVCF1UBNUfazp",
 ///     "can": "sphere/publish"
 ///   }],
+///   // The time (in seconds since the Unix epoch) that the record was
+///   // issued at, which is used to order records for the same sphere.
+///   "nbf": 1674000000,
 ///   // Additional UCAN proofs needed to validate.
 ///   "prf": [],
 ///   // Facts contain a single entry with an "link" field containing
@@ -78,7 +81,9 @@ impl NSRecord {
         Self { token, link }
     }
 
-    /// Creates and signs a new NSRecord from an issuer key.
+    /// Creates and signs a new NSRecord from an issuer key. The record is not
+    /// valid before the time it was issued at, so that it can be ordered
+    /// against other records for the same sphere.
     ///
     /// ```
     /// use noosphere_ns::NSRecord;
@@ -111,6 +116,7 @@ impl NSRecord {
             .issued_by(issuer)
             .for_audience(sphere_id)
             .with_lifetime(SPHERE_LIFETIME)
+            .not_before(ucan::time::now())
             .claiming_capability(&capability)
             .with_fact(fact);
 
@@ -181,6 +187,26 @@ impl NSRecord {
         self.token.is_expired()
     }
 
+    /// The time (in seconds since the Unix epoch) that this record was issued
+    /// at, if known. Records created with [NSRecord::from_issuer] carry it as
+    /// the "not before" time of their token.
+    pub fn issued_at(&self) -> Option<u64> {
+        *self.token.not_before()
+    }
+
+    /// Returns true if this record was issued before the other record.
+    ///
+    /// The times of issuance are compared if both records have one. Records
+    /// issued without one (e.g., by older publishers) are ordered by their
+    /// expiration time instead, which only holds when both records were
+    /// issued with the same lifetime.
+    pub fn is_issued_before(&self, other: &NSRecord) -> bool {
+        match (self.issued_at(), other.issued_at()) {
+            (Some(issued_at), Some(other_issued_at)) => issued_at < other_issued_at,
+            _ => self.token.expires_at() < other.token.expires_at(),
+        }
+    }
+
     /// Encodes the underlying Ucan token back into a JWT string.
     pub fn try_to_string(&self) -> Result<String, AnyhowError> {
         self.token.encode()
@@ -333,6 +359,7 @@ mod test {
 
         assert_eq!(&Did::from(record.identity()), &sphere_identity);
         assert_eq!(record.link(), Some(&cid_link));
+        assert!(record.issued_at().is_some());
         record
             .validate(&store, &mut DidParser::new(SUPPORTED_KEYS))
             .await?;
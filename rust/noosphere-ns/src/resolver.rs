@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use noosphere_core::data::{AddressIpld, Did, Jwt};
+use noosphere_storage::{SphereDb, Storage};
+use ucan::crypto::did::DidParser;
+
+use crate::{NSRecord, NameSystemClient};
+
+/// The outcome of resolving the identity that a petname refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PetnameResolution {
+    /// A valid record was found that is newer than the last known record; it
+    /// should be stored as the petname's last known record
+    Updated(Jwt),
+    /// The record that was found is the same as the last known record
+    Unchanged,
+    /// A valid record was found, but it was issued before the last known
+    /// record (for example, an old record that has been replayed); the last
+    /// known record should be kept
+    Outdated,
+    /// No record could be found for the identity
+    NotFound,
+    /// A record was found, but it did not pass validation
+    Invalid(String),
+}
+
+/// A petname from a sphere's address book, along with the outcome of
+/// resolving the identity it refers to
+#[derive(Debug, Clone)]
+pub struct ResolvedPetname {
+    pub petname: String,
+    pub identity: Did,
+    pub resolution: PetnameResolution,
+}
+
+/// Look up the identity behind each of the given petnames through the name
+/// system, validating any records that are found. Validation requires that
+/// the proofs of each record are available in the given [SphereDb].
+///
+/// Errors talking to the name system are reported as a [ResolvedPetname] with
+/// a resolution of [PetnameResolution::NotFound], so that one unreachable
+/// identity does not prevent the rest from being resolved.
+pub async fn resolve_petnames<C, S>(
+    client: &C,
+    petnames: &BTreeMap<String, AddressIpld>,
+    db: &SphereDb<S>,
+    did_parser: &mut DidParser,
+) -> Result<Vec<ResolvedPetname>>
+where
+    C: NameSystemClient + ?Sized,
+    S: Storage,
+{
+    let mut resolved = Vec::new();
+
+    for (petname, address) in petnames {
+        let record = match client.get_record(&address.identity).await {
+            Ok(record) => record,
+            Err(error) => {
+                warn!("Failed to resolve {}: {:?}", address.identity, error);
+                None
+            }
+        };
+
+        let resolution = match record {
+            None => PetnameResolution::NotFound,
+            Some(record) if record.identity() != address.identity.as_str() => {
+                PetnameResolution::Invalid(format!(
+                    "Record is for {} rather than {}",
+                    record.identity(),
+                    address.identity
+                ))
+            }
+            Some(record) => match record.validate(db, did_parser).await {
+                Ok(_) => {
+                    let jwt = Jwt(record.try_to_string()?);
+
+                    match &address.last_known_record {
+                        Some(last_known_record) if last_known_record == &jwt => {
+                            PetnameResolution::Unchanged
+                        }
+                        Some(last_known_record) if is_newer(last_known_record, &record) => {
+                            PetnameResolution::Outdated
+                        }
+                        _ => PetnameResolution::Updated(jwt),
+                    }
+                }
+                Err(error) => PetnameResolution::Invalid(error.to_string()),
+            },
+        };
+
+        resolved.push(ResolvedPetname {
+            petname: petname.clone(),
+            identity: address.identity.clone(),
+            resolution,
+        });
+    }
+
+    Ok(resolved)
+}
+
+/// Returns true if the last known record was issued after the given record. A
+/// last known record that cannot be parsed is never considered newer, so that
+/// it may be replaced by a valid record.
+fn is_newer(last_known_record: &Jwt, record: &NSRecord) -> bool {
+    match NSRecord::try_from(last_known_record.as_str()) {
+        Ok(last_known_record) => record.is_issued_before(&last_known_record),
+        Err(error) => {
+            warn!("Could not parse last known record: {:?}", error);
+            false
+        }
+    }
+}
@@ -105,6 +105,17 @@ impl NameSystemClient for HTTPClient {
         Ok(self.client.get(url).send().await?.json().await?)
     }
 
+    /// Returns the chain of [NSRecord]s that have been seen for the provided
+    /// identity, ordered from oldest to newest.
+    async fn get_record_history(&self, identity: &Did) -> Result<Vec<NSRecord>> {
+        let mut url = self.api_base.clone();
+        let path = Route::GetRecordHistory
+            .to_string()
+            .replace(":identity", identity.into());
+        url.set_path(&path);
+        Ok(self.client.get(url).send().await?.json().await?)
+    }
+
     /// Propagates the corresponding managed sphere's [NSRecord] on nearby peers
     /// in the DHT network.
     async fn put_record(&self, record: NSRecord) -> Result<()> {
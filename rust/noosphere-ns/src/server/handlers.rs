@@ -104,6 +104,18 @@ pub async fn get_record(
     Ok(Json(record))
 }
 
+pub async fn get_record_history(
+    Extension(name_system): Extension<Arc<Mutex<NameSystem>>>,
+    Path(did): Path<Did>,
+) -> JsonResponse<Vec<NSRecord>> {
+    let ns = name_system.lock().await;
+    let history = ns
+        .get_record_history(&did)
+        .await
+        .map_err(move |error| JsonErr(StatusCode::INTERNAL_SERVER_ERROR, error.to_string()))?;
+    Ok(Json(history))
+}
+
 pub async fn post_record(
     Extension(name_system): Extension<Arc<Mutex<NameSystem>>>,
     Json(record): Json<NSRecord>,
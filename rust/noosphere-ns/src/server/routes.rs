@@ -14,6 +14,7 @@ pub enum Route {
     Address,
 
     GetRecord,
+    GetRecordHistory,
     PostRecord,
 
     Bootstrap,
@@ -33,6 +34,7 @@ impl Display for Route {
             Route::Address => "addresses",
 
             Route::GetRecord => "records/:identity",
+            Route::GetRecordHistory => "records/:identity/history",
             Route::PostRecord => "records",
 
             Route::Bootstrap => "bootstrap",
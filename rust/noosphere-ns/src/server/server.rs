@@ -30,6 +30,10 @@ impl APIServer {
             )
             .route(&Route::Address.to_string(), get(handlers::get_address))
             .route(&Route::GetRecord.to_string(), get(handlers::get_record))
+            .route(
+                &Route::GetRecordHistory.to_string(),
+                get(handlers::get_record_history),
+            )
             .route(&Route::PostRecord.to_string(), post(handlers::post_record))
             .route(&Route::Bootstrap.to_string(), post(handlers::bootstrap))
             .layer(Extension(ns))
@@ -36,6 +36,7 @@ pub fn generate_capability(identity: &str) -> Capability<SphereReference, Sphere
         with: With::Resource {
             kind: Resource::Scoped(SphereReference {
                 did: identity.to_owned(),
+                path: None,
             }),
         },
         can: SphereAction::Publish,
@@ -1,12 +1,18 @@
 use crate::dht::RecordValidator;
+use crate::history::RecordHistory;
 use crate::records::NSRecord;
 use async_trait::async_trait;
 use noosphere_core::authority::SUPPORTED_KEYS;
 use noosphere_storage::{SphereDb, Storage};
 use ucan::crypto::did::DidParser;
 
+/// Validates [NSRecord]s that pass through the DHT: a record must be
+/// authorized to publish its sphere, and must not be older than the record
+/// currently held for that sphere (see [RecordHistory]). Records that pass
+/// validation are appended to the history.
 pub struct Validator<S: Storage> {
     store: SphereDb<S>,
+    history: RecordHistory<S>,
     did_parser: DidParser,
 }
 
@@ -16,6 +22,7 @@ where
 {
     pub fn new(store: SphereDb<S>) -> Self {
         Validator {
+            history: RecordHistory::new(store.clone()),
             store,
             did_parser: DidParser::new(SUPPORTED_KEYS),
         }
@@ -29,10 +36,29 @@ where
 {
     async fn validate(&mut self, record_value: &[u8]) -> bool {
         if let Ok(record) = NSRecord::try_from(record_value) {
-            return record
+            if record
                 .validate(&self.store, &mut self.did_parser)
                 .await
-                .is_ok();
+                .is_err()
+            {
+                return false;
+            }
+
+            return match self.history.try_append(&record).await {
+                Ok(is_current) => {
+                    if !is_current {
+                        warn!(
+                            "Refusing record for {} that is older than the current record",
+                            record.identity()
+                        );
+                    }
+                    is_current
+                }
+                Err(error) => {
+                    warn!("Could not update record history: {:?}", error);
+                    false
+                }
+            };
         }
         return false;
     }
@@ -2,19 +2,30 @@
 #![cfg(test)]
 pub mod utils;
 use anyhow::Result;
-use noosphere_core::{authority::generate_ed25519_key, data::Did, view::SPHERE_LIFETIME};
+use noosphere_core::{
+    authority::{generate_ed25519_key, SUPPORTED_KEYS},
+    data::{AddressIpld, Did, Jwt},
+    view::SPHERE_LIFETIME,
+};
 use noosphere_ns::{
+    resolve_petnames,
     utils::{generate_capability, generate_fact, wait_for_peers},
-    DHTConfig, Multiaddr, NSRecord, NameSystem, NameSystemClient,
+    DHTConfig, Multiaddr, NSRecord, NameSystem, NameSystemClient, PetnameResolution,
 };
 use noosphere_storage::{derive_cid, MemoryStorage, SphereDb};
 use utils::generate_default_listening_address;
 
 use futures::future::try_join_all;
 use libipld_cbor::DagCborCodec;
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 use tokio::sync::Mutex;
-use ucan::{builder::UcanBuilder, crypto::KeyMaterial, store::UcanJwtStore, time::now, Ucan};
+use ucan::{
+    builder::UcanBuilder,
+    crypto::{did::DidParser, KeyMaterial},
+    store::UcanJwtStore,
+    time::now,
+    Ucan,
+};
 use ucan_key_support::ed25519::Ed25519KeyMaterial;
 
 /// Data related to an owner sphere and a NameSystem running
@@ -294,3 +305,80 @@ async fn it_is_thread_safe() -> Result<()> {
 
     Ok(())
 }
+
+#[test_log::test(tokio::test)]
+async fn it_resolves_petnames_to_validated_records() -> Result<()> {
+    let (_bootstrap_node, store, mut ns_data) = generate_name_systems_network(2).await?;
+    let [ns_1, ns_2] = [ns_data.remove(0), ns_data.remove(0)];
+    let address = derive_cid::<DagCborCodec>(b"00000000");
+
+    let record: NSRecord = UcanBuilder::default()
+        .issued_by(&ns_1.owner_key)
+        .for_audience(&ns_1.sphere_id)
+        .with_lifetime(SPHERE_LIFETIME - 1000)
+        .claiming_capability(&generate_capability(&ns_1.sphere_id))
+        .with_fact(generate_fact(&address.to_string()))
+        .witnessed_by(&ns_1.delegation)
+        .build()?
+        .sign()
+        .await?
+        .into();
+
+    ns_1.ns.put_record(record.clone()).await?;
+
+    let mut petnames = BTreeMap::new();
+    petnames.insert(
+        "alice".to_string(),
+        AddressIpld {
+            identity: ns_1.sphere_id.clone(),
+            last_known_record: None,
+        },
+    );
+    petnames.insert(
+        "bob".to_string(),
+        AddressIpld {
+            identity: Did::from("did:key:unknown"),
+            last_known_record: None,
+        },
+    );
+
+    let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+    let resolved = resolve_petnames(&ns_2.ns, &petnames, &store, &mut did_parser).await?;
+
+    assert_eq!(resolved.len(), 2);
+    assert_eq!(resolved[0].petname, "alice");
+    assert_eq!(
+        resolved[0].resolution,
+        PetnameResolution::Updated(Jwt(record.try_to_string()?))
+    );
+    assert_eq!(resolved[1].petname, "bob");
+    assert_eq!(resolved[1].resolution, PetnameResolution::NotFound);
+
+    petnames.get_mut("alice").unwrap().last_known_record = Some(Jwt(record.try_to_string()?));
+
+    let resolved = resolve_petnames(&ns_2.ns, &petnames, &store, &mut did_parser).await?;
+
+    assert_eq!(resolved[0].resolution, PetnameResolution::Unchanged);
+
+    // A record that differs from the last known record, but was issued before
+    // it, must not replace it
+    let newer_record: NSRecord = UcanBuilder::default()
+        .issued_by(&ns_1.owner_key)
+        .for_audience(&ns_1.sphere_id)
+        .with_lifetime(SPHERE_LIFETIME)
+        .claiming_capability(&generate_capability(&ns_1.sphere_id))
+        .with_fact(generate_fact(&address.to_string()))
+        .witnessed_by(&ns_1.delegation)
+        .build()?
+        .sign()
+        .await?
+        .into();
+
+    petnames.get_mut("alice").unwrap().last_known_record = Some(Jwt(newer_record.try_to_string()?));
+
+    let resolved = resolve_petnames(&ns_2.ns, &petnames, &store, &mut did_parser).await?;
+
+    assert_eq!(resolved[0].resolution, PetnameResolution::Outdated);
+
+    Ok(())
+}
@@ -210,15 +210,17 @@ where
     }
 }
 
-#[cfg(all(target_arch = "wasm32", feature = "gateway-storage"))]
-use crate::{KuboStorage, KuboStore};
-#[cfg(all(target_arch = "wasm32", feature = "gateway-storage"))]
+#[cfg(feature = "kubo-storage")]
+use crate::{KuboStorage, KuboStorageConditionalSendSync, KuboStore};
+#[cfg(feature = "kubo-storage")]
 use url::Url;
 
-#[cfg(all(target_arch = "wasm32", feature = "gateway-storage"))]
+/// Wrap the block storage of a [SphereDb] so that blocks that are missing
+/// locally are fetched from the given IPFS API (see [KuboStore])
+#[cfg(feature = "kubo-storage")]
 impl<S> From<(SphereDb<S>, &Url)> for SphereDb<KuboStorage<S>>
 where
-    S: Storage,
+    S: Storage + KuboStorageConditionalSendSync,
 {
     fn from((db, ipfs_api): (SphereDb<S>, &Url)) -> Self {
         SphereDb {
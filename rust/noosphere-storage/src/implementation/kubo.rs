@@ -11,6 +11,7 @@ use url::Url;
 #[cfg(doc)]
 use crate::KeyValueStore;
 
+use crate::verify_block;
 use crate::BlockStore;
 use crate::Storage;
 
@@ -174,6 +175,11 @@ where
                     match response.status() {
                         StatusCode::OK => {
                             let bytes = response.bytes().await?;
+
+                            // The gateway is not trusted to send us the block
+                            // we asked for, so check it before storing it
+                            verify_block(cid, &bytes)?;
+
                             let mut local_store = self.local_store.write().await;
                             local_store.put_block(cid, &bytes).await?;
 
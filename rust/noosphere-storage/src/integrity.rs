@@ -0,0 +1,151 @@
+use std::fmt;
+
+use cid::{
+    multihash::{Code, MultihashDigest},
+    Cid,
+};
+use libipld_cbor::DagCborCodec;
+use libipld_core::raw::RawCodec;
+
+/// The reason that a single block failed verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockIntegrityFailure {
+    /// The block bytes do not hash to the digest in the block's [Cid]
+    DigestMismatch,
+    /// The [Cid] uses a hash function that we cannot verify
+    UnsupportedHash(u64),
+    /// The [Cid] claims a codec that we do not know how to handle
+    UnsupportedCodec(u64),
+}
+
+impl fmt::Display for BlockIntegrityFailure {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockIntegrityFailure::DigestMismatch => write!(fmt, "digest mismatch"),
+            BlockIntegrityFailure::UnsupportedHash(code) => {
+                write!(fmt, "unsupported hash function 0x{:x}", code)
+            }
+            BlockIntegrityFailure::UnsupportedCodec(codec) => {
+                write!(fmt, "unsupported codec 0x{:x}", codec)
+            }
+        }
+    }
+}
+
+/// An error that is produced when one or more blocks received from elsewhere
+/// cannot be trusted to be the blocks that their [Cid]s claim them to be.
+/// Blocks that fail verification must never be written to local storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockIntegrityError {
+    /// Every offending [Cid], paired with the reason it failed verification
+    pub failures: Vec<(Cid, BlockIntegrityFailure)>,
+}
+
+impl BlockIntegrityError {
+    /// The [Cid]s of all the blocks that failed verification
+    pub fn cids(&self) -> Vec<&Cid> {
+        self.failures.iter().map(|(cid, _)| cid).collect()
+    }
+}
+
+impl std::error::Error for BlockIntegrityError {}
+impl fmt::Display for BlockIntegrityError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{} block(s) failed verification:", self.failures.len())?;
+
+        for (cid, failure) in self.failures.iter() {
+            write!(fmt, " {} ({});", cid, failure)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<(Cid, BlockIntegrityFailure)> for BlockIntegrityError {
+    fn from(failure: (Cid, BlockIntegrityFailure)) -> Self {
+        BlockIntegrityError {
+            failures: vec![failure],
+        }
+    }
+}
+
+/// Verify that a block may be stored under the given [Cid]: its codec must be
+/// one that we support, and its bytes must hash to the digest in the [Cid].
+pub fn verify_block(cid: &Cid, block: &[u8]) -> Result<(), BlockIntegrityError> {
+    check_block(cid, block).map_err(|failure| (*cid, failure).into())
+}
+
+/// Verify a batch of blocks, reporting every block that fails verification
+/// rather than only the first one.
+pub fn verify_blocks<'a, I>(blocks: I) -> Result<(), BlockIntegrityError>
+where
+    I: IntoIterator<Item = (&'a Cid, &'a [u8])>,
+{
+    let failures: Vec<(Cid, BlockIntegrityFailure)> = blocks
+        .into_iter()
+        .filter_map(|(cid, block)| check_block(cid, block).err().map(|failure| (*cid, failure)))
+        .collect();
+
+    match failures.is_empty() {
+        true => Ok(()),
+        false => Err(BlockIntegrityError { failures }),
+    }
+}
+
+fn check_block(cid: &Cid, block: &[u8]) -> Result<(), BlockIntegrityFailure> {
+    let codec = cid.codec();
+
+    if codec != u64::from(DagCborCodec) && codec != u64::from(RawCodec) {
+        return Err(BlockIntegrityFailure::UnsupportedCodec(codec));
+    }
+
+    let hash_code = cid.hash().code();
+    let code =
+        Code::try_from(hash_code).map_err(|_| BlockIntegrityFailure::UnsupportedHash(hash_code))?;
+
+    if code.digest(block).digest() != cid.hash().digest() {
+        return Err(BlockIntegrityFailure::DigestMismatch);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+    use libipld_cbor::DagCborCodec;
+    use libipld_core::raw::RawCodec;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    use crate::{derive_cid, verify_block, verify_blocks, BlockIntegrityFailure};
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    async fn it_names_every_block_that_fails_verification() {
+        let foo_cid = derive_cid::<RawCodec>(b"foo");
+        let bar_cid = derive_cid::<DagCborCodec>(b"bar");
+        let baz_cid = Cid::new_v1(0x0129, *derive_cid::<RawCodec>(b"baz").hash());
+
+        assert!(verify_block(&foo_cid, b"foo").is_ok());
+
+        let error = verify_blocks([
+            (&foo_cid, &b"foo"[..]),
+            (&bar_cid, &b"not bar"[..]),
+            (&baz_cid, &b"baz"[..]),
+        ])
+        .unwrap_err();
+
+        assert_eq!(
+            error.failures,
+            vec![
+                (bar_cid, BlockIntegrityFailure::DigestMismatch),
+                (baz_cid, BlockIntegrityFailure::UnsupportedCodec(0x0129)),
+            ]
+        );
+    }
+}
@@ -11,6 +11,7 @@ mod key_value;
 
 mod db;
 mod encoding;
+mod integrity;
 mod storage;
 mod store;
 mod ucan;
@@ -20,6 +21,7 @@ pub use block::*;
 pub use db::*;
 pub use encoding::*;
 pub use implementation::*;
+pub use integrity::*;
 pub use key_value::*;
 pub use storage::*;
 pub use store::*;
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 use anyhow::Result;
 use cid::Cid;
@@ -6,8 +6,8 @@ use noosphere_api::client::Client;
 
 use noosphere_core::{
     authority::{Author, SUPPORTED_KEYS},
-    data::Did,
-    view::Sphere,
+    data::{AddressIpld, Did, Jwt},
+    view::{ResolvedConflict, Sphere},
 };
 use noosphere_fs::SphereFs;
 use noosphere_storage::{KeyValueStore, SphereDb, Storage};
@@ -17,7 +17,7 @@ use url::Url;
 
 use crate::error::NoosphereError;
 
-use super::{metadata::GATEWAY_URL, GatewaySyncStrategy};
+use super::{metadata::GATEWAY_URL, ConflictResolver, GatewaySyncStrategy};
 
 /// A [SphereContext] is an accessor construct over locally replicated sphere
 /// data. It embodies both the storage layer that contains the sphere's data
@@ -127,6 +127,53 @@ where
         ))
     }
 
+    /// Assign a petname to the sphere with the given identity, saving the
+    /// change as a new revision of the sphere
+    pub async fn set_petname(&mut self, name: &str, identity: &Did) -> Result<Cid> {
+        let mut fs = self.fs().await?;
+
+        fs.set_petname(name, identity).await?;
+        fs.save(None).await
+    }
+
+    /// Remove a petname from the sphere's address book, saving the change as a
+    /// new revision of the sphere; returns the identity the petname used to
+    /// refer to, or `None` (and saves nothing) if it was not assigned
+    pub async fn unset_petname(&mut self, name: &str) -> Result<Option<Did>> {
+        let mut fs = self.fs().await?;
+
+        let identity = fs.unset_petname(name).await?;
+
+        if identity.is_some() {
+            fs.save(None).await?;
+        }
+
+        Ok(identity)
+    }
+
+    /// Get every petname in the sphere's address book, along with the address
+    /// that it is assigned to
+    pub async fn list_petnames(&self) -> Result<BTreeMap<String, AddressIpld>> {
+        self.fs().await?.list_petnames().await
+    }
+
+    /// Record the latest (validated) name system records that were resolved
+    /// for the given petnames, saving the change as a new revision of the
+    /// sphere; nothing is saved if no records are given
+    pub async fn set_petname_records(&mut self, records: &[(String, Jwt)]) -> Result<Option<Cid>> {
+        if records.is_empty() {
+            return Ok(None);
+        }
+
+        let mut fs = self.fs().await?;
+
+        for (name, record) in records {
+            fs.set_petname_record(name, record).await?;
+        }
+
+        Ok(Some(fs.save(None).await?))
+    }
+
     /// Get a [Client] that will interact with a configured gateway (if a URL
     /// for one has been configured). This will initialize a [Client] if one is
     /// not already intialized, and will fail if the [Client] is unable to
@@ -158,11 +205,30 @@ where
     /// fetched to local storage. Then, the local changes will be replayed on
     /// top of those changes. Finally, the synchronized local history will be
     /// pushed up to the gateway.
-    pub async fn sync(&mut self) -> Result<()> {
+    pub async fn sync(&mut self) -> Result<()>
+    where
+        S: 'static,
+    {
         let sync_strategy = GatewaySyncStrategy::default();
         sync_strategy.sync(self).await?;
         Ok(())
     }
+
+    /// Same as [SphereContext::sync], except that local changes are merged
+    /// with the changes fetched from the gateway rather than replayed over
+    /// them. Slugs that were changed both locally and elsewhere are resolved
+    /// by the given [ConflictResolver], and a report of each conflict and its
+    /// resolution is returned.
+    pub async fn sync_with_conflict_resolver(
+        &mut self,
+        conflict_resolver: ConflictResolver,
+    ) -> Result<Vec<ResolvedConflict>>
+    where
+        S: 'static,
+    {
+        let sync_strategy = GatewaySyncStrategy::with_conflict_resolver(conflict_resolver);
+        sync_strategy.sync(self).await
+    }
 }
 
 #[cfg(all(target_arch = "wasm32", feature = "gateway-storage"))]
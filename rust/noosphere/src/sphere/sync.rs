@@ -1,9 +1,12 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Arc};
 
 use anyhow::{anyhow, Result};
 use cid::Cid;
-use noosphere_api::data::{FetchParameters, FetchResponse, PushBody, PushResponse};
-use noosphere_core::{data::Did, view::Sphere};
+use noosphere_api::data::{FetchParameters, FetchResponseHeader, PushBodyHeader, PushResponse};
+use noosphere_core::{
+    data::Did,
+    view::{ConflictResolution, LinkConflict, ResolvedConflict, Sphere},
+};
 use noosphere_storage::{SphereDb, Storage};
 use ucan::crypto::KeyMaterial;
 
@@ -18,15 +21,27 @@ use super::SphereContext;
 /// on the counterpart sphere's reckoning of the authoritative lineage of the
 /// user's sphere. Finally, after the rebase, the reconciled local lineage is
 /// pushed to the gateway.
+///
+/// By default, the rebase is "conflict-free": local changes are replayed on top
+/// of the counterpart's lineage, so if both lineages changed the same slug the
+/// local change silently wins. A strategy created with
+/// [GatewaySyncStrategy::with_conflict_resolver] instead performs a three-way
+/// merge against the common ancestor of the two lineages, and defers to the
+/// given [ConflictResolver] for each slug that both lineages changed.
 pub struct GatewaySyncStrategy<K, S>
 where
     K: KeyMaterial + Clone + 'static,
     S: Storage,
 {
+    conflict_resolver: Option<ConflictResolver>,
     key_type: PhantomData<K>,
     store_type: PhantomData<S>,
 }
 
+/// A callback that decides how a [LinkConflict] that is encountered while
+/// syncing should be resolved
+pub type ConflictResolver = Arc<dyn Fn(&LinkConflict) -> ConflictResolution + Send + Sync>;
+
 impl<K, S> Default for GatewaySyncStrategy<K, S>
 where
     K: KeyMaterial + Clone + 'static,
@@ -34,6 +49,7 @@ where
 {
     fn default() -> Self {
         Self {
+            conflict_resolver: None,
             key_type: Default::default(),
             store_type: Default::default(),
         }
@@ -43,11 +59,24 @@ where
 impl<K, S> GatewaySyncStrategy<K, S>
 where
     K: KeyMaterial + Clone + 'static,
-    S: Storage,
+    S: Storage + 'static,
 {
+    /// Create a sync strategy that merges the local and counterpart lineages
+    /// rather than rebasing the local changes over the counterpart's, using
+    /// the given [ConflictResolver] to decide the outcome for each slug that
+    /// was changed in both lineages.
+    pub fn with_conflict_resolver(conflict_resolver: ConflictResolver) -> Self {
+        Self {
+            conflict_resolver: Some(conflict_resolver),
+            ..Default::default()
+        }
+    }
+
     /// Synchronize a local sphere's data with the data in a gateway, and rollback
-    /// if there is an error.
-    pub async fn sync(&self, context: &mut SphereContext<K, S>) -> Result<()> {
+    /// if there is an error. The returned list describes any conflicts that
+    /// were encountered and resolved along the way (it is always empty when no
+    /// [ConflictResolver] is configured).
+    pub async fn sync(&self, context: &mut SphereContext<K, S>) -> Result<Vec<ResolvedConflict>> {
         let client = context.client().await?;
         let counterpart_sphere_identity = client.session.sphere_identity.clone();
         let local_sphere_identity = context.identity().clone();
@@ -58,8 +87,8 @@ where
             .get_version(&counterpart_sphere_identity)
             .await?;
 
-        let result: Result<(), anyhow::Error> = {
-            let (local_sphere_version, counterpart_sphere_version) = self
+        let result: Result<Vec<ResolvedConflict>, anyhow::Error> = {
+            let (local_sphere_version, counterpart_sphere_version, resolved_conflicts) = self
                 .fetch_remote_changes(
                     context,
                     local_sphere_version.as_ref(),
@@ -74,7 +103,7 @@ where
                 &counterpart_sphere_version,
             )
             .await?;
-            Ok(())
+            Ok(resolved_conflicts)
         };
 
         // Rollback if there is an error while syncing
@@ -93,36 +122,39 @@ where
     }
 
     /// Fetches the latest changes from a gateway and updates the local lineage
-    /// using a conflict-free rebase strategy
+    /// using a conflict-free rebase strategy (or a three-way merge, if a
+    /// [ConflictResolver] is configured)
     async fn fetch_remote_changes(
         &self,
         context: &mut SphereContext<K, S>,
         local_sphere_tip: Option<&Cid>,
         counterpart_sphere_identity: &Did,
         counterpart_sphere_base: Option<&Cid>,
-    ) -> Result<(Option<Cid>, Cid)> {
+    ) -> Result<(Option<Cid>, Cid, Vec<ResolvedConflict>)> {
         let local_sphere_identity = context.identity().clone();
         let client = context.client().await?;
         let fetch_response = client
-            .fetch(&FetchParameters {
-                since: counterpart_sphere_base.cloned(),
-            })
+            .fetch_into(
+                &FetchParameters {
+                    since: counterpart_sphere_base.cloned(),
+                },
+                context.db_mut(),
+            )
             .await?;
 
-        let (counterpart_sphere_tip, new_blocks) = match fetch_response {
-            FetchResponse::NewChanges { tip, blocks } => (tip, blocks),
-            FetchResponse::UpToDate => {
+        let counterpart_sphere_tip = match fetch_response {
+            FetchResponseHeader::NewChanges { tip } => tip,
+            FetchResponseHeader::UpToDate => {
                 println!("Local history is already up to date...");
                 return Ok((
                     local_sphere_tip.cloned(),
                     *counterpart_sphere_base
                         .ok_or_else(|| anyhow!("Counterpart sphere history is missing!"))?,
+                    Vec::new(),
                 ));
             }
         };
 
-        new_blocks.load_into(context.db_mut()).await?;
-
         Sphere::try_hydrate_range(
             counterpart_sphere_base,
             &counterpart_sphere_tip,
@@ -146,6 +178,8 @@ where
             .await?
             .cloned();
 
+        let mut resolved_conflicts = Vec::new();
+
         let local_sphere_tip = match (
             local_sphere_tip,
             local_sphere_old_base,
@@ -153,14 +187,32 @@ where
         ) {
             (Some(current_tip), Some(old_base), Some(new_base)) => {
                 println!("Syncing received local sphere revisions...");
-                let new_tip = Sphere::at(current_tip, context.db())
-                    .try_sync(
-                        &old_base,
-                        &new_base,
-                        &context.author().key,
-                        context.author().authorization.as_ref(),
-                    )
-                    .await?;
+                let local_sphere = Sphere::at(current_tip, context.db());
+                let new_tip = match &self.conflict_resolver {
+                    Some(conflict_resolver) => {
+                        let (new_tip, conflicts) = local_sphere
+                            .try_merge(
+                                &new_base,
+                                &context.author().key,
+                                context.author().authorization.as_ref(),
+                                |conflict| conflict_resolver(conflict),
+                            )
+                            .await?;
+
+                        resolved_conflicts = conflicts;
+                        new_tip
+                    }
+                    None => {
+                        local_sphere
+                            .try_sync(
+                                &old_base,
+                                &new_base,
+                                &context.author().key,
+                                context.author().authorization.as_ref(),
+                            )
+                            .await?
+                    }
+                };
 
                 context
                     .db_mut()
@@ -192,7 +244,7 @@ where
             .set_version(counterpart_sphere_identity, &counterpart_sphere_tip)
             .await?;
 
-        Ok((local_sphere_tip, counterpart_sphere_tip))
+        Ok((local_sphere_tip, counterpart_sphere_tip, resolved_conflicts))
     }
 
     /// Attempts to push the latest local lineage to the gateway, causing the
@@ -216,7 +268,7 @@ where
             }
         };
 
-        let local_sphere_base = Sphere::at(counterpart_sphere_tip, context.db())
+        let mut local_sphere_base = Sphere::at(counterpart_sphere_tip, context.db())
             .try_get_links()
             .await?
             .get(context.identity())
@@ -228,39 +280,64 @@ where
             return Ok(());
         }
 
-        println!("Collecting blocks from new local history...");
-
-        let bundle = Sphere::at(local_sphere_tip, context.db())
-            .try_bundle_until_ancestor(local_sphere_base.as_ref())
-            .await?;
-
         let client = context.client().await?;
+        let local_sphere = Sphere::at(local_sphere_tip, context.db());
+        let mut has_retried = false;
+
+        let (counterpart_sphere_updated_tip, new_blocks) = loop {
+            println!(
+                "Pushing new local history to gateway {}...",
+                client.session.gateway_identity
+            );
+
+            let result = client
+                .push_stream(
+                    PushBodyHeader {
+                        sphere: context.identity().to_string(),
+                        base: local_sphere_base,
+                        tip: *local_sphere_tip,
+                    },
+                    local_sphere.stream_blocks_until_ancestor(local_sphere_base.as_ref()),
+                )
+                .await?;
 
-        println!(
-            "Pushing new local history to gateway {}...",
-            client.session.gateway_identity
-        );
-
-        let result = client
-            .push(&PushBody {
-                sphere: context.identity().to_string(),
-                base: local_sphere_base,
-                tip: *local_sphere_tip,
-                blocks: bundle,
-            })
-            .await?;
-
-        let (counterpart_sphere_updated_tip, new_blocks) = match result {
-            PushResponse::Accepted { new_tip, blocks } => (new_tip, blocks),
-            PushResponse::NoChange => {
-                return Err(anyhow!("Gateway already up to date!"));
+            match result {
+                PushResponse::Accepted { new_tip, blocks } => break (new_tip, blocks),
+                PushResponse::NoChange => {
+                    return Err(anyhow!("Gateway already up to date!"));
+                }
+                PushResponse::MissingRevisions { since } if !has_retried => {
+                    // The gateway's record of our history is older than the
+                    // base we pushed; as long as that record is part of our
+                    // local lineage, we can push again starting from there
+                    let is_local_revision = matches!(
+                        local_sphere.try_find_common_ancestor(&since).await,
+                        Ok(Some(ancestor)) if ancestor == since
+                    );
+
+                    if !is_local_revision {
+                        return Err(anyhow!(
+                            "Gateway's latest revision {} is not part of the local history",
+                            since
+                        ));
+                    }
+
+                    println!("Gateway is missing local revisions since {}...", since);
+
+                    local_sphere_base = Some(since);
+                    has_retried = true;
+                }
+                PushResponse::MissingRevisions { since } => {
+                    return Err(anyhow!(
+                        "Gateway is still missing local revisions since {}",
+                        since
+                    ));
+                }
             }
         };
 
         println!("Saving updated counterpart sphere history...");
 
-        new_blocks.load_into(context.db_mut()).await?;
-
         Sphere::try_hydrate_range(
             Some(counterpart_sphere_tip),
             &counterpart_sphere_updated_tip,
@@ -26,6 +26,9 @@ pub struct SphereFile {
 
     #[wasm_bindgen(skip)]
     pub fs: SphereFs<PlatformStorage, PlatformKeyMaterial>,
+
+    #[wasm_bindgen(skip)]
+    pub slug: String,
 }
 
 #[wasm_bindgen]
@@ -86,14 +89,20 @@ impl SphereFile {
     #[wasm_bindgen(js_name = "intoHtml")]
     /// Consume this SphereFile and return its contents formatted as HTML. A
     /// resolver function must be provided in order to convert slashlinks to
-    /// hypertext links according to the caller's use case.
+    /// hypertext links according to the caller's use case. Links to this file
+    /// from elsewhere in the sphere are listed in a "Linked from" section.
     ///
     /// Note that after this method is called, the SphereFile will be freed and
     /// is no longer usable.
     pub async fn into_html(self, resolver: Function) -> Result<String, String> {
+        let backlinks = self
+            .fs
+            .backlinks(&self.slug)
+            .await
+            .map_err(|error| format!("{:?}", error))?;
         let transform = JavaScriptTransform::new(resolver, self.fs.clone());
         Ok(
-            file_to_html_stream(transform, self.inner, HtmlOutput::Fragment)
+            file_to_html_stream(transform, self.inner, HtmlOutput::Fragment, backlinks)
                 .collect()
                 .await,
         )
@@ -32,6 +32,7 @@ impl SphereFs {
         Ok(file.map(|file| SphereFile {
             inner: file.boxed(),
             fs: self.inner.clone(),
+            slug,
         }))
     }
 
@@ -114,6 +115,7 @@ impl SphereFs {
             let file = SphereFile {
                 inner: file,
                 fs: self.inner.clone(),
+                slug: slug.clone(),
             };
             let slug = JsValue::from(slug);
 